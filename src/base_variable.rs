@@ -1,16 +1,94 @@
 pub mod variables {
     use super::variable::Variable;
+    use crate::globals::CALL_STACK;
+    use std::ptr::{addr_of, addr_of_mut};
     // use super::base_variables::BaseVariables::{Pi, E};
 
     pub static mut VARIABLE_STACK: Vec<Variable> = Vec::new();
+    // How many blocks of the script itself are open outside any function, and where the
+    // variables of the outermost one start. The variables below it are the globals
+    pub static mut SCRIPT_BLOCKS: (usize, usize) = (0, 0);
+
+    ///
+    ///Starts the variables of a block, a loop or a lambda call. Returns the depth to hand to
+    ///end_scope once it is done
+    ///
+    pub fn begin_scope() -> usize {
+        let depth = unsafe { (*addr_of!(VARIABLE_STACK)).len() };
+        unsafe {
+            if (*addr_of!(CALL_STACK)).is_empty() {
+                let (open, start) = SCRIPT_BLOCKS;
+                SCRIPT_BLOCKS = (open + 1, if open == 0 { depth } else { start });
+            }
+        }
+        depth
+    }
+
+    ///
+    ///Removes the variables declared since begin_scope returned depth
+    ///
+    pub fn end_scope(depth: usize) {
+        unsafe {
+            (*addr_of_mut!(VARIABLE_STACK)).truncate(depth);
+            if (*addr_of!(CALL_STACK)).is_empty() {
+                SCRIPT_BLOCKS.0 = SCRIPT_BLOCKS.0.saturating_sub(1);
+            }
+        }
+    }
+
+    ///
+    ///The position on the stack of the variable called name as the running code sees it: the
+    ///innermost one in the running function, or else a global. The variables of the code that
+    ///called the function are out of its reach
+    ///
+    pub fn find_index(name: &str) -> Option<usize> {
+        let stack = unsafe { &*addr_of!(VARIABLE_STACK) };
+        let find = |from: usize, to: usize| {
+            stack[from..to]
+                .iter()
+                .rposition(|variable| variable.name == name)
+                .map(|index| from + index)
+        };
+        match unsafe { (*addr_of!(CALL_STACK)).first() } {
+            None => find(0, stack.len()),
+            Some(outermost) => {
+                let frame = unsafe { (*addr_of!(CALL_STACK)).last() }.unwrap();
+                let globals = match unsafe { SCRIPT_BLOCKS } {
+                    (0, _) => outermost.variables,
+                    (_, start) => start,
+                };
+                find(frame.variables, stack.len()).or_else(|| find(0, globals))
+            }
+        }
+    }
 }
 
 pub mod variable {
     use super::base_types::BaseTypes;
     use super::variables::VARIABLE_STACK;
     use crate::base_variable::base_types::GetType;
+    use crate::collection::collections::type_name_of;
+    use crate::compilers::convert::cast;
+    use crate::globals::{report_error, STRICT};
+    use crate::heap::{visit, Shared};
     use crate::node::nodes::ASTNode;
     use std::fmt;
+    use std::rc::Rc;
+
+    ///
+    ///The int a float stored in the int variable called name becomes, the same for a let and a
+    ///later assignment. The float is cut to an int the way as cuts it, NaN and a float out of
+    ///the range of an int are errors. In strict mode only as converts it
+    ///
+    pub fn float_to_int(name: &str, value: f64) -> Result<BaseTypes, String> {
+        if unsafe { STRICT } {
+            return Err(format!(
+                "Type Error: Cannot store float in '{}' of type int in strict mode, convert it with `as int`",
+                name
+            ));
+        }
+        cast(BaseTypes::Float(value), "int")
+    }
 
     #[derive(Debug, Clone)]
     pub struct Variable {
@@ -45,13 +123,45 @@ pub mod variable {
         fn decrement(&mut self);
     }
 
+    ///
+    ///Compares two collections by what they hold. A collection always equals itself, and two
+    ///that hold each other are equal when nothing else tells them apart
+    ///
+    fn contents_equal<T>(left: &Shared<T>, right: &Shared<T>, equal: fn(&T, &T) -> bool) -> bool {
+        if Rc::ptr_eq(left, right) {
+            return true;
+        }
+        let key = (Rc::as_ptr(left) as usize, Rc::as_ptr(right) as usize);
+        visit(key, || match (left.try_borrow(), right.try_borrow()) {
+            (Ok(left), Ok(right)) => equal(&left, &right),
+            // one of them is being changed, so they are not the same right now
+            _ => false,
+        })
+        .unwrap_or(true)
+    }
+
     impl PartialEq for BaseTypes {
         fn eq(&self, other: &Self) -> bool {
             match (self, other) {
                 (BaseTypes::Int(x), BaseTypes::Int(y)) => x == y,
                 (BaseTypes::Float(x), BaseTypes::Float(y)) => x == y,
                 (BaseTypes::StringWrapper(s1), BaseTypes::StringWrapper(s2)) => s1 == s2,
+                (BaseTypes::Bool(b1), BaseTypes::Bool(b2)) => b1 == b2,
                 (BaseTypes::Char(c1), BaseTypes::Char(c2)) => c1 == c2,
+                (BaseTypes::Array(a1), BaseTypes::Array(a2)) => {
+                    contents_equal(a1, a2, |a1, a2| a1.data == a2.data)
+                }
+                (BaseTypes::Dictionary(d1), BaseTypes::Dictionary(d2)) => {
+                    contents_equal(d1, d2, |d1, d2| {
+                        d1.len() == d2.len()
+                            && d1.entries().all(|(key, value)| d2.get(key) == Some(value))
+                    })
+                }
+                (BaseTypes::Set(s1), BaseTypes::Set(s2)) => contents_equal(s1, s2, |s1, s2| {
+                    s1.len() == s2.len() && s1.values().iter().all(|value| s2.contains(value))
+                }),
+                (BaseTypes::Tuple(t1), BaseTypes::Tuple(t2)) => t1 == t2,
+                (BaseTypes::Lines(l1), BaseTypes::Lines(l2)) => Rc::ptr_eq(l1, l2),
                 _ => false,
             }
         }
@@ -208,74 +318,43 @@ pub mod variable {
         pub fn new(name: String, value: BaseTypes, var_type: BaseTypes) -> Variable {
             //println!("Variable info: {}, {:?}, {:?}", name, value, var_type);
 
-            // Ensure the value type matches the variable type
-            let checked_value = match var_type {
-                BaseTypes::Int(_) => match value {
-                    BaseTypes::Int(_) => value,
-                    BaseTypes::Null => BaseTypes::Int(0),
-                    BaseTypes::Float(_) => BaseTypes::Int(value.into()),
-                    _ => {
-                        println!(
-                            "Warning: Value type mismatch for '{}'. Setting default Int value.",
-                            name
-                        );
-                        BaseTypes::Int(0)
-                    }
+            // a value of another type is an error, the same as when it is assigned later. Only
+            // numbers are converted, a float is cut to an int unless in strict mode
+            let mismatch = |value: BaseTypes| -> BaseTypes {
+                report_error(format!(
+                    "Type Error: Cannot assign {} to '{}' of type {}",
+                    type_name_of(&value),
+                    name,
+                    type_name_of(&var_type)
+                ))
+            };
+            let checked_value = match (&var_type, value) {
+                (_, BaseTypes::Null) => match var_type {
+                    BaseTypes::Int(_) => BaseTypes::Int(0),
+                    BaseTypes::Float(_) => BaseTypes::Float(0.0),
+                    BaseTypes::StringWrapper(_) => BaseTypes::StringWrapper(String::new()),
+                    BaseTypes::Bool(_) => BaseTypes::Bool(false),
+                    BaseTypes::Char(_) => BaseTypes::Char('\0'),
+                    _ => BaseTypes::Null,
                 },
-                BaseTypes::Float(_) => {
-                    match value {
-                        BaseTypes::Float(_) => value,
-                        BaseTypes::Null => BaseTypes::Float(0.0),
-                        BaseTypes::Int(_) => BaseTypes::Float(value.into()),
-                        _ => {
-                            println!("Warning: Value type mismatch for '{}'. Setting default Float value.", name);
-                            BaseTypes::Float(0.0)
-                        }
-                    }
-                }
-                BaseTypes::StringWrapper(_) => {
-                    match value {
-                        BaseTypes::StringWrapper(_) => value,
-                        BaseTypes::Null => BaseTypes::StringWrapper(String::new()),
-
-                        _ => {
-                            println!("Warning: Value type mismatch for '{}'. Setting default String value.", name);
-                            BaseTypes::StringWrapper(String::new())
-                        }
-                    }
-                }
-                BaseTypes::Bool(_) => {
-                    match value {
-                        BaseTypes::Bool(_) => value,
-                        BaseTypes::Null => BaseTypes::Bool(false),
-
-                        _ => {
-                            println!("Warning: Value type mismatch for '{}'. Setting default Bool value.", name);
-                            BaseTypes::Bool(false)
-                        }
-                    }
-                }
-                BaseTypes::Char(_) => {
-                    match value {
-                        BaseTypes::Char(_) => value,
-                        BaseTypes::Null => BaseTypes::Char('\0'),
-
-                        _ => {
-                            println!("Warning: Value type mismatch for '{}'. Setting default Char value.", name);
-                            BaseTypes::Char('\0')
-                        }
-                    }
-                }
-
-                BaseTypes::Null => {
-                    match value {
-                        BaseTypes::Null => value,
-                        _ => {
-                            println!("Warning: Value type mismatch for '{}'. Setting default Null value.", name);
-                            BaseTypes::Null
-                        }
-                    }
+                (BaseTypes::Int(_), BaseTypes::Float(f)) => {
+                    float_to_int(&name, f).unwrap_or_else(|e| report_error(e))
                 }
+                (BaseTypes::Float(_), value @ BaseTypes::Int(_)) => BaseTypes::Float(value.into()),
+                (BaseTypes::Int(_), value @ BaseTypes::Int(_))
+                | (BaseTypes::Float(_), value @ BaseTypes::Float(_))
+                | (BaseTypes::StringWrapper(_), value @ BaseTypes::StringWrapper(_))
+                // no other value is taken for a bool, an int is not true or false
+                | (BaseTypes::Bool(_), value @ BaseTypes::Bool(_))
+                | (BaseTypes::Char(_), value @ BaseTypes::Char(_))
+                | (BaseTypes::Array(_), value @ BaseTypes::Array(_))
+                | (BaseTypes::Dictionary(_), value @ BaseTypes::Dictionary(_))
+                | (BaseTypes::Set(_), value @ BaseTypes::Set(_))
+                | (BaseTypes::Tuple(_), value @ BaseTypes::Tuple(_))
+                | (BaseTypes::Lines(_), value @ BaseTypes::Lines(_))
+                // a variable without a type takes any value
+                | (BaseTypes::Null, value) => value,
+                (_, value) => mismatch(value),
             };
 
             Variable {
@@ -487,8 +566,6 @@ pub mod variable {
         fn from(value: BaseTypes) -> Self {
             match value {
                 BaseTypes::Bool(b) => b,
-                BaseTypes::Int(1) => true,
-                BaseTypes::Int(0) => false,
                 _ => false,
             }
         }
@@ -517,6 +594,51 @@ pub mod variable {
                 BaseTypes::StringWrapper(s) => write!(f, "{}", s),
                 BaseTypes::Bool(b) => write!(f, "{}", b),
                 BaseTypes::Char(c) => write!(f, "{}", c),
+                // a collection inside itself is written as [...] or {...}
+                BaseTypes::Array(a) => visit((Rc::as_ptr(a) as usize, 0), || {
+                    write!(f, "[")?;
+                    for (i, value) in a.borrow().data.iter().enumerate() {
+                        if i > 0 {
+                            write!(f, ", ")?;
+                        }
+                        write!(f, "{}", value)?;
+                    }
+                    write!(f, "]")
+                })
+                .unwrap_or_else(|| write!(f, "[...]")),
+                BaseTypes::Dictionary(d) => visit((Rc::as_ptr(d) as usize, 0), || {
+                    write!(f, "{{")?;
+                    for (i, (key, value)) in d.borrow().entries().enumerate() {
+                        if i > 0 {
+                            write!(f, ", ")?;
+                        }
+                        write!(f, "{} => {}", key, value)?;
+                    }
+                    write!(f, "}}")
+                })
+                .unwrap_or_else(|| write!(f, "{{...}}")),
+                BaseTypes::Set(s) => visit((Rc::as_ptr(s) as usize, 0), || {
+                    write!(f, "{{")?;
+                    for (i, value) in s.borrow().values().iter().enumerate() {
+                        if i > 0 {
+                            write!(f, ", ")?;
+                        }
+                        write!(f, "{}", value)?;
+                    }
+                    write!(f, "}}")
+                })
+                .unwrap_or_else(|| write!(f, "{{...}}")),
+                BaseTypes::Tuple(values) => {
+                    write!(f, "(")?;
+                    for (i, value) in values.iter().enumerate() {
+                        if i > 0 {
+                            write!(f, ", ")?;
+                        }
+                        write!(f, "{}", value)?;
+                    }
+                    write!(f, ")")
+                }
+                BaseTypes::Lines(lines) => write!(f, "<lines of {}>", lines.borrow().path),
                 BaseTypes::Null => write!(f, "null"),
             }
         }
@@ -584,6 +706,14 @@ pub mod base_variables {
 pub mod base_types {
     //use std::fmt;
 
+    use crate::collection::collections::{Array, Dictionary, Set};
+    use crate::compilers::fs::Lines;
+    use crate::heap::Shared;
+    use std::hash::{Hash, Hasher};
+    use std::rc::Rc;
+
+    // Cloning a value copies ints and strings, collections are shared and only the handle is
+    // copied
     #[derive(Debug, Clone)]
     pub enum BaseTypes {
         Int(i32),
@@ -591,9 +721,39 @@ pub mod base_types {
         StringWrapper(String),
         Bool(bool),
         Char(char),
+        Array(Shared<Array>),
+        Dictionary(Shared<Dictionary>),
+        Set(Shared<Set>),
+        Tuple(Vec<BaseTypes>),
+        Lines(Shared<Lines>),
         Null,
     }
 
+    // Dictionaries refuse NaN keys, so every key that is stored equals itself
+    impl Eq for BaseTypes {}
+
+    impl Hash for BaseTypes {
+        fn hash<H: Hasher>(&self, state: &mut H) {
+            std::mem::discriminant(self).hash(state);
+            match self {
+                BaseTypes::Int(i) => i.hash(state),
+                // 0.0 and -0.0 are equal so they have to hash the same
+                BaseTypes::Float(f) => (if *f == 0.0 { 0.0 } else { *f }).to_bits().hash(state),
+                BaseTypes::StringWrapper(s) => s.hash(state),
+                BaseTypes::Bool(b) => b.hash(state),
+                BaseTypes::Char(c) => c.hash(state),
+                // an array can hold itself, its length is enough to keep equal arrays together
+                BaseTypes::Array(a) => a.borrow().data.len().hash(state),
+                // equal dictionaries can list their entries in any order
+                BaseTypes::Dictionary(d) => d.borrow().len().hash(state),
+                BaseTypes::Set(s) => s.borrow().len().hash(state),
+                BaseTypes::Tuple(values) => values.hash(state),
+                BaseTypes::Lines(lines) => Rc::as_ptr(lines).hash(state),
+                BaseTypes::Null => {}
+            }
+        }
+    }
+
     pub trait GetType {
         fn GetType(&self) -> String;
     }
@@ -606,6 +766,11 @@ pub mod base_types {
                 BaseTypes::StringWrapper(_) => "String".to_string(),
                 BaseTypes::Bool(_) => "Bool".to_string(),
                 BaseTypes::Char(_) => "Char".to_string(),
+                BaseTypes::Array(_) => "Array".to_string(),
+                BaseTypes::Dictionary(_) => "Dict".to_string(),
+                BaseTypes::Set(_) => "Set".to_string(),
+                BaseTypes::Tuple(_) => "Tuple".to_string(),
+                BaseTypes::Lines(_) => "Lines".to_string(),
                 BaseTypes::Null => "Null".to_string(),
             }
         }
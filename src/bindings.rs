@@ -0,0 +1,1184 @@
+/*
+* This file checks how a script changes its variables before it runs. A let can only be
+* assigned to again when it is a let mut, parameters, loop variables and caught errors never
+* can, and a const can never be changed. The value of a const is worked out here, from literals,
+* math constants and the consts declared before it, so a const that needs the script to run
+* is an error too. A collection is shared between the names it is given to, so an immutable one
+* cannot be given to a let mut, assigned or pushed into a mutable collection without a copy, not
+* in a literal and not as what a function returns either. In
+* strict mode a float cannot be given to an int without an as. Each error points at the
+* assignment and is labeled with the declaration
+*/
+use crate::base_variable::base_types::BaseTypes;
+use crate::collection::collections::{check_type, split_type, type_name_of};
+use crate::compilers::array::mutates as array_mutates;
+use crate::compilers::dictionary::mutates as dictionary_mutates;
+use crate::compilers::expression::{constant_value, parse_expression, static_type, Expression};
+use crate::compilers::math::module_constant;
+use crate::compilers::set::mutates as set_mutates;
+use crate::globals::STRICT;
+use crate::node::nodes::{token_to_node, ASTNode};
+use crate::statement_tokenizer::lexer::lexers::{read_name, skip_binding_keyword, Lexer};
+use crate::statement_tokenizer::tokenizer::tokenizers::ParseInfo;
+use crate::syntax::{line_starts, span_at, Span, SyntaxError};
+use crate::token_type::token_types::TokenTypes;
+use std::collections::HashMap;
+
+// an assignment to a let without mut, a parameter, a loop variable or a caught error
+const ASSIGN_TO_IMMUTABLE: &str = "E0009";
+// an assignment to a const
+const ASSIGN_TO_CONST: &str = "E0010";
+// a const whose value is not known before the script runs, or does not fit its type
+const NOT_CONSTANT: &str = "E0011";
+// a value of another type given to a let in strict mode, it would be converted without an as
+const IMPLICIT_CONVERSION: &str = "E0012";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Kind {
+    Mutable,
+    Immutable,
+    Const,
+}
+
+#[derive(Clone)]
+struct Binding {
+    name: String,
+    kind: Kind,
+    // the declared type, empty when none is written
+    type_name: String,
+    declared: Span,
+    // what the declaration is labeled with when the binding is changed
+    note: String,
+    // the value of a const
+    value: Option<BaseTypes>,
+}
+
+///
+///What a function returns that its caller would share, so the caller cannot give it to a
+///mutable name either
+///
+#[derive(Clone)]
+enum Returned {
+    // the collection given as the parameter at this position
+    Parameter(usize),
+    // an immutable collection declared outside the function
+    Binding(Binding),
+}
+
+///
+///The function whose body is being checked
+///
+struct Function {
+    name: String,
+    // how many scopes are open in its body
+    depth: usize,
+    // where its parameters are declared, in order
+    parameters: Vec<Span>,
+}
+
+struct Checker {
+    chars: Vec<char>,
+    lines: Vec<usize>,
+    // the tokens of the script with where they start, without its comments
+    tokens: Vec<(ParseInfo, usize)>,
+    scopes: Vec<Vec<Binding>>,
+    // what the next block declares before it is opened, like the parameters of a function
+    pending: Vec<Binding>,
+    errors: Vec<SyntaxError>,
+    // whether a value has to have the type of the let it is given to
+    strict: bool,
+    // what each function declared so far can return that is shared
+    returns: HashMap<String, Vec<Returned>>,
+    function: Option<Function>,
+}
+
+impl Checker {
+    fn span(&self, start: usize, length: usize) -> Span {
+        span_at(&self.lines, start, length)
+    }
+
+    fn end_of(&self, index: usize) -> usize {
+        let (info, start) = &self.tokens[index];
+        start + info.chars_read as usize
+    }
+
+    fn error(&mut self, code: &'static str, message: String, span: Span) {
+        self.errors.push(SyntaxError {
+            code,
+            message,
+            span,
+            labels: Vec::new(),
+        });
+    }
+
+    ///
+    ///The innermost binding called name
+    ///
+    fn lookup(&self, name: &str) -> Option<&Binding> {
+        self.scopes
+            .iter()
+            .rev()
+            .flat_map(|scope| scope.iter().rev())
+            .find(|binding| binding.name == name)
+    }
+
+    ///
+    ///The span of name as a whole word between from and to, from itself when it is not there
+    ///
+    fn find_name(&self, from: usize, to: usize, name: &str) -> Span {
+        let name: Vec<char> = name.chars().collect();
+        let is_word = |c: Option<&char>| c.is_some_and(|c| c.is_alphanumeric() || *c == '_');
+        let start = (from..to.saturating_sub(name.len()) + 1)
+            .find(|&i| {
+                self.chars.get(i..i + name.len()) == Some(&name[..])
+                    && !is_word(i.checked_sub(1).and_then(|i| self.chars.get(i)))
+                    && !is_word(self.chars.get(i + name.len()))
+            })
+            .unwrap_or(from);
+        self.span(start, name.len())
+    }
+
+    fn check(&mut self) {
+        let mut at_start = true;
+        let mut index = 0;
+        while index < self.tokens.len() {
+            let (info, start) = self.tokens[index].clone();
+            match &info.token {
+                TokenTypes::LeftCurly | TokenTypes::Else => {
+                    let pending = std::mem::take(&mut self.pending);
+                    self.scopes.push(pending);
+                }
+                TokenTypes::RightCurly if self.scopes.len() > 1 => {
+                    self.scopes.pop();
+                    if self
+                        .function
+                        .as_ref()
+                        .is_some_and(|function| self.scopes.len() < function.depth)
+                    {
+                        self.function = None;
+                    }
+                }
+                TokenTypes::Return => self.returned(index),
+                TokenTypes::Variable
+                | TokenTypes::Collection { .. }
+                | TokenTypes::Destructure { .. } => self.declaration(index),
+                TokenTypes::For { statement } => {
+                    if let Some((name, _)) = statement.split_once(" in ") {
+                        let declared = self.find_name(start, self.end_of(index), name.trim());
+                        self.pending.push(Binding {
+                            name: name.trim().to_string(),
+                            kind: Kind::Immutable,
+                            type_name: String::new(),
+                            declared,
+                            note: "loop variables cannot be changed".to_string(),
+                            value: None,
+                        });
+                    }
+                }
+                TokenTypes::Function {
+                    name: function,
+                    arguments,
+                    ..
+                } => {
+                    self.returns.insert(function.clone(), Vec::new());
+                    let mut parameters = Vec::new();
+                    for (name, type_name, _) in arguments {
+                        let declared = self.find_name(start, self.end_of(index), name);
+                        // a collection is shared with the caller, only a copy is its own
+                        let note = match split_type(type_name).0.as_str() {
+                            "array" | "dict" | "set" => {
+                                "parameters cannot be changed, copy it into a `let mut` with .copy()"
+                            }
+                            _ => "parameters cannot be changed, copy it into a `let mut`",
+                        };
+                        parameters.push(declared);
+                        self.pending.push(Binding {
+                            name: name.clone(),
+                            kind: Kind::Immutable,
+                            type_name: type_name.clone(),
+                            declared,
+                            note: note.to_string(),
+                            value: None,
+                        });
+                    }
+                    self.function = Some(Function {
+                        name: function.clone(),
+                        depth: self.scopes.len() + 1,
+                        parameters,
+                    });
+                }
+                TokenTypes::Catch => {
+                    if let Some((name, start)) = self
+                        .tokens
+                        .get(index + 1)
+                        .filter(|(next, _)| next.token == TokenTypes::VariableCall)
+                        .cloned()
+                    {
+                        self.pending.push(Binding {
+                            declared: self.span(start, name.chars_read as usize),
+                            name: name.value,
+                            kind: Kind::Immutable,
+                            type_name: String::new(),
+                            note: "the caught error cannot be changed".to_string(),
+                            value: None,
+                        });
+                        index += 1;
+                    }
+                }
+                TokenTypes::VariableCall if at_start => self.assignment(index),
+                TokenTypes::FunctionCall => self.method_call(index),
+                _ => {}
+            }
+            at_start = matches!(
+                info.token,
+                TokenTypes::SemiColon
+                    | TokenTypes::LeftCurly
+                    | TokenTypes::RightCurly
+                    | TokenTypes::Else
+            );
+            index += 1;
+        }
+    }
+
+    ///
+    ///Declares the names of a let, let mut or const. The value of a const is worked out first,
+    ///it can only use the names declared before it
+    ///
+    fn declaration(&mut self, index: usize) {
+        let (info, start) = self.tokens[index].clone();
+        let keyword_end = read_name(&self.chars, start);
+        let name_start = skip_binding_keyword(&self.chars, start);
+        let kind = match self.chars[start..keyword_end]
+            .iter()
+            .collect::<String>()
+            .as_str()
+        {
+            "const" => Kind::Const,
+            // anything but spaces between the let and the name is the mut
+            "let"
+                if self.chars[keyword_end..name_start]
+                    .iter()
+                    .any(|c| !c.is_whitespace()) =>
+            {
+                Kind::Mutable
+            }
+            "let" => Kind::Immutable,
+            // a collection declared without a let
+            _ => Kind::Mutable,
+        };
+
+        let (names, type_name) = match &info.token {
+            // the type is read as written, the token keeps the parts of a tuple type apart
+            TokenTypes::Collection { name, .. } => {
+                let end = self.end_of(index);
+                let written: String = self.chars[name_start..end].iter().collect();
+                let type_name = written.split_once(':').map_or("", |(_, t)| t.trim());
+                (vec![name.clone()], type_name.to_string())
+            }
+            TokenTypes::Destructure { names } => (names.clone(), String::new()),
+            _ => {
+                let type_name = match self.tokens.get(index + 1) {
+                    Some((next, _)) if next.token == TokenTypes::VarTypeAssignment => {
+                        next.value.clone()
+                    }
+                    _ => String::new(),
+                };
+                (vec![info.value.clone()], type_name)
+            }
+        };
+
+        let mut values = vec![None; names.len()];
+        if kind == Kind::Const {
+            if let Some(value) = self.const_value(index, &names, &type_name) {
+                values = match value {
+                    BaseTypes::Tuple(elements) if names.len() > 1 => {
+                        elements.into_iter().map(Some).collect()
+                    }
+                    value => vec![Some(value)],
+                };
+            }
+        }
+
+        let end = self.end_of(index);
+        if self.strict && kind != Kind::Const && info.token == TokenTypes::Variable {
+            let declared = self.find_name(name_start, end, &names[0]);
+            // let name: type = value
+            self.check_conversion(index + 2, &type_name, declared);
+        }
+        if kind == Kind::Mutable && names.len() == 1 {
+            let equals = match self.tokens.get(index + 1) {
+                Some((next, _)) if next.token == TokenTypes::VarTypeAssignment => index + 2,
+                _ => index + 1,
+            };
+            let assigns = self
+                .tokens
+                .get(equals)
+                .is_some_and(|(info, _)| info.token == TokenTypes::AssignmentOperator);
+            if assigns {
+                self.share(equals + 1, self.value_end(equals), &names[0]);
+            }
+        }
+        for (name, value) in names.iter().zip(values) {
+            let note = match kind {
+                Kind::Const => "declared as a constant here".to_string(),
+                _ => format!("declared here, use `let mut {}` to allow changing it", name),
+            };
+            let binding = Binding {
+                name: name.clone(),
+                kind,
+                type_name: type_name.clone(),
+                declared: self.find_name(name_start, end, name),
+                note,
+                value,
+            };
+            self.scopes.last_mut().unwrap().push(binding);
+        }
+    }
+
+    ///
+    ///Works out the value after the = of the const declared by the token at index. An error is
+    ///reported when it needs the script to run or does not fit the declared type
+    ///
+    fn const_value(
+        &mut self,
+        index: usize,
+        names: &[String],
+        type_name: &str,
+    ) -> Option<BaseTypes> {
+        let name = names.join(", ");
+        let equals = (index + 1..self.tokens.len())
+            .take(2)
+            .find(|&i| self.tokens[i].0.token == TokenTypes::AssignmentOperator);
+        let Some(equals) = equals else {
+            let span = self.span(
+                self.tokens[index].1,
+                self.tokens[index].0.chars_read as usize,
+            );
+            self.error(
+                NOT_CONSTANT,
+                format!("expected a value for constant `{}`", name),
+                span,
+            );
+            return None;
+        };
+
+        let (nodes, span) = self.value(equals)?;
+        let value = parse_expression(&nodes).and_then(|e| constant_value(&self.fold(e)));
+        let value = match value {
+            Ok(Some(value)) => value,
+            Ok(None) => {
+                let message = format!(
+                    "the value of constant `{}` has to be known before the script runs",
+                    name
+                );
+                self.error(NOT_CONSTANT, message, span);
+                return None;
+            }
+            Err(e) => {
+                let reason = e
+                    .split_once("Error: ")
+                    .map_or(e.as_str(), |(_, reason)| reason);
+                let message = format!(
+                    "the value of constant `{}` cannot be worked out: {}",
+                    name, reason
+                );
+                self.error(NOT_CONSTANT, message, span);
+                return None;
+            }
+        };
+        if type_name.is_empty() {
+            return Some(value);
+        }
+        match check_type(&BaseTypes::StringWrapper(type_name.to_string()), value) {
+            Ok(value) => Some(value),
+            Err(value) => {
+                let message = format!(
+                    "expected `{}` for constant `{}`, found `{}`",
+                    type_name,
+                    name,
+                    type_name_of(&value)
+                );
+                self.error(NOT_CONSTANT, message, span);
+                None
+            }
+        }
+    }
+
+    ///
+    ///The nodes of the value after the = at equals, up to the ; that ends it, and their span.
+    ///None when nothing follows the = or a literal in it is not valid
+    ///
+    fn value(&self, equals: usize) -> Option<(Vec<ASTNode>, Span)> {
+        let end = self.value_end(equals);
+        if end == equals + 1 {
+            return None;
+        }
+        let value_start = self.tokens[equals + 1].1;
+        let span = self.span(value_start, self.end_of(end - 1) - value_start);
+        // a literal that is not valid is reported by the syntax pass
+        let nodes = self.tokens[equals + 1..end]
+            .iter()
+            .map(|(info, _)| token_to_node(info.clone()))
+            .collect::<Result<Vec<ASTNode>, String>>()
+            .ok()?;
+        Some((nodes, span))
+    }
+
+    ///
+    ///The index of the ; that ends the value after the = at equals, or of the token that closes
+    ///the block it is in
+    ///
+    fn value_end(&self, equals: usize) -> usize {
+        let mut depth = 0;
+        let mut end = equals + 1;
+        while let Some((info, _)) = self.tokens.get(end) {
+            match info.token {
+                TokenTypes::LeftParenthesis | TokenTypes::LeftBracket | TokenTypes::LeftCurly => {
+                    depth += 1
+                }
+                TokenTypes::RightParenthesis
+                | TokenTypes::RightBracket
+                | TokenTypes::RightCurly => depth -= 1,
+                TokenTypes::SemiColon if depth == 0 => break,
+                _ => {}
+            }
+            if depth < 0 {
+                break;
+            }
+            end += 1;
+        }
+        end
+    }
+
+    ///
+    ///The immutable binding whose collection the tokens from up to to are on their own, or an
+    ///element of it that is a collection too, with their span. A mutable name given it could
+    ///change the collection
+    ///
+    fn shared_collection(&self, from: usize, to: usize) -> Option<(&Binding, Span)> {
+        let (info, start) = self.tokens.get(from)?;
+        if info.token != TokenTypes::VariableCall || to <= from {
+            return None;
+        }
+        let mut levels = 0;
+        let mut depth = 0;
+        for (next, _) in &self.tokens[from + 1..to] {
+            match next.token {
+                TokenTypes::LeftBracket => {
+                    levels += (depth == 0) as usize;
+                    depth += 1;
+                }
+                TokenTypes::RightBracket => depth -= 1,
+                _ if depth == 0 => return None,
+                _ => {}
+            }
+        }
+        let binding = self
+            .lookup(&info.value)
+            .filter(|binding| binding.kind != Kind::Mutable)?;
+        let mut type_name = binding.type_name.clone();
+        for _ in 0..levels {
+            let (base, parameters) = split_type(&type_name);
+            type_name = match base.as_str() {
+                "array" => parameters.first()?.clone(),
+                "dict" => parameters.get(1)?.clone(),
+                _ => return None,
+            };
+        }
+        match split_type(&type_name).0.as_str() {
+            "array" | "dict" | "set" => {
+                Some((binding, self.span(*start, self.end_of(to - 1) - start)))
+            }
+            _ => None,
+        }
+    }
+
+    ///
+    ///The immutable collections the tokens from up to to would share, with the span and the
+    ///token range of the value that shares each. Next to a name, these are the elements of a
+    ///collection or tuple literal and what a call returns of its arguments or of the script
+    ///
+    fn shared(&self, from: usize, to: usize) -> Vec<(Binding, Span, usize, usize)> {
+        if let Some((binding, span)) = self.shared_collection(from, to) {
+            return vec![(binding.clone(), span, from, to)];
+        }
+        let Some((info, start)) = self.tokens.get(from) else {
+            return Vec::new();
+        };
+        let (open, call) = match info.token {
+            TokenTypes::LeftBracket | TokenTypes::LeftParenthesis | TokenTypes::LeftCurly => {
+                (from, None)
+            }
+            TokenTypes::FunctionCall if !info.value.contains('.') => {
+                match self.returns.get(&info.value) {
+                    Some(returned) => (from + 1, Some(returned)),
+                    None => return Vec::new(),
+                }
+            }
+            _ => return Vec::new(),
+        };
+        let Some(parts) = self.parts(open, to) else {
+            return Vec::new();
+        };
+        let Some(returned) = call else {
+            return parts
+                .into_iter()
+                .flat_map(|(from, to)| self.shared(from, to))
+                .collect();
+        };
+        let span = self.span(*start, self.end_of(to - 1) - start);
+        let mut shared = Vec::new();
+        for returned in returned {
+            match returned {
+                Returned::Parameter(position) => {
+                    if let Some(&(from, to)) = parts.get(*position) {
+                        shared.extend(self.shared(from, to));
+                    }
+                }
+                Returned::Binding(binding) => shared.push((binding.clone(), span, from, to)),
+            }
+        }
+        shared
+    }
+
+    ///
+    ///The token ranges of the parts of the bracket at open that is closed by the token before
+    ///to, split at its commas. The part of a dict entry is its value
+    ///
+    fn parts(&self, open: usize, to: usize) -> Option<Vec<(usize, usize)>> {
+        if !matches!(
+            self.tokens.get(open)?.0.token,
+            TokenTypes::LeftBracket | TokenTypes::LeftParenthesis | TokenTypes::LeftCurly
+        ) || to < open + 2
+        {
+            return None;
+        }
+        let mut parts = Vec::new();
+        let mut depth = 0;
+        let mut part = open + 1;
+        for index in open..to {
+            match self.tokens[index].0.token {
+                TokenTypes::LeftParenthesis | TokenTypes::LeftBracket | TokenTypes::LeftCurly => {
+                    depth += 1
+                }
+                TokenTypes::RightParenthesis
+                | TokenTypes::RightBracket
+                | TokenTypes::RightCurly => {
+                    depth -= 1;
+                    // the bracket closes before to, the value goes on after it
+                    if depth == 0 && index != to - 1 {
+                        return None;
+                    }
+                }
+                TokenTypes::ArgumentSeparator if depth == 1 => {
+                    parts.push((part, index));
+                    part = index + 1;
+                }
+                TokenTypes::FatArrow if depth == 1 => part = index + 1,
+                _ => {}
+            }
+        }
+        if part < to - 1 {
+            parts.push((part, to - 1));
+        }
+        Some(parts)
+    }
+
+    ///
+    ///Checks that the tokens from up to to, given to the mutable target, do not share an
+    ///immutable collection, which target could then change
+    ///
+    fn share(&mut self, from: usize, to: usize, target: &str) {
+        for (binding, span, from, to) in self.shared(from, to) {
+            let (code, what) = match binding.kind {
+                Kind::Const => (ASSIGN_TO_CONST, "constant"),
+                _ => (ASSIGN_TO_IMMUTABLE, "immutable variable"),
+            };
+            let value: String = self.chars[self.tokens[from].1..self.end_of(to - 1)]
+                .iter()
+                .collect();
+            let message = format!(
+                "cannot share {} `{}` with mutable `{}`, give it `{}.copy()`",
+                what, binding.name, target, value
+            );
+            self.error(code, message, span);
+            self.errors
+                .last_mut()
+                .unwrap()
+                .labels
+                .push((binding.declared, binding.note));
+        }
+    }
+
+    ///
+    ///Records what the return at index gives the caller of the function it is in that the
+    ///caller would share: a parameter or an immutable collection declared outside the function
+    ///
+    fn returned(&mut self, index: usize) {
+        let Some(function) = &self.function else {
+            return;
+        };
+        let mut returned = Vec::new();
+        for (binding, ..) in self.shared(index + 1, self.value_end(index)) {
+            let position = function
+                .parameters
+                .iter()
+                .position(|declared| *declared == binding.declared);
+            let outside = self.scopes[..function.depth - 1]
+                .iter()
+                .flatten()
+                .any(|outer| outer.declared == binding.declared);
+            match position {
+                Some(position) => returned.push(Returned::Parameter(position)),
+                None if outside => returned.push(Returned::Binding(binding)),
+                None => {}
+            }
+        }
+        let name = function.name.clone();
+        self.returns.entry(name).or_default().extend(returned);
+    }
+
+    ///
+    ///In strict mode, checks that the value after the = at index, of a let or of an assignment to
+    ///a variable declared as type_name, is not a float given to an int, which would be cut
+    ///without an as. A value of an unrelated type is a type error in any mode
+    ///
+    fn check_conversion(&mut self, equals: usize, type_name: &str, declared: Span) {
+        let expected = match type_name.trim() {
+            "boolean" => "bool",
+            type_name @ ("int" | "float" | "string" | "char" | "bool") => type_name,
+            _ => return,
+        };
+        let assigns = self
+            .tokens
+            .get(equals)
+            .is_some_and(|(info, _)| info.token == TokenTypes::AssignmentOperator);
+        let Some((nodes, span)) = self.value(equals).filter(|_| assigns) else {
+            return;
+        };
+        let Ok(expression) = parse_expression(&nodes) else {
+            return;
+        };
+        let type_of = |name: &str| {
+            self.lookup(name)
+                .map(|binding| match binding.type_name.trim() {
+                    "boolean" => "bool".to_string(),
+                    type_name => type_name.to_string(),
+                })
+                .filter(|type_name| !type_name.is_empty() && type_name != "any")
+        };
+        let Some(found) = static_type(&expression, &type_of) else {
+            return;
+        };
+        if found != "float" || expected != "int" {
+            return;
+        }
+        let message = format!(
+            "implicit conversion from `{}` to `{}` in strict mode",
+            found, expected
+        );
+        let label = format!(
+            "declared as `{}` here, convert the value with `as {}`",
+            expected, expected
+        );
+        self.error(IMPLICIT_CONVERSION, message, span);
+        self.errors
+            .last_mut()
+            .unwrap()
+            .labels
+            .push((declared, label));
+    }
+
+    ///
+    ///The expression with the consts it names replaced by their values
+    ///
+    fn fold(&self, expression: Expression) -> Expression {
+        let fold = |expression: Box<Expression>| Box::new(self.fold(*expression));
+        match expression {
+            Expression::Variable(name) => match self.lookup(&name) {
+                Some(Binding {
+                    kind: Kind::Const,
+                    value: Some(value),
+                    ..
+                }) => Expression::Value(value.clone()),
+                _ => Expression::Variable(name),
+            },
+            Expression::Unary(operator, operand) => Expression::Unary(operator, fold(operand)),
+            Expression::Cast(value, type_name) => Expression::Cast(fold(value), type_name),
+            Expression::Binary(left, operator, right) => {
+                Expression::Binary(fold(left), operator, fold(right))
+            }
+            Expression::Index(target, index) => Expression::Index(fold(target), fold(index)),
+            Expression::List(elements) => {
+                Expression::List(elements.into_iter().map(|e| self.fold(e)).collect())
+            }
+            Expression::Tuple(elements) => {
+                Expression::Tuple(elements.into_iter().map(|e| self.fold(e)).collect())
+            }
+            Expression::Map(entries) => Expression::Map(
+                entries
+                    .into_iter()
+                    .map(|(key, value)| (self.fold(key), self.fold(value)))
+                    .collect(),
+            ),
+            other => other,
+        }
+    }
+
+    ///
+    ///Checks x = value, x += value, x++ and the same for an element x[i] of a collection, the
+    ///variable at index starts the statement
+    ///
+    fn assignment(&mut self, index: usize) {
+        let (info, start) = self.tokens[index].clone();
+        let mut operator = index + 1;
+        let mut element = false;
+        while self
+            .tokens
+            .get(operator)
+            .is_some_and(|(next, _)| next.token == TokenTypes::LeftBracket)
+        {
+            let mut depth = 0;
+            while let Some((next, _)) = self.tokens.get(operator) {
+                match next.token {
+                    TokenTypes::LeftBracket => depth += 1,
+                    TokenTypes::RightBracket => depth -= 1,
+                    _ => {}
+                }
+                operator += 1;
+                if depth == 0 {
+                    break;
+                }
+            }
+            element = true;
+        }
+        let assigns = self.tokens.get(operator).is_some_and(|(next, _)| {
+            next.token == TokenTypes::AssignmentOperator
+                || (next.token == TokenTypes::Operator
+                    && matches!(next.value.as_str(), "++" | "--"))
+        });
+        if !assigns {
+            return;
+        }
+        let span = self.span(start, self.end_of(operator) - start);
+        let name = info.value;
+        if module_constant(&name).is_some() {
+            let message = format!("cannot assign to constant `{}`", name);
+            return self.error(ASSIGN_TO_CONST, message, span);
+        }
+
+        let Some(binding) = self.lookup(&name) else {
+            return;
+        };
+        let (code, message) = match (binding.kind, element) {
+            (Kind::Mutable, _) => {
+                if self.tokens[operator].0.value == "=" {
+                    let (type_name, declared) = (binding.type_name.clone(), binding.declared);
+                    if self.strict && !element {
+                        self.check_conversion(operator, &type_name, declared);
+                    }
+                    self.share(operator + 1, self.value_end(operator), &name);
+                }
+                return;
+            }
+            (Kind::Immutable, false) => (
+                ASSIGN_TO_IMMUTABLE,
+                format!("cannot assign to immutable variable `{}`", name),
+            ),
+            (Kind::Immutable, true) => (
+                ASSIGN_TO_IMMUTABLE,
+                format!(
+                    "cannot assign to an element of immutable variable `{}`",
+                    name
+                ),
+            ),
+            (Kind::Const, false) => (
+                ASSIGN_TO_CONST,
+                format!("cannot assign to constant `{}`", name),
+            ),
+            (Kind::Const, true) => (
+                ASSIGN_TO_CONST,
+                format!("cannot assign to an element of constant `{}`", name),
+            ),
+        };
+        let label = (binding.declared, binding.note.clone());
+        self.error(code, message, span);
+        self.errors.last_mut().unwrap().labels.push(label);
+    }
+
+    ///
+    ///Checks a call like items.push(4) that changes the collection it is called on
+    ///
+    fn method_call(&mut self, index: usize) {
+        let (info, start) = self.tokens[index].clone();
+        let Some((receiver, method)) = info.value.split_once('.') else {
+            return;
+        };
+        let Some(binding) = self.lookup(receiver) else {
+            return;
+        };
+        let mutates = match split_type(&binding.type_name).0.as_str() {
+            "array" => array_mutates(method),
+            "dict" => dictionary_mutates(method),
+            "set" => set_mutates(method),
+            _ => false,
+        };
+        let (code, what) = match binding.kind {
+            _ if !mutates => return,
+            Kind::Mutable if holds_collections(&binding.type_name) => {
+                return self.shared_arguments(index + 1, receiver)
+            }
+            Kind::Mutable => return,
+            Kind::Immutable => (ASSIGN_TO_IMMUTABLE, "immutable variable"),
+            Kind::Const => (ASSIGN_TO_CONST, "constant"),
+        };
+        let message = format!("cannot change {} `{}` with `{}`", what, receiver, method);
+        let label = (binding.declared, binding.note.clone());
+        let span = self.span(start, info.chars_read as usize);
+        self.error(code, message, span);
+        self.errors.last_mut().unwrap().labels.push(label);
+    }
+
+    ///
+    ///Checks the arguments in the ( at open of a call that changes the mutable receiver, none of
+    ///them can be an immutable collection the receiver would then hold
+    ///
+    fn shared_arguments(&mut self, open: usize, receiver: &str) {
+        if self
+            .tokens
+            .get(open)
+            .is_none_or(|(info, _)| info.token != TokenTypes::LeftParenthesis)
+        {
+            return;
+        }
+        let mut depth = 0;
+        let mut argument = open + 1;
+        for index in open..self.tokens.len() {
+            match self.tokens[index].0.token {
+                TokenTypes::LeftParenthesis | TokenTypes::LeftBracket | TokenTypes::LeftCurly => {
+                    depth += 1
+                }
+                TokenTypes::RightParenthesis
+                | TokenTypes::RightBracket
+                | TokenTypes::RightCurly => depth -= 1,
+                TokenTypes::ArgumentSeparator if depth == 1 => {
+                    self.share(argument, index, receiver);
+                    argument = index + 1;
+                }
+                _ => {}
+            }
+            if depth == 0 {
+                return self.share(argument, index, receiver);
+            }
+        }
+    }
+}
+
+///
+///Whether the elements of a collection of type_name can be collections, which a call that adds
+///one keeps rather than copies
+///
+fn holds_collections(type_name: &str) -> bool {
+    let (base, parameters) = split_type(type_name);
+    let element = match base.as_str() {
+        "array" | "set" => parameters.first(),
+        "dict" => parameters.get(1),
+        _ => None,
+    };
+    element.is_some_and(|element| {
+        matches!(
+            split_type(element).0.as_str(),
+            "array" | "dict" | "set" | "any"
+        )
+    })
+}
+
+///
+///Whether the script asks for strict mode with a // #![strict] comment
+///
+pub fn strict_pragma(source: &str) -> bool {
+    Lexer::standalone(source).any(|info| {
+        let text = info.value.strip_prefix("//").or_else(|| {
+            info.value
+                .strip_prefix("/*")
+                .and_then(|text| text.strip_suffix("*/"))
+        });
+        info.token == TokenTypes::Comment && text.is_some_and(|text| text.trim() == "#![strict]")
+    })
+}
+
+///
+///The errors in how the script changes its variables and consts, in the order they appear.
+///The script is expected to be free of syntax errors
+///
+pub fn check_bindings(source: &str) -> Vec<SyntaxError> {
+    let tokens = {
+        let mut lexer = Lexer::standalone(source);
+        let mut tokens = Vec::new();
+        while let Some(info) = lexer.next() {
+            if info.token != TokenTypes::Comment {
+                let start = lexer.position() - info.chars_read as usize;
+                tokens.push((info, start));
+            }
+        }
+        tokens
+    };
+    let mut checker = Checker {
+        chars: source.chars().collect(),
+        lines: line_starts(source),
+        tokens,
+        scopes: vec![Vec::new()],
+        pending: Vec::new(),
+        errors: Vec::new(),
+        strict: unsafe { STRICT } || strict_pragma(source),
+        returns: HashMap::new(),
+        function: None,
+    };
+    checker.check();
+    checker.errors
+}
+
+#[cfg(test)]
+mod bindings_tests {
+    use super::check_bindings;
+
+    fn errors(source: &str) -> Vec<(usize, usize, &'static str, String)> {
+        check_bindings(source)
+            .into_iter()
+            .map(|e| (e.span.line, e.span.column, e.code, e.message))
+            .collect()
+    }
+
+    #[test]
+    fn test_immutable_bindings() {
+        let source = "let a: int = 1;\nlet mut b: int = 2;\na = 3;\nb += a;\na++;\n";
+        assert_eq!(
+            errors(source),
+            vec![
+                (
+                    3,
+                    1,
+                    "E0009",
+                    "cannot assign to immutable variable `a`".to_string()
+                ),
+                (
+                    5,
+                    1,
+                    "E0009",
+                    "cannot assign to immutable variable `a`".to_string()
+                ),
+            ]
+        );
+        let error = &check_bindings(source)[0];
+        assert_eq!((error.span.length, error.labels[0].0.column), (3, 5));
+
+        // a let mut in an inner block hides the outer let until the block ends
+        let source =
+            "let x: int = 1;\nif (x > 0) {\n    let mut x: int = 2;\n    x = 3;\n}\nx = 4;\n";
+        assert_eq!(
+            errors(source),
+            vec![(
+                6,
+                1,
+                "E0009",
+                "cannot assign to immutable variable `x`".to_string()
+            )]
+        );
+
+        let source = "let xs: array<int> = [1];\nxs[0] = 2;\nxs.push(3);\nlet mut ys: array<int> = [];\nys.push(1);\necholn(xs.len());\n";
+        assert_eq!(
+            errors(source),
+            vec![
+                (
+                    2,
+                    1,
+                    "E0009",
+                    "cannot assign to an element of immutable variable `xs`".to_string()
+                ),
+                (
+                    3,
+                    1,
+                    "E0009",
+                    "cannot change immutable variable `xs` with `push`".to_string()
+                ),
+            ]
+        );
+
+        let source = "func f(n: int) -> int {\n    n++;\n    return n;\n}\nfor (i in 0..3) {\n    i = 2;\n}\n";
+        assert_eq!(
+            errors(source)
+                .into_iter()
+                .map(|(line, _, code, _)| (line, code))
+                .collect::<Vec<_>>(),
+            vec![(2, "E0009"), (6, "E0009")]
+        );
+    }
+
+    #[test]
+    fn test_shared_collections() {
+        let source = "let xs: array<int> = [1];
+let grid: array<array<int>> = [[1]];
+let mut ys: array<int> = xs;
+let mut zs: array<int> = xs.copy();
+ys = xs;
+let mut row: array<int> = grid[0];
+let mut n: int = xs[0];
+let mut rows: array<array<int>> = [];
+rows.push(xs);
+rows[0] = zs;
+let mut counts: dict<string, int> = {};
+let more: dict<string, int> = {\"a\" => 1};
+counts.merge(more);
+";
+        assert_eq!(
+            errors(source),
+            vec![
+                (
+                    3,
+                    26,
+                    "E0009",
+                    "cannot share immutable variable `xs` with mutable `ys`, give it `xs.copy()`"
+                        .to_string()
+                ),
+                (
+                    5,
+                    6,
+                    "E0009",
+                    "cannot share immutable variable `xs` with mutable `ys`, give it `xs.copy()`"
+                        .to_string()
+                ),
+                (
+                    6,
+                    27,
+                    "E0009",
+                    "cannot share immutable variable `grid` with mutable `row`, give it `grid[0].copy()`"
+                        .to_string()
+                ),
+                (
+                    9,
+                    11,
+                    "E0009",
+                    "cannot share immutable variable `xs` with mutable `rows`, give it `xs.copy()`"
+                        .to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_shared_through_literals_and_calls() {
+        let source = "const A: array<int> = [1];
+let a: array<int> = [2];
+let mut g: array<array<int>> = [A];
+let mut d: dict<string, array<int>> = {\"k\" => a};
+let mut t: (array<int>, int) = (a, 1);
+func id(x: array<int>) -> array<int> {
+    return x;
+}
+func fresh() -> array<int> {
+    let xs: array<int> = [3];
+    return xs;
+}
+func fixed() -> array<int> {
+    return A;
+}
+let mut b: array<int> = id(a);
+let mut c: array<int> = id(a.copy());
+let mut e: array<int> = fresh();
+let mut f: array<int> = fixed();
+g.push([a]);
+";
+        assert_eq!(
+            errors(source)
+                .into_iter()
+                .map(|(line, column, code, _)| (line, column, code))
+                .collect::<Vec<_>>(),
+            vec![
+                (3, 33, "E0010"),
+                (4, 47, "E0009"),
+                (5, 33, "E0009"),
+                (16, 28, "E0009"),
+                (19, 25, "E0010"),
+                (20, 9, "E0009"),
+            ]
+        );
+        assert_eq!(
+            errors(source)[4].3,
+            "cannot share constant `A` with mutable `f`, give it `fixed().copy()`"
+        );
+    }
+
+    #[test]
+    fn test_consts() {
+        let source =
+            "const N: int = 2 * 3;\nconst AREA: float = math.PI * N * N;\nN = 1;\nmath.PI = 3.0;\n";
+        assert_eq!(
+            errors(source),
+            vec![
+                (3, 1, "E0010", "cannot assign to constant `N`".to_string()),
+                (
+                    4,
+                    1,
+                    "E0010",
+                    "cannot assign to constant `math.PI`".to_string()
+                ),
+            ]
+        );
+
+        let source = "let n: int = 2;\nconst A: int = n + 1;\nconst B: int = 1 / 0;\nconst C: string = 1;\nconst D: array<int> = [1, 2];\nD.push(3);\n";
+        assert_eq!(
+            errors(source),
+            vec![
+                (
+                    2,
+                    16,
+                    "E0011",
+                    "the value of constant `A` has to be known before the script runs".to_string()
+                ),
+                (
+                    3,
+                    16,
+                    "E0011",
+                    "the value of constant `B` cannot be worked out: Division by zero".to_string()
+                ),
+                (
+                    4,
+                    19,
+                    "E0011",
+                    "expected `string` for constant `C`, found `int`".to_string()
+                ),
+                (
+                    6,
+                    1,
+                    "E0010",
+                    "cannot change constant `D` with `push`".to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_strict_conversions() {
+        let source = "let a: float = 2.5;
+let n: int = 1;
+let b: int = a;
+let c: float = n * 2;
+let d: int = a as int;
+let e: string = n;
+const K: int = math.PI as int;
+";
+        // without strict mode the conversions are left to run, and an int given to a string is
+        // a type error when it runs in either mode
+        assert_eq!(errors(source), vec![]);
+        let strict = format!("// #![strict]\n{}", source);
+        assert_eq!(
+            errors(&strict),
+            vec![(
+                4,
+                14,
+                "E0012",
+                "implicit conversion from `float` to `int` in strict mode".to_string()
+            ),]
+        );
+    }
+}
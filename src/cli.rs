@@ -0,0 +1,460 @@
+/*
+* This file reads the command line of the jist binary into a Command. The script path may be
+* - to read the script from stdin, and -e 'code' runs a one-liner. A bare script path is the
+* same as jist run, so `jist script.jist` keeps working
+*/
+use crate::diagnostic::{ColorChoice, ErrorFormat};
+use crate::state_dump::DumpFormat;
+
+pub const USAGE: &str = "\
+Usage: jist [run] [--dump-state[=json]] <script.jist | - | -e code> [arguments...]
+       jist <command> <script.jist | - | -e code>
+       jist fmt [--check | --write] <script.jist | - | -e code>...
+       jist lint [--format=json] <script.jist | - | -e code>
+       jist debug <script.jist | -e code> [arguments...]
+       jist debug --dap
+       jist repl
+       jist lsp
+
+Commands:
+    run        Run a script, the arguments after it are passed to args()
+    check      Parse and type-check a script without running it
+    tokens     Print the tokens of every statement
+    ast        Print the syntax tree of every statement
+    highlight  Print the script with syntax highlighting
+    fmt        Print the script in the canonical style, --check fails when a script is not
+               formatted and --write formats the scripts in place
+    lint       Report likely mistakes such as unused variables or unreachable code, as text or
+               with --format=json as JSON
+    debug      Run a script stopping at breakpoints and between statements, reading debugger
+               commands from stdin, or with --dap serve an editor over the Debug Adapter
+               Protocol on stdin and stdout
+    repl       Read and run statements interactively
+    lsp        Run a language server for editors, speaking LSP over stdin and stdout
+
+Options:
+    -e <code>             Use code as the script
+    -                     Read the script from stdin
+    --dump-state[=json]   Print the variables and functions left after running, as text or JSON
+    --trace[=categories]  Trace what the interpreter does on stderr, for tokenizer, parser,
+                          runtime, gc or all, each with an optional :level of error, warn,
+                          info, debug or trace. JIST_TRACE takes the same setting
+    --trace-file=<path>   Write the trace to a file instead, also read from JIST_TRACE_FILE
+    --color=<when>        Colour errors always, never, or on auto when printing to a terminal
+    --error-format=json   Print each error as a line of JSON, for tools
+    --strict              Only convert a value to another type with as, the same as a
+                          // #![strict] comment in the script
+    -h, --help            Print this help
+    -V, --version         Print the version";
+
+///
+///Where the script comes from
+///
+#[derive(Debug, Clone, PartialEq)]
+pub enum Source {
+    Path(String),
+    Stdin,
+    Inline(String),
+}
+
+///
+///What jist fmt does with the formatted script
+///
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FmtMode {
+    Print,
+    Check,
+    Write,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Command {
+    Run {
+        source: Source,
+        dump: Option<DumpFormat>,
+        arguments: Vec<String>,
+    },
+    Check(Source),
+    Tokens(Source),
+    Ast(Source),
+    Highlight(Source),
+    Fmt {
+        sources: Vec<Source>,
+        mode: FmtMode,
+    },
+    Lint {
+        source: Source,
+        json: bool,
+    },
+    Debug {
+        source: Source,
+        arguments: Vec<String>,
+    },
+    DebugAdapter,
+    Repl,
+    Lsp,
+    Help,
+    Version,
+}
+
+const COMMANDS: [&str; 11] = [
+    "run",
+    "check",
+    "tokens",
+    "ast",
+    "highlight",
+    "fmt",
+    "lint",
+    "debug",
+    "repl",
+    "lsp",
+    "help",
+];
+
+///
+///The settings for every command given on the command line: tracing, how errors are printed
+///and strict mode
+///
+#[derive(Debug, Clone, PartialEq)]
+pub struct GlobalOptions {
+    pub levels: Option<String>,
+    pub file: Option<String>,
+    pub color: ColorChoice,
+    pub error_format: ErrorFormat,
+    pub strict: bool,
+}
+
+impl Default for GlobalOptions {
+    fn default() -> Self {
+        GlobalOptions {
+            levels: None,
+            file: None,
+            color: ColorChoice::Auto,
+            error_format: ErrorFormat::Human,
+            strict: false,
+        }
+    }
+}
+
+///
+///Takes --trace, --trace-file, --color, --error-format and --strict out of the options in front
+///of the script, which may come before or after the command name. The arguments after the
+///script belong to it and are kept
+///
+pub fn take_global_options(arguments: &[String]) -> Result<(GlobalOptions, Vec<String>), String> {
+    let mut options = GlobalOptions::default();
+    let mut rest = Vec::new();
+    for (i, argument) in arguments.iter().enumerate() {
+        if argument == "--trace" {
+            options.levels = Some("all".to_string());
+        } else if let Some(levels) = argument.strip_prefix("--trace=") {
+            options.levels = Some(levels.to_string());
+        } else if let Some(file) = argument.strip_prefix("--trace-file=") {
+            options.file = Some(file.to_string());
+        } else if let Some(color) = argument.strip_prefix("--color=") {
+            options.color = ColorChoice::parse(color)?;
+        } else if let Some(format) = argument.strip_prefix("--error-format=") {
+            options.error_format = ErrorFormat::parse(format)?;
+        } else if argument == "--strict" {
+            options.strict = true;
+        } else {
+            rest.push(argument.clone());
+            let is_command = rest.len() == 1 && COMMANDS.contains(&argument.as_str());
+            let is_option = argument.starts_with("--");
+            if !is_command && !is_option {
+                rest.extend_from_slice(&arguments[i + 1..]);
+                break;
+            }
+        }
+    }
+    Ok((options, rest))
+}
+
+///
+///Reads the source of a command, which is the next argument or -e with the code after it
+///
+fn read_source(arguments: &[String], position: &mut usize) -> Result<Source, String> {
+    let source = match arguments.get(*position).map(String::as_str) {
+        None => return Err("No file path provided".to_string()),
+        Some("-") => Source::Stdin,
+        Some("-e") => {
+            *position += 1;
+            match arguments.get(*position) {
+                Some(code) => Source::Inline(code.clone()),
+                None => return Err("-e needs the code to run".to_string()),
+            }
+        }
+        Some(flag) if flag.starts_with('-') => return Err(format!("Unknown option '{}'", flag)),
+        Some(path) => Source::Path(path.to_string()),
+    };
+    *position += 1;
+    Ok(source)
+}
+
+///
+///Parses the arguments after the binary name
+///
+pub fn parse_arguments(arguments: &[String]) -> Result<Command, String> {
+    let mut position = 0;
+    let command = match arguments.first().map(String::as_str) {
+        None | Some("-h") | Some("--help") | Some("help") => return Ok(Command::Help),
+        Some("-V") | Some("--version") => return Ok(Command::Version),
+        Some("repl") => return Ok(Command::Repl),
+        Some("lsp") => return Ok(Command::Lsp),
+        Some(
+            command @ ("run" | "check" | "tokens" | "ast" | "highlight" | "fmt" | "lint" | "debug"),
+        ) => {
+            position += 1;
+            command
+        }
+        // a bare script path is run
+        Some(_) => "run",
+    };
+
+    if command == "fmt" {
+        return parse_fmt(&arguments[position..]);
+    }
+
+    if command == "lint" {
+        let json = arguments.get(position).map(String::as_str) == Some("--format=json");
+        if json {
+            position += 1;
+        }
+        let source = read_source(arguments, &mut position)?;
+        if let Some(extra) = arguments.get(position) {
+            return Err(format!("Unexpected argument '{}' after the script", extra));
+        }
+        return Ok(Command::Lint { source, json });
+    }
+
+    if command == "debug" {
+        if arguments.get(position).map(String::as_str) == Some("--dap") {
+            if let Some(extra) = arguments.get(position + 1) {
+                return Err(format!("Unexpected argument '{}' after --dap", extra));
+            }
+            return Ok(Command::DebugAdapter);
+        }
+        // the debugger commands are read from stdin
+        if arguments.get(position).map(String::as_str) == Some("-") {
+            return Err(
+                "debug reads its commands from stdin, give a script path or -e".to_string(),
+            );
+        }
+        let source = read_source(arguments, &mut position)?;
+        return Ok(Command::Debug {
+            source,
+            arguments: arguments[position..].to_vec(),
+        });
+    }
+
+    if command != "run" {
+        let source = read_source(arguments, &mut position)?;
+        if let Some(extra) = arguments.get(position) {
+            return Err(format!("Unexpected argument '{}' after the script", extra));
+        }
+        return Ok(match command {
+            "check" => Command::Check(source),
+            "tokens" => Command::Tokens(source),
+            "ast" => Command::Ast(source),
+            _ => Command::Highlight(source),
+        });
+    }
+
+    // flags come before the script, everything after it belongs to the script
+    let mut dump = None;
+    while let Some(format) = arguments.get(position).and_then(|a| DumpFormat::parse(a)) {
+        dump = Some(format);
+        position += 1;
+    }
+    let source = read_source(arguments, &mut position)?;
+    Ok(Command::Run {
+        source,
+        dump,
+        arguments: arguments[position..].to_vec(),
+    })
+}
+
+///
+///jist fmt takes any number of scripts, with --check or --write anywhere among them
+///
+fn parse_fmt(arguments: &[String]) -> Result<Command, String> {
+    let mut mode = FmtMode::Print;
+    let mut sources = Vec::new();
+    let mut position = 0;
+    while let Some(argument) = arguments.get(position) {
+        let flag = match argument.as_str() {
+            "--check" => FmtMode::Check,
+            "--write" => FmtMode::Write,
+            _ => {
+                sources.push(read_source(arguments, &mut position)?);
+                continue;
+            }
+        };
+        if mode != FmtMode::Print && mode != flag {
+            return Err("fmt takes --check or --write, not both".to_string());
+        }
+        mode = flag;
+        position += 1;
+    }
+
+    if sources.is_empty() {
+        return Err("No file path provided".to_string());
+    }
+    if mode == FmtMode::Print && sources.len() > 1 {
+        return Err("fmt prints one script, use --check or --write for several".to_string());
+    }
+    if mode == FmtMode::Write && sources.iter().any(|s| !matches!(s, Source::Path(_))) {
+        return Err("fmt --write needs script paths".to_string());
+    }
+    Ok(Command::Fmt { sources, mode })
+}
+
+#[cfg(test)]
+mod cli_tests {
+    use super::{parse_arguments, take_global_options, Command, FmtMode, GlobalOptions, Source};
+    use crate::diagnostic::{ColorChoice, ErrorFormat};
+    use crate::state_dump::DumpFormat;
+
+    fn parse(arguments: &[&str]) -> Result<Command, String> {
+        let arguments: Vec<String> = arguments.iter().map(|a| a.to_string()).collect();
+        parse_arguments(&arguments)
+    }
+
+    #[test]
+    fn test_parse_run() {
+        assert_eq!(
+            parse(&["script.jist", "a", "-e"]),
+            Ok(Command::Run {
+                source: Source::Path("script.jist".to_string()),
+                dump: None,
+                arguments: vec!["a".to_string(), "-e".to_string()],
+            })
+        );
+        assert_eq!(
+            parse(&["run", "--dump-state=json", "-", "x"]),
+            Ok(Command::Run {
+                source: Source::Stdin,
+                dump: Some(DumpFormat::Json),
+                arguments: vec!["x".to_string()],
+            })
+        );
+        assert_eq!(
+            parse(&["-e", "echo(1);"]),
+            Ok(Command::Run {
+                source: Source::Inline("echo(1);".to_string()),
+                dump: None,
+                arguments: Vec::new(),
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_commands() {
+        assert_eq!(
+            parse(&["check", "a.jist"]),
+            Ok(Command::Check(Source::Path("a.jist".to_string())))
+        );
+        assert_eq!(
+            parse(&["tokens", "-e", "let a: int = 1;"]),
+            Ok(Command::Tokens(Source::Inline(
+                "let a: int = 1;".to_string()
+            )))
+        );
+        assert_eq!(parse(&[]), Ok(Command::Help));
+        assert_eq!(parse(&["--version"]), Ok(Command::Version));
+        assert_eq!(parse(&["repl"]), Ok(Command::Repl));
+        assert_eq!(parse(&["lsp"]), Ok(Command::Lsp));
+        assert!(parse(&["ast"]).is_err());
+        assert!(parse(&["fmt", "a.jist", "b.jist"]).is_err());
+        assert_eq!(
+            parse(&["fmt", "a.jist", "--check", "b.jist"]),
+            Ok(Command::Fmt {
+                sources: vec![
+                    Source::Path("a.jist".to_string()),
+                    Source::Path("b.jist".to_string())
+                ],
+                mode: FmtMode::Check,
+            })
+        );
+        assert!(parse(&["fmt", "--write", "-"]).is_err());
+        assert!(parse(&["fmt", "--check", "--write", "a.jist"]).is_err());
+        assert!(parse(&["run", "--verbose", "a.jist"]).is_err());
+        assert_eq!(
+            parse(&["lint", "--format=json", "a.jist"]),
+            Ok(Command::Lint {
+                source: Source::Path("a.jist".to_string()),
+                json: true,
+            })
+        );
+        assert!(parse(&["lint", "--format=xml", "a.jist"]).is_err());
+        assert_eq!(
+            parse(&["debug", "a.jist", "x"]),
+            Ok(Command::Debug {
+                source: Source::Path("a.jist".to_string()),
+                arguments: vec!["x".to_string()],
+            })
+        );
+        assert_eq!(parse(&["debug", "--dap"]), Ok(Command::DebugAdapter));
+        assert!(parse(&["debug", "-"]).is_err());
+        assert!(parse(&["debug", "--dap", "a.jist"]).is_err());
+    }
+
+    #[test]
+    fn test_take_global_options() {
+        let arguments =
+            |list: &[&str]| -> Vec<String> { list.iter().map(|a| a.to_string()).collect() };
+        assert_eq!(
+            take_global_options(&arguments(&[
+                "--trace=runtime",
+                "run",
+                "--trace-file=trace.log",
+                "a.jist",
+                "--trace"
+            ])),
+            Ok((
+                GlobalOptions {
+                    levels: Some("runtime".to_string()),
+                    file: Some("trace.log".to_string()),
+                    ..GlobalOptions::default()
+                },
+                arguments(&["run", "a.jist", "--trace"])
+            ))
+        );
+        assert_eq!(
+            take_global_options(&arguments(&["check", "--trace", "-e", "--trace"])),
+            Ok((
+                GlobalOptions {
+                    levels: Some("all".to_string()),
+                    ..GlobalOptions::default()
+                },
+                arguments(&["check", "-e", "--trace"])
+            ))
+        );
+        assert_eq!(
+            take_global_options(&arguments(&["a.jist", "--trace"]))
+                .unwrap()
+                .1,
+            arguments(&["a.jist", "--trace"])
+        );
+        assert_eq!(
+            take_global_options(&arguments(&[
+                "--color=always",
+                "check",
+                "--error-format=json",
+                "--strict",
+                "a.jist"
+            ])),
+            Ok((
+                GlobalOptions {
+                    color: ColorChoice::Always,
+                    error_format: ErrorFormat::Json,
+                    strict: true,
+                    ..GlobalOptions::default()
+                },
+                arguments(&["check", "a.jist"])
+            ))
+        );
+        assert!(take_global_options(&arguments(&["--color=sometimes", "a.jist"])).is_err());
+        assert!(take_global_options(&arguments(&["--error-format=xml", "a.jist"])).is_err());
+    }
+}
@@ -1,27 +1,25 @@
-use crate::collection::collections::Array;
-use crate::collection::collections::Dictionary;
-use lazy_static::lazy_static;
 /// this file will store code for collections
-/// there are 2 types, arrays and dictoinaries
+/// there are 4 types, arrays, dictoinaries, sets and tuples
 /// arrays are unordered collections of variable amounts, can store one type of data declared at initialization
 /// dictionaries are ordered collections of key value pairs, keys are one type, values are another type or the same
 /// declared at runtime
+/// sets hold each value once, in the order they were added
+/// tuples hold a fixed number of values that can each have their own type, like (int, string)
 /// arrays are declared with [a, b, c]
 /// dictionaries are declared with {a=>1, b=>2, c=>3}
+/// sets are declared from an array, let s: set<int> = [1, 2, 3]
+/// tuples are declared with (a, b)
+/// collections are ordinary values, so they can hold each other like array<dict<string, any>>
+/// arrays, dictionaries and sets are shared, let b = a gives the same array a second name, see heap.rs
 ///
-use std::sync::Mutex;
-
-lazy_static! {
-    pub static ref ARRAY_STACK: Mutex<Vec<Array>> = Mutex::new(Vec::new());
-    pub static ref DICTIONARY_STACK: Mutex<Vec<Dictionary>> = Mutex::new(Vec::new());
-}
-
 pub mod collections {
-    use crate::base_variable::base_types::BaseTypes;
+    use crate::base_variable::base_types::{BaseTypes, GetType};
+    use crate::heap::visit;
     //use crate::node::nodes::ASTNode;
+    use std::collections::HashMap;
     use std::fmt;
 
-    #[derive(Clone, Debug)]
+    #[derive(Clone)]
     pub struct Array {
         pub name: String,
         pub data: Vec<BaseTypes>,
@@ -41,6 +39,319 @@ pub mod collections {
         }
     }
 
+    // collections can hold themselves, so their fields are only written the first time
+    impl fmt::Debug for Array {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            visit((self as *const Array as usize, 0), || {
+                f.debug_struct("Array")
+                    .field("name", &self.name)
+                    .field("data", &self.data)
+                    .field("value_type", &self.value_type)
+                    .finish()
+            })
+            .unwrap_or_else(|| write!(f, "Array {{ .. }}"))
+        }
+    }
+
+    ///
+    ///Splits a type name like dict<string, array<int>> into dict and its parameters. A tuple
+    ///type like (int, string) comes back as tuple
+    ///
+    pub fn split_type(type_name: &str) -> (String, Vec<String>) {
+        let type_name = type_name.trim();
+        let (base, inner) = match type_name.find('<') {
+            _ if type_name.starts_with('(') && type_name.ends_with(')') => {
+                ("tuple", &type_name[1..type_name.len() - 1])
+            }
+            Some(i) if type_name.ends_with('>') => {
+                (&type_name[..i], &type_name[i + 1..type_name.len() - 1])
+            }
+            _ => return (type_name.to_string(), Vec::new()),
+        };
+
+        let mut parameters = Vec::new();
+        let mut depth = 0;
+        let mut current = String::new();
+        for c in inner.chars() {
+            match c {
+                '<' | '(' => depth += 1,
+                '>' | ')' => depth -= 1,
+                ',' if depth == 0 => {
+                    parameters.push(normalize_type(&current));
+                    current.clear();
+                    continue;
+                }
+                _ => {}
+            }
+            current.push(c);
+        }
+        if !current.trim().is_empty() {
+            parameters.push(normalize_type(&current));
+        }
+        (base.trim().to_string(), parameters)
+    }
+
+    ///
+    ///Writes a type name the one way it is displayed, dict<string,array< int >> becomes
+    ///dict<string, array<int>>
+    ///
+    pub fn normalize_type(type_name: &str) -> String {
+        let (base, parameters) = split_type(type_name);
+        if parameters.is_empty() {
+            base
+        } else if base == "tuple" {
+            format!("({})", parameters.join(", "))
+        } else {
+            format!("{}<{}>", base, parameters.join(", "))
+        }
+    }
+
+    ///
+    ///The type name a collection declared to hold this value would use, like int for 1 or
+    ///array<int> for [1]
+    ///
+    pub fn type_name_of(value: &BaseTypes) -> String {
+        match value {
+            BaseTypes::Int(_) => "int".to_string(),
+            BaseTypes::Float(_) => "float".to_string(),
+            BaseTypes::StringWrapper(_) => "string".to_string(),
+            BaseTypes::Bool(_) => "bool".to_string(),
+            BaseTypes::Char(_) => "char".to_string(),
+            BaseTypes::Array(a) => format!("array<{}>", a.borrow().value_type),
+            BaseTypes::Dictionary(d) => {
+                let d = d.borrow();
+                format!("dict<{}, {}>", d.types.0, d.types.1)
+            }
+            BaseTypes::Set(s) => format!("set<{}>", s.borrow().value_type),
+            BaseTypes::Tuple(values) => {
+                let types: Vec<String> = values.iter().map(type_name_of).collect();
+                format!("({})", types.join(", "))
+            }
+            BaseTypes::Lines(_) => "lines".to_string(),
+            BaseTypes::Null => "null".to_string(),
+        }
+    }
+
+    ///
+    ///Gives a collection the name it is shown as. Only the first name sticks, so after
+    ///let b = a the array is still shown as a
+    ///
+    pub fn name_collection(value: &BaseTypes, name: &str) {
+        let current = match value {
+            BaseTypes::Array(array) => &mut array.borrow_mut().name,
+            BaseTypes::Dictionary(dictionary) => &mut dictionary.borrow_mut().name,
+            BaseTypes::Set(set) => &mut set.borrow_mut().name,
+            _ => return,
+        };
+        if current.is_empty() {
+            *current = name.to_string();
+        }
+    }
+
+    pub fn value_type_of(value: &BaseTypes) -> BaseTypes {
+        type_name_of(value).into()
+    }
+
+    ///
+    ///The element type for a list of values, any when they differ or there are none
+    ///
+    pub fn common_type<'a>(values: impl Iterator<Item = &'a BaseTypes>) -> BaseTypes {
+        let mut common: Option<String> = None;
+        for value in values {
+            let name = type_name_of(value);
+            match &common {
+                None => common = Some(name),
+                Some(common) if *common == name => {}
+                Some(_) => return "any".into(),
+            }
+        }
+        common.unwrap_or_else(|| "any".to_string()).into()
+    }
+
+    ///
+    ///Checks a value against a declared type name such as int or array<dict<string, any>>,
+    ///widening ints when floats are expected. A collection that already has the declared
+    ///element types comes back as itself, otherwise as a new collection tagged with them. The
+    ///value is handed back when it does not fit
+    ///
+    pub fn check_type(value_type: &BaseTypes, value: BaseTypes) -> Result<BaseTypes, BaseTypes> {
+        let value_type = match value_type {
+            BaseTypes::StringWrapper(value_type) => value_type.as_str(),
+            _ => return Ok(value),
+        };
+        let (base, parameters) = split_type(value_type);
+        match (base.as_str(), value) {
+            ("any", value) => Ok(value),
+            ("float", BaseTypes::Int(i)) => Ok(BaseTypes::Float(i.into())),
+            ("int", value @ BaseTypes::Int(_))
+            | ("float", value @ BaseTypes::Float(_))
+            | ("string", value @ BaseTypes::StringWrapper(_))
+            | ("char", value @ BaseTypes::Char(_))
+            | ("bool" | "boolean", value @ BaseTypes::Bool(_))
+            | ("lines", value @ BaseTypes::Lines(_)) => Ok(value),
+            ("array", BaseTypes::Array(array)) => match parameters.as_slice() {
+                [] => Ok(BaseTypes::Array(array)),
+                [element_type] => {
+                    let element_type: BaseTypes = element_type.clone().into();
+                    // the elements of a typed array already fit its type
+                    if array.borrow().value_type == element_type {
+                        return Ok(BaseTypes::Array(array));
+                    }
+                    // otherwise the elements are checked into a new array, the old one keeps
+                    // its own type
+                    let checked = {
+                        let array = array.borrow();
+                        let mut data = Vec::with_capacity(array.data.len());
+                        for item in &array.data {
+                            match check_type(&element_type, item.clone()) {
+                                Ok(item) => data.push(item),
+                                Err(_) => break,
+                            }
+                        }
+                        (data.len() == array.data.len())
+                            .then(|| Array::new(String::new(), element_type, data))
+                    };
+                    match checked {
+                        Some(checked) => Ok(checked.into()),
+                        None => Err(BaseTypes::Array(array)),
+                    }
+                }
+                _ => Err(BaseTypes::Array(array)),
+            },
+            ("dict", BaseTypes::Dictionary(dictionary)) => match parameters.as_slice() {
+                [] => Ok(BaseTypes::Dictionary(dictionary)),
+                [key_type, value_type] => {
+                    let types: (BaseTypes, BaseTypes) =
+                        (key_type.clone().into(), value_type.clone().into());
+                    if dictionary.borrow().types == types {
+                        return Ok(BaseTypes::Dictionary(dictionary));
+                    }
+                    let checked = {
+                        let dictionary = dictionary.borrow();
+                        let mut entries = Vec::with_capacity(dictionary.len());
+                        for (key, value) in dictionary.entries() {
+                            match (
+                                check_type(&types.0, key.clone()),
+                                check_type(&types.1, value.clone()),
+                            ) {
+                                (Ok(key), Ok(value)) => entries.push((key, value)),
+                                _ => break,
+                            }
+                        }
+                        (entries.len() == dictionary.len())
+                            .then(|| Dictionary::new(String::new(), types.0, types.1, entries))
+                    };
+                    match checked {
+                        Some(checked) => Ok(checked.into()),
+                        None => Err(BaseTypes::Dictionary(dictionary)),
+                    }
+                }
+                _ => Err(BaseTypes::Dictionary(dictionary)),
+            },
+            ("set", BaseTypes::Set(set)) => match parameters.as_slice() {
+                [] => Ok(BaseTypes::Set(set)),
+                [element_type] => {
+                    let element_type: BaseTypes = element_type.clone().into();
+                    if set.borrow().value_type == element_type {
+                        return Ok(BaseTypes::Set(set));
+                    }
+                    let values = set.borrow().values().to_vec();
+                    match to_set(element_type, values) {
+                        Some(checked) => Ok(checked.into()),
+                        None => Err(BaseTypes::Set(set)),
+                    }
+                }
+                _ => Err(BaseTypes::Set(set)),
+            },
+            // a set is declared from an array, repeated values are dropped
+            ("set", BaseTypes::Array(array)) => {
+                let (value_type, data) = {
+                    let array = array.borrow();
+                    (array.value_type.clone(), array.data.clone())
+                };
+                let element_type: BaseTypes = match parameters.as_slice() {
+                    [] => value_type,
+                    [element_type] => element_type.clone().into(),
+                    _ => return Err(BaseTypes::Array(array)),
+                };
+                match to_set(element_type, data) {
+                    Some(set) => Ok(set.into()),
+                    None => Err(BaseTypes::Array(array)),
+                }
+            }
+            ("tuple", BaseTypes::Tuple(values)) => {
+                if parameters.len() != values.len() {
+                    return Err(BaseTypes::Tuple(values));
+                }
+                let mut checked = Vec::with_capacity(values.len());
+                for (element_type, value) in parameters.iter().zip(&values) {
+                    match check_type(&element_type.clone().into(), value.clone()) {
+                        Ok(value) => checked.push(value),
+                        Err(_) => return Err(BaseTypes::Tuple(values)),
+                    }
+                }
+                Ok(BaseTypes::Tuple(checked))
+            }
+            (_, value) => Err(value),
+        }
+    }
+
+    fn to_set(value_type: BaseTypes, values: Vec<BaseTypes>) -> Option<Set> {
+        let mut set = Set::new(String::new(), value_type, Vec::new());
+        for value in values {
+            set.add(set.check_value(value).ok()?);
+        }
+        Some(set)
+    }
+
+    ///
+    ///Checks that a value can be hashed, which is what dictionary keys and set elements need.
+    ///Only ints, floats, strings, chars and bools can be, and NaN never can since it is not
+    ///equal to itself
+    ///
+    fn check_hashable(value: &BaseTypes, usage: &str) -> Result<(), String> {
+        match value {
+            BaseTypes::Float(f) if f.is_nan() => {
+                Err(format!("Runtime Error: NaN cannot be used as a {}", usage))
+            }
+            BaseTypes::Int(_)
+            | BaseTypes::Float(_)
+            | BaseTypes::StringWrapper(_)
+            | BaseTypes::Char(_)
+            | BaseTypes::Bool(_) => Ok(()),
+            other => Err(format!(
+                "Runtime Error: {} cannot be used as a {}",
+                other.GetType(),
+                usage
+            )),
+        }
+    }
+
+    ///
+    ///Checks that the key type of every dictionary and the element type of every set in a
+    ///declared type is one check_hashable takes, any is left to the values themselves
+    ///
+    pub fn check_declared_type(type_name: &str) -> Result<(), String> {
+        let (base, parameters) = split_type(type_name);
+        let hashed = match (base.as_str(), parameters.as_slice()) {
+            ("dict", [key, _]) => Some((key, "dictionary key")),
+            ("set", [element]) => Some((element, "set element")),
+            _ => None,
+        };
+        if let Some((hashed, usage)) = hashed {
+            let hashable = ["int", "float", "string", "char", "bool", "boolean", "any"];
+            if !hashable.contains(&hashed.as_str()) {
+                return Err(format!(
+                    "Type Error: {} cannot be used as a {}",
+                    hashed, usage
+                ));
+            }
+        }
+        parameters
+            .iter()
+            .try_for_each(|parameter| check_declared_type(parameter))
+    }
+
     // functions for arrays: new, push, pop, remove, get(i), set(i), to_string
     impl Array {
         pub fn new(name: String, value_type: BaseTypes, data: Vec<BaseTypes>) -> Array {
@@ -51,6 +362,20 @@ pub mod collections {
             }
         }
 
+        ///
+        ///Checks that a value can be stored in this array, ints are widened when the array
+        ///holds floats. Arrays without a declared type accept anything
+        ///
+        pub fn check_value(&self, value: BaseTypes) -> Result<BaseTypes, String> {
+            check_type(&self.value_type, value).map_err(|value| {
+                format!(
+                    "Runtime Error: Cannot store {} in Array<{}>",
+                    value.GetType(),
+                    self.value_type
+                )
+            })
+        }
+
         pub fn push(&mut self, value: BaseTypes) {
             self.data.push(value);
         }
@@ -68,7 +393,7 @@ pub mod collections {
             if index < self.data.len() {
                 Some(self.data.remove(index));
             } else {
-                println!("Syntax Error, during removal")
+                eprintln!("Syntax Error, during removal")
             }
         }
 
@@ -85,10 +410,16 @@ pub mod collections {
         }
     }
 
-    #[derive(Clone, Debug)]
+    ///
+    ///A dictionary keeps its entries in insertion order and finds them through a hash index,
+    ///so lookups don't have to scan the entries. Adding an existing key replaces its value.
+    ///A removed entry leaves a gap, the gaps are closed once there are more of them than entries
+    ///
+    #[derive(Clone)]
     pub struct Dictionary {
         pub name: String,
-        pub values: Vec<(BaseTypes, BaseTypes)>,
+        values: Vec<Option<(BaseTypes, BaseTypes)>>,
+        index: HashMap<BaseTypes, usize>,
         pub types: (BaseTypes, BaseTypes),
     }
 
@@ -99,7 +430,7 @@ pub mod collections {
                 "{}: Dict<{}, {}> = {{",
                 self.name, self.types.0, self.types.1
             )?;
-            for (i, (key, value)) in self.values.iter().enumerate() {
+            for (i, (key, value)) in self.entries().enumerate() {
                 if i > 0 {
                     write!(f, ", ")?;
                 }
@@ -109,6 +440,19 @@ pub mod collections {
         }
     }
 
+    impl fmt::Debug for Dictionary {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            visit((self as *const Dictionary as usize, 0), || {
+                f.debug_struct("Dictionary")
+                    .field("name", &self.name)
+                    .field("values", &self.entries().collect::<Vec<_>>())
+                    .field("types", &self.types)
+                    .finish()
+            })
+            .unwrap_or_else(|| write!(f, "Dictionary {{ .. }}"))
+        }
+    }
+
     // functions for dictionaries: new, add, remove, get, set(key), keys, values, to_string
 
     impl Dictionary {
@@ -118,79 +462,379 @@ pub mod collections {
             value_type: BaseTypes,
             values: Vec<(BaseTypes, BaseTypes)>,
         ) -> Dictionary {
-            Dictionary {
+            let mut dictionary = Dictionary {
                 name,
-                values,
+                values: Vec::new(),
+                index: HashMap::new(),
                 types: (key_type, value_type),
+            };
+            for (key, value) in values {
+                dictionary.add(key, value);
             }
+            dictionary
         }
 
-        pub fn add(&mut self, key: BaseTypes, value: BaseTypes) {
-            self.values.push((key, value));
+        ///
+        ///Checks that a value can be used as a key of this dictionary
+        ///
+        pub fn check_key(&self, key: BaseTypes) -> Result<BaseTypes, String> {
+            check_hashable(&key, "dictionary key")?;
+            check_type(&self.types.0, key).map_err(|key| {
+                format!(
+                    "Runtime Error: Cannot use a {} key in Dict<{}, {}>",
+                    key.GetType(),
+                    self.types.0,
+                    self.types.1
+                )
+            })
         }
 
-        /*pub fn append(&mut self, key: ASTNode, value: ASTNode) {
-            // add to end of dict
-            self.values.add((key, value));
-        } */
+        pub fn check_value(&self, value: BaseTypes) -> Result<BaseTypes, String> {
+            check_type(&self.types.1, value).map_err(|value| {
+                format!(
+                    "Runtime Error: Cannot store {} in Dict<{}, {}>",
+                    value.GetType(),
+                    self.types.0,
+                    self.types.1
+                )
+            })
+        }
 
-        pub fn remove(&mut self, key: BaseTypes) -> Option<(BaseTypes, BaseTypes)> {
-            let mut index = None;
-            for (i, (k, _)) in self.values.iter().enumerate() {
-                if k == &key {
-                    index = Some(i);
-                    break;
-                }
+        ///
+        ///Adds the entry, replacing the value of an existing key in place. The old value is
+        ///returned
+        ///
+        pub fn add(&mut self, key: BaseTypes, value: BaseTypes) -> Option<BaseTypes> {
+            if let Some(old) = self.get_mut(&key) {
+                return Some(std::mem::replace(old, value));
             }
-            if let Some(i) = index {
-                Some(self.values.remove(i))
-            } else {
-                None
+            self.index.insert(key.clone(), self.values.len());
+            self.values.push(Some((key, value)));
+            None
+        }
+
+        ///
+        ///Removes the entry while keeping the order of the others. Its place is left empty
+        ///until the gaps outnumber the entries, so a removal does not move every entry after it
+        ///
+        pub fn remove(&mut self, key: &BaseTypes) -> Option<(BaseTypes, BaseTypes)> {
+            let i = self.index.remove(key)?;
+            let entry = self.values[i].take();
+            if self.values.len() - self.index.len() > self.index.len() {
+                self.values.retain(Option::is_some);
+                for (i, (key, _)) in self.values.iter().flatten().enumerate() {
+                    if let Some(position) = self.index.get_mut(key) {
+                        *position = i;
+                    }
+                }
             }
+            entry
         }
 
-        pub fn get(&self, key: BaseTypes) -> Option<&(BaseTypes, BaseTypes)> {
-            self.values.iter().find(|(k, _)| k == &key)
+        pub fn get(&self, key: &BaseTypes) -> Option<&BaseTypes> {
+            let i = *self.index.get(key)?;
+            self.values[i].as_ref().map(|(_, value)| value)
+        }
+
+        pub fn get_mut(&mut self, key: &BaseTypes) -> Option<&mut BaseTypes> {
+            let i = *self.index.get(key)?;
+            self.values[i].as_mut().map(|(_, value)| value)
         }
 
         pub fn set(&mut self, key: BaseTypes, value: BaseTypes) -> Option<BaseTypes> {
-            let mut index = None;
-            for (i, (k, _)) in self.values.iter().enumerate() {
-                if k == &key {
-                    index = Some(i);
-                    break;
+            self.get_mut(&key).map(|old| std::mem::replace(old, value))
+        }
+
+        pub fn contains_key(&self, key: &BaseTypes) -> bool {
+            self.index.contains_key(key)
+        }
+
+        pub fn len(&self) -> usize {
+            self.index.len()
+        }
+
+        pub fn is_empty(&self) -> bool {
+            self.index.is_empty()
+        }
+
+        ///
+        ///The entries in the order they were added
+        ///
+        pub fn entries(&self) -> impl Iterator<Item = &(BaseTypes, BaseTypes)> {
+            self.values.iter().flatten()
+        }
+
+        pub fn keys(&self) -> Vec<&BaseTypes> {
+            self.entries().map(|(k, _)| k).collect()
+        }
+
+        pub fn values(&self) -> Vec<&BaseTypes> {
+            self.entries().map(|(_, v)| v).collect()
+        }
+
+        pub fn clear(&mut self) {
+            self.values.clear();
+            self.index.clear();
+        }
+    }
+
+    ///
+    ///A set holds every value once. Like a dictionary it keeps the values in the order they
+    ///were added and finds them through a hash index
+    ///
+    #[derive(Clone)]
+    pub struct Set {
+        pub name: String,
+        values: Vec<BaseTypes>,
+        index: HashMap<BaseTypes, usize>,
+        pub value_type: BaseTypes,
+    }
+
+    impl fmt::Display for Set {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            write!(f, "{}: Set<{}> = {{", self.name, self.value_type)?;
+            for (i, value) in self.values.iter().enumerate() {
+                if i > 0 {
+                    write!(f, ", ")?;
                 }
+                write!(f, "{}", value)?;
             }
-            if let Some(i) = index {
-                Some(std::mem::replace(&mut self.values[i].1, value))
-            } else {
-                None
+            write!(f, "}}")
+        }
+    }
+
+    impl fmt::Debug for Set {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            visit((self as *const Set as usize, 0), || {
+                f.debug_struct("Set")
+                    .field("name", &self.name)
+                    .field("values", &self.values)
+                    .field("value_type", &self.value_type)
+                    .finish()
+            })
+            .unwrap_or_else(|| write!(f, "Set {{ .. }}"))
+        }
+    }
+
+    impl Set {
+        pub fn new(name: String, value_type: BaseTypes, values: Vec<BaseTypes>) -> Set {
+            let mut set = Set {
+                name,
+                values: Vec::new(),
+                index: HashMap::new(),
+                value_type,
+            };
+            for value in values {
+                set.add(value);
             }
+            set
         }
 
-        pub fn keys(&self) -> Vec<&BaseTypes> {
-            self.values.iter().map(|(k, _)| k).collect()
+        ///
+        ///Checks that a value can be stored in this set, ints are widened when it holds floats
+        ///
+        pub fn check_value(&self, value: BaseTypes) -> Result<BaseTypes, String> {
+            check_hashable(&value, "set element")?;
+            check_type(&self.value_type, value).map_err(|value| {
+                format!(
+                    "Runtime Error: Cannot store {} in Set<{}>",
+                    value.GetType(),
+                    self.value_type
+                )
+            })
         }
 
-        pub fn values(&self) -> Vec<&BaseTypes> {
-            self.values.iter().map(|(_, v)| v).collect()
+        ///
+        ///Adds the value, false when it was already in the set
+        ///
+        pub fn add(&mut self, value: BaseTypes) -> bool {
+            if self.index.contains_key(&value) {
+                return false;
+            }
+            self.index.insert(value.clone(), self.values.len());
+            self.values.push(value);
+            true
         }
 
-        /*pub fn to_string(&self) -> String {
-                    let mut output = String::new();
-                    output.push_str(&self.name);
-                    output.push_str(" = {");
-                    for (i, (key, value)) in self.values.iter().enumerate() {
-                        if i > 0 {
-                            output.push_str(", ");
-                        }
-                        output.push_str(&key.to_string());
-                        output.push_str("=>");
-                        output.push_str(&value.to_string());
-                    }
-                    output.push_str("}");
-                    output
+        ///
+        ///Removes the value while keeping the order of the others
+        ///
+        pub fn remove(&mut self, value: &BaseTypes) -> bool {
+            let i = match self.index.remove(value) {
+                Some(i) => i,
+                None => return false,
+            };
+            self.values.remove(i);
+            for value in &self.values[i..] {
+                if let Some(position) = self.index.get_mut(value) {
+                    *position -= 1;
                 }
-        */
+            }
+            true
+        }
+
+        pub fn contains(&self, value: &BaseTypes) -> bool {
+            self.index.contains_key(value)
+        }
+
+        pub fn len(&self) -> usize {
+            self.values.len()
+        }
+
+        pub fn is_empty(&self) -> bool {
+            self.values.is_empty()
+        }
+
+        pub fn values(&self) -> &[BaseTypes] {
+            &self.values
+        }
+
+        pub fn clear(&mut self) {
+            self.values.clear();
+            self.index.clear();
+        }
+    }
+}
+
+#[cfg(test)]
+mod collection_tests {
+    use crate::base_variable::base_types::BaseTypes;
+    use crate::collection::collections::{
+        check_type, normalize_type, type_name_of, Array, Dictionary, Set,
+    };
+
+    #[test]
+    fn test_dictionary_keeps_order_after_removal() {
+        let mut dictionary = Dictionary::new(String::new(), "int".into(), "int".into(), Vec::new());
+        for i in 0..20000 {
+            dictionary.add(BaseTypes::Int(i), BaseTypes::Int(i * 2));
+        }
+        assert_eq!(
+            dictionary.add(BaseTypes::Int(5), BaseTypes::Int(-1)),
+            Some(BaseTypes::Int(10))
+        );
+        assert_eq!(
+            dictionary.remove(&BaseTypes::Int(0)),
+            Some((BaseTypes::Int(0), BaseTypes::Int(0)))
+        );
+
+        assert_eq!(dictionary.len(), 19999);
+        assert_eq!(
+            dictionary.get(&BaseTypes::Int(5)),
+            Some(&BaseTypes::Int(-1))
+        );
+        assert_eq!(
+            dictionary.get(&BaseTypes::Int(19999)),
+            Some(&BaseTypes::Int(39998))
+        );
+        assert_eq!(
+            dictionary.keys()[..2],
+            [&BaseTypes::Int(1), &BaseTypes::Int(2)]
+        );
+
+        // removing the even keys from the front leaves gaps that are closed along the way
+        for i in (2..20000).step_by(2) {
+            assert!(dictionary.remove(&BaseTypes::Int(i)).is_some());
+        }
+        assert_eq!(dictionary.len(), 10000);
+        assert_eq!(dictionary.remove(&BaseTypes::Int(2)), None);
+        assert_eq!(
+            dictionary.keys()[..3],
+            [&BaseTypes::Int(1), &BaseTypes::Int(3), &BaseTypes::Int(5)]
+        );
+        assert_eq!(
+            dictionary.get(&BaseTypes::Int(19999)),
+            Some(&BaseTypes::Int(39998))
+        );
+        dictionary.add(BaseTypes::Int(2), BaseTypes::Int(0));
+        assert_eq!(dictionary.keys().last(), Some(&&BaseTypes::Int(2)));
+        assert_eq!(dictionary.entries().count(), 10001);
+    }
+
+    #[test]
+    fn test_dictionary_keys() {
+        let dictionary = Dictionary::new(String::new(), "float".into(), "int".into(), Vec::new());
+        assert_eq!(
+            dictionary.check_key(BaseTypes::Int(1)),
+            Ok(BaseTypes::Float(1.0))
+        );
+        assert!(dictionary.check_key(BaseTypes::Float(f64::NAN)).is_err());
+        assert!(dictionary
+            .check_key(BaseTypes::StringWrapper("1".to_string()))
+            .is_err());
+
+        let mut zeros = Dictionary::new(String::new(), "float".into(), "int".into(), Vec::new());
+        zeros.add(BaseTypes::Float(0.0), BaseTypes::Int(1));
+        assert!(zeros.contains_key(&BaseTypes::Float(-0.0)));
+    }
+
+    #[test]
+    fn test_nested_types() {
+        assert_eq!(
+            normalize_type("dict<string,array< dict<int,any> >>"),
+            "dict<string, array<dict<int, any>>>"
+        );
+
+        let row =
+            |data: Vec<BaseTypes>| BaseTypes::from(Array::new(String::new(), "int".into(), data));
+        let grid = BaseTypes::from(Array::new(
+            String::new(),
+            "array<int>".into(),
+            vec![row(vec![BaseTypes::Int(1)]), row(Vec::new())],
+        ));
+        assert_eq!(type_name_of(&grid), "array<array<int>>");
+        assert!(check_type(&"array<array<int>>".into(), grid.clone()).is_ok());
+        assert!(check_type(&"array<any>".into(), grid.clone()).is_ok());
+        assert!(check_type(&"array<array<string>>".into(), grid.clone()).is_err());
+
+        // ints widen to floats at any depth
+        let widened = check_type(&"array<array<float>>".into(), grid).unwrap();
+        assert_eq!(type_name_of(&widened), "array<array<float>>");
+        match widened {
+            BaseTypes::Array(rows) => {
+                assert_eq!(rows.borrow().data[0], row(vec![BaseTypes::Float(1.0)]))
+            }
+            other => panic!("Expected an array, found {}", other),
+        }
+    }
+
+    #[test]
+    fn test_set_and_tuple_types() {
+        let numbers = BaseTypes::from(Array::new(
+            String::new(),
+            "int".into(),
+            vec![BaseTypes::Int(2), BaseTypes::Int(1), BaseTypes::Int(2)],
+        ));
+        let set = check_type(&"set<int>".into(), numbers).unwrap();
+        assert_eq!(type_name_of(&set), "set<int>");
+        assert_eq!(set.to_string(), "{2, 1}");
+
+        let mut set = Set::new(String::new(), "int".into(), Vec::new());
+        assert!(set.add(BaseTypes::Int(1)));
+        assert!(!set.add(BaseTypes::Int(1)));
+        assert!(set
+            .check_value(BaseTypes::StringWrapper("a".into()))
+            .is_err());
+        assert!(set.remove(&BaseTypes::Int(1)));
+        assert!(set.is_empty());
+
+        assert_eq!(
+            normalize_type("(int,array< string >)"),
+            "(int, array<string>)"
+        );
+        let pair = BaseTypes::Tuple(vec![
+            BaseTypes::Int(1),
+            BaseTypes::StringWrapper("a".into()),
+        ]);
+        assert_eq!(type_name_of(&pair), "(int, string)");
+        assert!(check_type(&"(int, string)".into(), pair.clone()).is_ok());
+        assert!(check_type(&"(int, string, int)".into(), pair.clone()).is_err());
+        assert_eq!(
+            check_type(&"(float, string)".into(), pair).unwrap(),
+            BaseTypes::Tuple(vec![
+                BaseTypes::Float(1.0),
+                BaseTypes::StringWrapper("a".into())
+            ])
+        );
     }
 }
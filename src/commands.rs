@@ -0,0 +1,1079 @@
+/*
+* This file holds the jist commands other than run: check, tokens, ast, highlight, fmt and lint
+* look at a script without running it, and repl runs statements as they are typed
+*/
+use crate::base_variable::base_types::BaseTypes;
+use crate::base_variable::variables::VARIABLE_STACK;
+use crate::cli::{FmtMode, Source};
+use crate::collection::collections::{check_type, split_type, type_name_of};
+use crate::compilers::collection::declared_type;
+use crate::compilers::expression::{
+    constant_value, expect_bool, parse_expression, static_type, undefined_name,
+};
+use crate::compilers::loops::loop_compilers::range_position;
+use crate::compilers::math::module_constant;
+use crate::config::config_dir;
+use crate::diagnostic::{emit, is_json, line_span, Diagnostic};
+use crate::formatter::{format_source, FormatConfig};
+use crate::globals::catching;
+use crate::highlighter::{display_highlighted_code, highlight_code};
+use crate::lint::{lint_source, Level, Levels};
+use crate::node::nodes::{token_to_node, ASTNode};
+use crate::statement_tokenizer::lexer::lexers::{skip_binding_keyword, Lexer};
+use crate::statement_tokenizer::tokenizer::tokenizers::tokenize;
+use crate::statements::{
+    advance, block, branches, located_statements, numbered_statements, span_in, split_statements,
+    Branch,
+};
+use crate::syntax::{check_syntax, Span, SyntaxError};
+use crate::token_type::token_types::TokenTypes;
+use crate::{check_file_extension, function_declaration_nodes};
+use std::error::Error;
+use std::io::{self, BufRead, Read, Write};
+
+///
+///Reads the script, a path has to end in .jist
+///
+pub fn read_source(source: &Source) -> Result<String, String> {
+    match source {
+        Source::Path(path) => {
+            if check_file_extension(path.to_owned()).is_err() {
+                return Err("File path not valid: Does not have extension .jist".to_string());
+            }
+            std::fs::read_to_string(path)
+                .map_err(|e| format!("Failed to read script '{}': {}", path, e))
+        }
+        Source::Stdin => {
+            let mut contents = String::new();
+            io::stdin()
+                .read_to_string(&mut contents)
+                .map_err(|e| format!("Failed to read the script from stdin: {}", e))?;
+            Ok(contents)
+        }
+        Source::Inline(code) => Ok(code.clone()),
+    }
+}
+
+///
+///The name a script is shown with in messages
+///
+pub fn source_name(source: &Source) -> String {
+    match source {
+        Source::Path(path) => path.clone(),
+        Source::Stdin => "<stdin>".to_string(),
+        Source::Inline(_) => "<-e>".to_string(),
+    }
+}
+
+///
+///The nodes of one statement, the same way they are built when the script runs
+///
+pub fn statement_nodes(statement: &str) -> Result<Vec<ASTNode>, Box<dyn Error>> {
+    if let Some(nodes) = function_declaration_nodes(statement)? {
+        return Ok(nodes);
+    }
+    let nodes = tokenize(statement.to_string())
+        .into_iter()
+        .filter(|info| info.token != TokenTypes::Comment)
+        .map(token_to_node)
+        .collect::<Result<Vec<ASTNode>, String>>()?;
+    Ok(nodes)
+}
+
+pub fn print_tokens(contents: &str) -> Result<(), Box<dyn Error>> {
+    for (line, statement) in numbered_statements(contents, 1)? {
+        for info in tokenize(statement) {
+            println!("{:>4}  {:<24} {}", line, info.token.to_string(), info.value);
+        }
+    }
+    Ok(())
+}
+
+///
+///Prints the nodes of every statement, the nodes inside a block are indented
+///
+pub fn print_ast(contents: &str) -> Result<(), Box<dyn Error>> {
+    for (line, statement) in numbered_statements(contents, 1)? {
+        println!("Statement (line {})", line);
+        let mut depth = 1;
+        for node in statement_nodes(&statement)? {
+            if node == ASTNode::RightCurly {
+                depth -= 1;
+            }
+            println!("{}{}", "    ".repeat(depth), node);
+            if node == ASTNode::LeftCurly {
+                depth += 1;
+            }
+        }
+    }
+    Ok(())
+}
+
+///
+///Prints the script with each token coloured by what it is
+///
+pub fn print_highlighted(contents: &str) -> Result<(), Box<dyn Error>> {
+    display_highlighted_code(highlight_code(contents));
+    if !contents.ends_with('\n') {
+        println!();
+    }
+    Ok(())
+}
+
+///
+///The first line where the formatted script differs from the original
+///
+fn first_difference(original: &str, formatted: &str) -> usize {
+    let mut original_lines = original.lines();
+    let mut line = 1;
+    for formatted_line in formatted.lines() {
+        if original_lines.next() != Some(formatted_line) {
+            return line;
+        }
+        line += 1;
+    }
+    line
+}
+
+///
+///Formats each script with the settings of the jist.toml nearest to it and returns the exit
+///code. --check only reports the scripts that are not formatted, --write rewrites them
+///
+pub fn run_fmt(sources: &[Source], mode: FmtMode) -> i32 {
+    let mut code = 0;
+    for source in sources {
+        let name = source_name(source);
+        let path = match source {
+            Source::Path(path) => Some(path.as_str()),
+            _ => None,
+        };
+        let result = read_source(source).and_then(|contents| {
+            let config = FormatConfig::load(&config_dir(path))?;
+            let formatted =
+                format_source(&contents, &config).map_err(|e| format!("Syntax Error: {}", e))?;
+            Ok((contents, formatted))
+        });
+        let (contents, formatted) = match result {
+            Ok(result) => result,
+            Err(e) => {
+                eprintln!("{}: {}", name, e);
+                code = 1;
+                continue;
+            }
+        };
+
+        match (mode, path) {
+            (FmtMode::Print, _) => print!("{}", formatted),
+            _ if formatted == contents => {}
+            (FmtMode::Check, _) => {
+                println!(
+                    "{}:{}: not formatted",
+                    name,
+                    first_difference(&contents, &formatted)
+                );
+                code = 1;
+            }
+            (FmtMode::Write, Some(path)) => match std::fs::write(path, &formatted) {
+                Ok(()) => println!("Formatted {}", name),
+                Err(e) => {
+                    eprintln!("{}: Failed to write the script: {}", name, e);
+                    code = 1;
+                }
+            },
+            (FmtMode::Write, None) => {}
+        }
+    }
+    code
+}
+
+///
+///Lints the script with the [lint] levels of the nearest jist.toml and returns the exit code,
+///which is 1 when a lint at the deny level found something. A script with syntax errors is
+///not linted, the errors are reported instead
+///
+pub fn run_lint(source: &Source, contents: &str) -> i32 {
+    let name = source_name(source);
+    let errors = check_syntax(contents);
+    if !errors.is_empty() {
+        report_syntax_errors(&name, contents, &errors);
+        return 1;
+    }
+    let path = match source {
+        Source::Path(path) => Some(path.as_str()),
+        _ => None,
+    };
+    let levels = match Levels::load(&config_dir(path)) {
+        Ok(levels) => levels,
+        Err(e) => {
+            eprintln!("{}: {}", name, e);
+            return 1;
+        }
+    };
+
+    let diagnostics = lint_source(contents, levels);
+    if diagnostics.is_empty() && !is_json() {
+        println!("{}: no problems found", name);
+    }
+    emit(&name, contents, &diagnostics);
+    if diagnostics.iter().any(|d| d.kind == Level::Deny.kind()) {
+        1
+    } else {
+        0
+    }
+}
+
+///
+///Whether a constant value can be stored in a variable declared with type_name
+///
+fn fits_scalar(type_name: &str, value: &BaseTypes) -> Option<bool> {
+    let fits = match type_name {
+        "int" | "float" => matches!(value, BaseTypes::Int(_) | BaseTypes::Float(_)),
+        "string" => matches!(value, BaseTypes::StringWrapper(_)),
+        "char" => matches!(value, BaseTypes::Char(_)),
+        "bool" | "boolean" => matches!(value, BaseTypes::Bool(_)),
+        "lines" => matches!(value, BaseTypes::Lines(_)),
+        _ => return None,
+    };
+    Some(fits)
+}
+
+///
+///The place x++ or x-- changes, None when the statement is not one
+///
+pub fn postfix(nodes: &[ASTNode]) -> Option<&[ASTNode]> {
+    let nodes = match nodes.split_last() {
+        Some((ASTNode::SemiColon, rest)) => rest,
+        _ => nodes,
+    };
+    match nodes.split_last() {
+        Some((ASTNode::Operator(o), target)) if o.operator == "++" || o.operator == "--" => {
+            Some(target)
+        }
+        _ => None,
+    }
+}
+
+///
+///Whether a value whose type is found can be stored in a variable declared with type_name,
+///None when the names alone cannot tell. Only the kind of a collection is compared, its
+///elements are checked when it is stored
+///
+fn fits_type(type_name: &str, found: &str) -> Option<bool> {
+    let kind = |type_name: &str| match split_type(type_name).0.as_str() {
+        "int" | "float" => "number".to_string(),
+        "boolean" => "bool".to_string(),
+        kind => kind.to_string(),
+    };
+    let (declared, found) = (kind(type_name), kind(found));
+    if ["", "any", "tuple"].contains(&declared.as_str())
+        || ["any", "tuple"].contains(&found.as_str())
+    {
+        return None;
+    }
+    Some(declared == found)
+}
+
+///
+///The variables a statement declares with their types, empty when none is written. They are
+///read from its tokens, so a declaration with a literal that is not valid still declares them
+///
+fn declared_names(statement: &str) -> Vec<(String, String)> {
+    let mut tokens = tokenize(statement.to_string())
+        .into_iter()
+        .filter(|info| info.token != TokenTypes::Comment);
+    match tokens.next() {
+        Some(info) if info.token == TokenTypes::Variable => {
+            let type_name = tokens
+                .next()
+                .filter(|next| next.token == TokenTypes::VarTypeAssignment)
+                .map(|next| next.value)
+                .unwrap_or_default();
+            vec![(info.value, type_name)]
+        }
+        Some(info) => match token_to_node(info) {
+            Ok(ASTNode::Collection(collection)) => {
+                vec![(collection.name.clone(), declared_type(&collection))]
+            }
+            Ok(ASTNode::Destructure(destructure)) => destructure
+                .names
+                .into_iter()
+                .map(|name| (name, String::new()))
+                .collect(),
+            _ => Vec::new(),
+        },
+        None => Vec::new(),
+    }
+}
+
+///
+///The names jist check knows while it walks the script: the variables of each open block and
+///the functions, each with its declared type, empty when none is written
+///
+struct Names {
+    scopes: Vec<Vec<(String, String)>>,
+    functions: Vec<(String, String)>,
+    // the variables declared outside the functions. A function body reads the variables of
+    // whoever calls it, so any of them can be there when it runs
+    globals: Vec<(String, String)>,
+    in_function: bool,
+}
+
+impl Names {
+    fn find(&self, name: &str) -> Option<&str> {
+        let globals = self.globals.iter().filter(|_| self.in_function);
+        self.scopes
+            .iter()
+            .rev()
+            .flat_map(|scope| scope.iter().rev())
+            .chain(globals)
+            .find(|(declared, _)| declared == name)
+            .map(|(_, type_name)| type_name.as_str())
+    }
+
+    fn is_function(&self, name: &str) -> bool {
+        self.functions.iter().any(|(declared, _)| declared == name)
+    }
+
+    fn declare(&mut self, name: &str, type_name: &str) {
+        let scope = self.scopes.last_mut().unwrap();
+        scope.push((name.to_string(), type_name.to_string()));
+    }
+
+    ///
+    ///The declared type of a variable or the return type of a function
+    ///
+    fn type_of(&self, name: &str) -> Option<String> {
+        let function = || {
+            self.functions
+                .iter()
+                .find(|(declared, _)| declared == name)
+                .map(|(_, type_name)| type_name.as_str())
+        };
+        self.find(name)
+            .or_else(function)
+            .filter(|type_name| !type_name.is_empty() && *type_name != "any")
+            .map(|type_name| match type_name.trim() {
+                "boolean" => "bool".to_string(),
+                type_name => type_name.to_string(),
+            })
+    }
+
+    ///
+    ///Parses the nodes as one expression and checks that every variable and function it uses
+    ///is declared, pointing at the first name that is not. spans are the spans of the nodes.
+    ///The value is returned too when the expression is made of literals only
+    ///
+    fn check_expression(
+        &self,
+        nodes: &[ASTNode],
+        spans: &[Span],
+    ) -> Result<Option<BaseTypes>, Diagnostic> {
+        let span = expression_span(nodes, spans);
+        let expression = parse_expression(nodes).map_err(|e| problem(&e, span))?;
+        let is_defined = |name: &str| self.find(name).is_some();
+        let is_function = |name: &str| self.is_function(name);
+        if let Some(undefined) = undefined_name(&expression, &is_defined, &is_function) {
+            // a method call is one node, the receiver or the method can be the missing name
+            let named = nodes.iter().position(|node| match node {
+                ASTNode::VariableCall(call) => call.name == undefined.name(),
+                ASTNode::FunctionCall(call) => call.name.split('.').any(|n| n == undefined.name()),
+                _ => false,
+            });
+            let span = named.and_then(|i| spans.get(i).copied()).or(span);
+            return Err(problem(&undefined.to_string(), span));
+        }
+        constant_value(&expression).map_err(|e| problem(&e, span))
+    }
+
+    ///
+    ///The type of the value in nodes when it is known and cannot be stored in a variable
+    ///declared with type_name
+    ///
+    fn misfit(&self, type_name: &str, nodes: &[ASTNode]) -> Option<String> {
+        let expression = parse_expression(nodes).ok()?;
+        let found = static_type(&expression, &|name| self.type_of(name))?;
+        (fits_type(type_name, &found) == Some(false)).then_some(found)
+    }
+}
+
+///
+///The spans of the tokens of a statement that starts at the line and column of start, without
+///its comments. A token over several lines is spanned on the line it starts on
+///
+fn token_spans(statement: &str, start: (usize, usize)) -> Vec<Span> {
+    let chars: Vec<char> = statement.chars().collect();
+    let mut lexer = Lexer::standalone(statement);
+    let mut spans = Vec::new();
+    let (mut position, mut read) = (start, 0);
+    while let Some(info) = lexer.next() {
+        let end = lexer.position();
+        let token_start = end - info.chars_read as usize;
+        position = advance(
+            position,
+            &chars[read..token_start].iter().collect::<String>(),
+        );
+        read = token_start;
+        if info.token != TokenTypes::Comment {
+            let text: String = chars[token_start..end]
+                .iter()
+                .take_while(|c| **c != '\n')
+                .collect();
+            spans.push(Span {
+                line: position.0,
+                column: position.1,
+                length: text.trim_end().chars().count(),
+            });
+        }
+    }
+    spans
+}
+
+///
+///The spans of the tokens of text for its nodes, the span of the statement for each of them
+///when they do not line up, like the nodes of a function declaration
+///
+pub fn node_spans(
+    text: &str,
+    start: (usize, usize),
+    nodes: &[ASTNode],
+    statement: Span,
+) -> Vec<Span> {
+    match token_spans(text, start) {
+        spans if spans.len() == nodes.len() => spans,
+        _ => vec![statement; nodes.len()],
+    }
+}
+
+///
+///The span from the first of spans to the end of the last one on the same line
+///
+fn joined(spans: &[Span]) -> Option<Span> {
+    let first = spans.first()?;
+    let last = spans.iter().rev().find(|span| span.line == first.line)?;
+    Some(Span {
+        line: first.line,
+        column: first.column,
+        length: last.column + last.length - first.column,
+    })
+}
+
+///
+///The span of the expression in nodes without the ; that ends it
+///
+pub fn expression_span(nodes: &[ASTNode], spans: &[Span]) -> Option<Span> {
+    let end = match nodes.last() {
+        Some(ASTNode::SemiColon) => nodes.len() - 1,
+        _ => nodes.len(),
+    };
+    joined(&spans[..end.min(spans.len())])
+}
+
+///
+///Where the condition inside the ( of the if, elif, while or for token at offset of a
+///statement starts, the statement starting at the line and column of start
+///
+pub fn condition_start(statement: &str, start: (usize, usize), offset: usize) -> (usize, usize) {
+    let chars: Vec<char> = statement.chars().collect();
+    let open = (offset..chars.len())
+        .find(|&i| chars[i] == '(')
+        .map_or(offset, |i| i + 1);
+    let inside = (open..chars.len())
+        .find(|&i| !chars[i].is_whitespace())
+        .unwrap_or(open);
+    let span = span_in(statement, start, inside);
+    (span.line, span.column)
+}
+
+///
+///The name and type of the declaration a statement starts with, like the name: string of
+///let name: string = "ann", ending with the token at end. None when it is not on one line
+///
+fn declaration_span(statement: &str, start: Span, end: Span) -> Option<Span> {
+    let chars: Vec<char> = statement.chars().collect();
+    let name = skip_binding_keyword(&chars, 0);
+    let column = start.column + name;
+    (end.line == start.line && !chars[..name].contains(&'\n')).then(|| Span {
+        line: start.line,
+        column,
+        length: end.column + end.length - column,
+    })
+}
+
+///
+///A problem jist check found, underlining span when it is known
+///
+fn problem(message: &str, span: Option<Span>) -> Diagnostic {
+    let diagnostic = Diagnostic::from_message(message);
+    match span {
+        Some(span) => diagnostic.label(span, ""),
+        None => diagnostic,
+    }
+}
+
+///
+///A value of type found given to name, which was declared with type_name. The value is
+///underlined and the declaration labeled when it is in the same statement
+///
+fn mismatch(
+    found: &str,
+    name: &str,
+    type_name: &str,
+    value: Span,
+    declared: Option<Span>,
+) -> Diagnostic {
+    let message = format!(
+        "Cannot assign {} to '{}' of type {}",
+        found, name, type_name
+    );
+    let expected = format!("expected {}, found {}", type_name, found);
+    let diagnostic = Diagnostic::new("Type Error", &message).label(value, &expected);
+    match declared {
+        Some(span) => diagnostic.secondary(span, &format!("declared here as {}", type_name)),
+        None => diagnostic,
+    }
+}
+
+///
+///Checks one statement and the statements of the block it opens. Expressions are parsed
+///without running them, the names they use have to be declared, and the value given to a
+///variable has to fit its type when that can be told up front. start is the span of the code
+///of the statement on the line it starts on. A problem that has no span of its own underlines
+///the statement
+///
+fn check_statement(
+    start: Span,
+    statement: &str,
+    names: &mut Names,
+    problems: &mut Vec<Diagnostic>,
+) {
+    let first = problems.len();
+    check_statement_code(start, statement, names, problems);
+    for problem in &mut problems[first..] {
+        if problem.location().is_none() {
+            *problem = problem.clone().label(start, "");
+        }
+    }
+}
+
+fn check_statement_code(
+    start: Span,
+    statement: &str,
+    names: &mut Names,
+    problems: &mut Vec<Diagnostic>,
+) {
+    let position = (start.line, start.column);
+    let nodes = match statement_nodes(statement) {
+        Ok(nodes) => nodes,
+        Err(e) => {
+            problems.push(problem(&format!("Syntax Error: {}", e), Some(start)));
+            // what it declares is still known, so the uses further down are not reported too
+            for (name, type_name) in declared_names(statement) {
+                names.declare(&name, &type_name);
+            }
+            return;
+        }
+    };
+    let spans = node_spans(statement, position, &nodes, start);
+    let assignment = nodes
+        .iter()
+        .position(|node| matches!(node, ASTNode::AssignmentOperator(_)));
+    let value = assignment.map(|position| &nodes[position + 1..]);
+    let value_spans = assignment.map_or(&[][..], |position| &spans[position + 1..]);
+    let value_span = value
+        .and_then(|value| expression_span(value, value_spans))
+        .unwrap_or(start);
+    let checked = value.map(|value| names.check_expression(value, value_spans));
+    // only x = value replaces the whole value, x[i] = value stores an element
+    let replaces = matches!(
+        assignment.map(|position| (&nodes[..position], &nodes[position])),
+        Some(([_], ASTNode::AssignmentOperator(a))) if a.operator == "="
+    );
+
+    let result = match nodes.first() {
+        Some(ASTNode::Collection(collection)) => {
+            let declared = declared_type(collection);
+            let declaration = declaration_span(statement, start, spans[0]);
+            let mismatch =
+                |found: &str| mismatch(found, &collection.name, &declared, value_span, declaration);
+            match checked {
+                Some(Ok(Some(value))) => check_type(&declared.clone().into(), value)
+                    .map(|_| ())
+                    .map_err(|value| mismatch(&type_name_of(&value))),
+                Some(Err(e)) => Err(e),
+                Some(Ok(None)) => match names.misfit(&declared, value.unwrap()) {
+                    Some(found) => Err(mismatch(&found)),
+                    None => Ok(()),
+                },
+                None => Ok(()),
+            }
+        }
+        Some(ASTNode::Variable(variable)) => {
+            let typed = nodes
+                .iter()
+                .position(|node| matches!(node, ASTNode::VariableType(_)));
+            let type_name = match typed.map(|i| &nodes[i]) {
+                Some(ASTNode::VariableType(t)) => t.value.clone(),
+                _ => String::new(),
+            };
+            let type_span = typed.map(|i| spans[i]);
+            let declaration = type_span.and_then(|end| declaration_span(statement, start, end));
+            let mismatch =
+                |found: &str| mismatch(found, &variable.value, &type_name, value_span, declaration);
+            match checked {
+                _ if fits_scalar(&type_name, &BaseTypes::Null).is_none() => Err(problem(
+                    &format!("Syntax Error: Unrecognized type '{}'", type_name),
+                    type_span,
+                )),
+                Some(Ok(Some(value))) if fits_scalar(&type_name, &value) == Some(false) => {
+                    Err(mismatch(&type_name_of(&value)))
+                }
+                Some(Err(e)) => Err(e),
+                Some(Ok(None)) => match names.misfit(&type_name, value.unwrap()) {
+                    Some(found) => Err(mismatch(&found)),
+                    None => Ok(()),
+                },
+                Some(_) => Ok(()),
+                None => Err(problem(
+                    &format!(
+                        "Syntax Error: Expected '=' in the declaration of '{}'",
+                        variable.value
+                    ),
+                    Some(start),
+                )),
+            }
+        }
+        Some(ASTNode::Destructure(destructure)) => match checked {
+            Some(Ok(Some(BaseTypes::Tuple(values)))) if values.len() != destructure.names.len() => {
+                let message = format!(
+                    "Type Error: Cannot unpack a tuple of {} into {} names",
+                    values.len(),
+                    destructure.names.len()
+                );
+                Err(problem(&message, Some(value_span))
+                    .help("give one name for each value of the tuple"))
+            }
+            Some(Ok(Some(value))) if !matches!(value, BaseTypes::Tuple(_)) => {
+                let message = format!(
+                    "Type Error: Cannot unpack {} into ({})",
+                    type_name_of(&value),
+                    destructure.names.join(", ")
+                );
+                Err(problem(&message, Some(value_span)))
+            }
+            Some(Err(e)) => Err(e),
+            _ => Ok(()),
+        },
+        Some(ASTNode::VariableCall(call)) => match (checked, postfix(&nodes)) {
+            _ if names.find(&call.name).is_none() && module_constant(&call.name).is_none() => {
+                let message = format!("Runtime Error: Variable '{}' is not defined", call.name);
+                Err(problem(&message, Some(spans[0])))
+            }
+            (Some(Ok(_)), _) if replaces => {
+                let type_name = names.find(&call.name).unwrap_or_default();
+                match names.misfit(type_name, value.unwrap()) {
+                    Some(found) => Err(mismatch(&found, &call.name, type_name, value_span, None)),
+                    None => Ok(()),
+                }
+            }
+            (Some(Ok(_)), _) => {
+                let target = assignment.unwrap();
+                names
+                    .check_expression(&nodes[..target], &spans[..target])
+                    .map(|_| ())
+            }
+            (Some(Err(e)), _) => Err(e),
+            // x++ reads x the way an expression does
+            (None, Some(target)) => names
+                .check_expression(target, &spans[..target.len()])
+                .map(|_| ()),
+            (None, _) => names.check_expression(&nodes, &spans).map(|_| ()),
+        },
+        Some(ASTNode::FunctionCall(_)) => names.check_expression(&nodes, &spans).map(|_| ()),
+        Some(ASTNode::Return) => match &nodes[1..] {
+            [] | [ASTNode::SemiColon] => Ok(()),
+            rest => names.check_expression(rest, &spans[1..]).map(|_| ()),
+        },
+        Some(ASTNode::For(node)) => match node.condition.split_once(" in ") {
+            // both bounds of a range are expressions of their own
+            Some((name, iterable)) => match statement_nodes(iterable) {
+                Ok(nodes) => {
+                    let before = format!("{} in ", name);
+                    let at = advance(condition_start(statement, position, 0), &before);
+                    let spans = node_spans(iterable, at, &nodes, start);
+                    match range_position(&nodes) {
+                        Some(range) => names
+                            .check_expression(&nodes[..range], &spans[..range])
+                            .and_then(|_| {
+                                names.check_expression(&nodes[range + 1..], &spans[range + 1..])
+                            }),
+                        None => names.check_expression(&nodes, &spans),
+                    }
+                    .map(|_| ())
+                }
+                Err(e) => Err(problem(&format!("Syntax Error: {}", e), Some(start))),
+            },
+            None => Ok(()),
+        },
+        _ => Ok(()),
+    };
+    if let Err(e) = result {
+        problems.push(e);
+    }
+
+    for (name, type_name) in declared_names(statement) {
+        names.declare(&name, &type_name);
+    }
+
+    // a condition has to use declared names, and one made of literals only has to be a bool
+    let conditions: Vec<(usize, String)> = match branches(statement, start.line) {
+        Some(branches) => branches
+            .into_iter()
+            .filter_map(|branch| match branch.keyword {
+                TokenTypes::If { statement } | TokenTypes::Elif { statement } => {
+                    Some((branch.start, statement))
+                }
+                _ => None,
+            })
+            .collect(),
+        None => match block(statement, start.line).map(|block| block.keyword) {
+            Some(TokenTypes::While { statement }) => vec![(0, statement)],
+            _ => Vec::new(),
+        },
+    };
+    for (offset, condition) in conditions {
+        let Ok(nodes) = statement_nodes(&condition) else {
+            continue;
+        };
+        let at = condition_start(statement, position, offset);
+        let spans = node_spans(&condition, at, &nodes, start);
+        match names.check_expression(&nodes, &spans) {
+            Ok(Some(value)) => {
+                if let Err(e) = expect_bool(&value) {
+                    problems.push(problem(&e, expression_span(&nodes, &spans)));
+                }
+            }
+            Err(e) if e.kind != "Syntax Error" => problems.push(e),
+            _ => {}
+        }
+    }
+
+    // functions, ifs, trys and loops have statements of their own, every branch of an if or
+    // try in a block. Parameters, loop variables and caught errors are declared in the block
+    let declared: Vec<(String, String)> = match nodes.first() {
+        Some(ASTNode::Function(function)) => function
+            .arguments
+            .iter()
+            .map(|(name, type_name, _)| (name.clone(), type_name.clone()))
+            .collect(),
+        Some(ASTNode::For(node)) => node
+            .condition
+            .split_once(" in ")
+            .map(|(name, _)| (name.trim().to_string(), String::new()))
+            .into_iter()
+            .collect(),
+        _ => Vec::new(),
+    };
+    let opens_block = matches!(
+        nodes.first(),
+        Some(ASTNode::Function(_) | ASTNode::While(_) | ASTNode::For(_))
+    );
+    let bodies: Vec<(Branch, Vec<(String, String)>)> = match branches(statement, start.line) {
+        Some(branches) => branches
+            .into_iter()
+            .map(|branch| {
+                let caught = match (&branch.keyword, &branch.name) {
+                    (TokenTypes::Catch, Some(name)) => vec![(name.clone(), String::new())],
+                    _ => Vec::new(),
+                };
+                (branch, caught)
+            })
+            .collect(),
+        None => block(statement, start.line)
+            .filter(|_| opens_block)
+            .map(|block| (block, declared))
+            .into_iter()
+            .collect(),
+    };
+    let in_function = names.in_function;
+    names.in_function |= matches!(nodes.first(), Some(ASTNode::Function(_)));
+    for (branch, declared) in bodies {
+        names.scopes.push(declared);
+        let body = span_in(statement, position, branch.body_start);
+        match located_statements(&branch.body, body.line, body.column) {
+            Ok(statements) => {
+                for (span, statement) in statements {
+                    check_statement(span, &statement, names, problems);
+                }
+            }
+            Err(e) => {
+                // the { that opens the block
+                let open = span_in(statement, position, branch.body_start - 1);
+                problems.push(problem(&format!("Syntax Error: {}", e), Some(open)));
+            }
+        }
+        names.scopes.pop();
+    }
+    names.in_function = in_function;
+}
+
+///
+///Checks the script without running it and returns the problems found
+///
+pub fn check_source(contents: &str) -> Vec<Diagnostic> {
+    let mut problems = Vec::new();
+    let statements = match located_statements(contents, 1, 1) {
+        Ok(statements) => statements,
+        Err(e) => {
+            let message = format!("Syntax Error: {}", e);
+            return vec![problem(&message, line_span(contents, 1))];
+        }
+    };
+
+    // the functions and the variables outside them are known before the first statement, so a
+    // function can be called above the line that declares it
+    let mut names = Names {
+        scopes: vec![Vec::new()],
+        functions: Vec::new(),
+        globals: Vec::new(),
+        in_function: false,
+    };
+    for (_, statement) in &statements {
+        match statement_nodes(statement).as_deref() {
+            Ok([ASTNode::Function(function), ..]) => names
+                .functions
+                .push((function.name.clone(), function.return_type.clone())),
+            _ => names.globals.extend(declared_names(statement)),
+        }
+    }
+
+    for (span, statement) in statements {
+        check_statement(span, &statement, &mut names, &mut problems);
+    }
+    problems
+}
+
+///
+///Prints every syntax error with the lines of the script it is about
+///
+pub fn report_syntax_errors(name: &str, contents: &str, errors: &[SyntaxError]) {
+    let diagnostics: Vec<Diagnostic> = errors.iter().map(Diagnostic::from).collect();
+    emit(name, contents, &diagnostics);
+    if is_json() {
+        return;
+    }
+    let count = match errors.len() {
+        1 => "1 syntax error".to_string(),
+        count => format!("{} syntax errors", count),
+    };
+    eprintln!("{}: {} found", name, count);
+}
+
+///
+///Prints the problems jist check found with the lines of the script they are about
+///
+pub fn report_problems(name: &str, contents: &str, problems: &[Diagnostic]) {
+    emit(name, contents, problems);
+}
+
+///
+///Reads statements from stdin and runs each one once it is complete, so a block can be typed
+///over several lines. Variables and functions stay defined between statements
+///
+pub fn repl(run: impl Fn(&str) -> Result<(), Box<dyn Error>>) {
+    println!(
+        "Jist {} (type :quit or press Ctrl-D to leave)",
+        env!("CARGO_PKG_VERSION")
+    );
+    let stdin = io::stdin();
+    let mut buffer = String::new();
+    // what ran so far, so the consts and immutable variables it declared are known
+    let mut history = String::new();
+    loop {
+        print!("{}", if buffer.is_empty() { "jist> " } else { "...> " });
+        let _ = io::stdout().flush();
+
+        let mut line = String::new();
+        match stdin.lock().read_line(&mut line) {
+            Ok(0) | Err(_) => break,
+            Ok(_) => {}
+        }
+        if buffer.is_empty() && line.trim() == ":quit" {
+            break;
+        }
+        buffer.push_str(&line);
+
+        // wait for more lines while a string, a block or the statement is still open
+        let statements = match split_statements(&buffer) {
+            Ok(statements) => statements,
+            Err(e) if e.to_string().starts_with("Unterminated string") => continue,
+            Err(e) => {
+                eprintln!("Syntax Error: {}", e);
+                buffer.clear();
+                continue;
+            }
+        };
+        let complete: usize = statements.iter().map(String::len).sum();
+        if !buffer[complete..].trim().is_empty() {
+            continue;
+        }
+        let errors = repl_errors(&history, &buffer);
+        if !errors.is_empty() {
+            let diagnostics: Vec<Diagnostic> = errors.iter().map(Diagnostic::from).collect();
+            emit("<repl>", &buffer, &diagnostics);
+            buffer.clear();
+            continue;
+        }
+        // a runtime error ends what was typed, not the session. The variables it declared are
+        // dropped, since the blocks it left early did not remove theirs
+        let depth = unsafe { (*std::ptr::addr_of!(VARIABLE_STACK)).len() };
+        match catching(|| run(&buffer)) {
+            Ok(Ok(())) => {
+                history.push_str(&buffer);
+                if !history.ends_with('\n') {
+                    history.push('\n');
+                }
+            }
+            Ok(Err(e)) => eprintln!("Syntax Error: {}", e),
+            Err(error) => {
+                unsafe { (*std::ptr::addr_of_mut!(VARIABLE_STACK)).truncate(depth) };
+                eprintln!("{}", error.message);
+            }
+        }
+        buffer.clear();
+    }
+    println!();
+}
+
+///
+///The syntax errors in buffer when it runs after history, the statements the REPL already ran,
+///at their lines in buffer
+///
+fn repl_errors(history: &str, buffer: &str) -> Vec<SyntaxError> {
+    let before = history.lines().count();
+    let mut errors = check_syntax(&format!("{}{}", history, buffer));
+    errors.retain(|error| error.span.line > before);
+    for error in &mut errors {
+        error.span.line -= before;
+        // what it points at in earlier statements can't be shown
+        error.labels.retain(|(span, _)| span.line > before);
+        for (span, _) in &mut error.labels {
+            span.line -= before;
+        }
+    }
+    errors
+}
+
+#[cfg(test)]
+mod commands_tests {
+    use super::{check_source, first_difference};
+    use crate::syntax::Span;
+
+    #[test]
+    fn test_first_difference() {
+        assert_eq!(first_difference("a\nb\n", "a\nb\n"), 3);
+        assert_eq!(first_difference("a\n  b\nc\n", "a\nb\nc\n"), 2);
+        assert_eq!(first_difference("a", "a\nb\n"), 2);
+    }
+
+    ///
+    ///The problems jist check finds in source, at the line and column they underline first
+    ///
+    fn problems(source: &str) -> Vec<(usize, usize, String)> {
+        check_source(source)
+            .into_iter()
+            .map(|problem| {
+                let span = problem.location().unwrap();
+                let message = format!("{}: {}", problem.kind, problem.message);
+                (span.line, span.column, message)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_problems_without_a_value_underline_the_statement() {
+        let source = "let x: int = ;\nfor (i in 0..) {\n}";
+        let spans: Vec<_> = check_source(source)
+            .iter()
+            .map(|problem| problem.location())
+            .collect();
+        assert_eq!(
+            spans,
+            vec![
+                Some(Span {
+                    line: 1,
+                    column: 1,
+                    length: 14
+                }),
+                Some(Span {
+                    line: 2,
+                    column: 1,
+                    length: 16
+                }),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_check_source() {
+        let source = "let a: int = 1;\nlet b: int = \"two\";\n\nfunc f() -> int {\n    let c: array<int> = [1, \"x\"];\n    return (1 +;\n}\nlet (x, y) = (1, 2, 3);";
+        assert_eq!(
+            problems(source),
+            vec![
+                (
+                    2,
+                    14,
+                    "Type Error: Cannot assign string to 'b' of type int".to_string()
+                ),
+                (
+                    5,
+                    25,
+                    "Type Error: Cannot assign array<any> to 'c' of type array<int>".to_string()
+                ),
+                (
+                    6,
+                    12,
+                    "Syntax Error: Expected a value, found SemiColon".to_string()
+                ),
+                (
+                    8,
+                    14,
+                    "Type Error: Cannot unpack a tuple of 3 into 2 names".to_string()
+                ),
+            ]
+        );
+    }
+    #[test]
+    fn test_check_names_and_types() {
+        let source = "let name: string = \"ann\";\nlet total: int = name;\necholn(missing(2));\nfunc half(n: int) -> float {\n    return n / 2.0 + offset;\n}\nlet h: string = half(3);\nlet mut count: int = 0;\ncount = name;\nfor (i in 0..count) {\n    echoln(i + y);\n}\nlet offset: float = 0.5;\necholn([1, 2].map(x => x * 2), name.to_uppercase(), math.PI);";
+        assert_eq!(
+            problems(source),
+            vec![
+                (
+                    2,
+                    18,
+                    "Type Error: Cannot assign string to 'total' of type int".to_string()
+                ),
+                (
+                    3,
+                    8,
+                    "Runtime Error: Function 'missing' is not defined".to_string()
+                ),
+                (
+                    7,
+                    17,
+                    "Type Error: Cannot assign float to 'h' of type string".to_string()
+                ),
+                (
+                    9,
+                    9,
+                    "Type Error: Cannot assign string to 'count' of type int".to_string()
+                ),
+                (
+                    11,
+                    16,
+                    "Runtime Error: Variable 'y' is not defined".to_string()
+                ),
+            ]
+        );
+    }
+}
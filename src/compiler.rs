@@ -3,17 +3,16 @@
 * /compilers directory.
 */
 pub mod compilers {
+    use crate::base_variable::base_types::BaseTypes;
     use crate::compilers::collection::*;
-    use crate::compilers::conditional::conditional_compilers::compile_if_elif_else_statement;
+    use crate::compilers::expression::evaluate_expression;
     use crate::compilers::function::*;
-    use crate::compilers::loops::loop_compilers::{compile_for_loop, compile_while_loop};
-    use crate::globals::{IF_ELSE_SKIP, MAKE_LOOP};
+    use crate::globals::{report_error, FUNCTION_DEPTH, RETURN_VALUE};
 
     use crate::compilers::variable::parse_variable_call;
-    use crate::compilers::variable::{compile_variable_call, parse_variable_declaration};
-    use crate::node::nodes::{ASTNode, IntNode, OperatorNode};
+    use crate::compilers::variable::{compile_assignment, parse_variable_declaration};
+    use crate::node::nodes::{ASTNode, BoolNode, IntNode, OperatorNode};
     use crate::token_type::token_types::*;
-    use std::process::exit;
 
     pub struct Parser {
         pub tokens: Vec<TokenTypes>,
@@ -71,72 +70,59 @@ pub mod compilers {
                             let result = IntNode { value: result };
                             return ASTNode::Int(result);
                         } else {
-                            println!("Syntax Error: Division by zero.");
-                            exit(1);
+                            report_error("Syntax Error: Division by zero.");
                         }
                     }
                 }
                 ">" => {
                     if let (ASTNode::Int(left_val), ASTNode::Int(right_val)) = (left, right) {
                         let result = left_val.value > right_val.value;
-                        let result = IntNode {
-                            value: result as i32,
-                        };
-                        return ASTNode::Int(result);
+                        return ASTNode::Bool(BoolNode::new(result));
                     }
                 }
                 "<" => {
                     if let (ASTNode::Int(left_val), ASTNode::Int(right_val)) = (left, right) {
                         let result = left_val.value < right_val.value;
-                        let result = IntNode {
-                            value: result as i32,
-                        };
-                        return ASTNode::Int(result);
+                        return ASTNode::Bool(BoolNode::new(result));
                     }
                 }
                 ">=" => {
                     if let (ASTNode::Int(left_val), ASTNode::Int(right_val)) = (left, right) {
                         let result = left_val.value >= right_val.value;
-                        let result = IntNode {
-                            value: result as i32,
-                        };
-                        return ASTNode::Int(result);
+                        return ASTNode::Bool(BoolNode::new(result));
                     }
                 }
                 "<=" => {
                     if let (ASTNode::Int(left_val), ASTNode::Int(right_val)) = (left, right) {
                         let result = left_val.value <= right_val.value;
-                        let result = IntNode {
-                            value: result as i32,
-                        };
-                        return ASTNode::Int(result);
+                        return ASTNode::Bool(BoolNode::new(result));
                     }
                 }
                 "==" => {
                     if let (ASTNode::Int(left_val), ASTNode::Int(right_val)) = (left, right) {
                         let result = left_val.value == right_val.value;
-                        let result = IntNode {
-                            value: result as i32,
-                        };
-                        return ASTNode::Int(result);
+                        return ASTNode::Bool(BoolNode::new(result));
                     }
                 }
                 "!=" => {
                     if let (ASTNode::Int(left_val), ASTNode::Int(right_val)) = (left, right) {
                         let result = left_val.value != right_val.value;
-                        let result = IntNode {
-                            value: result as i32,
-                        };
-                        return ASTNode::Int(result);
+                        return ASTNode::Bool(BoolNode::new(result));
                     }
                 }
                 "!" => {
-                    if let (ASTNode::Int(left_val), ASTNode::Int(right_val)) = (left, right) {
-                        let result = !(left_val.value != 0 && right_val.value != 0);
-                        let result = IntNode {
-                            value: result as i32,
-                        };
-                        return ASTNode::Int(result);
+                    if let ASTNode::Bool(value) = right {
+                        return ASTNode::Bool(BoolNode::new(!value.value));
+                    }
+                }
+                "&&" => {
+                    if let (ASTNode::Bool(left_val), ASTNode::Bool(right_val)) = (left, right) {
+                        return ASTNode::Bool(BoolNode::new(left_val.value && right_val.value));
+                    }
+                }
+                "||" => {
+                    if let (ASTNode::Bool(left_val), ASTNode::Bool(right_val)) = (left, right) {
+                        return ASTNode::Bool(BoolNode::new(left_val.value || right_val.value));
                     }
                 }
                 "++" => {
@@ -154,13 +140,14 @@ pub mod compilers {
                     }
                 }
                 _ => {
-                    println!("Syntax Error: Unrecognized operator '{}'", o.operator);
-                    exit(1);
+                    report_error(format!(
+                        "Syntax Error: Unrecognized operator '{}'",
+                        o.operator
+                    ));
                 }
             },
             _ => {
-                println!("Syntax Error: Expected an operator.");
-                exit(1);
+                report_error("Syntax Error: Expected an operator.");
             }
         }
         ASTNode::None
@@ -197,11 +184,10 @@ pub mod compilers {
                     }
                 }
                 _ => {
-                    println!(
+                    report_error(format!(
                         "Syntax Error: Expected operator or number, found {:?}",
                         next_node
-                    );
-                    exit(1);
+                    ));
                 }
             }
         }
@@ -218,44 +204,24 @@ pub mod compilers {
         while index < expression.len() {
             let node = &expression[index]; // Access node by index
             let next_node = expression.get(index + 1);
-            println!("Node: {:?}", node);
+            trace!(Parser, Trace, "Node: {:?}", node);
 
             match node {
                 ASTNode::LeftCurly => {
-                    println!("Parsing LeftCurlyNode");
-                }
-                ASTNode::If(_i) => {
-                    let result = compile_if_elif_else_statement(expression);
-                    if result {
-                        index += 2; // Skip to the next statement after processing `if`
-                        unsafe { IF_ELSE_SKIP = true };
-                        continue;
-                    } else {
-                        return true;
-                    }
+                    trace!(Parser, Trace, "Parsing LeftCurlyNode");
                 }
-                ASTNode::Elif(_i) => {
-                    let result = compile_if_elif_else_statement(expression);
-                    if result {
-                        index += 2; // Skip to the next statement after processing `elif`
-                        unsafe { IF_ELSE_SKIP = true };
-                        continue;
-                    } else {
-                        return true;
-                    }
-                }
-                ASTNode::For(_f) => {
-                    let result = compile_for_loop(expression);
-                    unsafe { MAKE_LOOP = result };
+                // these are run with their blocks, only a statement missing its block is left
+                ASTNode::If(_) | ASTNode::Elif(_) => {
+                    report_error("Syntax Error: Expected a block after the condition");
                 }
-                ASTNode::While(w) => {
-                    // Evaluate the condition
-                    let condition_result = compile_while_loop(expression);
-                    println!("Condition Result: {}\n", condition_result);
-                    return true;
+                ASTNode::For(_) | ASTNode::While(_) => {
+                    report_error("Syntax Error: Expected a block after the loop");
                 }
                 ASTNode::Try => {
-                    println!("Parsing TryNode");
+                    trace!(Parser, Trace, "Parsing TryNode");
+                }
+                ASTNode::Destructure(_d) => {
+                    return compile_destructure(&expression[index..]);
                 }
                 ASTNode::Collection(_c) => {
                     let _value = parse_collection_declaration(expression);
@@ -268,29 +234,29 @@ pub mod compilers {
                     }
                 }
                 ASTNode::Else => {
-                    println!("Parsing ElseNode");
+                    trace!(Parser, Trace, "Parsing ElseNode");
                 }
                 ASTNode::Int(n) => {
                     if expression.len() == 1 {
-                        println!("Result: {:?}", ASTNode::Int(n.clone()));
+                        trace!(Runtime, Debug, "Result: {:?}", ASTNode::Int(n.clone()));
                         break;
                     } else {
                         let result = operation(expression);
-                        println!("Result: {:?}", result);
+                        trace!(Runtime, Debug, "Result: {:?}", result);
                         break;
                     }
                 }
-                ASTNode::Function(_f) => {
-                    let end = parse_function_declaration(expression);
-                    if end {
-                        return true;
-                    }
+                ASTNode::Function(f) => {
+                    report_error(format!(
+                        "Syntax Error: Expected '{{' after function '{}'",
+                        f.name
+                    ));
                 }
                 ASTNode::String(s) => {
-                    println!("String: {}", s.value);
+                    trace!(Runtime, Debug, "String: {}", s.value);
                 }
                 ASTNode::Char(c) => {
-                    println!("Char: {}", c.value);
+                    trace!(Runtime, Debug, "Char: {}", c.value);
                 }
                 ASTNode::FunctionCall(_f) => {
                     let function_expression: Vec<ASTNode> = expression[index..].to_vec();
@@ -298,26 +264,38 @@ pub mod compilers {
                     return true;
                 }
                 ASTNode::VariableCall(_v) => {
-                    let call_result = compile_variable_call(expression);
-                    if call_result {
-                        return true;
-                    }
+                    return compile_assignment(&expression[index..]);
                 }
                 ASTNode::Comment(_c) => {
                     return true;
                 }
+                ASTNode::Return => {
+                    if unsafe { FUNCTION_DEPTH } == 0 {
+                        report_error("Syntax Error: return can only be used inside a function");
+                    }
+                    let value = match &expression[index + 1..] {
+                        [] | [ASTNode::SemiColon, ..] => Ok(BaseTypes::Null),
+                        rest => evaluate_expression(rest),
+                    };
+                    match value {
+                        Ok(value) => unsafe { RETURN_VALUE = Some(value) },
+                        Err(e) => {
+                            report_error(e);
+                        }
+                    }
+                    return true;
+                }
                 ASTNode::LeftParenthesis => {
                     let value = operation(expression);
-                    println!("Result: {:?}", value);
+                    trace!(Runtime, Debug, "Result: {:?}", value);
                     break;
                 }
                 ASTNode::None => {
-                    println!("Syntax Error: Unhandled node type.");
-                    std::process::exit(1);
+                    report_error("Syntax Error: Unhandled node type.");
                 }
                 ASTNode::RightParenthesis => {}
                 _ => {
-                    println!("Syntax Error: Unhandled node: {:?}", node);
+                    report_error(format!("Syntax Error: Unhandled node: {:?}", node));
                 }
             }
 
@@ -405,6 +383,24 @@ mod complier_tests {
         }
     }
 
+    #[test]
+    fn test_parse_operator_comparison() {
+        let left = ASTNode::Int(IntNode { value: 5 });
+        let operator = ASTNode::Operator(OperatorNode {
+            operator: "<=".to_string(),
+        });
+        let right = ASTNode::Int(IntNode { value: 5 });
+        let result = parse_operator(&left, &operator, &right);
+        match result {
+            ASTNode::Bool(b) => {
+                assert!(b.value);
+            }
+            _ => {
+                panic!("Result is not a BoolNode");
+            }
+        }
+    }
+
     /*
     #[test]
     fn test_operator_unrecognized() {
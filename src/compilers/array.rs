@@ -0,0 +1,395 @@
+/*
+* This file holds the methods that can be called on arrays from Jist code, like a.sort() or
+* a.map(x => x * 2). Callbacks arrive already wrapped as Rust closures so this file does not
+* need to know whether they are lambdas or named functions
+*/
+use crate::base_variable::base_types::{BaseTypes, GetType};
+use crate::collection::collections::{common_type, Array};
+use std::cmp::Ordering;
+
+pub type Callback<'a> = Box<dyn Fn(Vec<BaseTypes>) -> Result<BaseTypes, String> + 'a>;
+
+pub enum Argument<'a> {
+    Value(BaseTypes),
+    Function(Callback<'a>),
+}
+
+const METHODS: [&str; 21] = [
+    "len", "contains", "index_of", "push", "pop", "insert", "remove", "sort", "sort_by", "reverse",
+    "map", "filter", "reduce", "any", "all", "join", "zip", "get", "set", "append", "copy",
+];
+
+// Methods that change the array they are called on
+const MUTATING_METHODS: [&str; 9] = [
+    "push", "pop", "insert", "remove", "sort", "sort_by", "reverse", "set", "append",
+];
+
+pub fn is_array_method(name: &str) -> bool {
+    METHODS.contains(&name)
+}
+
+pub fn mutates(name: &str) -> bool {
+    MUTATING_METHODS.contains(&name)
+}
+
+pub fn expect_arguments<T>(
+    name: &str,
+    arguments: &[T],
+    range: (usize, usize),
+) -> Result<(), String> {
+    let (min, max) = range;
+    if arguments.len() < min || arguments.len() > max {
+        let expected = if min == max {
+            min.to_string()
+        } else {
+            format!("{} to {}", min, max)
+        };
+        return Err(format!(
+            "Runtime Error: {}(): Expected {} argument(s) but found {}",
+            name,
+            expected,
+            arguments.len()
+        ));
+    }
+    Ok(())
+}
+
+fn value<'b>(name: &str, argument: &'b Argument) -> Result<&'b BaseTypes, String> {
+    match argument {
+        Argument::Value(value) => Ok(value),
+        Argument::Function(_) => Err(format!(
+            "Runtime Error: {}(): Expected a value but found a function",
+            name
+        )),
+    }
+}
+
+fn callback<'b, 'a>(name: &str, argument: &'b Argument<'a>) -> Result<&'b Callback<'a>, String> {
+    match argument {
+        Argument::Function(function) => Ok(function),
+        Argument::Value(value) => Err(format!(
+            "Runtime Error: {}(): Expected a function but found {}",
+            name,
+            value.GetType()
+        )),
+    }
+}
+
+fn index(name: &str, argument: &Argument, length: usize) -> Result<usize, String> {
+    match value(name, argument)? {
+        BaseTypes::Int(i) if *i >= 0 && (*i as usize) < length => Ok(*i as usize),
+        BaseTypes::Int(i) => Err(format!(
+            "Runtime Error: Index {} is out of bounds for length {}",
+            i, length
+        )),
+        other => Err(format!(
+            "Runtime Error: Expected an int index, found {}",
+            other.GetType()
+        )),
+    }
+}
+
+fn condition(name: &str, value: BaseTypes) -> Result<bool, String> {
+    match value {
+        BaseTypes::Bool(b) => Ok(b),
+        other => Err(format!(
+            "Runtime Error: {}(): Expected the function to return a Bool but it returned {}",
+            name,
+            other.GetType()
+        )),
+    }
+}
+
+///
+///The natural order used by sort(), only values of the same kind can be compared
+///
+pub fn compare(left: &BaseTypes, right: &BaseTypes) -> Result<Ordering, String> {
+    let ordering = match (left, right) {
+        (BaseTypes::Int(x), BaseTypes::Int(y)) => x.partial_cmp(y),
+        (BaseTypes::Int(_) | BaseTypes::Float(_), BaseTypes::Int(_) | BaseTypes::Float(_)) => {
+            f64::from(left.clone()).partial_cmp(&f64::from(right.clone()))
+        }
+        (BaseTypes::StringWrapper(x), BaseTypes::StringWrapper(y)) => x.partial_cmp(y),
+        (BaseTypes::Char(x), BaseTypes::Char(y)) => x.partial_cmp(y),
+        (BaseTypes::Bool(x), BaseTypes::Bool(y)) => x.partial_cmp(y),
+        _ => {
+            return Err(format!(
+                "Runtime Error: Cannot compare {} and {}",
+                left.GetType(),
+                right.GetType()
+            ))
+        }
+    };
+    // NaN sorts as equal to everything
+    Ok(ordering.unwrap_or(Ordering::Equal))
+}
+
+///
+///Sorts with a comparison that can fail, the first error stops the result from being used
+///
+fn sort_with<F>(data: &mut [BaseTypes], compare: F) -> Result<(), String>
+where
+    F: Fn(&BaseTypes, &BaseTypes) -> Result<Ordering, String>,
+{
+    let mut error = None;
+    data.sort_by(|left, right| {
+        if error.is_some() {
+            return Ordering::Equal;
+        }
+        compare(left, right).unwrap_or_else(|e| {
+            error = Some(e);
+            Ordering::Equal
+        })
+    });
+    match error {
+        Some(e) => Err(e),
+        None => Ok(()),
+    }
+}
+
+///
+///Builds an array from computed values, its type is the type the values share, any when
+///they differ, or fallback when there are none
+///
+fn new_array(data: Vec<BaseTypes>, fallback: &BaseTypes) -> BaseTypes {
+    let value_type = if data.is_empty() {
+        fallback.clone()
+    } else {
+        common_type(data.iter())
+    };
+    BaseTypes::from(Array::new(String::new(), value_type, data))
+}
+
+fn values_equal(left: &BaseTypes, right: &BaseTypes) -> bool {
+    compare(left, right).is_ok_and(|ordering| ordering.is_eq())
+}
+
+///
+///Calls the method name on the array, the array is changed in place by the mutating methods.
+///sort, sort_by and reverse also return the array so they can be chained
+///
+pub fn call_array_method(
+    array: &mut Array,
+    name: &str,
+    arguments: Vec<Argument>,
+) -> Result<BaseTypes, String> {
+    match name {
+        "len" => {
+            expect_arguments(name, &arguments, (0, 0))?;
+            Ok(BaseTypes::Int(array.data.len() as i32))
+        }
+        "copy" => {
+            // a new array with the same elements, arrays inside it are still shared
+            expect_arguments(name, &arguments, (0, 0))?;
+            Ok(BaseTypes::from(Array::new(
+                String::new(),
+                array.value_type.clone(),
+                array.data.clone(),
+            )))
+        }
+        "contains" => {
+            expect_arguments(name, &arguments, (1, 1))?;
+            let needle = value(name, &arguments[0])?;
+            Ok(BaseTypes::Bool(
+                array.data.iter().any(|item| values_equal(item, needle)),
+            ))
+        }
+        "index_of" => {
+            expect_arguments(name, &arguments, (1, 1))?;
+            let needle = value(name, &arguments[0])?;
+            let position = array
+                .data
+                .iter()
+                .position(|item| values_equal(item, needle));
+            Ok(BaseTypes::Int(position.map_or(-1, |i| i as i32)))
+        }
+        "get" => {
+            expect_arguments(name, &arguments, (1, 1))?;
+            let i = index(name, &arguments[0], array.data.len())?;
+            Ok(array.data[i].clone())
+        }
+        "set" => {
+            expect_arguments(name, &arguments, (2, 2))?;
+            let i = index(name, &arguments[0], array.data.len())?;
+            let value = array.check_value(value(name, &arguments[1])?.clone())?;
+            array.set(i, value);
+            Ok(BaseTypes::Null)
+        }
+        "push" => {
+            expect_arguments(name, &arguments, (1, 1))?;
+            let value = array.check_value(value(name, &arguments[0])?.clone())?;
+            array.push(value);
+            Ok(BaseTypes::Null)
+        }
+        "append" => {
+            expect_arguments(name, &arguments, (1, 1))?;
+            let mut values = match value(name, &arguments[0])? {
+                BaseTypes::Array(other) => other
+                    .borrow()
+                    .data
+                    .iter()
+                    .map(|value| array.check_value(value.clone()))
+                    .collect::<Result<Vec<BaseTypes>, String>>()?,
+                other => {
+                    return Err(format!(
+                        "Runtime Error: append(): Expected an Array but found {}",
+                        other.GetType()
+                    ))
+                }
+            };
+            array.append(&mut values);
+            Ok(BaseTypes::Null)
+        }
+        "pop" => {
+            expect_arguments(name, &arguments, (0, 0))?;
+            array
+                .pop()
+                .ok_or_else(|| "Runtime Error: pop(): The array is empty".to_string())
+        }
+        "insert" => {
+            expect_arguments(name, &arguments, (2, 2))?;
+            // inserting at the length appends
+            let i = index(name, &arguments[0], array.data.len() + 1)?;
+            let value = array.check_value(value(name, &arguments[1])?.clone())?;
+            array.data.insert(i, value);
+            Ok(BaseTypes::Null)
+        }
+        "remove" => {
+            expect_arguments(name, &arguments, (1, 1))?;
+            let i = index(name, &arguments[0], array.data.len())?;
+            Ok(array.data.remove(i))
+        }
+        "sort" => {
+            expect_arguments(name, &arguments, (0, 0))?;
+            sort_with(&mut array.data, compare)?;
+            Ok(BaseTypes::from(array.clone()))
+        }
+        "sort_by" => {
+            expect_arguments(name, &arguments, (1, 1))?;
+            let function = callback(name, &arguments[0])?;
+            sort_with(&mut array.data, |left, right| {
+                match function(vec![left.clone(), right.clone()])? {
+                    BaseTypes::Int(i) => Ok(i.cmp(&0)),
+                    other => Err(format!(
+                        "Runtime Error: sort_by(): Expected the function to return an Int but it returned {}",
+                        other.GetType()
+                    )),
+                }
+            })?;
+            Ok(BaseTypes::from(array.clone()))
+        }
+        "reverse" => {
+            expect_arguments(name, &arguments, (0, 0))?;
+            array.data.reverse();
+            Ok(BaseTypes::from(array.clone()))
+        }
+        "map" => {
+            expect_arguments(name, &arguments, (1, 1))?;
+            let function = callback(name, &arguments[0])?;
+            let data = array
+                .data
+                .iter()
+                .map(|item| function(vec![item.clone()]))
+                .collect::<Result<Vec<BaseTypes>, String>>()?;
+            Ok(new_array(data, &array.value_type))
+        }
+        "filter" => {
+            expect_arguments(name, &arguments, (1, 1))?;
+            let function = callback(name, &arguments[0])?;
+            let mut data = Vec::new();
+            for item in &array.data {
+                if condition(name, function(vec![item.clone()])?)? {
+                    data.push(item.clone());
+                }
+            }
+            Ok(BaseTypes::from(Array::new(
+                String::new(),
+                array.value_type.clone(),
+                data,
+            )))
+        }
+        "reduce" => {
+            // reduce(f) starts from the first element, reduce(f, initial) from initial
+            expect_arguments(name, &arguments, (1, 2))?;
+            let function = callback(name, &arguments[0])?;
+            let mut items = array.data.iter().cloned();
+            let mut accumulator = match arguments.get(1) {
+                Some(initial) => value(name, initial)?.clone(),
+                None => items.next().ok_or_else(|| {
+                    "Runtime Error: reduce(): Cannot reduce an empty array without an initial value"
+                        .to_string()
+                })?,
+            };
+            for item in items {
+                accumulator = function(vec![accumulator, item])?;
+            }
+            Ok(accumulator)
+        }
+        "any" | "all" => {
+            expect_arguments(name, &arguments, (1, 1))?;
+            let function = callback(name, &arguments[0])?;
+            // any stops at the first match, all at the first miss
+            let looking_for = name == "any";
+            for item in &array.data {
+                if condition(name, function(vec![item.clone()])?)? == looking_for {
+                    return Ok(BaseTypes::Bool(looking_for));
+                }
+            }
+            Ok(BaseTypes::Bool(!looking_for))
+        }
+        "join" => {
+            expect_arguments(name, &arguments, (0, 1))?;
+            let separator = match arguments.first() {
+                Some(argument) => match value(name, argument)? {
+                    BaseTypes::StringWrapper(s) => s.clone(),
+                    BaseTypes::Char(c) => c.to_string(),
+                    other => {
+                        return Err(format!(
+                            "Runtime Error: join(): Expected a String separator but found {}",
+                            other.GetType()
+                        ))
+                    }
+                },
+                None => String::new(),
+            };
+            let parts: Vec<String> = array.data.iter().map(|item| item.to_string()).collect();
+            Ok(BaseTypes::StringWrapper(parts.join(&separator)))
+        }
+        "zip" => {
+            // pairs are two element arrays, they stop at the end of the shorter array
+            expect_arguments(name, &arguments, (1, 1))?;
+            let other = match value(name, &arguments[0])? {
+                BaseTypes::Array(other) => other.borrow(),
+                other => {
+                    return Err(format!(
+                        "Runtime Error: zip(): Expected an Array but found {}",
+                        other.GetType()
+                    ))
+                }
+            };
+            let pair_type = if array.value_type == other.value_type {
+                array.value_type.clone()
+            } else {
+                "any".into()
+            };
+            let pairs = array
+                .data
+                .iter()
+                .zip(other.data.iter())
+                .map(|(left, right)| {
+                    BaseTypes::from(Array::new(
+                        String::new(),
+                        pair_type.clone(),
+                        vec![left.clone(), right.clone()],
+                    ))
+                })
+                .collect();
+            Ok(BaseTypes::from(Array::new(
+                String::new(),
+                format!("array<{}>", pair_type).into(),
+                pairs,
+            )))
+        }
+        _ => Err(format!("Runtime Error: Arrays have no method '{}'", name)),
+    }
+}
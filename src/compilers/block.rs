@@ -0,0 +1,217 @@
+/*
+* This file runs a block of statements: the script itself, the body of a function, a loop or a
+* branch of an if. A statement that opens a block of its own runs that block through here too,
+* so any statement can be inside any block, to any depth
+*/
+use crate::base_variable::base_types::BaseTypes;
+use crate::base_variable::variable::Variable;
+use crate::base_variable::variables::{begin_scope, end_scope, VARIABLE_STACK};
+use crate::compiler::compilers::route_to_parser;
+use crate::compilers::conditional::conditional_compilers::compile_conditional_statement;
+use crate::compilers::function::parse_function_declaration;
+use crate::compilers::loops::loop_compilers::{run_for_loop, run_while_loop};
+use crate::globals::{at_statement, catching, raise, report_error, RETURN_VALUE};
+use crate::node::nodes::{match_token_to_node, ASTNode, FunctionNode};
+use crate::statement_tokenizer::tokenizer::tokenizers::tokenize;
+use crate::statements::{block, branches, located_statements, span_in, Branch};
+use crate::syntax::Span;
+use crate::token_type::token_types::TokenTypes;
+use std::error::Error;
+
+///
+///Runs the statements of the source one after the other, the source starting at first_column
+///of first_line. Stops after a return statement, the function it is in picks up the value
+///
+pub fn run_block(
+    source: &str,
+    first_line: usize,
+    first_column: usize,
+) -> Result<(), Box<dyn Error>> {
+    for (span, statement) in located_statements(source, first_line, first_column)? {
+        if !statement.is_empty() {
+            at_statement(span);
+        }
+        run_statement(span, &statement)?;
+        if returning() {
+            break;
+        }
+    }
+    Ok(())
+}
+
+///
+///Runs the body of a branch or a loop, the variables declared in it are gone once it ends
+///
+pub fn run_scoped_block(
+    source: &str,
+    first_line: usize,
+    first_column: usize,
+) -> Result<(), Box<dyn Error>> {
+    let depth = begin_scope();
+    let result = run_block(source, first_line, first_column);
+    end_scope(depth);
+    result
+}
+
+///
+///Whether a return statement has run and the blocks around it should stop
+///
+pub fn returning() -> bool {
+    unsafe { (*std::ptr::addr_of!(RETURN_VALUE)).is_some() }
+}
+
+///
+///The nodes of a condition, the same way a statement is turned into nodes
+///
+pub fn condition_nodes(condition: &str) -> Vec<ASTNode> {
+    tokenize(condition.to_string())
+        .into_iter()
+        .filter(|info| info.token != TokenTypes::Comment)
+        .map(match_token_to_node)
+        .collect()
+}
+
+fn run_statement(span: Span, statement: &str) -> Result<(), Box<dyn Error>> {
+    let start = (span.line, span.column);
+    if let Some(branches) = branches(statement, span.line) {
+        return run_branches(statement, start, &branches);
+    }
+    if let Some(block) = block(statement, span.line) {
+        let body_column = span_in(statement, start, block.body_start).column;
+        return match block.keyword {
+            TokenTypes::While { statement } => {
+                run_while_loop(&statement, &block.body, block.body_line, body_column)
+            }
+            TokenTypes::For { statement } => {
+                run_for_loop(&statement, &block.body, block.body_line, body_column)
+            }
+            TokenTypes::Function {
+                name,
+                return_type,
+                arguments,
+            } => {
+                let function = FunctionNode::new(name, return_type, arguments);
+                parse_function_declaration(&function, block.body, block.body_line, body_column);
+                Ok(())
+            }
+            _ => Ok(()),
+        };
+    }
+
+    let mut expression = Vec::new();
+    for node in condition_nodes(statement) {
+        if node != ASTNode::SemiColon {
+            expression.push(node);
+            continue;
+        }
+        match expression.first() {
+            None => report_error("Syntax error: expression must be more than a semicolon"),
+            Some(ASTNode::Elif(_)) => {
+                report_error("Syntax Error: elif without an if in front of it")
+            }
+            Some(ASTNode::Else) => report_error("Syntax Error: else without an if in front of it"),
+            Some(_) => {
+                route_to_parser(&mut expression, None);
+            }
+        }
+        expression.clear();
+    }
+    Ok(())
+}
+
+///
+///Runs the block of the first branch of an if statement whose condition holds, or the blocks
+///of a try statement. The statement starts at the line and column of start
+///
+fn run_branches(
+    statement: &str,
+    start: (usize, usize),
+    branches: &[Branch],
+) -> Result<(), Box<dyn Error>> {
+    if branches[0].keyword == TokenTypes::Try {
+        return run_try(statement, start, branches);
+    }
+    for branch in branches {
+        let body_column = span_in(statement, start, branch.body_start).column;
+        match &branch.keyword {
+            TokenTypes::If {
+                statement: condition,
+            }
+            | TokenTypes::Elif {
+                statement: condition,
+            } => {
+                // the if starts the statement, which has been reached already
+                if branch.start != branches[0].start {
+                    at_statement(span_in(statement, start, branch.start));
+                }
+                if compile_conditional_statement(&mut condition_nodes(condition)) {
+                    return run_scoped_block(&branch.body, branch.body_line, body_column);
+                }
+            }
+            TokenTypes::Else => {
+                return run_scoped_block(&branch.body, branch.body_line, body_column)
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+///
+///Runs the try block. An error in it runs the catch block with the message bound to the name
+///of the catch. The finally block runs however the try and catch blocks were left: at their
+///end, by a return or by an error. A return waits for it and an error nothing catches is
+///reported once it has run, unless the finally block returns itself
+///
+fn run_try(
+    statement: &str,
+    start: (usize, usize),
+    branches: &[Branch],
+) -> Result<(), Box<dyn Error>> {
+    let body_column = |branch: &Branch| span_in(statement, start, branch.body_start).column;
+    // an error leaves the blocks it was in without removing their variables
+    let depth = unsafe { (*std::ptr::addr_of!(VARIABLE_STACK)).len() };
+    let mut ended = catching(|| {
+        let body = &branches[0];
+        run_scoped_block(&body.body, body.body_line, body_column(body))
+    });
+    unsafe { (*std::ptr::addr_of_mut!(VARIABLE_STACK)).truncate(depth) };
+    let catch = branches
+        .iter()
+        .find(|branch| branch.keyword == TokenTypes::Catch);
+    if let (Some(catch), Err(error)) = (catch, &ended) {
+        at_statement(span_in(statement, start, catch.start));
+        let message = error.message.clone();
+        ended = catching(|| run_catch(catch, body_column(catch), message));
+        unsafe { (*std::ptr::addr_of_mut!(VARIABLE_STACK)).truncate(depth) };
+    }
+
+    let finally = branches
+        .iter()
+        .find(|branch| branch.keyword == TokenTypes::Finally);
+    if let Some(finally) = finally {
+        let pending = unsafe { (*std::ptr::addr_of_mut!(RETURN_VALUE)).take() };
+        at_statement(span_in(statement, start, finally.start));
+        run_scoped_block(&finally.body, finally.body_line, body_column(finally))?;
+        if returning() {
+            return Ok(());
+        }
+        unsafe { RETURN_VALUE = pending };
+    }
+    match ended {
+        Ok(result) => result,
+        Err(error) => raise(error),
+    }
+}
+
+fn run_catch(catch: &Branch, body_column: usize, message: String) -> Result<(), Box<dyn Error>> {
+    let depth = begin_scope();
+    if let Some(name) = &catch.name {
+        let value = BaseTypes::StringWrapper(message);
+        let variable = Variable::new(name.clone(), value.clone(), value);
+        unsafe { (*std::ptr::addr_of_mut!(VARIABLE_STACK)).push(variable) };
+    }
+    let result = run_block(&catch.body, catch.body_line, body_column);
+    end_scope(depth);
+    result
+}
@@ -1,227 +1,279 @@
-use crate::base_variable::base_types::BaseTypes;
-use crate::collection::collections::{Array, Dictionary};
-use crate::collection::ARRAY_STACK;
-use crate::collection::DICTIONARY_STACK;
-use crate::node::nodes::ASTNode;
-//use std::sync::Mutex;
-//use lazy_static::lazy_static;
+use crate::base_variable::base_types::{BaseTypes, GetType};
+use crate::base_variable::variable::Variable;
+use crate::base_variable::variables::{find_index, VARIABLE_STACK};
+use crate::collection::collections::{
+    check_declared_type, check_type, name_collection, type_name_of, Array, Dictionary, Set,
+};
+use crate::compilers::dictionary::lookup;
+use crate::compilers::expression::{evaluate_expression, evaluate_leading_expression};
+use crate::globals::report_error;
+use crate::node::nodes::{ASTNode, CollectionNode};
 
-fn add_to_dictionary_stack(dict: Dictionary) {
-    DICTIONARY_STACK.lock().unwrap().push(dict.clone());
-    // You can still use `dict` after this line because we cloned it
-    //println!("dict pushed to stack")
+///
+///The full declared type of a collection, like array<int> or dict<string, array<int>>
+///
+pub fn declared_type(collection: &CollectionNode) -> String {
+    let single = collection.value_type_single.as_deref().unwrap_or("");
+    let (key, value) = collection
+        .value_type_tuple
+        .clone()
+        .unwrap_or_else(|| (String::new(), String::new()));
+    match collection.collection_type.as_str() {
+        "array" if single.is_empty() => "array".to_string(),
+        "array" => format!("array<{}>", single),
+        "dict" if key.is_empty() => "dict".to_string(),
+        "dict" => format!("dict<{}, {}>", key, value),
+        "set" if single.is_empty() => "set".to_string(),
+        "set" => format!("set<{}>", single),
+        // the tuple type is already whole, like (int, string)
+        "tuple" => single.to_string(),
+        other => other.to_string(),
+    }
 }
 
-fn add_to_array_stack(array: Array) {
-    ARRAY_STACK.lock().unwrap().push(array.clone());
+///
+///An empty collection of the same type, variables keep one as their type
+///
+fn empty_like(value: &BaseTypes) -> BaseTypes {
+    match value {
+        BaseTypes::Array(array) => {
+            let array = array.borrow();
+            BaseTypes::from(Array::new(
+                array.name.clone(),
+                array.value_type.clone(),
+                Vec::new(),
+            ))
+        }
+        BaseTypes::Dictionary(dictionary) => {
+            let dictionary = dictionary.borrow();
+            BaseTypes::from(Dictionary::new(
+                dictionary.name.clone(),
+                dictionary.types.0.clone(),
+                dictionary.types.1.clone(),
+                Vec::new(),
+            ))
+        }
+        BaseTypes::Set(set) => {
+            let set = set.borrow();
+            BaseTypes::from(Set::new(
+                set.name.clone(),
+                set.value_type.clone(),
+                Vec::new(),
+            ))
+        }
+        other => other.clone(),
+    }
 }
 
-pub fn parse_collection_declaration(expression: &[ASTNode]) -> bool {
-    //let mut array_stack = ARRAY_STACK.lock().unwrap();
-    //let mut dict_stack = DICTIONARY_STACK.lock().unwrap();
-
-    // Check if the first node is a Collection
-    if let Some(node) = expression.get(0) {
-        if let ASTNode::Collection(collection_node) = node {
-            // Access fields from the CollectionNode
-            let name = &collection_node.name;
-            let collection_type = &collection_node.collection_type;
-            let value_type_single = collection_node.value_type_single.as_deref().unwrap_or("");
-            let value_type_tuple = collection_node
-                .value_type_tuple
-                .as_ref()
-                .map(|(v1, v2)| (v1.clone(), v2.clone()));
+///
+///Evaluates the [index] parts after the name, returning them with the position after the last
+///
+pub fn read_indexes(expression: &[ASTNode]) -> Result<(Vec<BaseTypes>, usize), String> {
+    let mut indexes = Vec::new();
+    let mut position = 1;
+    while matches!(expression.get(position), Some(ASTNode::LeftBracket)) {
+        let (index, read) = evaluate_leading_expression(&expression[position + 1..])?;
+        position += 1 + read;
+        if !matches!(expression.get(position), Some(ASTNode::RightBracket)) {
+            return Err("Syntax Error: Expected ']' after the index".to_string());
+        }
+        indexes.push(index);
+        position += 1;
+    }
+    Ok((indexes, position))
+}
 
-            // Convert the tuple elements to BaseTypes
-            let single_key_type: BaseTypes = value_type_single.clone().into();
-            let key_type: BaseTypes = value_type_tuple
-                .as_ref()
-                .map_or(BaseTypes::Null, |(v1, _)| v1.clone().into());
-            let value_type: BaseTypes = value_type_tuple
-                .as_ref()
-                .map_or(BaseTypes::Null, |(_, v2)| v2.clone().into());
+fn array_position(array: &Array, index: &BaseTypes) -> Result<usize, String> {
+    let length = array.data.len();
+    match index {
+        BaseTypes::Int(i) if *i >= 0 && (*i as usize) < length => Ok(*i as usize),
+        BaseTypes::Int(i) => Err(format!(
+            "Runtime Error: Index {} is out of bounds for length {}",
+            i, length
+        )),
+        other => Err(format!(
+            "Runtime Error: Expected an int index, found {}",
+            other.GetType()
+        )),
+    }
+}
 
-            // Print out the collected values for debugging
-            /*
-            println!("Collection Name: {}", name);
-            println!("Collection Type: {}", collection_type);
-            println!("Single Value Type: {}", value_type_single);
-            println!("Tuple Value Types: {:?}", value_type_tuple);
-            */
+///
+///The element an index refers to, a collection in it comes back as another handle to the same
+///collection so a later index can reach into it
+///
+fn element(target: &BaseTypes, index: &BaseTypes) -> Result<BaseTypes, String> {
+    match target {
+        BaseTypes::Array(array) => {
+            let array = array.borrow();
+            let i = array_position(&array, index)?;
+            Ok(array.data[i].clone())
+        }
+        BaseTypes::Dictionary(dictionary) => lookup(&dictionary.borrow(), index),
+        other => Err(format!("Runtime Error: Cannot index {}", other.GetType())),
+    }
+}
 
-            match collection_type.as_str() {
-                "array" => {
-                    // Create a new Array
-                    let mut values: Vec<BaseTypes> = Vec::new();
-                    for node in &expression[1..] {
-                        match node {
-                            ASTNode::Int(int) => values.push(BaseTypes::Int(int.value)),
-                            ASTNode::Float(float) => {
-                                values.push(BaseTypes::Float(float.value.into()))
-                            }
-                            ASTNode::String(string) => {
-                                values.push(BaseTypes::StringWrapper(string.value.clone()))
-                            }
-                            ASTNode::Char(char) => values.push(BaseTypes::Char(char.value)),
-                            ASTNode::Bool(bool) => {
-                                //println!("Bool: {}", bool.value);
-                                values.push(BaseTypes::Bool(bool.value));
-                            }
-                            ASTNode::AssignmentOperator(_)
-                            | ASTNode::ArgumentSeparator
-                            | ASTNode::LeftBracket => {}
-                            ASTNode::RightBracket => break,
-                            _ => {
-                                println!("Syntax Error: Value type not recognized.");
-                                return false;
-                            }
-                        }
-                    }
+fn variable_value(name: &str) -> Result<BaseTypes, String> {
+    find_index(name)
+        .map(|index| unsafe { (&*std::ptr::addr_of!(VARIABLE_STACK))[index].value.clone() })
+        .ok_or_else(|| format!("Runtime Error: Variable '{}' is not defined", name))
+}
 
-                    let array = Array::new(name.clone(), single_key_type.clone(), values);
-                    add_to_array_stack(array);
-                    //println!("Added array to stack");
-                }
-                "dict" => {
-                    // Create a new Dictionary
-                    let mut values: Vec<(BaseTypes, BaseTypes)> = Vec::new();
-                    let mut key: Option<BaseTypes> = None;
-                    let mut have_fat_arrow = false;
+///
+///The element the indexes lead to in the variable called name
+///
+pub fn get_element(name: &str, indexes: &[BaseTypes]) -> Result<BaseTypes, String> {
+    let mut target = variable_value(name)?;
+    for index in indexes {
+        target = element(&target, index)?;
+    }
+    Ok(target)
+}
 
-                    for node in &expression[1..] {
-                        //println!("Node: {:?}", node);
-                        match node {
-                            ASTNode::Int(int) => {
-                                let base_int = BaseTypes::Int(int.value);
-                                if have_fat_arrow {
-                                    // This is the value
-                                    if let Some(k) = key.take() {
-                                        values.push((k, base_int));
-                                        have_fat_arrow = false;
-                                    } else {
-                                        println!("Syntax Error: Missing key.");
-                                        return false;
-                                    }
-                                } else {
-                                    // This is the key
-                                    if key.is_some() {
-                                        println!("Syntax Error: Unexpected key.");
-                                        return false;
-                                    }
-                                    key = Some(base_int);
-                                }
-                            }
-                            ASTNode::Float(float) => {
-                                let base_float = BaseTypes::Float(float.value.into());
-                                if have_fat_arrow {
-                                    if let Some(k) = key.take() {
-                                        values.push((k, base_float));
-                                        have_fat_arrow = false;
-                                    } else {
-                                        println!("Syntax Error: Missing key.");
-                                        return false;
-                                    }
-                                } else {
-                                    if key.is_some() {
-                                        println!("Syntax Error: Unexpected key.");
-                                        return false;
-                                    }
-                                    key = Some(base_float);
-                                }
-                            }
-                            ASTNode::String(string) => {
-                                let base_string = BaseTypes::StringWrapper(string.value.clone());
-                                if have_fat_arrow {
-                                    if let Some(k) = key.take() {
-                                        values.push((k, base_string));
-                                        have_fat_arrow = false;
-                                    } else {
-                                        println!("Syntax Error: Missing key.");
-                                        return false;
-                                    }
-                                } else {
-                                    if key.is_some() {
-                                        println!("Syntax Error: Unexpected key.");
-                                        return false;
-                                    }
-                                    key = Some(base_string);
-                                }
-                            }
-                            ASTNode::Char(char) => {
-                                let base_char = BaseTypes::Char(char.value);
-                                if have_fat_arrow {
-                                    if let Some(k) = key.take() {
-                                        values.push((k, base_char));
-                                        have_fat_arrow = false;
-                                    } else {
-                                        println!("Syntax Error: Missing key.");
-                                        return false;
-                                    }
-                                } else {
-                                    if key.is_some() {
-                                        println!("Syntax Error: Unexpected key.");
-                                        return false;
-                                    }
-                                    key = Some(base_char);
-                                }
-                            }
-                            ASTNode::Bool(bool) => {
-                                let base_bool = BaseTypes::Bool(bool.value);
-                                if have_fat_arrow {
-                                    if let Some(k) = key.take() {
-                                        values.push((k, base_bool));
-                                        have_fat_arrow = false;
-                                    } else {
-                                        println!("Syntax Error: Missing key.");
-                                        return false;
-                                    }
-                                } else {
-                                    if key.is_some() {
-                                        println!("Syntax Error: Unexpected key.");
-                                        return false;
-                                    }
-                                    key = Some(base_bool);
-                                }
-                            }
-                            ASTNode::FatArrow => {
-                                have_fat_arrow = true;
-                            }
-                            ASTNode::AssignmentOperator(_) => {}
-                            ASTNode::LeftCurly => {}
-                            ASTNode::RightCurly => {
-                                //println!("found right curly");
-                            }
-                            ASTNode::ArgumentSeparator => {
-                                // Continue to the next key-value pair
-                            }
-                            ASTNode::SemiColon => {}
-                            _ => {
-                                println!("Syntax Error: Unexpected node type:{} ", node);
-                                return false;
-                            }
-                        }
-                    }
+///
+///Stores value under the last index, a dictionary gains the key when it does not have it yet
+///
+pub fn set_element(name: &str, indexes: Vec<BaseTypes>, value: BaseTypes) -> Result<(), String> {
+    let (last, path) = match indexes.split_last() {
+        Some(split) => split,
+        None => return Err("Syntax Error: Expected an index".to_string()),
+    };
+    let mut target = variable_value(name)?;
+    for index in path {
+        target = element(&target, index)?;
+    }
 
-                    // Create and print the dictionary with the parsed values
-                    let dict = Dictionary::new(name.clone(), key_type, value_type, values);
-                    // add dict to the stack
+    // the value is checked before the collection is changed, it can be the collection itself
+    match &target {
+        BaseTypes::Array(array) => {
+            let (i, value) = {
+                let array = array.borrow();
+                (array_position(&array, last)?, array.check_value(value)?)
+            };
+            array.borrow_mut().set(i, value);
+            Ok(())
+        }
+        BaseTypes::Dictionary(dictionary) => {
+            let (key, value) = {
+                let dictionary = dictionary.borrow();
+                (
+                    dictionary.check_key(last.clone())?,
+                    dictionary.check_value(value)?,
+                )
+            };
+            dictionary.borrow_mut().add(key, value);
+            Ok(())
+        }
+        other => Err(format!(
+            "Runtime Error: Cannot assign to an index of {}",
+            other.GetType()
+        )),
+    }
+}
 
-                    //println!("Before pushing to stack");
-                    add_to_dictionary_stack(dict.clone());
-                    //println!("Created new Dictionary with values: {}", dict.to_string());
-                }
-                _ => {
-                    println!("Collection type not recognized.");
-                    return false;
-                }
-            }
-            return true;
-        } else {
-            println!("The first node is not a collection.");
+///
+///Declares a collection such as let a: array<int> = [1, 2]. The value can be any expression
+///that evaluates to a collection of the declared type
+///
+pub fn parse_collection_declaration(expression: &[ASTNode]) -> bool {
+    let collection = match expression.first() {
+        Some(ASTNode::Collection(collection)) => collection,
+        _ => {
+            eprintln!("The first node is not a collection.");
             return false;
         }
-    } else {
-        println!("The expression vector is empty.");
+    };
+    if !["array", "dict", "set", "tuple"].contains(&collection.collection_type.as_str()) {
+        eprintln!("Collection type not recognized.");
         return false;
     }
+
+    let declared = declared_type(collection);
+    if let Err(e) = check_declared_type(&declared) {
+        report_error(e);
+    }
+    let position = expression
+        .iter()
+        .position(|node| matches!(node, ASTNode::AssignmentOperator(_)))
+        .unwrap_or_else(|| {
+            report_error(format!(
+                "Syntax Error: Expected '=' in the declaration of '{}'",
+                collection.name
+            ));
+        });
+
+    let value = evaluate_expression(&expression[position + 1..]).and_then(|value| {
+        check_type(&declared.clone().into(), value).map_err(|value| {
+            format!(
+                "Runtime Error: Cannot assign {} to '{}' of type {}",
+                type_name_of(&value),
+                collection.name,
+                declared
+            )
+        })
+    });
+    let value = match value {
+        Ok(value) => value,
+        Err(e) => {
+            report_error(e);
+        }
+    };
+    name_collection(&value, &collection.name);
+    let variable = Variable {
+        name: collection.name.clone(),
+        var_type: empty_like(&value),
+        value,
+    };
+    unsafe { (*std::ptr::addr_of_mut!(VARIABLE_STACK)).push(variable) };
+    true
+}
+
+///
+///Runs let (a, b) = value. The value has to be a tuple with one element per name, and _ skips
+///an element
+///
+pub fn compile_destructure(expression: &[ASTNode]) -> bool {
+    let names = match expression.first() {
+        Some(ASTNode::Destructure(destructure)) => &destructure.names,
+        _ => return false,
+    };
+    if !matches!(expression.get(1), Some(ASTNode::AssignmentOperator(_))) {
+        report_error(format!(
+            "Syntax Error: Expected '=' after ({})",
+            names.join(", ")
+        ));
+    }
+    let values = match evaluate_expression(&expression[2..]) {
+        Ok(BaseTypes::Tuple(values)) if values.len() == names.len() => values,
+        Ok(BaseTypes::Tuple(values)) => {
+            report_error(format!(
+                "Runtime Error: Cannot unpack a tuple of {} into {} names",
+                values.len(),
+                names.len()
+            ));
+        }
+        Ok(other) => {
+            report_error(format!(
+                "Runtime Error: Cannot unpack {} into ({})",
+                type_name_of(&other),
+                names.join(", ")
+            ));
+        }
+        Err(e) => {
+            report_error(e);
+        }
+    };
+    for (name, value) in names.iter().zip(values) {
+        if name == "_" {
+            continue;
+        }
+        let variable = Variable {
+            name: name.clone(),
+            var_type: empty_like(&value),
+            value,
+        };
+        unsafe { (*std::ptr::addr_of_mut!(VARIABLE_STACK)).push(variable) };
+    }
+    true
 }
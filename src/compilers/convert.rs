@@ -0,0 +1,206 @@
+/*
+* This file converts values between the primitive types, for x as int, x.to_string() and
+* s.parse::<float>(). A value that cannot be converted is an error a catch block can catch, it
+* is never turned into a 0 or an empty string instead
+*/
+use crate::base_variable::base_types::BaseTypes;
+use crate::collection::collections::type_name_of;
+
+// The types a value can be converted to
+pub const TYPES: [&str; 5] = ["int", "float", "string", "char", "bool"];
+
+///
+///The type named in a conversion, boolean is read as bool
+///
+fn target_type(type_name: &str) -> Result<&'static str, String> {
+    match type_name {
+        "boolean" => Ok("bool"),
+        _ => TYPES
+            .into_iter()
+            .find(|target| *target == type_name)
+            .ok_or_else(|| {
+                format!(
+                    "Type Error: Cannot convert to {}, expected one of {}",
+                    type_name,
+                    TYPES.join(", ")
+                )
+            }),
+    }
+}
+
+fn article(type_name: &str) -> &'static str {
+    match type_name {
+        "int" => "an",
+        _ => "a",
+    }
+}
+
+///
+///The value of value as type_name, for value as type_name. A float loses its fraction, a
+///string is parsed and an int becomes the char with that code
+///
+pub fn cast(value: BaseTypes, type_name: &str) -> Result<BaseTypes, String> {
+    let target = target_type(type_name)?;
+    match (value, target) {
+        (BaseTypes::StringWrapper(text), target) => parse(&text, target),
+        (value, "string") => Ok(BaseTypes::StringWrapper(value.to_string())),
+        (value @ BaseTypes::Int(_), "int")
+        | (value @ BaseTypes::Float(_), "float")
+        | (value @ BaseTypes::Char(_), "char")
+        | (value @ BaseTypes::Bool(_), "bool") => Ok(value),
+        (BaseTypes::Int(i), "float") => Ok(BaseTypes::Float(i.into())),
+        (BaseTypes::Float(f), "int") => {
+            let truncated = f.trunc();
+            if f.is_nan() || truncated < i32::MIN.into() || truncated > i32::MAX.into() {
+                return Err(format!("Runtime Error: {} does not fit in an int", f));
+            }
+            Ok(BaseTypes::Int(truncated as i32))
+        }
+        (BaseTypes::Bool(b), "int") => Ok(BaseTypes::Int(b.into())),
+        (BaseTypes::Char(c), "int") => Ok(BaseTypes::Int(c as i32)),
+        (BaseTypes::Int(i), "char") => u32::try_from(i)
+            .ok()
+            .and_then(char::from_u32)
+            .map(BaseTypes::Char)
+            .ok_or_else(|| format!("Runtime Error: {} is not the code of a char", i)),
+        (value, target) => Err(format!(
+            "Type Error: Cannot convert {} to {}",
+            type_name_of(&value),
+            target
+        )),
+    }
+}
+
+///
+///Reads text as a value of type_name, surrounding spaces are ignored for numbers
+///
+pub fn parse(text: &str, type_name: &str) -> Result<BaseTypes, String> {
+    let target = target_type(type_name)?;
+    let value = match target {
+        "int" => text.trim().parse().ok().map(BaseTypes::Int),
+        "float" => text.trim().parse().ok().map(BaseTypes::Float),
+        "bool" => text.parse().ok().map(BaseTypes::Bool),
+        "char" => {
+            let mut chars = text.chars();
+            match (chars.next(), chars.next()) {
+                (Some(c), None) => Some(BaseTypes::Char(c)),
+                _ => None,
+            }
+        }
+        _ => Some(BaseTypes::StringWrapper(text.to_string())),
+    };
+    value.ok_or_else(|| {
+        format!(
+            "Runtime Error: Cannot parse \"{}\" as {} {}",
+            text,
+            article(target),
+            target
+        )
+    })
+}
+
+///
+///Whether name is a conversion method, to_string or parse with the type to parse to
+///
+pub fn is_conversion(name: &str) -> bool {
+    name == "to_string" || name == "parse" || name.starts_with("parse::<")
+}
+
+///
+///The type the conversion method name gives, None when name is no conversion
+///
+pub fn conversion_type(name: &str) -> Option<String> {
+    match name {
+        "to_string" => Some("string".to_string()),
+        _ => name
+            .strip_prefix("parse::<")
+            .and_then(|type_name| target_type(type_name.strip_suffix('>')?).ok())
+            .map(str::to_string),
+    }
+}
+
+///
+///Calls the conversion method name on value, like value.to_string() or value.parse::<int>()
+///
+pub fn call_conversion(
+    value: BaseTypes,
+    name: &str,
+    arguments: &[BaseTypes],
+) -> Result<BaseTypes, String> {
+    if !arguments.is_empty() {
+        return Err(format!(
+            "Runtime Error: {}() takes no arguments but was given {}",
+            name,
+            arguments.len()
+        ));
+    }
+    let parse_type = name
+        .strip_prefix("parse::<")
+        .and_then(|type_name| type_name.strip_suffix('>'));
+    match (name, value) {
+        ("to_string", value) => Ok(BaseTypes::StringWrapper(value.to_string())),
+        (_, BaseTypes::StringWrapper(text)) => match parse_type {
+            Some(type_name) => parse(&text, type_name),
+            None => Err(
+                "Syntax Error: parse needs the type to parse to, like parse::<int>()".to_string(),
+            ),
+        },
+        (_, value) => Err(format!(
+            "Type Error: parse needs a string, found {}",
+            type_name_of(&value)
+        )),
+    }
+}
+
+#[cfg(test)]
+mod convert_tests {
+    use super::{call_conversion, cast, parse};
+    use crate::base_variable::base_types::BaseTypes;
+
+    #[test]
+    fn test_cast() {
+        assert_eq!(cast(BaseTypes::Float(-2.9), "int"), Ok(BaseTypes::Int(-2)));
+        assert_eq!(cast(BaseTypes::Int(3), "float"), Ok(BaseTypes::Float(3.0)));
+        assert_eq!(cast(BaseTypes::Char('A'), "int"), Ok(BaseTypes::Int(65)));
+        assert_eq!(cast(BaseTypes::Int(97), "char"), Ok(BaseTypes::Char('a')));
+        assert_eq!(cast(BaseTypes::Bool(true), "int"), Ok(BaseTypes::Int(1)));
+        assert_eq!(
+            cast(BaseTypes::StringWrapper(" 2.5 ".to_string()), "float"),
+            Ok(BaseTypes::Float(2.5))
+        );
+        assert_eq!(
+            cast(BaseTypes::Float(1.5), "string"),
+            Ok(BaseTypes::StringWrapper("1.5".to_string()))
+        );
+        assert!(cast(BaseTypes::Float(f64::NAN), "int").is_err());
+        assert!(cast(BaseTypes::Float(1e10), "int").is_err());
+        assert!(cast(BaseTypes::Int(-1), "char").is_err());
+        assert!(cast(BaseTypes::Int(1), "bool").is_err());
+        assert!(cast(BaseTypes::Int(1), "array").is_err());
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(parse("42", "int"), Ok(BaseTypes::Int(42)));
+        assert_eq!(parse("true", "boolean"), Ok(BaseTypes::Bool(true)));
+        assert_eq!(parse("x", "char"), Ok(BaseTypes::Char('x')));
+        assert_eq!(
+            parse("4.2", "int"),
+            Err("Runtime Error: Cannot parse \"4.2\" as an int".to_string())
+        );
+        assert!(parse("xy", "char").is_err());
+        assert!(parse("yes", "bool").is_err());
+
+        let text = BaseTypes::StringWrapper("7".to_string());
+        assert_eq!(
+            call_conversion(text.clone(), "parse::<int>", &[]),
+            Ok(BaseTypes::Int(7))
+        );
+        assert!(call_conversion(text, "parse", &[]).is_err());
+        assert!(call_conversion(BaseTypes::Int(7), "parse::<int>", &[]).is_err());
+        assert_eq!(
+            call_conversion(BaseTypes::Int(7), "to_string", &[]),
+            Ok(BaseTypes::StringWrapper("7".to_string()))
+        );
+    }
+}
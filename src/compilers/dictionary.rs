@@ -0,0 +1,153 @@
+/*
+* This file holds the methods that can be called on dictionaries from Jist code, like
+* d.has_key("a") or d.get_or("a", 0)
+*/
+use crate::base_variable::base_types::{BaseTypes, GetType};
+use crate::collection::collections::{Array, Dictionary};
+use crate::compilers::array::expect_arguments;
+
+const METHODS: [&str; 12] = [
+    "len", "get", "set", "has_key", "remove", "keys", "values", "entries", "get_or", "merge",
+    "is_empty", "copy",
+];
+
+// Methods that change the dictionary they are called on
+const MUTATING_METHODS: [&str; 3] = ["set", "remove", "merge"];
+
+pub fn is_dictionary_method(name: &str) -> bool {
+    METHODS.contains(&name)
+}
+
+pub fn mutates(name: &str) -> bool {
+    MUTATING_METHODS.contains(&name)
+}
+
+///
+///Looks a key up, d[key] and d.get(key) fail when the key is missing
+///
+pub fn lookup(dictionary: &Dictionary, key: &BaseTypes) -> Result<BaseTypes, String> {
+    dictionary.get(key).cloned().ok_or_else(|| missing_key(key))
+}
+
+///
+///The error for a key the dictionary does not have, a string key is shown quoted
+///
+pub fn missing_key(key: &BaseTypes) -> String {
+    format!("Runtime Error: Key {} not found", describe(key))
+}
+
+fn describe(key: &BaseTypes) -> String {
+    match key {
+        BaseTypes::StringWrapper(s) => format!("\"{}\"", s),
+        BaseTypes::Char(c) => format!("'{}'", c),
+        other => other.to_string(),
+    }
+}
+
+fn to_array(value_type: &BaseTypes, data: Vec<BaseTypes>) -> BaseTypes {
+    BaseTypes::from(Array::new(String::new(), value_type.clone(), data))
+}
+
+///
+///Calls the method name on the dictionary, set, remove and merge change it in place
+///
+pub fn call_dictionary_method(
+    dictionary: &mut Dictionary,
+    name: &str,
+    arguments: Vec<BaseTypes>,
+) -> Result<BaseTypes, String> {
+    match name {
+        "len" => {
+            expect_arguments(name, &arguments, (0, 0))?;
+            Ok(BaseTypes::Int(dictionary.len() as i32))
+        }
+        "is_empty" => {
+            expect_arguments(name, &arguments, (0, 0))?;
+            Ok(BaseTypes::Bool(dictionary.is_empty()))
+        }
+        "copy" => {
+            expect_arguments(name, &arguments, (0, 0))?;
+            let mut copy = dictionary.clone();
+            copy.name = String::new();
+            Ok(BaseTypes::from(copy))
+        }
+        "get" => {
+            expect_arguments(name, &arguments, (1, 1))?;
+            lookup(dictionary, &arguments[0])
+        }
+        "get_or" => {
+            expect_arguments(name, &arguments, (2, 2))?;
+            Ok(dictionary
+                .get(&arguments[0])
+                .cloned()
+                .unwrap_or_else(|| arguments[1].clone()))
+        }
+        "has_key" => {
+            expect_arguments(name, &arguments, (1, 1))?;
+            Ok(BaseTypes::Bool(dictionary.contains_key(&arguments[0])))
+        }
+        "set" => {
+            expect_arguments(name, &arguments, (2, 2))?;
+            let mut arguments = arguments.into_iter();
+            let key = dictionary.check_key(arguments.next().unwrap())?;
+            let value = dictionary.check_value(arguments.next().unwrap())?;
+            dictionary.add(key, value);
+            Ok(BaseTypes::Null)
+        }
+        "remove" => {
+            expect_arguments(name, &arguments, (1, 1))?;
+            dictionary
+                .remove(&arguments[0])
+                .map(|(_, value)| value)
+                .ok_or_else(|| missing_key(&arguments[0]))
+        }
+        "keys" => {
+            expect_arguments(name, &arguments, (0, 0))?;
+            let keys = dictionary.keys().into_iter().cloned().collect();
+            Ok(to_array(&dictionary.types.0, keys))
+        }
+        "values" => {
+            expect_arguments(name, &arguments, (0, 0))?;
+            let values = dictionary.values().into_iter().cloned().collect();
+            Ok(to_array(&dictionary.types.1, values))
+        }
+        "entries" => {
+            // entries are [key, value] pairs in insertion order
+            expect_arguments(name, &arguments, (0, 0))?;
+            let (key_type, value_type) = &dictionary.types;
+            let pair_type = if key_type == value_type {
+                key_type.clone()
+            } else {
+                "any".into()
+            };
+            let entries = dictionary
+                .entries()
+                .map(|(key, value)| to_array(&pair_type, vec![key.clone(), value.clone()]))
+                .collect();
+            Ok(to_array(&format!("array<{}>", pair_type).into(), entries))
+        }
+        "merge" => {
+            // entries of the other dictionary win when both have a key
+            expect_arguments(name, &arguments, (1, 1))?;
+            let other = match &arguments[0] {
+                BaseTypes::Dictionary(other) => other.borrow(),
+                other => {
+                    return Err(format!(
+                        "Runtime Error: merge(): Expected a Dict but found {}",
+                        other.GetType()
+                    ))
+                }
+            };
+            for (key, value) in other.entries() {
+                let key = dictionary.check_key(key.clone())?;
+                let value = dictionary.check_value(value.clone())?;
+                dictionary.add(key, value);
+            }
+            Ok(BaseTypes::from(dictionary.clone()))
+        }
+        _ => Err(format!(
+            "Runtime Error: Dictionaries have no method '{}'",
+            name
+        )),
+    }
+}
@@ -0,0 +1,1171 @@
+/*
+* This file evaluates expressions such as 1 + 2 * x, name.to_uppercase(), line[0..3],
+* total as float or numbers.map(n => n * 2)
+* The ASTNodes are first parsed into an Expression tree using precedence climbing and then
+* evaluated, so && and || only run their right side when they need to
+*/
+use crate::base_variable::base_types::{BaseTypes, GetType};
+use crate::base_variable::variable::Variable;
+use crate::base_variable::variables::{begin_scope, end_scope, find_index, VARIABLE_STACK};
+use crate::collection::collections::{common_type, type_name_of, Array, Dictionary};
+use crate::compilers::array::{
+    call_array_method, is_array_method, mutates as array_mutates, Argument,
+};
+use crate::compilers::convert::{call_conversion, cast, conversion_type, is_conversion};
+use crate::compilers::dictionary::{call_dictionary_method, is_dictionary_method, lookup};
+use crate::compilers::fs::{
+    call_fs_function, call_lines_method, is_fs_function, is_lines_method, MODULE,
+};
+use crate::compilers::function::{call_user_function, find_user_function, get_function_result};
+use crate::compilers::gc::{call_gc_function, is_gc_function};
+use crate::compilers::math::{module_constant, CONSTANTS, MODULE as MATH};
+use crate::compilers::process::{call_process_function, is_process_function};
+use crate::compilers::set::{call_set_method, is_set_method};
+use crate::function_map::FUNCTIONS;
+use crate::heap::same;
+use crate::node::nodes::ASTNode;
+
+///
+///The parsed form of an expression, jist lint walks it to find the names an expression uses
+///
+#[derive(Debug, Clone)]
+pub enum Expression {
+    Value(BaseTypes),
+    Variable(String),
+    Call(String, Vec<Expression>),
+    Method(Box<Expression>, String, Vec<Expression>),
+    Index(Box<Expression>, Box<Expression>),
+    Slice(
+        Box<Expression>,
+        Option<Box<Expression>>,
+        Option<Box<Expression>>,
+    ),
+    Unary(String, Box<Expression>),
+    Binary(Box<Expression>, String, Box<Expression>),
+    Cast(Box<Expression>, String),
+    Lambda(Vec<String>, Box<Expression>),
+    List(Vec<Expression>),
+    Map(Vec<(Expression, Expression)>),
+    Tuple(Vec<Expression>),
+}
+
+// Unary operators bind tighter than every binary operator, -x as int converts -x
+const UNARY_POWER: u8 = 7;
+
+fn binding_power(operator: &str) -> Option<u8> {
+    match operator {
+        "as" => Some(UNARY_POWER),
+        "||" => Some(1),
+        "&&" => Some(2),
+        "==" | "!=" => Some(3),
+        "<" | ">" | "<=" | ">=" => Some(4),
+        "+" | "-" => Some(5),
+        "*" | "/" | "%" => Some(6),
+        _ => None,
+    }
+}
+
+struct ExpressionParser<'a> {
+    nodes: &'a [ASTNode],
+    position: usize,
+    // the index of the node parsing stopped at and what was expected there
+    failed: Option<(usize, String)>,
+}
+
+impl<'a> ExpressionParser<'a> {
+    fn peek(&self) -> Option<&'a ASTNode> {
+        self.nodes.get(self.position)
+    }
+
+    fn next(&mut self) -> Option<&'a ASTNode> {
+        let node = self.nodes.get(self.position);
+        self.position += 1;
+        node
+    }
+
+    ///
+    ///The error for the node read last, which is not what was expected
+    ///
+    fn unexpected(&mut self, what: &str) -> String {
+        self.unexpected_at(self.position - 1, what)
+    }
+
+    fn unexpected_at(&mut self, at: usize, what: &str) -> String {
+        self.failed = Some((at, what.to_string()));
+        expected(what, self.nodes.get(at))
+    }
+
+    fn peek_operator(&self, operator: &str) -> bool {
+        matches!(self.peek(), Some(ASTNode::Operator(o)) if o.operator == operator)
+    }
+
+    fn parse_expression(&mut self, min_power: u8) -> Result<Expression, String> {
+        let mut left = self.parse_prefix()?;
+
+        while let Some(ASTNode::Operator(o)) = self.peek() {
+            let power = match binding_power(&o.operator) {
+                Some(power) if power > min_power => power,
+                _ => break,
+            };
+            self.position += 1;
+            // the right of an as is the type to convert to
+            if o.operator == "as" {
+                let type_name = match self.next() {
+                    Some(ASTNode::VariableCall(v)) => v.name.clone(),
+                    _ => return Err(self.unexpected("a type after 'as'")),
+                };
+                left = Expression::Cast(Box::new(left), type_name);
+                continue;
+            }
+            let right = self.parse_expression(power)?;
+            left = Expression::Binary(Box::new(left), o.operator.clone(), Box::new(right));
+        }
+
+        Ok(left)
+    }
+
+    fn parse_prefix(&mut self) -> Result<Expression, String> {
+        let expression = match self.next() {
+            Some(ASTNode::Int(n)) => Expression::Value(BaseTypes::Int(n.value)),
+            Some(ASTNode::Float(f)) => Expression::Value(BaseTypes::Float(f.value.into())),
+            Some(ASTNode::String(s)) => {
+                Expression::Value(BaseTypes::StringWrapper(s.value.clone()))
+            }
+            Some(ASTNode::Char(c)) => Expression::Value(BaseTypes::Char(c.value)),
+            Some(ASTNode::Bool(b)) => Expression::Value(BaseTypes::Bool(b.value)),
+            Some(ASTNode::VariableCall(v)) => {
+                if matches!(self.peek(), Some(ASTNode::FatArrow)) {
+                    self.position += 1;
+                    let body = self.parse_expression(0)?;
+                    return Ok(Expression::Lambda(vec![v.name.clone()], Box::new(body)));
+                }
+                Expression::Variable(v.name.clone())
+            }
+            Some(ASTNode::FunctionCall(f)) => {
+                let arguments = self.parse_arguments()?;
+                // receiver.method(...) arrives as a single call named "receiver.method"
+                match f.name.split_once('.') {
+                    None => Expression::Call(f.name.clone(), arguments),
+                    Some((receiver, method)) if !receiver.is_empty() && !method.contains('.') => {
+                        Expression::Method(
+                            Box::new(Expression::Variable(receiver.to_string())),
+                            method.to_string(),
+                            arguments,
+                        )
+                    }
+                    _ => {
+                        self.failed = Some((self.position - 1, "a function name".to_string()));
+                        return Err(format!("Syntax Error: Unexpected method call '{}'", f.name));
+                    }
+                }
+            }
+            Some(ASTNode::LeftParenthesis) => {
+                if let Some(parameters) = self.lambda_parameters() {
+                    let body = self.parse_expression(0)?;
+                    return Ok(Expression::Lambda(parameters, Box::new(body)));
+                }
+                let inner = self.parse_expression(0)?;
+                match self.next() {
+                    Some(ASTNode::RightParenthesis) => inner,
+                    // a comma makes it a tuple, (1, "one")
+                    Some(ASTNode::ArgumentSeparator) => {
+                        let mut elements = vec![inner];
+                        loop {
+                            elements.push(self.parse_expression(0)?);
+                            match self.next() {
+                                Some(ASTNode::ArgumentSeparator) => {}
+                                Some(ASTNode::RightParenthesis) => break,
+                                _ => return Err(self.unexpected("',' or ')'")),
+                            }
+                        }
+                        Expression::Tuple(elements)
+                    }
+                    _ => return Err(self.unexpected("')'")),
+                }
+            }
+            Some(ASTNode::LeftBracket) => Expression::List(self.parse_list()?),
+            Some(ASTNode::LeftCurly) => Expression::Map(self.parse_map()?),
+            Some(ASTNode::Operator(o)) if o.operator == "-" || o.operator == "!" => {
+                let operand = self.parse_expression(UNARY_POWER)?;
+                return Ok(Expression::Unary(o.operator.clone(), Box::new(operand)));
+            }
+            _ => return Err(self.unexpected("a value")),
+        };
+
+        self.parse_postfix(expression)
+    }
+
+    ///
+    ///Reads the (a, b) => part of a lambda once its ( has been read. Nothing is consumed when
+    ///the parenthesis turns out to be an ordinary grouping
+    ///
+    fn lambda_parameters(&mut self) -> Option<Vec<String>> {
+        let mut parameters = Vec::new();
+        let mut position = self.position;
+        loop {
+            match self.nodes.get(position) {
+                Some(ASTNode::VariableCall(v)) => parameters.push(v.name.clone()),
+                Some(ASTNode::RightParenthesis) if parameters.is_empty() => break,
+                _ => return None,
+            }
+            position += 1;
+            match self.nodes.get(position) {
+                Some(ASTNode::ArgumentSeparator) => position += 1,
+                Some(ASTNode::RightParenthesis) => break,
+                _ => return None,
+            }
+        }
+        if !matches!(self.nodes.get(position + 1), Some(ASTNode::FatArrow)) {
+            return None;
+        }
+        self.position = position + 2;
+        Some(parameters)
+    }
+
+    ///
+    ///Handles the method calls and indexes that can follow a value, like
+    ///s.trim().len() or s[1..3]
+    ///
+    fn parse_postfix(&mut self, mut expression: Expression) -> Result<Expression, String> {
+        loop {
+            match self.peek() {
+                Some(ASTNode::FunctionCall(f)) if f.name.starts_with('.') => {
+                    self.position += 1;
+                    let method = f.name[1..].to_string();
+                    if method.is_empty() || method.contains('.') {
+                        self.failed = Some((self.position - 1, "a method name".to_string()));
+                        return Err(format!("Syntax Error: Unexpected method call '{}'", f.name));
+                    }
+                    let arguments = self.parse_arguments()?;
+                    expression = Expression::Method(Box::new(expression), method, arguments);
+                }
+                Some(ASTNode::LeftBracket) => {
+                    self.position += 1;
+                    expression = self.parse_index(expression)?;
+                }
+                _ => return Ok(expression),
+            }
+        }
+    }
+
+    fn parse_index(&mut self, target: Expression) -> Result<Expression, String> {
+        let start = if self.peek_operator("..") {
+            None
+        } else {
+            Some(Box::new(self.parse_expression(0)?))
+        };
+
+        let expression = if self.peek_operator("..") {
+            self.position += 1;
+            let end = if matches!(self.peek(), Some(ASTNode::RightBracket)) {
+                None
+            } else {
+                Some(Box::new(self.parse_expression(0)?))
+            };
+            Expression::Slice(Box::new(target), start, end)
+        } else {
+            match start {
+                Some(index) => Expression::Index(Box::new(target), index),
+                None => return Err(self.unexpected_at(self.position, "an index")),
+            }
+        };
+
+        match self.next() {
+            Some(ASTNode::RightBracket) => Ok(expression),
+            _ => Err(self.unexpected("']'")),
+        }
+    }
+
+    ///
+    ///Reads the elements of an array literal once its [ has been read
+    ///
+    fn parse_list(&mut self) -> Result<Vec<Expression>, String> {
+        let mut elements = Vec::new();
+        if matches!(self.peek(), Some(ASTNode::RightBracket)) {
+            self.position += 1;
+            return Ok(elements);
+        }
+        loop {
+            elements.push(self.parse_expression(0)?);
+            match self.next() {
+                Some(ASTNode::ArgumentSeparator) => {}
+                Some(ASTNode::RightBracket) => return Ok(elements),
+                _ => return Err(self.unexpected("',' or ']'")),
+            }
+        }
+    }
+
+    ///
+    ///Reads the key => value entries of a dictionary literal once its { has been read
+    ///
+    fn parse_map(&mut self) -> Result<Vec<(Expression, Expression)>, String> {
+        let mut entries = Vec::new();
+        if matches!(self.peek(), Some(ASTNode::RightCurly)) {
+            self.position += 1;
+            return Ok(entries);
+        }
+        loop {
+            let key = self.parse_expression(0)?;
+            match self.next() {
+                Some(ASTNode::FatArrow) => {}
+                _ => return Err(self.unexpected("'=>'")),
+            }
+            entries.push((key, self.parse_expression(0)?));
+            match self.next() {
+                Some(ASTNode::ArgumentSeparator) => {}
+                Some(ASTNode::RightCurly) => return Ok(entries),
+                _ => return Err(self.unexpected("',' or '}'")),
+            }
+        }
+    }
+
+    fn parse_arguments(&mut self) -> Result<Vec<Expression>, String> {
+        match self.next() {
+            Some(ASTNode::LeftParenthesis) => {}
+            _ => return Err(self.unexpected("'('")),
+        }
+
+        let mut arguments = Vec::new();
+        if matches!(self.peek(), Some(ASTNode::RightParenthesis)) {
+            self.position += 1;
+            return Ok(arguments);
+        }
+
+        loop {
+            arguments.push(self.parse_expression(0)?);
+            match self.next() {
+                Some(ASTNode::ArgumentSeparator) => {}
+                Some(ASTNode::RightParenthesis) => return Ok(arguments),
+                _ => return Err(self.unexpected("',' or ')'")),
+            }
+        }
+    }
+}
+
+fn expected(what: &str, found: Option<&ASTNode>) -> String {
+    match found {
+        Some(node) => format!("Syntax Error: Expected {}, found {}", what, node),
+        None => format!(
+            "Syntax Error: Expected {}, found the end of the expression",
+            what
+        ),
+    }
+}
+
+///
+///Evaluates the whole slice of nodes as one expression
+///
+pub fn evaluate_expression(nodes: &[ASTNode]) -> Result<BaseTypes, String> {
+    let (value, read) = evaluate_leading_expression(nodes)?;
+    match nodes.get(read) {
+        None | Some(ASTNode::SemiColon) => Ok(value),
+        found => Err(expected("an operator", found)),
+    }
+}
+
+///
+///Evaluates the expression at the start of the nodes and returns its value along with the
+///number of nodes it used, anything after it is left for the caller
+///
+pub fn evaluate_leading_expression(nodes: &[ASTNode]) -> Result<(BaseTypes, usize), String> {
+    let mut parser = ExpressionParser {
+        nodes,
+        position: 0,
+        failed: None,
+    };
+    let expression = parser.parse_expression(0)?;
+    let value = evaluate(&expression)?;
+    Ok((value, parser.position))
+}
+
+///
+///Where the whole slice of nodes stops reading as one expression: the index of the node that
+///cannot be used there, the length of the slice when it ends too early, and what was expected
+///
+pub fn expression_error(nodes: &[ASTNode]) -> Option<(usize, String)> {
+    let mut parser = ExpressionParser {
+        nodes,
+        position: 0,
+        failed: None,
+    };
+    match parser.parse_expression(0) {
+        Ok(_) if parser.position < nodes.len() => {
+            Some((parser.position, "an operator".to_string()))
+        }
+        Ok(_) => None,
+        Err(_) => parser.failed,
+    }
+}
+
+///
+///Parses the whole slice of nodes as one expression without running it
+///
+pub fn parse_expression(nodes: &[ASTNode]) -> Result<Expression, String> {
+    let mut parser = ExpressionParser {
+        nodes,
+        position: 0,
+        failed: None,
+    };
+    let expression = parser.parse_expression(0)?;
+    match nodes.get(parser.position) {
+        None | Some(ASTNode::SemiColon) => Ok(expression),
+        found => Err(expected("an operator", found)),
+    }
+}
+
+///
+///Parses the nodes as one expression without running it. When the expression is made of
+///literals only its value is returned too, since working it out cannot change anything
+///
+pub fn check_expression(nodes: &[ASTNode]) -> Result<Option<BaseTypes>, String> {
+    constant_value(&parse_expression(nodes)?)
+}
+
+///
+///The value of an expression made of literals and math constants only, None when it needs a
+///variable or a call
+///
+pub fn constant_value(expression: &Expression) -> Result<Option<BaseTypes>, String> {
+    if is_constant(expression) {
+        return evaluate(expression).map(Some);
+    }
+    Ok(None)
+}
+
+fn is_constant(expression: &Expression) -> bool {
+    match expression {
+        Expression::Value(_) => true,
+        Expression::Variable(name) => module_constant(name).is_some(),
+        Expression::Unary(_, operand) | Expression::Cast(operand, _) => is_constant(operand),
+        Expression::Binary(left, _, right) => is_constant(left) && is_constant(right),
+        Expression::Index(target, index) => is_constant(target) && is_constant(index),
+        Expression::List(elements) | Expression::Tuple(elements) => {
+            elements.iter().all(is_constant)
+        }
+        Expression::Map(entries) => entries
+            .iter()
+            .all(|(key, value)| is_constant(key) && is_constant(value)),
+        _ => false,
+    }
+}
+
+///
+///The type an expression has when it can be told without running it. type_of gives the
+///declared type of a variable or the return type of a function, when one is known
+///
+pub fn static_type(
+    expression: &Expression,
+    type_of: &dyn Fn(&str) -> Option<String>,
+) -> Option<String> {
+    let is_number = |type_name: &str| type_name == "int" || type_name == "float";
+    match expression {
+        Expression::Value(value) => Some(type_name_of(value)),
+        Expression::Variable(name) if module_constant(name).is_some() => Some("float".to_string()),
+        Expression::Variable(name) | Expression::Call(name, _) => type_of(name),
+        Expression::Unary(operator, operand) => match operator.as_str() {
+            "!" => Some("bool".to_string()),
+            _ => static_type(operand, type_of),
+        },
+        Expression::Cast(_, type_name) => match type_name.as_str() {
+            "boolean" => Some("bool".to_string()),
+            _ => Some(type_name.clone()),
+        },
+        Expression::Method(_, name, _) => conversion_type(name),
+        Expression::Binary(left, operator, right) => match operator.as_str() {
+            "==" | "!=" | "<" | ">" | "<=" | ">=" | "&&" | "||" => Some("bool".to_string()),
+            _ => {
+                let left = static_type(left, type_of)?;
+                let right = static_type(right, type_of)?;
+                if left == "int" && right == "int" {
+                    Some(left)
+                } else if is_number(&left) && is_number(&right) {
+                    Some("float".to_string())
+                } else if operator == "+"
+                    && matches!(left.as_str(), "string" | "char")
+                    && matches!(right.as_str(), "string" | "char")
+                {
+                    Some("string".to_string())
+                } else {
+                    None
+                }
+            }
+        },
+        _ => None,
+    }
+}
+
+///
+///Whether name can be called as a method, on a value of some type or from the function map
+///
+fn is_method(name: &str) -> bool {
+    is_conversion(name)
+        || is_array_method(name)
+        || is_dictionary_method(name)
+        || is_set_method(name)
+        || is_lines_method(name)
+        || FUNCTIONS.lock().unwrap().contains_key(name)
+}
+
+///
+///A variable or function an expression names that does not exist
+///
+#[derive(Debug, Clone, PartialEq)]
+pub enum Undefined {
+    Variable(String),
+    Function(String),
+}
+
+impl Undefined {
+    pub fn name(&self) -> &str {
+        match self {
+            Undefined::Variable(name) | Undefined::Function(name) => name,
+        }
+    }
+}
+
+///
+///The error running the expression gives for it
+///
+impl std::fmt::Display for Undefined {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Undefined::Variable(name) => {
+                write!(f, "Runtime Error: Variable '{}' is not defined", name)
+            }
+            Undefined::Function(name) => {
+                write!(f, "Runtime Error: Function '{}' is not defined", name)
+            }
+        }
+    }
+}
+
+///
+///The first variable or function the expression names that does not exist. is_defined tells
+///whether a variable is declared and is_function whether a function is declared in the
+///program, builtins are known here
+///
+pub fn undefined_name(
+    expression: &Expression,
+    is_defined: &dyn Fn(&str) -> bool,
+    is_function: &dyn Fn(&str) -> bool,
+) -> Option<Undefined> {
+    let all = |expressions: &[Expression]| {
+        expressions
+            .iter()
+            .find_map(|e| undefined_name(e, is_defined, is_function))
+    };
+    let function = |name: &str| Undefined::Function(name.to_string());
+    match expression {
+        Expression::Value(_) => None,
+        // a module constant is checked when it is read, a function can be passed as a callback
+        Expression::Variable(name)
+            if name.starts_with(&format!("{}.", MATH))
+                || is_defined(name)
+                || is_function(name)
+                || FUNCTIONS.lock().unwrap().contains_key(name.as_str()) =>
+        {
+            None
+        }
+        Expression::Variable(name) => Some(Undefined::Variable(name.clone())),
+        Expression::Call(name, arguments) => all(arguments).or_else(|| {
+            let defined = is_function(name)
+                || is_process_function(name)
+                || is_gc_function(name)
+                || FUNCTIONS.lock().unwrap().contains_key(name.as_str())
+                || (!arguments.is_empty() && is_method(name));
+            (!defined).then(|| function(name))
+        }),
+        Expression::Method(receiver, name, arguments) => {
+            let module = matches!(&**receiver, Expression::Variable(module)
+                if module == MODULE && is_fs_function(name) && !is_defined(module));
+            let receiver = match module {
+                true => None,
+                false => undefined_name(receiver, is_defined, is_function),
+            };
+            receiver
+                .or_else(|| all(arguments))
+                .or_else(|| (!module && !is_method(name)).then(|| function(name)))
+        }
+        Expression::Index(target, index) => undefined_name(target, is_defined, is_function)
+            .or_else(|| undefined_name(index, is_defined, is_function)),
+        Expression::Slice(target, start, end) => undefined_name(target, is_defined, is_function)
+            .or_else(|| {
+                [start, end]
+                    .into_iter()
+                    .flatten()
+                    .find_map(|e| undefined_name(e, is_defined, is_function))
+            }),
+        Expression::Unary(_, operand) | Expression::Cast(operand, _) => {
+            undefined_name(operand, is_defined, is_function)
+        }
+        Expression::Binary(left, _, right) => undefined_name(left, is_defined, is_function)
+            .or_else(|| undefined_name(right, is_defined, is_function)),
+        Expression::Lambda(parameters, body) => {
+            let is_defined = |name: &str| parameters.iter().any(|p| p == name) || is_defined(name);
+            undefined_name(body, &is_defined, is_function)
+        }
+        Expression::List(elements) | Expression::Tuple(elements) => all(elements),
+        Expression::Map(entries) => entries.iter().find_map(|(key, value)| {
+            undefined_name(key, is_defined, is_function)
+                .or_else(|| undefined_name(value, is_defined, is_function))
+        }),
+    }
+}
+
+///
+///Decides whether a condition holds. Only a bool is a condition, an int is not taken for one
+///
+pub fn expect_bool(value: &BaseTypes) -> Result<bool, String> {
+    match value {
+        BaseTypes::Bool(b) => Ok(*b),
+        other => Err(format!(
+            "Type Error: Expected a bool condition, found {}",
+            type_name_of(other)
+        )),
+    }
+}
+
+///
+///The bool operand of !, && or ||
+///
+fn bool_operand(operator: &str, value: &BaseTypes) -> Result<bool, String> {
+    match value {
+        BaseTypes::Bool(b) => Ok(*b),
+        other => Err(format!(
+            "Type Error: Cannot apply '{}' to {}, it needs a bool",
+            operator,
+            type_name_of(other)
+        )),
+    }
+}
+
+fn find_variable(name: &str) -> Result<BaseTypes, String> {
+    match find_index(name) {
+        Some(index) => Ok(unsafe { (&*std::ptr::addr_of!(VARIABLE_STACK))[index].value.clone() }),
+        None => Err(format!("Runtime Error: Variable '{}' is not defined", name)),
+    }
+}
+
+///
+///Runs f on the value of the variable called name without copying it, so lookups in large
+///collections stay cheap. None when there is no such variable
+///
+fn with_variable<R>(name: &str, f: impl FnOnce(&mut BaseTypes) -> R) -> Option<R> {
+    let index = find_index(name)?;
+    Some(f(unsafe {
+        &mut (&mut *std::ptr::addr_of_mut!(VARIABLE_STACK))[index].value
+    }))
+}
+
+///
+///Runs a lambda with its parameters bound as variables, they are removed again afterwards
+///
+fn call_lambda(
+    parameters: &[String],
+    body: &Expression,
+    values: Vec<BaseTypes>,
+) -> Result<BaseTypes, String> {
+    if parameters.len() != values.len() {
+        return Err(format!(
+            "Runtime Error: Function takes {} argument(s) but was given {}",
+            parameters.len(),
+            values.len()
+        ));
+    }
+    let depth = begin_scope();
+    for (name, value) in parameters.iter().zip(values) {
+        let variable = Variable::new(name.clone(), value.clone(), value);
+        unsafe { (*std::ptr::addr_of_mut!(VARIABLE_STACK)).push(variable) };
+    }
+    let result = evaluate(body);
+    end_scope(depth);
+    result
+}
+
+///
+///Turns a method argument into a value, or into a callback for lambdas and the names of
+///built-in functions
+///
+fn argument(expression: &Expression) -> Result<Argument<'_>, String> {
+    match expression {
+        Expression::Lambda(parameters, body) => Ok(Argument::Function(Box::new(|values| {
+            call_lambda(parameters, body, values)
+        }))),
+        Expression::Variable(name) => match find_variable(name) {
+            Ok(value) => Ok(Argument::Value(value)),
+            Err(_) if find_user_function(name).is_some() => {
+                let function = find_user_function(name).unwrap();
+                Ok(Argument::Function(Box::new(move |values| {
+                    call_user_function(&function, values)
+                })))
+            }
+            Err(_) if FUNCTIONS.lock().unwrap().contains_key(name.as_str()) => {
+                Ok(Argument::Function(Box::new(|mut values| {
+                    get_function_result(name.clone(), &mut values)
+                })))
+            }
+            Err(e) => Err(e),
+        },
+        other => Ok(Argument::Value(evaluate(other)?)),
+    }
+}
+
+///
+///Calls name with receiver as its first argument. Arrays get their own methods, everything
+///else goes to the function map
+///
+fn call_method(
+    receiver: &Expression,
+    name: &str,
+    arguments: &[Expression],
+) -> Result<BaseTypes, String> {
+    // fs.read_to_string(path) is a module call unless a variable is named fs
+    if let Expression::Variable(module) = receiver {
+        if module == MODULE && is_fs_function(name) && with_variable(module, |_| ()).is_none() {
+            return call_fs_function(name, evaluate_all(arguments)?);
+        }
+    }
+    if is_conversion(name) {
+        return call_conversion(evaluate(receiver)?, name, &evaluate_all(arguments)?);
+    }
+
+    let value = evaluate(receiver)?;
+    match &value {
+        BaseTypes::Array(shared) if is_array_method(name) => {
+            let arguments = arguments
+                .iter()
+                .map(argument)
+                .collect::<Result<Vec<Argument>, String>>()?;
+            // the method runs on a copy so a lambda can still read the array, a change is
+            // written back afterwards and shows through every name of the array
+            let mut array = shared.borrow().clone();
+            let result = call_array_method(&mut array, name, arguments)?;
+            if array_mutates(name) {
+                *shared.borrow_mut() = array;
+            }
+            return Ok(result);
+        }
+        // dictionaries and sets are used in place, so their hash index is not copied for
+        // every call
+        BaseTypes::Dictionary(shared) if is_dictionary_method(name) => {
+            let values = detach(evaluate_all(arguments)?, &value);
+            return call_dictionary_method(&mut shared.borrow_mut(), name, values);
+        }
+        BaseTypes::Set(shared) if is_set_method(name) => {
+            let values = detach(evaluate_all(arguments)?, &value);
+            return call_set_method(&mut shared.borrow_mut(), name, values);
+        }
+        BaseTypes::Lines(shared) if is_lines_method(name) => {
+            return call_lines_method(&mut shared.borrow_mut(), name, evaluate_all(arguments)?);
+        }
+        _ => {}
+    }
+    let mut values = vec![value];
+    values.extend(evaluate_all(arguments)?);
+    get_function_result(name.to_string(), &mut values)
+}
+
+///
+///Copies the arguments that are the receiver itself, like the d in d.merge(d), since the
+///receiver cannot be read while its method changes it
+///
+fn detach(arguments: Vec<BaseTypes>, receiver: &BaseTypes) -> Vec<BaseTypes> {
+    arguments
+        .into_iter()
+        .map(|argument| match &argument {
+            BaseTypes::Dictionary(dictionary) if same(&argument, receiver) => {
+                dictionary.borrow().clone().into()
+            }
+            BaseTypes::Set(set) if same(&argument, receiver) => set.borrow().clone().into(),
+            _ => argument,
+        })
+        .collect()
+}
+
+fn evaluate_all(expressions: &[Expression]) -> Result<Vec<BaseTypes>, String> {
+    expressions.iter().map(evaluate).collect()
+}
+
+fn evaluate(expression: &Expression) -> Result<BaseTypes, String> {
+    match expression {
+        Expression::Value(value) => Ok(value.clone()),
+        Expression::Variable(name) => match name.split_once('.') {
+            Some((module, constant)) if module == MATH => {
+                module_constant(name).map(BaseTypes::Float).ok_or_else(|| {
+                    format!(
+                        "Runtime Error: The math module has no constant '{}', expected one of {}",
+                        constant,
+                        CONSTANTS.join(", ")
+                    )
+                })
+            }
+            _ => find_variable(name),
+        },
+        // functions declared in the program shadow the builtins
+        Expression::Call(name, arguments) if find_user_function(name).is_some() => {
+            let function = find_user_function(name).unwrap();
+            call_user_function(&function, evaluate_all(arguments)?)
+        }
+        Expression::Call(name, arguments) if is_process_function(name) => {
+            call_process_function(name, evaluate_all(arguments)?)
+        }
+        Expression::Call(name, arguments) if is_gc_function(name) => {
+            call_gc_function(name, evaluate_all(arguments)?)
+        }
+        // a method call is the function called with the receiver as its first argument
+        Expression::Call(name, arguments) => match arguments.split_first() {
+            Some((receiver, arguments)) => call_method(receiver, name, arguments),
+            None => get_function_result(name.clone(), &mut Vec::new()),
+        },
+        Expression::Method(receiver, name, arguments) => call_method(receiver, name, arguments),
+        Expression::Index(target, index) => {
+            let index = evaluate(index)?;
+            index_value(evaluate(target)?, index)
+        }
+        Expression::Slice(target, start, end) => {
+            let start = match start {
+                Some(start) => Some(evaluate(start)?),
+                None => None,
+            };
+            let end = match end {
+                Some(end) => Some(evaluate(end)?),
+                None => None,
+            };
+            slice_value(evaluate(target)?, start, end)
+        }
+        Expression::Unary(operator, operand) => {
+            let value = evaluate(operand)?;
+            match (operator.as_str(), value) {
+                ("-", BaseTypes::Int(i)) => i
+                    .checked_neg()
+                    .map(BaseTypes::Int)
+                    .ok_or_else(|| "Runtime Error: Integer overflow".to_string()),
+                ("-", BaseTypes::Float(f)) => Ok(BaseTypes::Float(-f)),
+                ("!", value) => Ok(BaseTypes::Bool(!bool_operand("!", &value)?)),
+                (operator, value) => Err(format!(
+                    "Runtime Error: Cannot apply '{}' to {}",
+                    operator,
+                    value.GetType()
+                )),
+            }
+        }
+        Expression::List(elements) => {
+            let data = evaluate_all(elements)?;
+            let value_type = common_type(data.iter());
+            Ok(BaseTypes::from(Array::new(String::new(), value_type, data)))
+        }
+        Expression::Map(entries) => {
+            let mut keys = Vec::new();
+            let mut values = Vec::new();
+            for (key, value) in entries {
+                keys.push(evaluate(key)?);
+                values.push(evaluate(value)?);
+            }
+            let key_type = common_type(keys.iter());
+            let value_type = common_type(values.iter());
+            let mut dictionary = Dictionary::new(String::new(), key_type, value_type, Vec::new());
+            for (key, value) in keys.into_iter().zip(values) {
+                let key = dictionary.check_key(key)?;
+                if dictionary.contains_key(&key) {
+                    return Err(format!(
+                        "Runtime Error: Duplicate key {} in dictionary literal",
+                        key
+                    ));
+                }
+                dictionary.add(key, value);
+            }
+            Ok(BaseTypes::from(dictionary))
+        }
+        Expression::Tuple(elements) => Ok(BaseTypes::Tuple(evaluate_all(elements)?)),
+        Expression::Cast(value, type_name) => cast(evaluate(value)?, type_name),
+        Expression::Lambda(_, _) => Err(
+            "Runtime Error: A lambda can only be passed to a method like map or filter".to_string(),
+        ),
+        Expression::Binary(left, operator, right) => {
+            let left = evaluate(left)?;
+            match operator.as_str() {
+                "&&" => {
+                    if !bool_operand(operator, &left)? {
+                        return Ok(BaseTypes::Bool(false));
+                    }
+                    Ok(BaseTypes::Bool(bool_operand(operator, &evaluate(right)?)?))
+                }
+                "||" => {
+                    if bool_operand(operator, &left)? {
+                        return Ok(BaseTypes::Bool(true));
+                    }
+                    Ok(BaseTypes::Bool(bool_operand(operator, &evaluate(right)?)?))
+                }
+                _ => apply_operator(left, operator, evaluate(right)?),
+            }
+        }
+    }
+}
+
+///
+///Applies a binary operator such as + or == to two values
+///
+pub fn apply_operator(
+    left: BaseTypes,
+    operator: &str,
+    right: BaseTypes,
+) -> Result<BaseTypes, String> {
+    let mismatch = |left: &BaseTypes, right: &BaseTypes| {
+        format!(
+            "Runtime Error: Cannot apply '{}' to {} and {}",
+            operator,
+            left.GetType(),
+            right.GetType()
+        )
+    };
+
+    match operator {
+        "==" => Ok(BaseTypes::Bool(values_equal(&left, &right))),
+        "!=" => Ok(BaseTypes::Bool(!values_equal(&left, &right))),
+        "<" | ">" | "<=" | ">=" => {
+            let ordering = match (&left, &right) {
+                (BaseTypes::Int(x), BaseTypes::Int(y)) => x.partial_cmp(y),
+                (
+                    BaseTypes::Int(_) | BaseTypes::Float(_),
+                    BaseTypes::Int(_) | BaseTypes::Float(_),
+                ) => f64::from(left.clone()).partial_cmp(&f64::from(right.clone())),
+                (BaseTypes::StringWrapper(x), BaseTypes::StringWrapper(y)) => x.partial_cmp(y),
+                (BaseTypes::Char(x), BaseTypes::Char(y)) => x.partial_cmp(y),
+                _ => return Err(mismatch(&left, &right)),
+            };
+            // NaN compares false with everything
+            let result = match ordering {
+                Some(ordering) => match operator {
+                    "<" => ordering.is_lt(),
+                    ">" => ordering.is_gt(),
+                    "<=" => ordering.is_le(),
+                    _ => ordering.is_ge(),
+                },
+                None => false,
+            };
+            Ok(BaseTypes::Bool(result))
+        }
+        _ => match (&left, &right) {
+            (BaseTypes::Int(x), BaseTypes::Int(y)) => {
+                let (x, y) = (*x, *y);
+                if (operator == "/" || operator == "%") && y == 0 {
+                    return Err("Runtime Error: Division by zero".to_string());
+                }
+                let result = match operator {
+                    "+" => x.checked_add(y),
+                    "-" => x.checked_sub(y),
+                    "*" => x.checked_mul(y),
+                    "/" => x.checked_div(y),
+                    "%" => x.checked_rem(y),
+                    _ => return Err(mismatch(&left, &right)),
+                };
+                result
+                    .map(BaseTypes::Int)
+                    .ok_or_else(|| "Runtime Error: Integer overflow".to_string())
+            }
+            (BaseTypes::Int(_) | BaseTypes::Float(_), BaseTypes::Int(_) | BaseTypes::Float(_)) => {
+                let x = f64::from(left.clone());
+                let y = f64::from(right.clone());
+                let result = match operator {
+                    "+" => x + y,
+                    "-" => x - y,
+                    "*" => x * y,
+                    "/" => x / y,
+                    "%" => x % y,
+                    _ => return Err(mismatch(&left, &right)),
+                };
+                Ok(BaseTypes::Float(result))
+            }
+            (
+                BaseTypes::StringWrapper(_) | BaseTypes::Char(_),
+                BaseTypes::StringWrapper(_) | BaseTypes::Char(_),
+            ) if operator == "+" => Ok(BaseTypes::StringWrapper(format!("{}{}", left, right))),
+            _ => Err(mismatch(&left, &right)),
+        },
+    }
+}
+
+fn values_equal(left: &BaseTypes, right: &BaseTypes) -> bool {
+    match (left, right) {
+        (BaseTypes::Int(x), BaseTypes::Float(y)) | (BaseTypes::Float(y), BaseTypes::Int(x)) => {
+            (*x as f64) == *y
+        }
+        _ => left == right,
+    }
+}
+
+fn position(index: &BaseTypes, length: usize) -> Result<usize, String> {
+    match index {
+        BaseTypes::Int(i) if *i >= 0 && (*i as usize) <= length => Ok(*i as usize),
+        BaseTypes::Int(i) => Err(format!(
+            "Runtime Error: Index {} is out of bounds for length {}",
+            i, length
+        )),
+        other => Err(format!(
+            "Runtime Error: Expected an int index, found {}",
+            other.GetType()
+        )),
+    }
+}
+
+fn index_value(target: BaseTypes, index: BaseTypes) -> Result<BaseTypes, String> {
+    match target {
+        BaseTypes::StringWrapper(s) => {
+            // strings are indexed by char so multi byte characters count once
+            let length = s.chars().count();
+            let i = position(&index, length)?;
+            s.chars().nth(i).map(BaseTypes::Char).ok_or_else(|| {
+                format!(
+                    "Runtime Error: Index {} is out of bounds for length {}",
+                    i, length
+                )
+            })
+        }
+        BaseTypes::Array(array) => {
+            let array = array.borrow();
+            let length = array.data.len();
+            let i = position(&index, length)?;
+            array.get(i).ok_or_else(|| {
+                format!(
+                    "Runtime Error: Index {} is out of bounds for length {}",
+                    i, length
+                )
+            })
+        }
+        BaseTypes::Dictionary(dictionary) => lookup(&dictionary.borrow(), &index),
+        BaseTypes::Tuple(values) => {
+            let length = values.len();
+            let i = position(&index, length)?;
+            values.get(i).cloned().ok_or_else(|| {
+                format!(
+                    "Runtime Error: Index {} is out of bounds for length {}",
+                    i, length
+                )
+            })
+        }
+        other => Err(format!("Runtime Error: Cannot index {}", other.GetType())),
+    }
+}
+
+fn slice_value(
+    target: BaseTypes,
+    start: Option<BaseTypes>,
+    end: Option<BaseTypes>,
+) -> Result<BaseTypes, String> {
+    let length = match &target {
+        BaseTypes::StringWrapper(s) => s.chars().count(),
+        BaseTypes::Array(array) => array.borrow().data.len(),
+        other => return Err(format!("Runtime Error: Cannot slice {}", other.GetType())),
+    };
+    let start = match start {
+        Some(start) => position(&start, length)?,
+        None => 0,
+    };
+    let end = match end {
+        Some(end) => position(&end, length)?,
+        None => length,
+    };
+    if start > end {
+        return Err(format!(
+            "Runtime Error: Slice start {} is after its end {}",
+            start, end
+        ));
+    }
+
+    match target {
+        BaseTypes::StringWrapper(s) => Ok(BaseTypes::StringWrapper(
+            s.chars().skip(start).take(end - start).collect(),
+        )),
+        BaseTypes::Array(array) => {
+            let array = array.borrow();
+            Ok(BaseTypes::from(Array::new(
+                String::new(),
+                array.value_type.clone(),
+                array.data[start..end].to_vec(),
+            )))
+        }
+        other => Err(format!("Runtime Error: Cannot slice {}", other.GetType())),
+    }
+}
+
+#[cfg(test)]
+mod expression_tests {
+    use super::evaluate_expression;
+    use crate::base_variable::base_types::BaseTypes;
+    use crate::base_variable::variable::Variable;
+    use crate::base_variable::variables::VARIABLE_STACK;
+    use crate::collection::collections::Array;
+    use crate::node::nodes::{match_token_to_node, ASTNode};
+    use crate::statement_tokenizer::tokenizer::tokenizers::tokenize;
+
+    fn evaluate(source: &str) -> Result<BaseTypes, String> {
+        let nodes: Vec<ASTNode> = tokenize(source.to_string())
+            .into_iter()
+            .map(match_token_to_node)
+            .collect();
+        evaluate_expression(&nodes)
+    }
+
+    #[test]
+    fn test_operator_precedence() {
+        assert_eq!(evaluate("1 + 2 * 3 - 4 / 2"), Ok(BaseTypes::Int(5)));
+        assert_eq!(evaluate("(1 + 2) * 3"), Ok(BaseTypes::Int(9)));
+        assert_eq!(evaluate("-2 * 3 + 7 % 4"), Ok(BaseTypes::Int(-3)));
+        assert_eq!(evaluate("1 + 2 == 3 && 2 < 1"), Ok(BaseTypes::Bool(false)));
+    }
+
+    #[test]
+    fn test_string_methods_count_chars() {
+        assert_eq!(evaluate(r#""héllo😀".len()"#), Ok(BaseTypes::Int(6)));
+        assert_eq!(evaluate(r#""héllo😀"[1]"#), Ok(BaseTypes::Char('é')));
+        assert_eq!(
+            evaluate(r#""héllo😀"[1..3]"#),
+            Ok(BaseTypes::StringWrapper("él".to_string()))
+        );
+        assert_eq!(evaluate(r#""héllo😀".find("😀")"#), Ok(BaseTypes::Int(5)));
+        assert_eq!(
+            evaluate(r#"" Hi ".trim().to_uppercase().repeat(2)"#),
+            Ok(BaseTypes::StringWrapper("HIHI".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_expression_errors() {
+        assert!(evaluate(r#""abc"[3]"#).is_err());
+        assert!(evaluate(r#""abc".parse_int()"#).is_err());
+        assert!(evaluate(r#""a" * 2"#).is_err());
+        assert!(evaluate("1 / 0").is_err());
+    }
+
+    #[test]
+    fn test_array_methods() {
+        let data = vec![BaseTypes::Int(3), BaseTypes::Int(1), BaseTypes::Int(2)];
+        let array = BaseTypes::from(Array::new(
+            "expression_test_numbers".to_string(),
+            "int".into(),
+            data,
+        ));
+        let variable = Variable::new("expression_test_numbers".to_string(), array.clone(), array);
+        unsafe { VARIABLE_STACK.push(variable) };
+
+        assert_eq!(
+            evaluate("expression_test_numbers[2]"),
+            Ok(BaseTypes::Int(2))
+        );
+        assert_eq!(
+            evaluate("expression_test_numbers.reduce((a, b) => a * 10 + b)"),
+            Ok(BaseTypes::Int(312))
+        );
+        assert_eq!(
+            evaluate(r#"expression_test_numbers[1..].map(n => n + 1).join("-")"#),
+            Ok(BaseTypes::StringWrapper("2-3".to_string()))
+        );
+        assert_eq!(
+            evaluate("expression_test_numbers.any(n => n > 2)"),
+            Ok(BaseTypes::Bool(true))
+        );
+        assert!(evaluate("expression_test_numbers.filter(n => n + 1)").is_err());
+        assert!(evaluate(r#"expression_test_numbers.push("four")"#).is_err());
+        assert!(evaluate("expression_test_numbers[3]").is_err());
+    }
+}
@@ -0,0 +1,290 @@
+/*
+* This file holds the fs module, called from Jist code as fs.read_to_string("data.csv") or
+* fs.write("report.txt", text). Every failure comes back as a runtime error naming the function
+* and the path, so a missing file never panics the interpreter. fs.read_lines returns a lines
+* handle that reads the file as it is looped over instead of loading it all at once
+*/
+use crate::base_variable::base_types::{BaseTypes, GetType};
+use crate::collection::collections::{Array, Dictionary};
+use crate::compilers::array::expect_arguments;
+use std::cell::RefCell;
+use std::fs;
+use std::io::{BufRead, BufReader, Write};
+use std::rc::Rc;
+use std::time::UNIX_EPOCH;
+
+// The name the module is called through
+pub const MODULE: &str = "fs";
+
+const FUNCTIONS: [&str; 11] = [
+    "read_to_string",
+    "write",
+    "append",
+    "read_lines",
+    "exists",
+    "list_dir",
+    "create_dir_all",
+    "remove",
+    "copy",
+    "rename",
+    "metadata",
+];
+
+const LINES_METHODS: [&str; 3] = ["next", "has_next", "to_array"];
+
+pub fn is_fs_function(name: &str) -> bool {
+    FUNCTIONS.contains(&name)
+}
+
+pub fn is_lines_method(name: &str) -> bool {
+    LINES_METHODS.contains(&name)
+}
+
+///
+///An open file read one line at a time. Like a collection it is shared, so every name for it
+///reads from the same place. The file is closed once its last line has been read
+///
+#[derive(Debug, Default)]
+pub struct Lines {
+    pub path: String,
+    reader: Option<BufReader<fs::File>>,
+}
+
+impl Lines {
+    pub fn open(path: &str) -> std::io::Result<Lines> {
+        Ok(Lines {
+            path: path.to_string(),
+            reader: Some(BufReader::new(fs::File::open(path)?)),
+        })
+    }
+
+    ///
+    ///The next line without its line ending, None at the end of the file
+    ///
+    pub fn next_line(&mut self) -> Result<Option<String>, String> {
+        let Some(reader) = &mut self.reader else {
+            return Ok(None);
+        };
+        let mut line = String::new();
+        let read = reader
+            .read_line(&mut line)
+            .map_err(|e| io_error("read_lines", &self.path, e))?;
+        if read == 0 {
+            self.reader = None;
+            return Ok(None);
+        }
+        if line.ends_with('\n') {
+            line.pop();
+            if line.ends_with('\r') {
+                line.pop();
+            }
+        }
+        Ok(Some(line))
+    }
+
+    pub fn has_next(&mut self) -> Result<bool, String> {
+        let Some(reader) = &mut self.reader else {
+            return Ok(false);
+        };
+        let buffered = reader
+            .fill_buf()
+            .map_err(|e| io_error("read_lines", &self.path, e))?;
+        Ok(!buffered.is_empty())
+    }
+}
+
+///
+///Calls a method of a lines handle: next() returns the next line, has_next() whether there is
+///one and to_array() the lines that have not been read yet
+///
+pub fn call_lines_method(
+    lines: &mut Lines,
+    name: &str,
+    arguments: Vec<BaseTypes>,
+) -> Result<BaseTypes, String> {
+    expect_arguments(name, &arguments, (0, 0))?;
+    match name {
+        "next" => match lines.next_line()? {
+            Some(line) => Ok(BaseTypes::StringWrapper(line)),
+            None => Err(format!(
+                "Runtime Error: next(): '{}' has no more lines",
+                lines.path
+            )),
+        },
+        "has_next" => Ok(BaseTypes::Bool(lines.has_next()?)),
+        "to_array" => {
+            let mut rest = Vec::new();
+            while let Some(line) = lines.next_line()? {
+                rest.push(line);
+            }
+            Ok(string_array(rest))
+        }
+        _ => Err(format!("Runtime Error: lines have no method '{}'", name)),
+    }
+}
+
+///
+///The string argument at position, paths and file contents have to be strings
+///
+fn string_argument<'a>(
+    name: &str,
+    arguments: &'a [BaseTypes],
+    position: usize,
+) -> Result<&'a str, String> {
+    match &arguments[position] {
+        BaseTypes::StringWrapper(s) => Ok(s),
+        other => Err(format!(
+            "Runtime Error: fs.{}(): Expected a String but found {}",
+            name,
+            other.GetType()
+        )),
+    }
+}
+
+fn io_error(name: &str, path: &str, error: std::io::Error) -> String {
+    format!("Runtime Error: fs.{}(): '{}': {}", name, path, error)
+}
+
+fn string_array(data: Vec<String>) -> BaseTypes {
+    BaseTypes::from(Array::new(
+        String::new(),
+        "string".into(),
+        data.into_iter().map(BaseTypes::StringWrapper).collect(),
+    ))
+}
+
+///
+///Sizes and times are ints when they fit and floats when they do not
+///
+fn number(value: u64) -> BaseTypes {
+    match i32::try_from(value) {
+        Ok(value) => BaseTypes::Int(value),
+        Err(_) => BaseTypes::Float(value as f64),
+    }
+}
+
+fn metadata(name: &str, path: &str) -> Result<BaseTypes, String> {
+    let metadata = fs::metadata(path).map_err(|e| io_error(name, path, e))?;
+    // seconds since 1970, 0 when the platform does not record it
+    let modified = metadata
+        .modified()
+        .ok()
+        .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+        .map_or(0, |duration| duration.as_secs());
+
+    let mut dictionary = Dictionary::new(String::new(), "string".into(), "any".into(), Vec::new());
+    let entries = [
+        ("size", number(metadata.len())),
+        ("is_file", BaseTypes::Bool(metadata.is_file())),
+        ("is_dir", BaseTypes::Bool(metadata.is_dir())),
+        (
+            "readonly",
+            BaseTypes::Bool(metadata.permissions().readonly()),
+        ),
+        ("modified", number(modified)),
+    ];
+    for (key, value) in entries {
+        dictionary.add(BaseTypes::StringWrapper(key.to_string()), value);
+    }
+    Ok(BaseTypes::from(dictionary))
+}
+
+///
+///Calls fs.name with the arguments
+///
+pub fn call_fs_function(name: &str, arguments: Vec<BaseTypes>) -> Result<BaseTypes, String> {
+    let qualified = format!("{}.{}", MODULE, name);
+    match name {
+        "read_to_string" => {
+            expect_arguments(&qualified, &arguments, (1, 1))?;
+            let path = string_argument(name, &arguments, 0)?;
+            fs::read_to_string(path)
+                .map(BaseTypes::StringWrapper)
+                .map_err(|e| io_error(name, path, e))
+        }
+        "write" | "append" => {
+            expect_arguments(&qualified, &arguments, (2, 2))?;
+            let path = string_argument(name, &arguments, 0)?;
+            // anything can be written, it is stored the way echo would print it
+            let contents = arguments[1].to_string();
+            let file = fs::OpenOptions::new()
+                .create(true)
+                .write(true)
+                .append(name == "append")
+                .truncate(name == "write")
+                .open(path);
+            file.and_then(|mut file| file.write_all(contents.as_bytes()))
+                .map_err(|e| io_error(name, path, e))?;
+            Ok(BaseTypes::Null)
+        }
+        "read_lines" => {
+            // only opens the file, its lines are read as they are asked for
+            expect_arguments(&qualified, &arguments, (1, 1))?;
+            let path = string_argument(name, &arguments, 0)?;
+            let lines = Lines::open(path).map_err(|e| io_error(name, path, e))?;
+            Ok(BaseTypes::Lines(Rc::new(RefCell::new(lines))))
+        }
+        "exists" => {
+            expect_arguments(&qualified, &arguments, (1, 1))?;
+            let path = string_argument(name, &arguments, 0)?;
+            Ok(BaseTypes::Bool(std::path::Path::new(path).exists()))
+        }
+        "list_dir" => {
+            // names only, sorted so scripts see the same order on every platform
+            expect_arguments(&qualified, &arguments, (1, 1))?;
+            let path = string_argument(name, &arguments, 0)?;
+            let mut names = fs::read_dir(path)
+                .and_then(|entries| {
+                    entries
+                        .map(|entry| entry.map(|e| e.file_name().to_string_lossy().into_owned()))
+                        .collect::<Result<Vec<String>, _>>()
+                })
+                .map_err(|e| io_error(name, path, e))?;
+            names.sort();
+            Ok(string_array(names))
+        }
+        "create_dir_all" => {
+            expect_arguments(&qualified, &arguments, (1, 1))?;
+            let path = string_argument(name, &arguments, 0)?;
+            fs::create_dir_all(path).map_err(|e| io_error(name, path, e))?;
+            Ok(BaseTypes::Null)
+        }
+        "remove" => {
+            // removes a file or an empty directory
+            expect_arguments(&qualified, &arguments, (1, 1))?;
+            let path = string_argument(name, &arguments, 0)?;
+            let result = if std::path::Path::new(path).is_dir() {
+                fs::remove_dir(path)
+            } else {
+                fs::remove_file(path)
+            };
+            result.map_err(|e| io_error(name, path, e))?;
+            Ok(BaseTypes::Null)
+        }
+        "copy" => {
+            // returns the number of bytes copied
+            expect_arguments(&qualified, &arguments, (2, 2))?;
+            let from = string_argument(name, &arguments, 0)?;
+            let to = string_argument(name, &arguments, 1)?;
+            fs::copy(from, to)
+                .map(number)
+                .map_err(|e| io_error(name, from, e))
+        }
+        "rename" => {
+            expect_arguments(&qualified, &arguments, (2, 2))?;
+            let from = string_argument(name, &arguments, 0)?;
+            let to = string_argument(name, &arguments, 1)?;
+            fs::rename(from, to).map_err(|e| io_error(name, from, e))?;
+            Ok(BaseTypes::Null)
+        }
+        "metadata" => {
+            expect_arguments(&qualified, &arguments, (1, 1))?;
+            let path = string_argument(name, &arguments, 0)?;
+            metadata(name, path)
+        }
+        _ => Err(format!(
+            "Runtime Error: The fs module has no function '{}'",
+            name
+        )),
+    }
+}
@@ -1,203 +1,236 @@
 use crate::base_variable::variable::Variable;
-use crate::node::nodes::ASTNode;
+use crate::node::nodes::{ASTNode, FunctionNode};
 //use std::collections::HashMap;
 use std::process::exit;
 //use std::sync::MutexGuard;
 
-use crate::base_variable::base_types::BaseTypes;
+use crate::base_variable::base_types::{BaseTypes, GetType};
 use crate::base_variable::variables::VARIABLE_STACK;
+use crate::collection::collections::{check_type, type_name_of, Array};
+use crate::compilers::block::run_block;
+use crate::compilers::expression::{evaluate_expression, evaluate_leading_expression};
 use crate::function::functions::call_function;
 use crate::function::functions::Function;
 use crate::function::functions::FunctionTypes;
 use crate::function::FUNCTION_STACK;
 use crate::function_map::{
     FUNCTIONS, STD_FUNCTIONS, STD_FUNCTIONS_DOUBLE, STD_FUNCTIONS_ECHO, STD_FUNCTIONS_SINGLE,
-    USER_FUNCTIONS,
 };
+use crate::globals::{
+    report_error, Frame, CALL_STACK, CURRENT_STATEMENT, FUNCTION_DEPTH, MAX_CALL_DEPTH,
+    RETURN_VALUE,
+};
+use crate::heap::copy;
+use crate::node::nodes::match_token_to_node;
+use crate::statement_tokenizer::tokenizer::tokenizers::tokenize;
 use std::any::Any;
 
 fn add_to_function_stack(func: Function) {
-    FUNCTION_STACK.lock().unwrap().push(func);
-    //USER_FUNCTIONS.lock().unwrap().push(func);
+    FUNCTION_STACK.with(|stack| stack.borrow_mut().push(func));
     // You can still use `dict` after this line because we cloned it
     //println!("dict pushed to stack")
 }
 
 fn find_function_in_stack(function_name: &str) -> Function {
-    let function_stack = FUNCTION_STACK.lock().unwrap(); // Lock the Mutex, unwrap if the lock is successful
-
-    for function in function_stack.iter() {
-        if function_name == function.name {
-            return function.clone(); // If Function is Clone, clone it and return
-        }
+    let found = FUNCTION_STACK.with(|stack| {
+        stack
+            .borrow()
+            .iter()
+            .find(|function| function_name == function.name)
+            .cloned()
+    });
+    if let Some(function) = found {
+        return function;
     }
 
     eprintln!("Function not in user functions");
     exit(1);
 }
 
-pub fn parse_function_declaration(expression: &Vec<ASTNode>) -> bool {
-    let mut function_name: String = String::new();
+pub fn parse_function_declaration(
+    function: &FunctionNode,
+    body: String,
+    body_line: usize,
+    body_column: usize,
+) {
+    // Parameters keep their declared type name in var_type, the value is the default or
+    // Null when the argument has to be given
     let mut parameters: Vec<Variable> = Vec::new();
-    let mut return_type: BaseTypes = BaseTypes::Null;
-    let mut function_body: Vec<ASTNode> = Vec::new();
-
-    let mut i = 0;
-    while i < expression.len() {
-        match &expression[i] {
-            ASTNode::Function(f) => {
-                // Store function name and return type
-                function_name = f.name.clone();
-                return_type = BaseTypes::StringWrapper(f.return_type.clone());
-
-                for arg in &f.arguments {
-                    let var_type = match arg.2.clone().as_str() {
-                        "int" => BaseTypes::Int(0),
-                        "float" => BaseTypes::Float(0.0),
-                        "string" => BaseTypes::StringWrapper(String::new()),
-                        "boolean" => BaseTypes::Bool(false),
-                        "char" => BaseTypes::Char('\0'),
-                        "null" => BaseTypes::Null,
-                        _ => {
-                            println!("Syntax Error: Unrecognized type '{}'", arg.2);
-                            return false; // Exit if an unrecognized type is found
-                        }
-                    };
-
-                    let var_value = match arg.2.clone().as_str() {
-                        "int" => arg
-                            .1
-                            .parse::<i32>()
-                            .map(BaseTypes::Int)
-                            .unwrap_or(BaseTypes::Null),
-                        "float" => arg
-                            .1
-                            .parse::<f64>()
-                            .map(BaseTypes::Float)
-                            .unwrap_or(BaseTypes::Null),
-                        "string" => BaseTypes::StringWrapper(arg.1.clone()),
-                        "boolean" => arg
-                            .1
-                            .parse::<bool>()
-                            .map(BaseTypes::Bool)
-                            .unwrap_or(BaseTypes::Null),
-                        "char" => {
-                            if let Some(first_char) = arg.1.chars().next() {
-                                BaseTypes::Char(first_char)
-                            } else {
-                                BaseTypes::Null // Handle empty char case
-                            }
-                        }
-                        "null" => BaseTypes::Null,
-                        _ => {
-                            println!("Syntax Error: Unrecognized type '{}'", arg.2);
-                            return false; // Exit if an unrecognized type is found
-                        }
-                    };
-
-                    // Create the variable and add it to the parameters
-                    let var = Variable::new(
-                        arg.0.clone(), // Variable name
-                        var_type,      // Variable type
-                        var_value,     // Variable value
-                    );
-                    parameters.push(var);
+    for (name, arg_type, default) in &function.arguments {
+        let value = if default == "null" {
+            BaseTypes::Null
+        } else {
+            let nodes: Vec<ASTNode> = tokenize(default.clone())
+                .into_iter()
+                .map(match_token_to_node)
+                .collect();
+            let value = evaluate_expression(&nodes).and_then(|value| {
+                check_type(&arg_type.clone().into(), value).map_err(|value| {
+                    format!(
+                        "Syntax Error: Default value {} of '{}' is not a {}",
+                        value, name, arg_type
+                    )
+                })
+            });
+            match value {
+                Ok(value) => value,
+                Err(e) => {
+                    report_error(e);
                 }
             }
-            ASTNode::LeftCurly => {
-                // Now we need to store the function body
-                function_body.clear(); // Clear any previous function body
-                i += 1; // Move to the next node after '{'
-
-                // Collect nodes until we reach the matching right curly brace
-                let mut curly_brace_count = 1; // We've encountered one '{'
-
-                while i < expression.len() {
-                    match &expression[i] {
-                        ASTNode::LeftCurly => curly_brace_count += 1,
-                        ASTNode::RightCurly => {
-                            curly_brace_count -= 1;
-                            if curly_brace_count == 0 {
-                                break; // Found matching '}'
-                            }
-                        }
-                        _ => {}
-                    }
-                    function_body.push(expression[i].clone());
-                    i += 1;
-                }
+        };
+        parameters.push(Variable {
+            name: name.clone(),
+            value,
+            var_type: BaseTypes::StringWrapper(arg_type.clone()),
+        });
+    }
+
+    add_to_function_stack(Function::new(
+        function.name.clone(),
+        BaseTypes::StringWrapper(function.return_type.clone()),
+        parameters,
+        body,
+        body_line,
+        body_column,
+    ));
+}
 
-                // After collecting the function body, create the Function object
-                let function = Function::new(
-                    function_name.clone(),
-                    return_type.clone(),
-                    parameters.clone(),
-                    function_body.clone(),
-                );
-                // add to FUNCTION_STACK
-                println!("Function: {}", function);
-                add_to_function_stack(function);
+///
+///Finds a function declared in the program, these are looked up before the builtins
+///
+pub fn find_user_function(function_name: &str) -> Option<Function> {
+    FUNCTION_STACK.with(|stack| {
+        stack
+            .borrow()
+            .iter()
+            .rev()
+            .find(|function| function.name == function_name)
+            .cloned()
+    })
+}
+
+///
+///Runs a declared function. The arguments are checked against the parameter types and bound
+///as variables, which are removed again once the body has run
+///
+pub fn call_user_function(
+    function: &Function,
+    values: Vec<BaseTypes>,
+) -> Result<BaseTypes, String> {
+    if values.len() > function.arguments.len() {
+        return Err(format!(
+            "Runtime Error: {}(): Expected {} argument(s) but found {}",
+            function.name,
+            function.arguments.len(),
+            values.len()
+        ));
+    }
+    // a recursion that does not end stops here instead of running out of stack
+    if unsafe { (*std::ptr::addr_of!(CALL_STACK)).len() } >= MAX_CALL_DEPTH {
+        return Err(format!(
+            "Runtime Error: {}(): Calls nested more than {} deep",
+            function.name, MAX_CALL_DEPTH
+        ));
+    }
+
+    let mut bound = Vec::new();
+    for (i, parameter) in function.arguments.iter().enumerate() {
+        let value = match values.get(i) {
+            Some(value) => value.clone(),
+            // a default collection is copied so one call cannot change it for the next
+            None if parameter.value != BaseTypes::Null => copy(&parameter.value),
+            None => {
+                return Err(format!(
+                    "Runtime Error: {}(): Missing argument '{}'",
+                    function.name, parameter.name
+                ))
             }
-            _ => println!("Unhandled node: {:?}", expression[i]),
-        }
-        i += 1;
+        };
+        let value = check_type(&parameter.var_type, value).map_err(|value| {
+            format!(
+                "Runtime Error: {}(): Argument '{}' should be {} but found {}",
+                function.name,
+                parameter.name,
+                parameter.var_type,
+                type_name_of(&value)
+            )
+        })?;
+        let var_type = value.clone();
+        bound.push(Variable {
+            name: parameter.name.clone(),
+            value,
+            var_type,
+        });
+    }
+
+    let depth = unsafe { (*std::ptr::addr_of!(VARIABLE_STACK)).len() };
+    unsafe { (*std::ptr::addr_of_mut!(VARIABLE_STACK)).extend(bound) };
+    unsafe {
+        (*std::ptr::addr_of_mut!(CALL_STACK)).push(Frame {
+            function: function.name.clone(),
+            line: 0,
+            variables: depth,
+        })
+    };
+    let result = run_function_body(function);
+    unsafe { (*std::ptr::addr_of_mut!(CALL_STACK)).pop() };
+    unsafe { (*std::ptr::addr_of_mut!(VARIABLE_STACK)).truncate(depth) };
+
+    let return_type = match &function.return_type {
+        BaseTypes::StringWrapper(t) if !t.is_empty() => t.clone(),
+        _ => return Ok(BaseTypes::Null),
+    };
+    match result {
+        Some(value) => check_type(&return_type.clone().into(), value).map_err(|value| {
+            format!(
+                "Runtime Error: {}(): Should return {} but returned {}",
+                function.name,
+                return_type,
+                type_name_of(&value)
+            )
+        }),
+        None => Err(format!(
+            "Runtime Error: {}(): Should return {} but returned nothing",
+            function.name, return_type
+        )),
     }
-    // Placeholder return value, should likely be more meaningful
-    true
 }
 
+///
+///Runs the statements of a function body through the same blocks as the file itself.
+///Returns the value of the first return statement reached
+///
+fn run_function_body(function: &Function) -> Option<BaseTypes> {
+    // an error after the call returns is on the line that called it
+    let caller = unsafe { CURRENT_STATEMENT };
+    unsafe { FUNCTION_DEPTH += 1 };
+    if let Err(e) = run_block(&function.body, function.body_line, function.body_column) {
+        report_error(format!("Syntax Error: {}", e));
+    }
+    let returned = unsafe { (*std::ptr::addr_of_mut!(RETURN_VALUE)).take() };
+    unsafe { FUNCTION_DEPTH -= 1 };
+    unsafe { CURRENT_STATEMENT = caller };
+    returned
+}
+
+///
+///Runs the call at the start of the expression, including any method calls chained onto
+///it, and returns its result. Errors end the program
+///
 pub fn parse_function_call(expression: &Vec<ASTNode>) -> BaseTypes {
-    let mut function_name: String = "None".to_string();
-    let mut parameter_and_value: Vec<BaseTypes> = Vec::new();
-    let mut i = 0;
-
-    match expression.get(i).unwrap() {
-        ASTNode::FunctionCall(f) => {
-            function_name = f.name.clone();
-            i += 1;
-            while i < expression.len() {
-                match &expression[i] {
-                    ASTNode::FunctionCallArguments(_) => {
-                        parameter_and_value = parse_function_call_arguments(&expression[i + 1..]);
-                    }
-                    ASTNode::RightParenthesis => {}
-                    ASTNode::LeftParenthesis => {
-                        parameter_and_value = parse_function_call_arguments(&expression[i + 1..]);
-                        break;
-                    }
-                    /*ASTNode::VariableCall(v) => {
-                                            let mut arg1_value = BaseTypes::StringWrapper(String::new());
-                                            let mut arg1_name = String::new();
-                                            for var in unsafe { VARIABLE_STACK.iter() } {
-                                                if var.name == v.name {
-                                                    arg1_value = var.value.clone();
-                                                    arg1_name = var.name.clone();
-                                                }
-                                            }
-                                            let arg1 = (arg1_name, arg1_value);
-                                            parameter_and_value.push(arg1);
-                                        }
-                                        ASTNode::Int(n) => {
-                                            let arg1 = (String::new(), BaseTypes::Int(n.value.clone()));
-                                            parameter_and_value.push(arg1);
-                                        }
-                    */
-                    _ => println!("Unhandled node in function call: {:?}", expression[i]),
-                }
-                i += 1;
-            }
+    match evaluate_leading_expression(expression) {
+        Ok((value, _)) => value,
+        Err(e) => {
+            report_error(e);
         }
-        _ => println!("Unhandled node: {:?}", expression[i]),
     }
-
-    // Handle the result or error
-    get_function_result(function_name, &mut parameter_and_value)
 }
 
 pub fn get_function_result(
     function_name: String,
     parameter_and_value: &mut Vec<BaseTypes>,
-) -> BaseTypes {
+) -> Result<BaseTypes, String> {
     let std_echo = match STD_FUNCTIONS_ECHO.lock() {
         Ok(guard) => guard,
         Err(poisoned) => poisoned.into_inner(),
@@ -222,87 +255,69 @@ pub fn get_function_result(
     if let Some(func) = std_functions.get(function_name.as_str()) {
         //println!("Function call is in STD_FUNCTIONS: {}", function_name);
 
-        // Convert Int to Float for the first two parameters
-        for param in parameter_and_value.iter_mut().take(2) {
-            if let BaseTypes::Int(x) = *param {
-                *param = BaseTypes::Float(x as f64);
-            }
-        }
-        /*
-                // Ensure at least two parameters are provided
-                if parameter_and_value.len() < 2 {
-                    println!(
-                        "Syntax Error: Not enough parameters supplied to function, {}/2 Provided.",
-                        parameter_and_value.len()
-                    );
-                    exit(1);
-                }
-        */
-
         let mut params: Vec<Box<dyn Any>> = Vec::new();
 
-        if function_name == "echo" {
-            // Check if there's at least one parameter
-            if parameter_and_value.len() < 1 {
-                println!("Syntax Error: Not enough parameters supplied to function, 0/1 Provided.");
-                exit(1);
-            }
-
-            // Convert only the first parameter to a String and box it
-            let boxed_param: Box<dyn Any> = match &parameter_and_value[0] {
-                BaseTypes::Int(x) => Box::new(x.to_string()),
-                BaseTypes::Float(x) => Box::new(x.to_string()),
-                BaseTypes::StringWrapper(x) => Box::new(x.clone()),
-                BaseTypes::Bool(x) => Box::new(x.to_string()),
-                BaseTypes::Char(x) => Box::new(x.to_string()),
-                _ => panic!("Unknown parameter type"),
+        for param in parameter_and_value.iter() {
+            let boxed_param: Box<dyn Any> = match (func, param) {
+                // echo prints any value, so everything is handed over as a string
+                (FunctionTypes::EchoFn(_), _) => Box::new(param.to_string()),
+                (_, BaseTypes::Int(x)) => Box::new(*x),
+                (_, BaseTypes::Float(x)) => Box::new(*x),
+                (_, BaseTypes::StringWrapper(x)) => Box::new(x.clone()),
+                (_, BaseTypes::Bool(x)) => Box::new(*x),
+                (_, BaseTypes::Char(x)) => Box::new(*x),
+                (_, other) => {
+                    return Err(format!(
+                        "Runtime Error: {}() cannot take a {} argument",
+                        function_name,
+                        other.GetType()
+                    ))
+                }
             };
 
             params.push(boxed_param);
-        } else {
-            // Call the function and return the result
-            for param in parameter_and_value.iter() {
-                //println!("Parameter: {:?}", param);
-                let boxed_param: Box<dyn Any> = match param {
-                    BaseTypes::Int(x) => Box::new(*x),
-                    BaseTypes::Float(x) => Box::new(*x),
-                    BaseTypes::StringWrapper(x) => Box::new(x.clone()),
-                    BaseTypes::Bool(x) => Box::new(*x),
-                    BaseTypes::Char(x) => Box::new(*x),
-                    _ => panic!("Unknown parameter type"),
-                };
-
-                params.push(boxed_param);
-            }
         }
 
-        // Create a vector of Box<dyn Any> for parameters
-
         // Call the function and handle the result
-        let result = call_function(func, params);
+        let result = call_function(func, params)
+            .map_err(|e| format!("Runtime Error: {}(): {}", function_name, e))?;
         // convert the result to the appropriate type
         if result.is::<f64>() {
-            //println!("Result of Function: {:?} of type float", result);
-            return BaseTypes::Float(*result.downcast::<f64>().unwrap());
+            return Ok(BaseTypes::Float(*result.downcast::<f64>().unwrap()));
         }
         if result.is::<i32>() {
-            //println!("Result of Function: {:?} of type int", result);
-            return BaseTypes::Int(*result.downcast::<i32>().unwrap());
+            return Ok(BaseTypes::Int(*result.downcast::<i32>().unwrap()));
         }
         if result.is::<String>() {
-            //println!("Result of Function: {:?} of type string", result);
-            return BaseTypes::StringWrapper(result.downcast::<String>().unwrap().to_string());
+            return Ok(BaseTypes::StringWrapper(
+                *result.downcast::<String>().unwrap(),
+            ));
         }
         if result.is::<bool>() {
-            //println!("Result of Function: {:?} of type bool", result);
-            return BaseTypes::Bool(*result.downcast::<bool>().unwrap());
+            return Ok(BaseTypes::Bool(*result.downcast::<bool>().unwrap()));
         }
         if result.is::<char>() {
-            //println!("Result of Function: {:?} of type char", result);
-            return BaseTypes::Char(*result.downcast::<char>().unwrap());
-        } else {
-            return BaseTypes::Null;
+            return Ok(BaseTypes::Char(*result.downcast::<char>().unwrap()));
+        }
+        if result.is::<Vec<String>>() {
+            let values = result.downcast::<Vec<String>>().unwrap();
+            let data = values.into_iter().map(BaseTypes::StringWrapper).collect();
+            return Ok(BaseTypes::from(Array::new(
+                String::new(),
+                "string".into(),
+                data,
+            )));
+        }
+        if result.is::<Vec<char>>() {
+            let values = result.downcast::<Vec<char>>().unwrap();
+            let data = values.into_iter().map(BaseTypes::Char).collect();
+            return Ok(BaseTypes::from(Array::new(
+                String::new(),
+                "char".into(),
+                data,
+            )));
         }
+        return Ok(BaseTypes::Null);
     }
 
     /*
@@ -321,11 +336,10 @@ pub fn get_function_result(
 
         // Ensure at least two parameters are provided
         if parameter_and_value.len() < 2 {
-            println!(
+            report_error(format!(
                 "Syntax Error: Not enough parameters supplied to function, {}/2 Provided.",
                 parameter_and_value.len()
-            );
-            exit(1);
+            ));
         }
 
         // Call the function and return the result
@@ -361,8 +375,7 @@ pub fn get_function_result(
 
         // Ensure at least one parameter is provided
         if parameter_and_value.is_empty() {
-            println!("Syntax Error: No parameters supplied to function.");
-            exit(1);
+            report_error("Syntax Error: No parameters supplied to function.");
         }
 
         // Call the function and return the result
@@ -381,8 +394,7 @@ pub fn get_function_result(
 
         // Ensure at least one parameter is provided
         if parameter_and_value.is_empty() {
-            println!("Syntax Error: No parameters supplied to function.");
-            exit(1);
+            report_error("Syntax Error: No parameters supplied to function.");
         }
 
         // Convert the first parameter to f64
@@ -401,64 +413,11 @@ pub fn get_function_result(
 
     // Function not found
 
-    println!("Function call is not in any of the STD_FUNCTIONS.");
-
     // Check if the function is a user-defined function
     // If it is, call the function parse the ASTNodes in the body and return result
 
-    exit(1);
-}
-
-fn parse_function_call_arguments(expression: &[ASTNode]) -> Vec<BaseTypes> {
-    let mut arguments: Vec<BaseTypes> = Vec::new();
-    let mut i = 0;
-
-    while i < expression.len() {
-        match &expression[i] {
-            ASTNode::VariableCall(v) => {
-                // Process variable call, you could push its value from a variable store
-                // For now, let's assume variables are stored in VARIABLE_STACK and extract their values
-                for var in unsafe { VARIABLE_STACK.iter() } {
-                    if var.name == v.name {
-                        arguments.push(var.value.clone());
-                    }
-                }
-            }
-            ASTNode::Int(n) => {
-                // Handle integer argument
-                arguments.push(BaseTypes::Int(n.value));
-            }
-            ASTNode::Float(f) => {
-                // Handle float argument
-                arguments.push(BaseTypes::Float(f.value.into()));
-            }
-            ASTNode::String(s) => {
-                // Handle string argument
-                arguments.push(BaseTypes::StringWrapper(s.value.clone()));
-            }
-            ASTNode::Bool(b) => {
-                // Handle boolean argument
-                arguments.push(BaseTypes::Bool(b.value));
-            }
-            ASTNode::Char(c) => {
-                // Handle char argument
-                arguments.push(BaseTypes::Char(c.value));
-            }
-            ASTNode::ArgumentSeparator => {
-                // Simply skip over argument separators (commas)
-            }
-            ASTNode::RightParenthesis => {
-                // End of arguments, break out of the loop
-                break;
-            }
-            _ => {
-                println!("Unhandled node in arguments: {:?}", expression[i]);
-            }
-        }
-        i += 1;
-    }
-
-    // Return the collected arguments
-    //println!("@@@@@@@@@@@Arguments: {:?}", arguments);
-    arguments
+    Err(format!(
+        "Runtime Error: Function '{}' is not defined",
+        function_name
+    ))
 }
@@ -0,0 +1,42 @@
+/*
+* This file holds the builtins that look at the memory collections use: gc() frees the
+* collections that only hold each other and returns how many there were, gc_stats() returns a
+* dict<string, int> with the allocated, live, collected and collections counts
+*/
+use crate::base_variable::base_types::BaseTypes;
+use crate::collection::collections::Dictionary;
+use crate::compilers::array::expect_arguments;
+use crate::heap::{collect, stats};
+
+const FUNCTIONS: [&str; 2] = ["gc", "gc_stats"];
+
+pub fn is_gc_function(name: &str) -> bool {
+    FUNCTIONS.contains(&name)
+}
+
+pub fn call_gc_function(name: &str, arguments: Vec<BaseTypes>) -> Result<BaseTypes, String> {
+    expect_arguments(name, &arguments, (0, 0))?;
+    match name {
+        "gc" => Ok(BaseTypes::Int(collect() as i32)),
+        "gc_stats" => {
+            let stats = stats();
+            let counts = [
+                ("allocated", stats.allocated),
+                ("live", stats.live),
+                ("collected", stats.collected),
+                ("collections", stats.collections),
+            ];
+            let entries = counts
+                .into_iter()
+                .map(|(name, count)| {
+                    (
+                        BaseTypes::StringWrapper(name.to_string()),
+                        BaseTypes::Int(count as i32),
+                    )
+                })
+                .collect();
+            Ok(Dictionary::new(String::new(), "string".into(), "int".into(), entries).into())
+        }
+        _ => Err(format!("Runtime Error: Function '{}' is not defined", name)),
+    }
+}
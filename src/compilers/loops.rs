@@ -1,137 +1,143 @@
 pub mod loop_compilers {
     use crate::base_variable::base_types::BaseTypes;
-    use crate::compiler::compilers::parse_operator;
-    use crate::compiler::compilers::route_to_parser;
+    use crate::base_variable::variable::Variable;
+    use crate::base_variable::variables::{begin_scope, end_scope, VARIABLE_STACK};
+    use crate::collection::collections::type_name_of;
+    use crate::compilers::block::{condition_nodes, returning, run_scoped_block};
     use crate::compilers::conditional::conditional_compilers::compile_conditional_statement;
-    use crate::compilers::function::parse_function_call;
-    use crate::compilers::variable::parse_variable_call;
-    use crate::node::nodes::from_base_type;
-    use crate::node::nodes::match_token_to_node;
-    use crate::node::nodes::to_base_type;
+    use crate::compilers::expression::evaluate_expression;
+    use crate::globals::report_error;
     use crate::node::nodes::ASTNode;
-    use crate::statement_tokenizer::tokenizer::tokenizers::tokenize;
-    use crate::statement_tokenizer::tokenizer::tokenizers::ParseInfo;
-    use crate::token_type::token_types::TokenTypes;
-
-    static mut make_loop: bool = false;
-
-    fn set_make_loop(value: bool) {
-        unsafe {
-            make_loop = value;
+    use std::error::Error;
+
+    ///
+    ///Runs the body of a while loop for as long as the condition holds. The condition is
+    ///checked again before every run of the body
+    ///
+    pub fn run_while_loop(
+        condition: &str,
+        body: &str,
+        body_line: usize,
+        body_column: usize,
+    ) -> Result<(), Box<dyn Error>> {
+        let nodes = condition_nodes(condition);
+        trace!(Runtime, Debug, "While loop with condition: {}", condition);
+        while compile_conditional_statement(&mut nodes.clone()) {
+            run_scoped_block(body, body_line, body_column)?;
+            if returning() {
+                break;
+            }
         }
+        trace!(Runtime, Debug, "While loop processing completed.");
+        Ok(())
     }
 
-    fn get_make_loop() -> bool {
-        unsafe { make_loop }
+    ///
+    ///Runs the body of for (name in values) once for each value, with name bound to it. The
+    ///values are a range start..end of ints or an array, set, tuple, string, the keys of a
+    ///dictionary or the lines of fs.read_lines, which are read one per run of the body
+    ///
+    pub fn run_for_loop(
+        condition: &str,
+        body: &str,
+        body_line: usize,
+        body_column: usize,
+    ) -> Result<(), Box<dyn Error>> {
+        let (name, iterable) = match condition.split_once(" in ") {
+            Some((name, iterable)) if !name.trim().is_empty() => (name.trim(), iterable),
+            _ => report_error(format!(
+                "Syntax Error: Expected 'name in values' in for ({})",
+                condition
+            )),
+        };
+        let values = match for_values(&condition_nodes(iterable)) {
+            Ok(values) => values,
+            Err(e) => report_error(e),
+        };
+
+        let depth = begin_scope();
+        for value in values {
+            let value = match value {
+                Ok(value) => value,
+                Err(e) => report_error(e),
+            };
+            let var_type = value.clone();
+            let variable = Variable::new(name.to_string(), value, var_type);
+            unsafe {
+                let stack = &mut *std::ptr::addr_of_mut!(VARIABLE_STACK);
+                stack.truncate(depth);
+                stack.push(variable);
+            }
+            run_scoped_block(body, body_line, body_column)?;
+            if returning() {
+                break;
+            }
+        }
+        end_scope(depth);
+        Ok(())
     }
 
-    pub fn compile_for_loop(expression: &Vec<ASTNode>) -> bool {
-        // check the condition and run
-        let mut tokenized: Vec<ParseInfo> = Vec::new();
-        let mut index = 0;
-
-        while index < expression.len() {
-            let node = &expression[index];
-            match node {
-                ASTNode::For(ifnode) => {
-                    let tokenized_statement = tokenize(ifnode.condition.clone());
+    // the values of a loop, a lines handle reads each one as the loop gets to it
+    type Values = Box<dyn Iterator<Item = Result<BaseTypes, String>>>;
 
-                    tokenized.extend(tokenized_statement.clone());
-                    let mut nodes: Vec<ASTNode> = Vec::new();
-                    // convert to ast nodes
-                    for token in tokenized_statement {
-                        nodes.push(match_token_to_node(token));
-                    }
+    fn listed(values: Vec<BaseTypes>) -> Values {
+        Box::new(values.into_iter().map(Ok))
+    }
 
-                    // call the operation function or make custom function for conditional operations
-                    let mut result = compile_conditional_statement(&mut nodes);
-                    return result;
-                }
-                ASTNode::Else => {}
+    ///
+    ///Where the .. of a range like 0..n is in the values of a for loop, None when they are no
+    ///range
+    ///
+    pub fn range_position(nodes: &[ASTNode]) -> Option<usize> {
+        // a .. inside brackets is a slice of the values, not a range
+        let mut depth = 0;
+        nodes.iter().position(|node| {
+            match node {
+                ASTNode::LeftBracket | ASTNode::LeftParenthesis => depth += 1,
+                ASTNode::RightBracket | ASTNode::RightParenthesis => depth -= 1,
                 _ => {}
             }
-            index += 1;
-        }
-        return true;
+            depth == 0 && matches!(node, ASTNode::Operator(o) if o.operator == "..")
+        })
     }
 
-    pub fn compile_while_loop(expression: &mut Vec<ASTNode>) -> bool {
-        let mut tokenized: Vec<ParseInfo> = Vec::new();
-        let mut index = 0;
-
-        while index < expression.len() {
-            let node = &expression[index];
-            match node {
-                ASTNode::While(while_node) => {
-                    println!(
-                        "While loop detected with condition: {}",
-                        while_node.condition
-                    );
-
-                    // Tokenize and evaluate the condition
-                    let tokenized_statement = tokenize(while_node.condition.clone());
-                    tokenized.extend(tokenized_statement.clone());
-
-                    // Convert tokens to AST nodes
-                    let mut condition_nodes: Vec<ASTNode> = tokenized_statement
-                        .into_iter()
-                        .map(match_token_to_node)
-                        .collect();
-
-                    // Evaluate the initial condition
-                    let mut result = compile_conditional_statement(&mut condition_nodes);
-                    println!("Initial condition evaluation result: {}", result);
-
-                    while result {
-                        set_make_loop(true);
-
-                        println!("Entering while loop body");
-
-                        // Process the body of the while loop
-                        let mut body_index = index + 1; // Start after the while node
-                        while body_index < expression.len() {
-                            let body_node = &expression[body_index];
-
-                            println!("Processing body node: {:?}", body_node);
-
-                            // Handle each body node
-                            let body_result = route_to_parser(expression, Some(body_index));
-                            if !body_result {
-                                println!("Parsing failed for body node. Exiting loop.");
-                                return false; // Exit the loop if parsing stops
-                            }
-
-                            body_index += 1; // Move to the next body node
-                        }
-
-                        // Re-evaluate the while loop condition after each iteration
-                        result = compile_conditional_statement(&mut condition_nodes);
-                        println!("Condition re-evaluation result: {}", result);
-
-                        if !result {
-                            println!("Condition is false. Exiting while loop.");
-                            return false; // Exit the loop if the condition is false
-                        }
-                    }
-
-                    set_make_loop(false);
-
-                    // Increment index to move to the next node after the while
-                    index += 1;
-                    println!("Moving to next node after while loop.");
-                    continue; // Skip to the next iteration
-                }
-                ASTNode::Else => {
-                    println!("Else node detected.");
-                    // Handle else statements if needed
-                }
-                _ => {
-                    println!("Unhandled node: {:?}", node);
+    fn for_values(nodes: &[ASTNode]) -> Result<Values, String> {
+        if let Some(range) = range_position(nodes) {
+            return match (
+                evaluate_expression(&nodes[..range])?,
+                evaluate_expression(&nodes[range + 1..])?,
+            ) {
+                (BaseTypes::Int(start), BaseTypes::Int(end)) => {
+                    Ok(Box::new((start..end).map(|i| Ok(BaseTypes::Int(i)))))
                 }
+                (start, end) => Err(format!(
+                    "Type Error: A range needs int bounds, found {}..{}",
+                    type_name_of(&start),
+                    type_name_of(&end)
+                )),
+            };
+        }
+        match evaluate_expression(nodes)? {
+            // the loop goes over the elements as they were when it started
+            BaseTypes::Array(array) => Ok(listed(array.borrow().data.clone())),
+            BaseTypes::Set(set) => Ok(listed(set.borrow().values().to_vec())),
+            BaseTypes::Tuple(values) => Ok(listed(values)),
+            BaseTypes::Dictionary(dictionary) => Ok(listed(
+                dictionary.borrow().keys().into_iter().cloned().collect(),
+            )),
+            BaseTypes::StringWrapper(string) => {
+                Ok(listed(string.chars().map(BaseTypes::Char).collect()))
+            }
+            BaseTypes::Lines(lines) => {
+                let next = move || lines.borrow_mut().next_line().transpose();
+                Ok(Box::new(
+                    std::iter::from_fn(next).map(|line| line.map(BaseTypes::StringWrapper)),
+                ))
             }
-            index += 1; // Move to the next node
+            other => Err(format!(
+                "Type Error: Cannot loop over {}",
+                type_name_of(&other)
+            )),
         }
-        println!("While loop processing completed.");
-        true // Indicate successful processing
     }
 }
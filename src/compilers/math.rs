@@ -0,0 +1,51 @@
+/*
+* This file holds the math module, its constants are read from Jist code as math.PI or
+* math.TAU. They are floats and can be used in a const, they are known before the script runs
+*/
+use crate::base_variable::base_variables::{Pi, E};
+
+// The name the module is read through
+pub const MODULE: &str = "math";
+
+pub const CONSTANTS: [&str; 5] = ["PI", "E", "TAU", "INF", "NAN"];
+
+///
+///The value of math.name, None when the module has no constant called name
+///
+pub fn math_constant(name: &str) -> Option<f64> {
+    match name {
+        "PI" => Some(Pi::new().get_value()),
+        "E" => Some(E::new().get_value()),
+        "TAU" => Some(2.0 * Pi::new().get_value()),
+        "INF" => Some(f64::INFINITY),
+        "NAN" => Some(f64::NAN),
+        _ => None,
+    }
+}
+
+///
+///The value of a dotted name like math.PI, None when it does not name a constant of the module
+///
+pub fn module_constant(name: &str) -> Option<f64> {
+    match name.split_once('.') {
+        Some((module, constant)) if module == MODULE => math_constant(constant),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod math_tests {
+    use super::{module_constant, CONSTANTS, MODULE};
+
+    #[test]
+    fn test_module_constants() {
+        for constant in CONSTANTS {
+            assert!(module_constant(&format!("{}.{}", MODULE, constant)).is_some());
+        }
+        assert_eq!(module_constant("math.TAU"), Some(std::f64::consts::TAU));
+        assert!(module_constant("math.NAN").unwrap().is_nan());
+        assert_eq!(module_constant("math.pi"), None);
+        assert_eq!(module_constant("fs.PI"), None);
+        assert_eq!(module_constant("PI"), None);
+    }
+}
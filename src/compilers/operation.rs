@@ -1,8 +1,9 @@
 pub mod operation {
+    use crate::globals::report_error;
     use crate::node::nodes::ASTNode;
 
     pub fn parse_operation(expression: &Vec<ASTNode>) {
-        println!("Parsing operation.");
+        trace!(Parser, Trace, "Parsing operation.");
         let mut operation: Option<String> = None;
         let mut left_operand: Option<String> = None;
         let mut right_operand: Option<String> = None;
@@ -23,13 +24,16 @@ pub mod operation {
                     }
                 }
                 _ => {
-                    println!("Syntax Error: Unhandled node in operation: {:?}", node);
+                    report_error(format!(
+                        "Syntax Error: Unhandled node in operation: {:?}",
+                        node
+                    ));
                 }
             }
         }
 
         if operation.is_none() || left_operand.is_none() || right_operand.is_none() {
-            println!("Syntax Error: Operation is incomplete.");
+            eprintln!("Syntax Error: Operation is incomplete.");
             return;
         }
 
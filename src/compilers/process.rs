@@ -0,0 +1,89 @@
+/*
+* This file holds the builtins that let a script talk to the process running it: args() for
+* the command-line arguments after the script path, env(name) and set_env(name, value) for
+* environment variables and exit(code) to stop with an exit code
+*/
+use crate::base_variable::base_types::{BaseTypes, GetType};
+use crate::collection::collections::Array;
+use crate::compilers::array::expect_arguments;
+use crate::globals::SCRIPT_ARGUMENTS;
+use std::io::Write;
+
+const FUNCTIONS: [&str; 4] = ["args", "env", "set_env", "exit"];
+
+pub fn is_process_function(name: &str) -> bool {
+    FUNCTIONS.contains(&name)
+}
+
+fn string_argument<'a>(name: &str, argument: &'a BaseTypes) -> Result<&'a str, String> {
+    match argument {
+        BaseTypes::StringWrapper(s) => Ok(s),
+        other => Err(format!(
+            "Runtime Error: {}(): Expected a String but found {}",
+            name,
+            other.GetType()
+        )),
+    }
+}
+
+///
+///Calls name with the arguments, env(name) is null when the variable is not set unless a
+///default is given as env(name, default)
+///
+pub fn call_process_function(name: &str, arguments: Vec<BaseTypes>) -> Result<BaseTypes, String> {
+    match name {
+        "args" => {
+            expect_arguments(name, &arguments, (0, 0))?;
+            let data = unsafe { (*std::ptr::addr_of!(SCRIPT_ARGUMENTS)).clone() };
+            Ok(BaseTypes::from(Array::new(
+                String::new(),
+                "string".into(),
+                data.into_iter().map(BaseTypes::StringWrapper).collect(),
+            )))
+        }
+        "env" => {
+            expect_arguments(name, &arguments, (1, 2))?;
+            let variable = string_argument(name, &arguments[0])?;
+            match std::env::var(variable) {
+                Ok(value) => Ok(BaseTypes::StringWrapper(value)),
+                Err(_) => Ok(arguments.get(1).cloned().unwrap_or(BaseTypes::Null)),
+            }
+        }
+        "set_env" => {
+            expect_arguments(name, &arguments, (2, 2))?;
+            let variable = string_argument(name, &arguments[0])?;
+            if variable.is_empty() || variable.contains('=') || variable.contains('\0') {
+                return Err(format!(
+                    "Runtime Error: set_env(): '{}' is not a valid variable name",
+                    variable
+                ));
+            }
+            // the value is stored the way echo would print it
+            std::env::set_var(variable, arguments[1].to_string());
+            Ok(BaseTypes::Null)
+        }
+        "exit" => {
+            expect_arguments(name, &arguments, (0, 1))?;
+            let code = match arguments.first() {
+                None => 0,
+                // the system keeps only the low byte, exit(256) would end with 0
+                Some(BaseTypes::Int(code)) if !(0..=255).contains(code) => {
+                    return Err(format!(
+                        "Runtime Error: exit(): {} is not an exit code, it has to be from 0 to 255",
+                        code
+                    ))
+                }
+                Some(BaseTypes::Int(code)) => *code,
+                Some(other) => {
+                    return Err(format!(
+                        "Runtime Error: exit(): Expected an Int but found {}",
+                        other.GetType()
+                    ))
+                }
+            };
+            let _ = std::io::stdout().flush();
+            std::process::exit(code);
+        }
+        _ => Err(format!("Runtime Error: Function '{}' is not defined", name)),
+    }
+}
@@ -0,0 +1,143 @@
+/*
+* This file holds the methods that can be called on sets from Jist code, like s.contains(1) or
+* a.union(b). union, intersection and difference return a new set and leave both sides alone
+*/
+use crate::base_variable::base_types::{BaseTypes, GetType};
+use crate::collection::collections::{Array, Set};
+use crate::compilers::array::expect_arguments;
+
+const METHODS: [&str; 11] = [
+    "len",
+    "is_empty",
+    "copy",
+    "contains",
+    "add",
+    "remove",
+    "union",
+    "intersection",
+    "difference",
+    "is_subset",
+    "to_array",
+];
+
+// Methods that change the set they are called on
+const MUTATING_METHODS: [&str; 2] = ["add", "remove"];
+
+pub fn is_set_method(name: &str) -> bool {
+    METHODS.contains(&name)
+}
+
+pub fn mutates(name: &str) -> bool {
+    MUTATING_METHODS.contains(&name)
+}
+
+///
+///Membership goes through the set's own type first, so 1 is found in a set<float>
+///
+fn contains(set: &Set, value: &BaseTypes) -> bool {
+    set.check_value(value.clone())
+        .is_ok_and(|value| set.contains(&value))
+}
+
+///
+///The values of the other side of union, intersection and difference, which can be a set or
+///an array
+///
+fn other_values(name: &str, argument: &BaseTypes) -> Result<Vec<BaseTypes>, String> {
+    match argument {
+        BaseTypes::Set(other) => Ok(other.borrow().values().to_vec()),
+        BaseTypes::Array(other) => Ok(other.borrow().data.clone()),
+        other => Err(format!(
+            "Runtime Error: {}(): Expected a Set but found {}",
+            name,
+            other.GetType()
+        )),
+    }
+}
+
+fn new_set(set: &Set, values: Vec<BaseTypes>) -> BaseTypes {
+    BaseTypes::from(Set::new(String::new(), set.value_type.clone(), values))
+}
+
+///
+///Calls the method name on the set, add and remove change it in place
+///
+pub fn call_set_method(
+    set: &mut Set,
+    name: &str,
+    arguments: Vec<BaseTypes>,
+) -> Result<BaseTypes, String> {
+    match name {
+        "len" => {
+            expect_arguments(name, &arguments, (0, 0))?;
+            Ok(BaseTypes::Int(set.len() as i32))
+        }
+        "is_empty" => {
+            expect_arguments(name, &arguments, (0, 0))?;
+            Ok(BaseTypes::Bool(set.is_empty()))
+        }
+        "copy" => {
+            expect_arguments(name, &arguments, (0, 0))?;
+            Ok(new_set(set, set.values().to_vec()))
+        }
+        "contains" => {
+            expect_arguments(name, &arguments, (1, 1))?;
+            Ok(BaseTypes::Bool(contains(set, &arguments[0])))
+        }
+        "add" => {
+            // returns whether the value was new
+            expect_arguments(name, &arguments, (1, 1))?;
+            let value = set.check_value(arguments[0].clone())?;
+            Ok(BaseTypes::Bool(set.add(value)))
+        }
+        "remove" => {
+            // returns whether the value was there
+            expect_arguments(name, &arguments, (1, 1))?;
+            let removed = match set.check_value(arguments[0].clone()) {
+                Ok(value) => set.remove(&value),
+                Err(_) => false,
+            };
+            Ok(BaseTypes::Bool(removed))
+        }
+        "union" => {
+            expect_arguments(name, &arguments, (1, 1))?;
+            let mut values = set.values().to_vec();
+            for value in other_values(name, &arguments[0])? {
+                values.push(set.check_value(value)?);
+            }
+            Ok(new_set(set, values))
+        }
+        "intersection" | "difference" | "is_subset" => {
+            expect_arguments(name, &arguments, (1, 1))?;
+            let other = Set::new(
+                String::new(),
+                set.value_type.clone(),
+                other_values(name, &arguments[0])?
+                    .into_iter()
+                    .filter_map(|value| set.check_value(value).ok())
+                    .collect(),
+            );
+            let keep = name == "intersection";
+            if name == "is_subset" {
+                let subset = set.values().iter().all(|value| other.contains(value));
+                return Ok(BaseTypes::Bool(subset));
+            }
+            let values = set
+                .values()
+                .iter()
+                .filter(|value| other.contains(value) == keep)
+                .cloned()
+                .collect();
+            Ok(new_set(set, values))
+        }
+        "to_array" => {
+            expect_arguments(name, &arguments, (0, 0))?;
+            Ok(BaseTypes::from(Array::new(
+                String::new(),
+                set.value_type.clone(),
+                set.values().to_vec(),
+            )))
+        }
+        _ => Err(format!("Runtime Error: Sets have no method '{}'", name)),
+    }
+}
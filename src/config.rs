@@ -0,0 +1,148 @@
+/*
+* This file reads jist.toml, the project settings of the jist tools. Only the small part of TOML
+* the settings need is read: [sections] holding `key = value` lines and # comments. The file is
+* found by looking in the script's directory and then in each directory above it
+*/
+use std::path::{Path, PathBuf};
+
+pub const CONFIG_FILE: &str = "jist.toml";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Setting {
+    pub section: String,
+    pub key: String,
+    pub value: String,
+    pub line: usize,
+}
+
+///
+///The nearest jist.toml in dir or a directory above it
+///
+pub fn find_config(dir: &Path) -> Option<PathBuf> {
+    dir.ancestors()
+        .map(|dir| dir.join(CONFIG_FILE))
+        .find(|path| path.is_file())
+}
+
+///
+///The directory the settings of a script are looked up from, the current directory when the
+///script does not come from a file
+///
+pub fn config_dir(script_path: Option<&str>) -> PathBuf {
+    let current = std::env::current_dir().unwrap_or_else(|_| PathBuf::from("."));
+    match script_path.and_then(|path| Path::new(path).parent()) {
+        Some(parent) => current.join(parent),
+        None => current,
+    }
+}
+
+///
+///Reads the settings of a jist.toml, a setting outside any section has an empty section name.
+///Quotes around a string value are removed
+///
+pub fn parse_config(contents: &str) -> Result<Vec<Setting>, String> {
+    let mut settings = Vec::new();
+    let mut section = String::new();
+    for (i, line) in contents.lines().enumerate() {
+        let line_number = i + 1;
+        let line = match line.find('#') {
+            // a # inside a quoted value is part of the value
+            Some(hash) if !line[..hash].contains('"') => &line[..hash],
+            _ => line,
+        }
+        .trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        if let Some(name) = line.strip_prefix('[') {
+            match name.strip_suffix(']') {
+                Some(name) if !name.trim().is_empty() => section = name.trim().to_string(),
+                _ => return Err(format!("{}:{}: Invalid section", CONFIG_FILE, line_number)),
+            }
+            continue;
+        }
+
+        let (key, value) = match line.split_once('=') {
+            Some((key, value)) if !key.trim().is_empty() && !value.trim().is_empty() => {
+                (key.trim(), value.trim())
+            }
+            _ => {
+                return Err(format!(
+                    "{}:{}: Expected `key = value`",
+                    CONFIG_FILE, line_number
+                ))
+            }
+        };
+        let value = value
+            .strip_prefix('"')
+            .and_then(|v| v.strip_suffix('"'))
+            .unwrap_or(value);
+        settings.push(Setting {
+            section: section.clone(),
+            key: key.to_string(),
+            value: value.to_string(),
+            line: line_number,
+        });
+    }
+    Ok(settings)
+}
+
+///
+///Reads the settings of one section from the nearest jist.toml, with the path it was read from.
+///No file gives no settings
+///
+pub fn load_section(dir: &Path, section: &str) -> Result<Vec<Setting>, String> {
+    let path = match find_config(dir) {
+        Some(path) => path,
+        None => return Ok(Vec::new()),
+    };
+    let contents = std::fs::read_to_string(&path)
+        .map_err(|e| format!("Failed to read '{}': {}", path.display(), e))?;
+    let settings = parse_config(&contents).map_err(|e| format!("{} ({})", e, path.display()))?;
+    Ok(settings
+        .into_iter()
+        .filter(|setting| setting.section == section)
+        .collect())
+}
+
+///
+///A setting that has to be a whole number of at least 1
+///
+pub fn positive_number(setting: &Setting) -> Result<usize, String> {
+    match setting.value.parse::<usize>() {
+        Ok(number) if number > 0 => Ok(number),
+        _ => Err(format!(
+            "{}:{}: {}.{} must be a whole number above 0, found '{}'",
+            CONFIG_FILE, setting.line, setting.section, setting.key, setting.value
+        )),
+    }
+}
+
+#[cfg(test)]
+mod config_tests {
+    use super::{parse_config, positive_number};
+
+    #[test]
+    fn test_parse_config() {
+        let settings =
+            parse_config("# jist settings\nname = \"demo\"\n\n[fmt]\nindent_width = 2 # spaces\n")
+                .unwrap();
+        assert_eq!(settings.len(), 2);
+        assert_eq!(
+            (settings[0].section.as_str(), settings[0].value.as_str()),
+            ("", "demo")
+        );
+        assert_eq!(settings[1].section, "fmt");
+        assert_eq!(settings[1].key, "indent_width");
+        assert_eq!(positive_number(&settings[1]), Ok(2));
+
+        assert!(parse_config("[fmt\n").is_err());
+        assert_eq!(
+            parse_config("[fmt]\nindent_width 2\n"),
+            Err("jist.toml:2: Expected `key = value`".to_string())
+        );
+        let zero = parse_config("[fmt]\nindent_width = 0").unwrap();
+        assert!(positive_number(&zero[0]).is_err());
+    }
+}
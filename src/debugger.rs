@@ -0,0 +1,1061 @@
+/*
+* This file holds jist debug, which runs a script and stops it at breakpoints and between
+* statements to look at the running functions and their variables. From the terminal it reads
+* commands like break, next and print, with --dap it serves an editor over the Debug Adapter
+* Protocol on stdin and stdout. The interpreter calls back before each statement with its line,
+* user function bodies carry those lines as Line nodes
+*/
+use crate::base_variable::base_types::BaseTypes;
+use crate::base_variable::variable::Variable;
+use crate::base_variable::variables::VARIABLE_STACK;
+use crate::collection::collections::type_name_of;
+use crate::commands::report_syntax_errors;
+use crate::compilers::expression::{evaluate_expression, expect_bool};
+use crate::globals::{Frame, CALL_STACK, SCRIPT_ARGUMENTS, STATEMENT_HOOK};
+use crate::json::{object, Json};
+use crate::lsp::{read_message, write_message};
+use crate::node::nodes::{token_to_node, ASTNode};
+use crate::parse_source;
+use crate::state_dump::json_string;
+use crate::statement_tokenizer::tokenizer::tokenizers::tokenize;
+use crate::statements::{block, branches, numbered_statements, split_leading_comments};
+use crate::syntax::check_syntax;
+use crate::token_type::token_types::TokenTypes;
+use std::cell::RefCell;
+use std::error::Error;
+use std::fs::File;
+use std::io::{self, BufRead, Read, Write};
+use std::path::Path;
+use std::process::exit;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Receiver};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+const CONSOLE_HELP: &str = "\
+    break <line> [if <condition>]  Stop before the statement on a line, b for short
+    delete <line>                  Remove the breakpoint on a line
+    breakpoints                    List the breakpoints
+    continue                       Run to the next breakpoint, c for short
+    next                           Run the statement, stepping over function calls, n for short
+    step                           Run to the next statement, into function calls, s for short
+    finish                         Run until the current function returns
+    backtrace                      Print the running functions, bt for short
+    locals                         Print the variables of the current function
+    globals                        Print the variables of the script
+    print <expression>             Print the value of an expression, p for short
+    list                           Print the source around the current line, l for short
+    quit                           Stop the script and leave, q for short";
+
+// the script runs on one thread, which is all the protocol reports
+const THREAD_ID: usize = 1;
+
+///
+///A line to stop on, only when the condition is true if it has one
+///
+#[derive(Debug, Clone, PartialEq)]
+pub struct Breakpoint {
+    pub line: usize,
+    pub condition: Option<String>,
+}
+
+///
+///What the script is doing between two stops
+///
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Mode {
+    Run,
+    // stop at the first statement
+    Entry,
+    Pause,
+    StepIn,
+    // stop once the call stack is no deeper than this
+    StepOver(usize),
+    // stop once the call stack is shallower than this
+    StepOut(usize),
+}
+
+///
+///Why the script stopped, the reason is the one the protocol uses
+///
+struct Stop {
+    reason: &'static str,
+    note: Option<String>,
+}
+
+struct Session {
+    name: String,
+    lines: Vec<String>,
+    statement_lines: Vec<usize>,
+    breakpoints: Vec<Breakpoint>,
+    mode: Mode,
+    // the line the script itself is on, the lines of running functions are in their frames
+    script_line: usize,
+    // the editor, None when the commands come from the terminal
+    client: Option<Client>,
+    requests: Option<Receiver<Json>>,
+    // the variable lists handed to the editor while stopped, a reference is an index plus one
+    handles: Vec<Vec<(String, BaseTypes)>>,
+}
+
+thread_local! {
+    static SESSION: RefCell<Option<Session>> = const { RefCell::new(None) };
+}
+
+fn call_stack() -> &'static [Frame] {
+    unsafe { &*std::ptr::addr_of!(CALL_STACK) }
+}
+
+fn variable_stack() -> &'static [Variable] {
+    unsafe { &*std::ptr::addr_of!(VARIABLE_STACK) }
+}
+
+///
+///The lines the debugger stops on, those of the statements of the script and of the bodies of
+///its functions. A breakpoint on any other line moves to the next one of these
+///
+pub fn statement_lines(contents: &str) -> Vec<usize> {
+    let mut lines = statement_lines_from(contents, 1);
+    lines.sort_unstable();
+    lines.dedup();
+    lines
+}
+
+fn statement_lines_from(contents: &str, first_line: usize) -> Vec<usize> {
+    let mut lines = Vec::new();
+    for (line, statement) in numbered_statements(contents, first_line).unwrap_or_default() {
+        if split_leading_comments(&statement).1.is_empty() {
+            continue;
+        }
+        lines.push(line);
+        // the statements in blocks are run one at a time too, and an elif is checked on its
+        // own line
+        for branch in branches(&statement, line).unwrap_or_default() {
+            if matches!(branch.keyword, TokenTypes::Elif { .. }) {
+                lines.push(branch.line);
+            }
+            lines.extend(statement_lines_from(&branch.body, branch.body_line));
+        }
+        if let Some(block) = block(&statement, line) {
+            lines.extend(statement_lines_from(&block.body, block.body_line));
+        }
+    }
+    lines
+}
+
+///
+///Evaluates an expression with the variables the script has now
+///
+pub fn evaluate(expression: &str) -> Result<BaseTypes, String> {
+    let nodes: Vec<ASTNode> = tokenize(expression.trim().trim_end_matches(';').to_string())
+        .into_iter()
+        .filter(|info| info.token != TokenTypes::Comment)
+        .map(token_to_node)
+        .collect::<Result<Vec<ASTNode>, String>>()?;
+    if nodes.is_empty() {
+        return Err("Expected an expression".to_string());
+    }
+    evaluate_expression(&nodes)
+}
+
+///
+///How a value is shown, strings and chars quoted so they stand out from numbers
+///
+pub fn describe(value: &BaseTypes) -> String {
+    match value {
+        BaseTypes::StringWrapper(s) => json_string(s),
+        BaseTypes::Char(c) => format!("'{}'", c),
+        other => other.to_string(),
+    }
+}
+
+///
+///The elements of a collection with the names they are shown under, empty for other values
+///
+pub fn children(value: &BaseTypes) -> Vec<(String, BaseTypes)> {
+    let indexed = |values: &[BaseTypes]| {
+        values
+            .iter()
+            .enumerate()
+            .map(|(i, value)| (format!("[{}]", i), value.clone()))
+            .collect()
+    };
+    match value {
+        BaseTypes::Array(array) => indexed(&array.borrow().data),
+        BaseTypes::Set(set) => indexed(set.borrow().values()),
+        BaseTypes::Tuple(values) => indexed(values),
+        BaseTypes::Dictionary(dictionary) => dictionary
+            .borrow()
+            .entries()
+            .map(|(key, value)| (format!("[{}]", describe(key)), value.clone()))
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+///
+///The functions that are running and the script itself, innermost first, each with the line
+///it is on
+///
+fn frames(script_line: usize) -> Vec<(String, usize)> {
+    let mut frames: Vec<(String, usize)> = call_stack()
+        .iter()
+        .rev()
+        .map(|frame| (frame.function.clone(), frame.line))
+        .collect();
+    frames.push(("script".to_string(), script_line));
+    frames
+}
+
+///
+///The variables of a frame, counted from the innermost one. The script itself has none of its
+///own, its variables are the globals
+///
+fn locals(frame: usize) -> Option<Vec<(String, BaseTypes)>> {
+    let calls = call_stack();
+    let index = calls.len().checked_sub(frame + 1)?;
+    let stack = variable_stack();
+    let end = calls
+        .get(index + 1)
+        .map_or(stack.len(), |next| next.variables);
+    Some(named(&stack[calls[index].variables..end]))
+}
+
+fn globals() -> Vec<(String, BaseTypes)> {
+    let stack = variable_stack();
+    let end = call_stack()
+        .first()
+        .map_or(stack.len(), |frame| frame.variables);
+    named(&stack[..end])
+}
+
+fn named(variables: &[Variable]) -> Vec<(String, BaseTypes)> {
+    variables
+        .iter()
+        .map(|variable| (variable.name.clone(), variable.value.clone()))
+        .collect()
+}
+
+///
+///Called by the interpreter before each statement
+///
+fn on_statement(line: usize) {
+    SESSION.with(|session| {
+        // a function called by an expression evaluated while stopped does not stop again
+        if let Ok(mut session) = session.try_borrow_mut() {
+            if let Some(session) = session.as_mut() {
+                session.statement(line);
+            }
+        }
+    });
+}
+
+impl Session {
+    fn new(name: String, contents: &str) -> Session {
+        Session {
+            name,
+            lines: contents.lines().map(String::from).collect(),
+            statement_lines: statement_lines(contents),
+            breakpoints: Vec::new(),
+            mode: Mode::Entry,
+            script_line: 0,
+            client: None,
+            requests: None,
+            handles: Vec::new(),
+        }
+    }
+
+    ///
+    ///Runs the script under the debugger and hands the session back once it finished
+    ///
+    fn run(self, contents: &str) -> (Session, Result<(), Box<dyn Error>>) {
+        SESSION.with(|session| *session.borrow_mut() = Some(self));
+        unsafe { STATEMENT_HOOK = Some(on_statement) };
+        let result = parse_source(contents);
+        unsafe { STATEMENT_HOOK = None };
+        let session = SESSION.with(|session| session.borrow_mut().take());
+        (session.expect("the session outlives the script"), result)
+    }
+
+    ///
+    ///Sets a breakpoint on the first statement at or after the line, returning the line it
+    ///ended up on. None when there is no statement there
+    ///
+    fn add_breakpoint(&mut self, line: usize, condition: Option<String>) -> Option<usize> {
+        let line = *self.statement_lines.iter().find(|&&l| l >= line)?;
+        self.breakpoints
+            .retain(|breakpoint| breakpoint.line != line);
+        self.breakpoints.push(Breakpoint { line, condition });
+        self.breakpoints.sort_by_key(|breakpoint| breakpoint.line);
+        Some(line)
+    }
+
+    fn statement(&mut self, line: usize) {
+        let depth = call_stack().len();
+        match unsafe { (*std::ptr::addr_of_mut!(CALL_STACK)).last_mut() } {
+            Some(frame) => frame.line = line,
+            None => self.script_line = line,
+        }
+
+        // the editor may have paused or changed breakpoints while the script ran
+        while let Some(request) = self.requests.as_ref().and_then(|r| r.try_recv().ok()) {
+            self.request(&request, None);
+        }
+
+        let reason = match self.mode {
+            Mode::Entry => Some("entry"),
+            Mode::Pause => Some("pause"),
+            Mode::StepIn => Some("step"),
+            Mode::StepOver(d) if depth <= d => Some("step"),
+            Mode::StepOut(d) if depth < d => Some("step"),
+            _ => None,
+        };
+        let stop = match reason {
+            Some(reason) => Stop { reason, note: None },
+            None => match self.breakpoint_hit(line) {
+                Some(stop) => stop,
+                None => return,
+            },
+        };
+        self.mode = match self.client {
+            Some(_) => self.stopped_in_editor(stop, depth),
+            None => self.stopped_in_console(stop, line, depth),
+        };
+        self.handles.clear();
+    }
+
+    fn breakpoint_hit(&self, line: usize) -> Option<Stop> {
+        let breakpoint = self.breakpoints.iter().find(|b| b.line == line)?;
+        let condition = match &breakpoint.condition {
+            Some(condition) if !condition.trim().is_empty() => condition,
+            _ => {
+                return Some(Stop {
+                    reason: "breakpoint",
+                    note: None,
+                })
+            }
+        };
+        // a condition that fails stops the script so the mistake is seen
+        match evaluate(condition).and_then(|value| expect_bool(&value)) {
+            Ok(true) => Some(Stop {
+                reason: "breakpoint",
+                note: None,
+            }),
+            Ok(false) => None,
+            Err(e) => Some(Stop {
+                reason: "breakpoint",
+                note: Some(format!(
+                    "Breakpoint condition '{}' failed: {}",
+                    condition, e
+                )),
+            }),
+        }
+    }
+
+    fn source_line(&self, line: usize) -> &str {
+        line.checked_sub(1)
+            .and_then(|i| self.lines.get(i))
+            .map_or("", |text| text.trim_end())
+    }
+
+    fn stopped_in_console(&mut self, stop: Stop, line: usize, depth: usize) -> Mode {
+        let function = frames(self.script_line).swap_remove(0).0;
+        println!("Stopped at line {} in {} ({})", line, function, stop.reason);
+        if let Some(note) = stop.note {
+            println!("{}", note);
+        }
+        println!("{:>4} | {}", line, self.source_line(line));
+
+        let stdin = io::stdin();
+        loop {
+            print!("(jist) ");
+            let _ = io::stdout().flush();
+            let mut input = String::new();
+            // without more commands the script runs to its end
+            if matches!(stdin.lock().read_line(&mut input), Ok(0) | Err(_)) {
+                println!();
+                self.breakpoints.clear();
+                return Mode::Run;
+            }
+            let input = input.trim();
+            let (command, rest) = input.split_once(' ').unwrap_or((input, ""));
+            let rest = rest.trim();
+            match command {
+                "" => {}
+                "c" | "continue" => return Mode::Run,
+                "n" | "next" => return Mode::StepOver(depth),
+                "s" | "step" => return Mode::StepIn,
+                "finish" => return Mode::StepOut(depth),
+                "b" | "break" => {
+                    let (at, condition) = match rest.split_once(" if ") {
+                        Some((at, condition)) => (at, Some(condition.trim().to_string())),
+                        None => (rest, None),
+                    };
+                    match at.trim().parse::<usize>() {
+                        Ok(at) => match self.add_breakpoint(at, condition) {
+                            Some(set) => println!("Breakpoint at line {}", set),
+                            None => println!("Error: No statement at or after line {}", at),
+                        },
+                        Err(_) => println!("Error: break needs a line number"),
+                    }
+                }
+                "delete" => match rest.parse::<usize>() {
+                    Ok(at) if self.breakpoints.iter().any(|b| b.line == at) => {
+                        self.breakpoints.retain(|b| b.line != at);
+                        println!("Deleted the breakpoint at line {}", at);
+                    }
+                    _ => println!("Error: No breakpoint at line '{}'", rest),
+                },
+                "breakpoints" => {
+                    if self.breakpoints.is_empty() {
+                        println!("No breakpoints");
+                    }
+                    for breakpoint in &self.breakpoints {
+                        match &breakpoint.condition {
+                            Some(condition) => {
+                                println!("line {} if {}", breakpoint.line, condition)
+                            }
+                            None => println!("line {}", breakpoint.line),
+                        }
+                    }
+                }
+                "bt" | "backtrace" => {
+                    for (i, (function, line)) in frames(self.script_line).iter().enumerate() {
+                        println!("#{} {} at line {}", i, function, line);
+                    }
+                }
+                "locals" => match locals(0) {
+                    Some(variables) => print_variables(&variables),
+                    None => println!("No function is running, see globals"),
+                },
+                "globals" => print_variables(&globals()),
+                "p" | "print" => match evaluate(rest) {
+                    Ok(value) => println!("{}", describe(&value)),
+                    Err(e) => println!("Error: {}", e),
+                },
+                "l" | "list" => {
+                    let first = line.saturating_sub(3).max(1);
+                    for number in first..=(line + 3).min(self.lines.len()) {
+                        let marker = if number == line { ">" } else { " " };
+                        println!("{}{:>4} | {}", marker, number, self.source_line(number));
+                    }
+                }
+                "q" | "quit" => exit(0),
+                "h" | "help" => println!("{}", CONSOLE_HELP),
+                _ => println!("Unknown command '{}', help lists the commands", command),
+            }
+        }
+    }
+
+    fn stopped_in_editor(&mut self, stop: Stop, depth: usize) -> Mode {
+        let client = self.client.clone().expect("stopped in an editor");
+        let mut body = vec![
+            ("reason", Json::from(stop.reason)),
+            ("threadId", THREAD_ID.into()),
+            ("allThreadsStopped", true.into()),
+        ];
+        if let Some(note) = stop.note {
+            body.push(("text", note.into()));
+        }
+        client.event("stopped", object(body));
+        loop {
+            let request = match self.requests.as_ref().and_then(|r| r.recv().ok()) {
+                Some(request) => request,
+                // the editor went away
+                None => exit(0),
+            };
+            if let Some(mode) = self.request(&request, Some(depth)) {
+                return mode;
+            }
+        }
+    }
+
+    ///
+    ///Answers a request from the editor. depth is the call stack depth when the script is
+    ///stopped, a request that resumes it returns the mode to run in
+    ///
+    fn request(&mut self, request: &Json, depth: Option<usize>) -> Option<Mode> {
+        let client = self.client.clone()?;
+        let arguments = request.get("arguments");
+        let stopped = |mode: Mode| match depth {
+            Some(_) => {
+                client.respond(request, object(vec![]));
+                Some(mode)
+            }
+            None => {
+                client.fail(request, "The script is not stopped");
+                None
+            }
+        };
+        match request.get("command").as_str().unwrap_or("") {
+            "initialize" => client.respond(request, capabilities()),
+            "setBreakpoints" => {
+                let body = self.set_breakpoints(arguments);
+                client.respond(request, body);
+            }
+            "setExceptionBreakpoints" => {
+                client.respond(request, object(vec![("breakpoints", Json::Array(vec![]))]))
+            }
+            "configurationDone" => client.respond(request, object(vec![])),
+            "threads" => client.respond(
+                request,
+                object(vec![(
+                    "threads",
+                    vec![object(vec![
+                        ("id", THREAD_ID.into()),
+                        ("name", "main".into()),
+                    ])]
+                    .into(),
+                )]),
+            ),
+            "stackTrace" if depth.is_some() => {
+                let source = object(vec![
+                    ("name", self.display_name().into()),
+                    ("path", self.name.clone().into()),
+                ]);
+                let frames: Vec<Json> = frames(self.script_line)
+                    .into_iter()
+                    .enumerate()
+                    .map(|(i, (function, line))| {
+                        object(vec![
+                            ("id", (i + 1).into()),
+                            ("name", function.into()),
+                            ("source", source.clone()),
+                            ("line", line.into()),
+                            ("column", 1usize.into()),
+                        ])
+                    })
+                    .collect();
+                let total = frames.len();
+                client.respond(
+                    request,
+                    object(vec![
+                        ("stackFrames", frames.into()),
+                        ("totalFrames", total.into()),
+                    ]),
+                );
+            }
+            "scopes" if depth.is_some() => {
+                let frame = arguments.get("frameId").as_usize().unwrap_or(1);
+                let mut scopes = Vec::new();
+                if let Some(variables) = locals(frame.saturating_sub(1)) {
+                    scopes.push(self.scope("Locals", variables));
+                }
+                scopes.push(self.scope("Globals", globals()));
+                client.respond(request, object(vec![("scopes", scopes.into())]));
+            }
+            "variables" if depth.is_some() => {
+                let reference = arguments.get("variablesReference").as_usize();
+                let variables = match reference.and_then(|r| self.handles.get(r.wrapping_sub(1))) {
+                    Some(variables) => variables.clone(),
+                    None => {
+                        client.fail(request, "Unknown variablesReference");
+                        return None;
+                    }
+                };
+                let variables: Vec<Json> = variables
+                    .into_iter()
+                    .map(|(name, value)| self.variable(&name, value))
+                    .collect();
+                client.respond(request, object(vec![("variables", variables.into())]));
+            }
+            "evaluate" => {
+                let expression = arguments.get("expression").as_str().unwrap_or("");
+                match evaluate(expression) {
+                    Ok(value) => {
+                        let reference = self.handle(&value);
+                        client.respond(
+                            request,
+                            object(vec![
+                                ("result", describe(&value).into()),
+                                ("type", type_name_of(&value).into()),
+                                ("variablesReference", reference.into()),
+                            ]),
+                        );
+                    }
+                    Err(e) => client.fail(request, &e),
+                }
+            }
+            "continue" => {
+                return match depth {
+                    Some(_) => {
+                        client.respond(request, object(vec![("allThreadsContinued", true.into())]));
+                        Some(Mode::Run)
+                    }
+                    None => {
+                        client.fail(request, "The script is not stopped");
+                        None
+                    }
+                }
+            }
+            "next" => return stopped(Mode::StepOver(depth.unwrap_or(0))),
+            "stepIn" => return stopped(Mode::StepIn),
+            "stepOut" => return stopped(Mode::StepOut(depth.unwrap_or(0))),
+            "pause" => {
+                client.respond(request, object(vec![]));
+                if depth.is_none() {
+                    self.mode = Mode::Pause;
+                }
+            }
+            // the script runs in this process, so leaving ends it
+            "disconnect" | "terminate" => {
+                client.respond(request, object(vec![]));
+                exit(0);
+            }
+            "stackTrace" | "scopes" | "variables" => {
+                client.fail(request, "The script is not stopped")
+            }
+            command => client.fail(request, &format!("Unsupported request '{}'", command)),
+        }
+        None
+    }
+
+    fn display_name(&self) -> String {
+        Path::new(&self.name)
+            .file_name()
+            .map_or(self.name.clone(), |name| name.to_string_lossy().to_string())
+    }
+
+    fn set_breakpoints(&mut self, arguments: &Json) -> Json {
+        let path = arguments.get("source").get("path").as_str();
+        // only the script being debugged has statements to stop on
+        let ours = path.is_none_or(|path| same_file(path, &self.name));
+        if ours {
+            self.breakpoints.clear();
+        }
+        let breakpoints: Vec<Json> = arguments
+            .get("breakpoints")
+            .as_array()
+            .iter()
+            .map(|breakpoint| {
+                let line = breakpoint.get("line").as_usize().unwrap_or(0);
+                let condition = breakpoint.get("condition").as_str().map(String::from);
+                match Some(line)
+                    .filter(|_| ours)
+                    .and_then(|l| self.add_breakpoint(l, condition))
+                {
+                    Some(line) => object(vec![("verified", true.into()), ("line", line.into())]),
+                    None => object(vec![
+                        ("verified", false.into()),
+                        ("line", line.into()),
+                        ("message", "No statement at or after this line".into()),
+                    ]),
+                }
+            })
+            .collect();
+        object(vec![("breakpoints", breakpoints.into())])
+    }
+
+    ///
+    ///A reference the editor can ask the variables of, 0 for a value without any
+    ///
+    fn handle(&mut self, value: &BaseTypes) -> usize {
+        let elements = children(value);
+        let compound = matches!(
+            value,
+            BaseTypes::Array(_)
+                | BaseTypes::Dictionary(_)
+                | BaseTypes::Set(_)
+                | BaseTypes::Tuple(_)
+        );
+        if !compound {
+            return 0;
+        }
+        self.handles.push(elements);
+        self.handles.len()
+    }
+
+    fn scope(&mut self, name: &str, variables: Vec<(String, BaseTypes)>) -> Json {
+        self.handles.push(variables);
+        object(vec![
+            ("name", name.into()),
+            ("variablesReference", self.handles.len().into()),
+            ("expensive", false.into()),
+        ])
+    }
+
+    fn variable(&mut self, name: &str, value: BaseTypes) -> Json {
+        let reference = self.handle(&value);
+        object(vec![
+            ("name", name.into()),
+            ("value", describe(&value).into()),
+            ("type", type_name_of(&value).into()),
+            ("variablesReference", reference.into()),
+        ])
+    }
+}
+
+fn same_file(a: &str, b: &str) -> bool {
+    match (Path::new(a).canonicalize(), Path::new(b).canonicalize()) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => a == b,
+    }
+}
+
+fn print_variables(variables: &[(String, BaseTypes)]) {
+    if variables.is_empty() {
+        println!("No variables");
+    }
+    for (name, value) in variables {
+        println!("{}: {} = {}", name, type_name_of(value), describe(value));
+    }
+}
+
+fn capabilities() -> Json {
+    object(vec![
+        ("supportsConfigurationDoneRequest", true.into()),
+        ("supportsConditionalBreakpoints", true.into()),
+        ("supportsEvaluateForHovers", true.into()),
+        ("supportsTerminateRequest", true.into()),
+    ])
+}
+
+///
+///Debugs a script from the terminal, stopping at its first statement
+///
+pub fn run_console(name: String, contents: &str, arguments: Vec<String>) -> i32 {
+    let errors = check_syntax(contents);
+    if !errors.is_empty() {
+        report_syntax_errors(&name, contents, &errors);
+        return 1;
+    }
+    unsafe { SCRIPT_ARGUMENTS = arguments };
+    println!("Debugging {}, help lists the commands", name);
+    let (_, result) = Session::new(name, contents).run(contents);
+    match result {
+        Ok(()) => {
+            println!("The script finished");
+            0
+        }
+        Err(e) => {
+            println!("Failed to parse file: {}", e);
+            1
+        }
+    }
+}
+
+///
+///Sends messages to the editor. Both the script and the thread forwarding its output send, so
+///each message is written whole under the lock
+///
+#[derive(Clone)]
+struct Client {
+    output: Arc<Mutex<Box<dyn Write + Send>>>,
+    seq: Arc<AtomicUsize>,
+}
+
+impl Client {
+    fn send(&self, kind: &str, mut entries: Vec<(&str, Json)>) {
+        let seq = self.seq.fetch_add(1, Ordering::SeqCst) + 1;
+        entries.insert(0, ("seq", seq.into()));
+        entries.insert(1, ("type", kind.into()));
+        let mut output = match self.output.lock() {
+            Ok(guard) => guard,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+        if let Err(e) = write_message(&mut *output, &object(entries)) {
+            eprintln!("jist debug: Failed to write a message: {}", e);
+            exit(1);
+        }
+    }
+
+    fn reply(&self, request: &Json, success: bool, extra: (&str, Json)) {
+        self.send(
+            "response",
+            vec![
+                ("request_seq", request.get("seq").clone()),
+                ("success", success.into()),
+                ("command", request.get("command").clone()),
+                extra,
+            ],
+        );
+    }
+
+    fn respond(&self, request: &Json, body: Json) {
+        self.reply(request, true, ("body", body));
+    }
+
+    fn fail(&self, request: &Json, message: &str) {
+        self.reply(request, false, ("message", message.into()));
+    }
+
+    fn event(&self, event: &str, body: Json) {
+        self.send("event", vec![("event", event.into()), ("body", body)]);
+    }
+}
+
+///
+///Takes stdout for the messages to the editor. What the script prints goes into a pipe
+///instead, which is read back and sent as output events
+///
+#[cfg(unix)]
+fn capture_output() -> (Box<dyn Write + Send>, Option<File>) {
+    use std::os::unix::io::FromRawFd;
+    extern "C" {
+        fn dup(fd: i32) -> i32;
+        fn dup2(from: i32, to: i32) -> i32;
+        fn pipe(fds: *mut i32) -> i32;
+        fn close(fd: i32) -> i32;
+    }
+    unsafe {
+        let stdout = dup(1);
+        let mut fds = [0; 2];
+        if stdout >= 0 && pipe(fds.as_mut_ptr()) == 0 && dup2(fds[1], 1) >= 0 {
+            close(fds[1]);
+            return (
+                Box::new(File::from_raw_fd(stdout)),
+                Some(File::from_raw_fd(fds[0])),
+            );
+        }
+    }
+    (Box::new(io::stdout()), None)
+}
+
+#[cfg(not(unix))]
+fn capture_output() -> (Box<dyn Write + Send>, Option<File>) {
+    (Box::new(io::stdout()), None)
+}
+
+///
+///Closes the pipe the script printed into, so the thread reading it sees the end
+///
+#[cfg(unix)]
+fn release_output() {
+    extern "C" {
+        fn dup2(from: i32, to: i32) -> i32;
+    }
+    let _ = io::stdout().flush();
+    unsafe { dup2(2, 1) };
+}
+
+#[cfg(not(unix))]
+fn release_output() {
+    let _ = io::stdout().flush();
+}
+
+fn forward_output(mut pipe: File, client: Client) {
+    let mut buffer = [0; 4096];
+    let mut pending: Vec<u8> = Vec::new();
+    loop {
+        let read = match pipe.read(&mut buffer) {
+            Ok(0) | Err(_) => break,
+            Ok(read) => read,
+        };
+        pending.extend_from_slice(&buffer[..read]);
+        // a character cut in two by the read waits for the rest of it
+        let complete = match std::str::from_utf8(&pending) {
+            Err(e) if e.error_len().is_none() => e.valid_up_to(),
+            _ => pending.len(),
+        };
+        let text = String::from_utf8_lossy(&pending[..complete]).to_string();
+        pending.drain(..complete);
+        if !text.is_empty() {
+            client.event(
+                "output",
+                object(vec![("category", "stdout".into()), ("output", text.into())]),
+            );
+        }
+    }
+}
+
+///
+///Serves an editor over the Debug Adapter Protocol until it disconnects. The script to debug
+///comes with the launch request, which the editor sends after initialize
+///
+pub fn run_adapter() -> i32 {
+    let (output, program_output) = capture_output();
+    let client = Client {
+        output: Arc::new(Mutex::new(output)),
+        seq: Arc::new(AtomicUsize::new(0)),
+    };
+    let forwarder = program_output.map(|pipe| {
+        let client = client.clone();
+        thread::spawn(move || forward_output(pipe, client))
+    });
+
+    // requests are read on their own thread so a pause can arrive while the script runs
+    let (sender, requests) = mpsc::channel();
+    thread::spawn(move || {
+        let stdin = io::stdin();
+        let mut input = stdin.lock();
+        while let Ok(Some(message)) = read_message(&mut input) {
+            match Json::parse(&message) {
+                Ok(request) => {
+                    if sender.send(request).is_err() {
+                        return;
+                    }
+                }
+                Err(e) => eprintln!("jist debug: {}", e),
+            }
+        }
+    });
+
+    let mut session: Option<Session> = None;
+    let mut configured = false;
+    while !configured || session.is_none() {
+        let request = match requests.recv() {
+            Ok(request) => request,
+            Err(_) => return 0,
+        };
+        match request.get("command").as_str().unwrap_or("") {
+            "initialize" => client.respond(&request, capabilities()),
+            "launch" => match launch(request.get("arguments")) {
+                Ok(mut launched) => {
+                    launched.client = Some(client.clone());
+                    session = Some(launched);
+                    client.respond(&request, object(vec![]));
+                    // breakpoints are only verified once the script is known
+                    client.event("initialized", object(vec![]));
+                }
+                Err(e) => client.fail(&request, &e),
+            },
+            "configurationDone" => {
+                configured = true;
+                client.respond(&request, object(vec![]));
+            }
+            "disconnect" | "terminate" => {
+                client.respond(&request, object(vec![]));
+                return 0;
+            }
+            _ => match session.as_mut() {
+                Some(session) => {
+                    session.request(&request, None);
+                }
+                None => client.fail(&request, "The script has not been launched"),
+            },
+        }
+    }
+
+    let mut session = session.expect("launched before running");
+    session.requests = Some(requests);
+    let contents = session.lines.join("\n");
+    let (mut session, result) = session.run(&contents);
+
+    release_output();
+    if let Some(forwarder) = forwarder {
+        let _ = forwarder.join();
+    }
+    let exit_code = match result {
+        Ok(()) => 0,
+        Err(e) => {
+            client.event(
+                "output",
+                object(vec![
+                    ("category", "stderr".into()),
+                    ("output", format!("Failed to parse file: {}\n", e).into()),
+                ]),
+            );
+            1
+        }
+    };
+    client.event("exited", object(vec![("exitCode", exit_code.into())]));
+    client.event("terminated", object(vec![]));
+
+    let requests = session
+        .requests
+        .take()
+        .expect("requests are read while running");
+    while let Ok(request) = requests.recv() {
+        match request.get("command").as_str() {
+            Some("disconnect") | Some("terminate") => {
+                client.respond(&request, object(vec![]));
+                return 0;
+            }
+            _ => {
+                session.request(&request, None);
+            }
+        }
+    }
+    0
+}
+
+///
+///Reads the script a launch request names and sets its arguments
+///
+fn launch(arguments: &Json) -> Result<Session, String> {
+    let program = arguments
+        .get("program")
+        .as_str()
+        .ok_or("The launch request needs the path of the script as program")?;
+    if !program.ends_with(".jist") {
+        return Err("File path not valid: Does not have extension .jist".to_string());
+    }
+    let contents = std::fs::read_to_string(program)
+        .map_err(|e| format!("Failed to read script '{}': {}", program, e))?;
+    if let Some(error) = check_syntax(&contents).first() {
+        return Err(format!(
+            "{}:{}:{}: {}",
+            program, error.span.line, error.span.column, error
+        ));
+    }
+    let script_arguments = arguments
+        .get("args")
+        .as_array()
+        .iter()
+        .filter_map(|argument| argument.as_str().map(String::from))
+        .collect();
+    unsafe { SCRIPT_ARGUMENTS = script_arguments };
+
+    let mut session = Session::new(program.to_string(), &contents);
+    if arguments.get("stopOnEntry").as_bool() != Some(true) {
+        session.mode = Mode::Run;
+    }
+    Ok(session)
+}
+
+#[cfg(test)]
+mod debugger_tests {
+    use super::{children, describe, statement_lines, Session};
+    use crate::base_variable::base_types::BaseTypes;
+
+    const SCRIPT: &str = "\
+// adds up to n
+func total(n: int) -> int {
+    let sum: int = 0;
+
+    return sum + n;
+}
+let result: int = total(3);
+echoln(result);
+";
+
+    #[test]
+    fn test_statement_lines() {
+        assert_eq!(statement_lines(SCRIPT), vec![2, 3, 5, 7, 8]);
+    }
+
+    #[test]
+    fn test_breakpoints_move_to_statements() {
+        let mut session = Session::new("total.jist".to_string(), SCRIPT);
+        assert_eq!(session.add_breakpoint(1, None), Some(2));
+        assert_eq!(
+            session.add_breakpoint(4, Some("n > 1".to_string())),
+            Some(5)
+        );
+        assert_eq!(session.add_breakpoint(9, None), None);
+        // a second breakpoint on a line replaces the first
+        assert_eq!(session.add_breakpoint(5, None), Some(5));
+        let lines: Vec<_> = session.breakpoints.iter().map(|b| b.line).collect();
+        assert_eq!(lines, vec![2, 5]);
+        assert_eq!(session.breakpoints[1].condition, None);
+    }
+
+    #[test]
+    fn test_describe_and_children() {
+        assert_eq!(
+            describe(&BaseTypes::StringWrapper("a\"b".into())),
+            "\"a\\\"b\""
+        );
+        assert_eq!(describe(&BaseTypes::Char('x')), "'x'");
+        assert_eq!(describe(&BaseTypes::Int(3)), "3");
+        let tuple = BaseTypes::Tuple(vec![BaseTypes::Int(1), BaseTypes::Bool(true)]);
+        assert_eq!(
+            children(&tuple),
+            vec![
+                ("[0]".to_string(), BaseTypes::Int(1)),
+                ("[1]".to_string(), BaseTypes::Bool(true))
+            ]
+        );
+        assert!(children(&BaseTypes::Int(1)).is_empty());
+    }
+}
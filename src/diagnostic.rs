@@ -0,0 +1,544 @@
+/*
+* This file renders the errors jist reports about a script the way rustc does: the kind and the
+* message, where in the script it is, the lines it is about with the spans underlined and
+* labeled, then notes and help. --color picks whether it is coloured and --error-format=json
+* writes each error as one line of JSON for tools instead
+*/
+use crate::globals::CALL_STACK;
+use crate::json::{object, Json};
+use crate::syntax::{Span, SyntaxError};
+use ansi_term::{Colour, Style};
+use std::env;
+use std::io::IsTerminal;
+use std::sync::OnceLock;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ColorChoice {
+    Never,
+    Always,
+    // coloured when stderr is a terminal and NO_COLOR is not set
+    Auto,
+}
+
+impl ColorChoice {
+    pub fn parse(text: &str) -> Result<Self, String> {
+        match text {
+            "never" => Ok(ColorChoice::Never),
+            "always" => Ok(ColorChoice::Always),
+            "auto" => Ok(ColorChoice::Auto),
+            _ => Err(format!(
+                "Unknown color choice '{}', expected never, always or auto",
+                text
+            )),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ErrorFormat {
+    Human,
+    Json,
+}
+
+impl ErrorFormat {
+    pub fn parse(text: &str) -> Result<Self, String> {
+        match text {
+            "human" => Ok(ErrorFormat::Human),
+            "json" => Ok(ErrorFormat::Json),
+            _ => Err(format!(
+                "Unknown error format '{}', expected human or json",
+                text
+            )),
+        }
+    }
+}
+
+///
+///A span of the source with what it says about it, the primary one is what the error is about
+///
+#[derive(Debug, Clone, PartialEq)]
+pub struct Label {
+    pub span: Span,
+    pub message: String,
+    pub primary: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    // like Syntax Error or Runtime Error
+    pub kind: String,
+    pub code: Option<String>,
+    pub message: String,
+    pub labels: Vec<Label>,
+    pub notes: Vec<String>,
+    pub help: Vec<String>,
+}
+
+// the kinds the interpreter starts its error messages with
+const KINDS: [&str; 3] = ["Syntax Error", "Type Error", "Runtime Error"];
+
+impl Diagnostic {
+    pub fn new(kind: &str, message: &str) -> Self {
+        Diagnostic {
+            kind: kind.to_string(),
+            code: None,
+            message: message.to_string(),
+            labels: Vec::new(),
+            notes: Vec::new(),
+            help: Vec::new(),
+        }
+    }
+
+    ///
+    ///A diagnostic from an error message like "Runtime Error: Key not found", the kind is read
+    ///off the front of it
+    ///
+    pub fn from_message(message: &str) -> Self {
+        KINDS
+            .iter()
+            .find_map(|kind| {
+                let rest = message.strip_prefix(kind)?;
+                let rest = rest.strip_prefix(':').or_else(|| rest.strip_prefix(','))?;
+                Some(Diagnostic::new(kind, rest.trim_start()))
+            })
+            .unwrap_or_else(|| Diagnostic::new("Error", message))
+    }
+
+    pub fn code(mut self, code: &str) -> Self {
+        self.code = Some(code.to_string());
+        self
+    }
+
+    pub fn label(mut self, span: Span, message: &str) -> Self {
+        self.labels.push(Label {
+            span,
+            message: message.to_string(),
+            primary: true,
+        });
+        self
+    }
+
+    pub fn secondary(mut self, span: Span, message: &str) -> Self {
+        self.labels.push(Label {
+            span,
+            message: message.to_string(),
+            primary: false,
+        });
+        self
+    }
+
+    pub fn note(mut self, note: &str) -> Self {
+        self.notes.push(note.to_string());
+        self
+    }
+
+    pub fn help(mut self, help: &str) -> Self {
+        self.help.push(help.to_string());
+        self
+    }
+
+    fn title(&self) -> String {
+        match &self.code {
+            Some(code) => format!("{}[{}]", self.kind, code),
+            None => self.kind.clone(),
+        }
+    }
+
+    ///
+    ///The span the error is reported at, the first primary one
+    ///
+    pub fn location(&self) -> Option<Span> {
+        self.labels
+            .iter()
+            .find(|label| label.primary)
+            .or_else(|| self.labels.first())
+            .map(|label| label.span)
+    }
+
+    ///
+    ///The diagnostic as text, with the lines of the source it is about
+    ///
+    pub fn render(&self, name: &str, source: &str, color: bool) -> String {
+        let paint = |style: Style, text: &str| {
+            if color {
+                style.paint(text).to_string()
+            } else {
+                text.to_string()
+            }
+        };
+        // a lint at the warn level is shown in yellow
+        let error = match self.kind.as_str() {
+            "Warning" => Colour::Yellow.bold(),
+            _ => Colour::Red.bold(),
+        };
+        let gutter = Colour::Blue.bold();
+        let bold = Style::new().bold();
+
+        let mut out = format!(
+            "{}{}\n",
+            paint(error, &self.title()),
+            paint(bold, &format!(": {}", self.message))
+        );
+        let lines: Vec<&str> = source.lines().collect();
+        let mut labeled: Vec<usize> = self.labels.iter().map(|l| l.span.line).collect();
+        labeled.sort_unstable();
+        labeled.dedup();
+        let width = labeled.last().map_or(1, |line| line.to_string().len());
+        let pad = " ".repeat(width);
+
+        let place = match self.location() {
+            Some(span) => format!("{}:{}:{}", name, span.line, span.column),
+            None => name.to_string(),
+        };
+        out += &format!("{}{} {}\n", pad, paint(gutter, "-->"), place);
+        if !labeled.is_empty() {
+            out += &format!("{} {}\n", pad, paint(gutter, "|"));
+        }
+
+        let mut previous: Option<usize> = None;
+        for &line in &labeled {
+            match previous {
+                Some(previous) if line == previous + 2 => {
+                    out += &source_line(&lines, previous + 1, width, &paint, gutter)
+                }
+                Some(previous) if line > previous + 2 => {
+                    out += &format!("{}\n", paint(gutter, "..."))
+                }
+                _ => {}
+            }
+            out += &source_line(&lines, line, width, &paint, gutter);
+
+            let text = expand_tabs(lines.get(line - 1).copied().unwrap_or(""));
+            let original = lines.get(line - 1).copied().unwrap_or("");
+            let mut on_line: Vec<&Label> =
+                self.labels.iter().filter(|l| l.span.line == line).collect();
+            on_line.sort_by_key(|label| (!label.primary, label.span.column));
+            for label in on_line {
+                let start = display_column(original, label.span.column);
+                let end = display_column(original, label.span.column + label.span.length);
+                // a span running on past the line is underlined to its end
+                let end = end.min(text.chars().count().max(start + 1)).max(start + 1);
+                let (mark, style) = if label.primary {
+                    ("^", error)
+                } else {
+                    ("-", gutter)
+                };
+                let underline = mark.repeat(end - start);
+                let mut marked = paint(style, &underline);
+                if !label.message.is_empty() {
+                    marked += &paint(style, &format!(" {}", label.message));
+                }
+                out += &format!(
+                    "{} {} {}{}\n",
+                    pad,
+                    paint(gutter, "|"),
+                    " ".repeat(start),
+                    marked
+                );
+            }
+            previous = Some(line);
+        }
+
+        if !self.notes.is_empty() || !self.help.is_empty() {
+            if !labeled.is_empty() {
+                out += &format!("{} {}\n", pad, paint(gutter, "|"));
+            }
+            let notes = self.notes.iter().map(|note| ("note:", note));
+            for (title, text) in notes.chain(self.help.iter().map(|help| ("help:", help))) {
+                out += &format!(
+                    "{} {} {} {}\n",
+                    pad,
+                    paint(gutter, "="),
+                    paint(bold, title),
+                    text
+                );
+            }
+        }
+        out
+    }
+
+    ///
+    ///The diagnostic as JSON, with the spans one by one and the rendered text for tools that
+    ///show it as it is
+    ///
+    pub fn to_json(&self, name: &str, source: &str) -> Json {
+        let spans: Vec<Json> = self
+            .labels
+            .iter()
+            .map(|label| {
+                object(vec![
+                    ("line", label.span.line.into()),
+                    ("column", label.span.column.into()),
+                    ("length", label.span.length.into()),
+                    ("label", label.message.as_str().into()),
+                    ("primary", label.primary.into()),
+                ])
+            })
+            .collect();
+        let strings = |list: &[String]| -> Json {
+            list.iter()
+                .map(|s| Json::from(s.as_str()))
+                .collect::<Vec<_>>()
+                .into()
+        };
+        object(vec![
+            ("kind", self.kind.as_str().into()),
+            ("code", self.code.as_deref().map_or(Json::Null, Json::from)),
+            ("message", self.message.as_str().into()),
+            ("file", name.into()),
+            ("spans", spans.into()),
+            ("notes", strings(&self.notes)),
+            ("help", strings(&self.help)),
+            ("rendered", self.render(name, source, false).into()),
+        ])
+    }
+}
+
+impl From<&SyntaxError> for Diagnostic {
+    fn from(error: &SyntaxError) -> Self {
+        let mut diagnostic = Diagnostic::new("Syntax Error", &error.message)
+            .code(error.code)
+            .label(error.span, "");
+        for (span, message) in &error.labels {
+            diagnostic = diagnostic.secondary(*span, message);
+        }
+        diagnostic
+    }
+}
+
+fn expand_tabs(text: &str) -> String {
+    text.replace('\t', "    ")
+}
+
+///
+///Where the character at column, counting from 1, is drawn once tabs are expanded
+///
+fn display_column(text: &str, column: usize) -> usize {
+    text.chars()
+        .take(column.saturating_sub(1))
+        .map(|c| if c == '\t' { 4 } else { 1 })
+        .sum::<usize>()
+        + column
+            .saturating_sub(1)
+            .saturating_sub(text.chars().count())
+}
+
+fn source_line(
+    lines: &[&str],
+    line: usize,
+    width: usize,
+    paint: &impl Fn(Style, &str) -> String,
+    gutter: Style,
+) -> String {
+    let text = expand_tabs(lines.get(line - 1).copied().unwrap_or(""));
+    let number = paint(gutter, &format!("{:>width$} |", line, width = width));
+    if text.is_empty() {
+        format!("{}\n", number)
+    } else {
+        format!("{} {}\n", number, text)
+    }
+}
+
+///
+///The span of the code on a line, without its indent and trailing whitespace
+///
+pub fn line_span(source: &str, line: usize) -> Option<Span> {
+    let text = source.lines().nth(line.checked_sub(1)?)?;
+    let code = text.trim();
+    if code.is_empty() {
+        return None;
+    }
+    let indent = text.len() - text.trim_start().len();
+    Some(Span {
+        line,
+        column: text[..indent].chars().count() + 1,
+        length: code.chars().count(),
+    })
+}
+
+struct Settings {
+    color: bool,
+    format: ErrorFormat,
+}
+
+static SETTINGS: OnceLock<Settings> = OnceLock::new();
+
+// the script runtime errors are reported against
+static SCRIPT: OnceLock<(String, String)> = OnceLock::new();
+
+///
+///Sets how errors are printed, from the command line. Only the first call has an effect
+///
+pub fn init(color: ColorChoice, format: ErrorFormat) {
+    let color = match color {
+        ColorChoice::Never => false,
+        ColorChoice::Always => true,
+        ColorChoice::Auto => std::io::stderr().is_terminal() && env::var_os("NO_COLOR").is_none(),
+    };
+    let _ = SETTINGS.set(Settings { color, format });
+}
+
+fn settings() -> &'static Settings {
+    SETTINGS.get_or_init(|| Settings {
+        color: false,
+        format: ErrorFormat::Human,
+    })
+}
+
+///
+///Whether errors are written as JSON, the summaries meant for people are left out then
+///
+pub fn is_json() -> bool {
+    settings().format == ErrorFormat::Json
+}
+
+///
+///Prints the diagnostics on stderr, so stdout only holds what the script echoes
+///
+pub fn emit(name: &str, source: &str, diagnostics: &[Diagnostic]) {
+    let settings = settings();
+    for diagnostic in diagnostics {
+        match settings.format {
+            ErrorFormat::Json => eprintln!("{}", diagnostic.to_json(name, source)),
+            ErrorFormat::Human => eprintln!("{}", diagnostic.render(name, source, settings.color)),
+        }
+    }
+}
+
+///
+///Reports the errors of the running script against its source, for the runtime errors
+///
+pub fn report_against(name: String, source: String) {
+    let _ = SCRIPT.set((name, source));
+    unsafe { crate::globals::ERROR_HOOK = Some(runtime_error) };
+}
+
+///
+///Prints an error the script stopped on, underlining the code of the statement it happened in
+///
+fn runtime_error(message: &str, statement: Span) {
+    let Some((name, source)) = SCRIPT.get() else {
+        eprintln!("{}", message);
+        return;
+    };
+    let mut diagnostic = Diagnostic::from_message(message);
+    if statement.line > 0 {
+        diagnostic = diagnostic.label(statement, "");
+    }
+    let calls: Vec<String> = unsafe { &*std::ptr::addr_of!(CALL_STACK) }
+        .iter()
+        .rev()
+        .map(|frame| format!("{}()", frame.function))
+        .collect();
+    if !calls.is_empty() {
+        diagnostic = diagnostic.note(&format!("in {}", calls.join(", called from ")));
+    }
+    emit(name, source, &[diagnostic]);
+}
+
+#[cfg(test)]
+mod diagnostic_tests {
+    use super::Diagnostic;
+    use crate::syntax::Span;
+
+    #[test]
+    fn test_render_with_labels_notes_and_help() {
+        let source = "let total: int = 1;\n\nlet name: string = total;\n";
+        let diagnostic = Diagnostic::from_message("Type Error: Cannot assign int to 'name'")
+            .label(
+                Span {
+                    line: 3,
+                    column: 20,
+                    length: 5,
+                },
+                "this is an int",
+            )
+            .secondary(
+                Span {
+                    line: 3,
+                    column: 5,
+                    length: 12,
+                },
+                "declared here as string",
+            )
+            .note("a value is not converted to string")
+            .help("use to_string(total)");
+        assert_eq!(
+            diagnostic.render("a.jist", source, false),
+            "Type Error: Cannot assign int to 'name'\n\
+             \x20--> a.jist:3:20\n\
+             \x20 |\n\
+             3 | let name: string = total;\n\
+             \x20 |                    ^^^^^ this is an int\n\
+             \x20 |     ------------ declared here as string\n\
+             \x20 |\n\
+             \x20 = note: a value is not converted to string\n\
+             \x20 = help: use to_string(total)\n"
+        );
+    }
+
+    #[test]
+    fn test_render_lines_and_gaps() {
+        let source = "if (a) {\n\techo(a;\n}\n\n\n\nlet b: int = 1;\n";
+        let diagnostic = Diagnostic::new("Syntax Error", "expected `)`, found `;`")
+            .code("E0004")
+            .label(
+                Span {
+                    line: 2,
+                    column: 8,
+                    length: 1,
+                },
+                "",
+            )
+            .secondary(
+                Span {
+                    line: 7,
+                    column: 1,
+                    length: 3,
+                },
+                "here",
+            );
+        assert_eq!(
+            diagnostic.render("a.jist", source, false),
+            "Syntax Error[E0004]: expected `)`, found `;`\n\
+             \x20--> a.jist:2:8\n\
+             \x20 |\n\
+             2 |     echo(a;\n\
+             \x20 |           ^\n\
+             ...\n\
+             7 | let b: int = 1;\n\
+             \x20 | --- here\n"
+        );
+        assert_eq!(
+            Diagnostic::from_message("Runtime Error: Key \"a\" not found").kind,
+            "Runtime Error"
+        );
+        assert_eq!(Diagnostic::from_message("no kind").kind, "Error");
+    }
+
+    #[test]
+    fn test_json() {
+        let source = "let a: int = 1 2;\n";
+        let diagnostic = Diagnostic::new("Syntax Error", "expected `;`, found `2`")
+            .code("E0007")
+            .label(
+                Span {
+                    line: 1,
+                    column: 16,
+                    length: 1,
+                },
+                "",
+            );
+        let json = diagnostic.to_json("a.jist", source);
+        assert_eq!(json.get("code").as_str(), Some("E0007"));
+        assert_eq!(json.get("file").as_str(), Some("a.jist"));
+        let span = &json.get("spans").as_array()[0];
+        assert_eq!(span.get("column").as_usize(), Some(16));
+        assert_eq!(span.get("primary").as_bool(), Some(true));
+        assert!(json
+            .get("rendered")
+            .as_str()
+            .unwrap()
+            .contains("1 | let a: int = 1 2;\n  |                ^\n"));
+    }
+}
@@ -0,0 +1,949 @@
+/*
+* This file holds the formatter behind jist fmt. The source is read into tokens, brackets are
+* grouped and the script is written back in one style: blocks indented, `} elif`, `} else` and
+* `} catch` on the line of the closing brace unless a comment follows it, one space around operators, and a group broken
+* into one element per line when its line would be longer than max_line_length. Comments and
+* single blank lines are kept, and formatting formatted code changes nothing
+*/
+use crate::config::{load_section, positive_number, CONFIG_FILE};
+use crate::statement_tokenizer::basic_tokenizer::basic_tokenizers::scan_literal;
+use std::path::Path;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FormatConfig {
+    pub indent_width: usize,
+    pub max_line_length: usize,
+}
+
+impl Default for FormatConfig {
+    fn default() -> Self {
+        FormatConfig {
+            indent_width: 4,
+            max_line_length: 100,
+        }
+    }
+}
+
+impl FormatConfig {
+    ///
+    ///The [fmt] settings of the nearest jist.toml, settings it does not have keep their default
+    ///
+    pub fn load(dir: &Path) -> Result<FormatConfig, String> {
+        let mut config = FormatConfig::default();
+        for setting in load_section(dir, "fmt")? {
+            match setting.key.as_str() {
+                "indent_width" => config.indent_width = positive_number(&setting)?,
+                "max_line_length" => config.max_line_length = positive_number(&setting)?,
+                _ => {
+                    return Err(format!(
+                        "{}:{}: Unknown fmt setting '{}'",
+                        CONFIG_FILE, setting.line, setting.key
+                    ))
+                }
+            }
+        }
+        Ok(config)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Kind {
+    Word,
+    // numbers, strings and chars
+    Literal,
+    Comment,
+    Operator,
+    Open,
+    Close,
+    Comma,
+    Semicolon,
+    Colon,
+    // . and ..
+    Dot,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Token {
+    pub kind: Kind,
+    pub text: String,
+    pub line: usize,
+    // where the token starts in its line, counted in UTF-16 units like editors count
+    pub column: usize,
+    // the line breaks between the token before and this one
+    pub newlines: usize,
+}
+
+const OPERATORS: [&str; 15] = [
+    "=>", "->", "==", "!=", "<=", ">=", "&&", "||", "++", "--", "+=", "-=", "*=", "/=", "%=",
+];
+
+pub const KEYWORDS: [&str; 16] = [
+    "let", "const", "mut", "func", "if", "elif", "else", "while", "for", "in", "return", "try",
+    "catch", "break", "continue", "as",
+];
+
+pub fn is_keyword(text: &str) -> bool {
+    KEYWORDS.contains(&text)
+}
+
+///
+///Splits the source into tokens. Nothing is dropped, a character the language does not use
+///becomes an operator of its own so it is written back as it was
+///
+pub fn lex(source: &str) -> Result<Vec<Token>, String> {
+    let chars: Vec<char> = source.chars().collect();
+    let at = |i: usize| chars.get(i).copied().unwrap_or('\0');
+    let mut tokens = Vec::new();
+    let mut line = 1;
+    let mut line_start = 0;
+    let mut newlines = 0;
+
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        if c == '\n' {
+            line += 1;
+            line_start = i + 1;
+            newlines += 1;
+            i += 1;
+            continue;
+        }
+        if c.is_whitespace() {
+            i += 1;
+            continue;
+        }
+
+        let start = i;
+        let kind = if c == '/' && at(i + 1) == '/' {
+            while i < chars.len() && chars[i] != '\n' {
+                i += 1;
+            }
+            Kind::Comment
+        } else if c == '/' && at(i + 1) == '*' {
+            match (i + 2..chars.len()).find(|&j| chars[j] == '*' && at(j + 1) == '/') {
+                Some(j) => i = j + 2,
+                None => return Err(format!("Unterminated comment starting at line {}", line)),
+            }
+            Kind::Comment
+        } else if let Some((end, closed)) = scan_literal(&chars, i) {
+            if !closed {
+                return Err(format!(
+                    "Unterminated string literal starting at line {}",
+                    line
+                ));
+            }
+            i = end;
+            Kind::Literal
+        } else if c.is_ascii_digit() {
+            // a . is part of the number only when a digit follows it, so 0..10 stays a range
+            while i < chars.len()
+                && (chars[i].is_alphanumeric()
+                    || chars[i] == '_'
+                    || (chars[i] == '.' && at(i + 1).is_ascii_digit()))
+            {
+                i += 1;
+            }
+            Kind::Literal
+        } else if c.is_alphanumeric() || c == '_' {
+            while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            // the type a call converts to stays on its name, like parse::<int>
+            if chars.get(i..i + 3) == Some(&[':', ':', '<'][..]) {
+                let end = (i + 3..chars.len())
+                    .find(|&j| !(chars[j].is_alphanumeric() || chars[j] == '_'))
+                    .unwrap_or(chars.len());
+                if end > i + 3 && at(end) == '>' {
+                    i = end + 1;
+                }
+            }
+            Kind::Word
+        } else {
+            i += 1;
+            match c {
+                '(' | '[' | '{' => Kind::Open,
+                ')' | ']' | '}' => Kind::Close,
+                ',' => Kind::Comma,
+                ';' => Kind::Semicolon,
+                ':' => Kind::Colon,
+                '.' => {
+                    if at(i) == '.' {
+                        i += 1;
+                    }
+                    Kind::Dot
+                }
+                _ => {
+                    let rest: String = chars[start..chars.len().min(start + 2)].iter().collect();
+                    if OPERATORS.contains(&rest.as_str()) {
+                        i = start + 2;
+                    }
+                    Kind::Operator
+                }
+            }
+        };
+
+        let text: String = chars[start..i].iter().collect();
+        let text = if kind == Kind::Comment {
+            text.trim_end().to_string()
+        } else {
+            text
+        };
+        let lines_in_token = text.matches('\n').count();
+        let column = chars[line_start..start].iter().map(|c| c.len_utf16()).sum();
+        if let Some(last) = chars[start..i].iter().rposition(|c| *c == '\n') {
+            line_start = start + last + 1;
+        }
+        tokens.push(Token {
+            kind,
+            text,
+            line,
+            column,
+            newlines,
+        });
+        line += lines_in_token;
+        newlines = 0;
+    }
+    Ok(tokens)
+}
+
+#[derive(Debug, Clone)]
+enum Item {
+    Token(Token),
+    Group(Group),
+}
+
+///
+///A bracketed list, the elements are the items between its commas
+///
+#[derive(Debug, Clone)]
+struct Group {
+    open: Token,
+    elements: Vec<Vec<Item>>,
+    trailing_comma: bool,
+    close: Token,
+}
+
+impl Item {
+    fn is_comment(&self) -> bool {
+        matches!(self, Item::Token(token) if token.kind == Kind::Comment)
+    }
+}
+
+impl Group {
+    fn code_elements(&self) -> usize {
+        self.elements
+            .iter()
+            .filter(|element| element.iter().any(|item| !item.is_comment()))
+            .count()
+    }
+
+    ///
+    ///A trailing comma is only kept where it means something, in a tuple of one
+    ///
+    fn keeps_trailing_comma(&self) -> bool {
+        self.trailing_comma && self.open.text == "(" && self.code_elements() == 1
+    }
+}
+
+///
+///Splits the comments off a list of items. Comments at the end that share the line of the code
+///stay at the end of the line, every other comment is moved onto a line of its own before it
+///
+fn split_comments(items: &[Item]) -> (Vec<&Token>, Vec<&Item>, Vec<&Token>) {
+    let mut trailing_start = items.len();
+    while trailing_start > 0 {
+        match &items[trailing_start - 1] {
+            Item::Token(token) if token.kind == Kind::Comment && token.newlines == 0 => {
+                trailing_start -= 1
+            }
+            _ => break,
+        }
+    }
+    // a list of nothing but comments keeps them on lines of their own
+    if items[..trailing_start].iter().all(Item::is_comment) {
+        trailing_start = items.len();
+    }
+
+    let mut leading = Vec::new();
+    let mut code = Vec::new();
+    for item in &items[..trailing_start] {
+        match item {
+            Item::Token(token) if token.kind == Kind::Comment => leading.push(token),
+            item => code.push(item),
+        }
+    }
+    let trailing = items[trailing_start..]
+        .iter()
+        .filter_map(|item| match item {
+            Item::Token(token) => Some(token),
+            Item::Group(_) => None,
+        })
+        .collect();
+    (leading, code, trailing)
+}
+
+///
+///Decides whether a space goes between two tokens, from the token before and whether a type is
+///being written, where `<` and `>` are brackets instead of comparisons
+///
+#[derive(Debug, Clone, Default)]
+struct Spacing {
+    prev: Option<(Kind, String)>,
+    // the token before was a prefix operator such as ! or a unary -
+    unary: bool,
+    in_type: bool,
+    angles: usize,
+}
+
+impl Spacing {
+    fn new(in_type: bool) -> Self {
+        Spacing {
+            in_type,
+            ..Spacing::default()
+        }
+    }
+
+    fn starts_operand(&self) -> bool {
+        match &self.prev {
+            None => true,
+            Some((kind, text)) => match kind {
+                Kind::Word => is_keyword(text),
+                Kind::Literal | Kind::Close => false,
+                // i++ - 1, the ++ ends an operand
+                Kind::Operator => text != "++" && text != "--",
+                _ => true,
+            },
+        }
+    }
+
+    fn space_before(&self, next: &Token) -> bool {
+        let (kind, text) = match &self.prev {
+            Some(prev) if !self.unary => prev,
+            _ => return false,
+        };
+        if matches!(kind, Kind::Open | Kind::Dot) {
+            return false;
+        }
+        if matches!(
+            next.kind,
+            Kind::Close | Kind::Comma | Kind::Semicolon | Kind::Colon | Kind::Dot
+        ) {
+            return false;
+        }
+        if self.in_type && (next.text == "<" || next.text == ">" || text == "<") {
+            return false;
+        }
+        if next.kind == Kind::Open && next.text != "{" {
+            // a call or an index sticks to what it is called on
+            return !matches!(kind, Kind::Word | Kind::Literal | Kind::Close) || is_keyword(text);
+        }
+        if (next.text == "++" || next.text == "--") && !self.starts_operand() {
+            return false;
+        }
+        true
+    }
+
+    fn advance(&mut self, token: &Token) {
+        self.unary = match token.text.as_str() {
+            "!" => true,
+            "-" | "++" | "--" => self.starts_operand(),
+            _ => false,
+        };
+        match token.text.as_str() {
+            ":" | "->" => {
+                self.in_type = true;
+                self.angles = 0;
+            }
+            "<" if self.in_type => self.angles += 1,
+            ">" if self.in_type => self.angles = self.angles.saturating_sub(1),
+            "=" | "{" | ";" => self.in_type = false,
+            "," if self.angles == 0 => self.in_type = false,
+            _ => {}
+        }
+        self.prev = Some((token.kind, token.text.clone()));
+    }
+
+    fn after_group(&mut self, close: &Token) {
+        self.unary = false;
+        self.prev = Some((close.kind, close.text.clone()));
+    }
+}
+
+///
+///How a statement ended
+///
+enum End {
+    Semicolon,
+    // the { opening the statement's block
+    Block(Token),
+    // a } closing the block around it, the end of the file or a new line starting a new statement
+    Open,
+}
+
+struct Formatter {
+    tokens: Vec<Token>,
+    position: usize,
+    config: FormatConfig,
+    out: String,
+    // nothing has been written since the last {, so no blank line goes here
+    block_start: bool,
+}
+
+impl Formatter {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        token
+    }
+
+    fn column(&self) -> usize {
+        let line_start = self.out.rfind('\n').map_or(0, |i| i + 1);
+        self.out[line_start..].chars().count()
+    }
+
+    fn push(&mut self, text: &str) {
+        self.out.push_str(text);
+    }
+
+    fn new_line(&mut self, depth: usize, blank: bool) {
+        self.out.truncate(self.out.trim_end_matches(' ').len());
+        if !self.out.is_empty() {
+            self.out.push('\n');
+            if blank && !self.block_start {
+                self.out.push('\n');
+            }
+        }
+        self.block_start = false;
+        let indent = " ".repeat(depth * self.config.indent_width);
+        self.out.push_str(&indent);
+    }
+
+    ///
+    ///Reads the group after open. The commas of a type such as dict<string, int> do not split
+    ///it, so the spacing rules are followed to know when a type is being read
+    ///
+    fn parse_group(&mut self, open: Token, in_type: bool) -> Result<Group, String> {
+        let close = match open.text.as_str() {
+            "(" => ")",
+            "[" => "]",
+            _ => "}",
+        };
+        let mut elements: Vec<Vec<Item>> = vec![Vec::new()];
+        let mut spacing = Spacing::new(in_type);
+        loop {
+            let token = self
+                .next()
+                .ok_or_else(|| format!("Unclosed '{}' at line {}", open.text, open.line))?;
+            match token.kind {
+                Kind::Close if token.text == close => {
+                    let last = elements.last().unwrap();
+                    let trailing_comma =
+                        elements.len() > 1 && last.iter().all(|item| item.is_comment());
+                    if trailing_comma && last.is_empty() {
+                        elements.pop();
+                    }
+                    return Ok(Group {
+                        open,
+                        elements,
+                        trailing_comma,
+                        close: token,
+                    });
+                }
+                Kind::Close => {
+                    return Err(format!("Unmatched '{}' at line {}", token.text, token.line))
+                }
+                Kind::Comma if spacing.in_type && spacing.angles > 0 => {
+                    spacing.advance(&token);
+                    elements.last_mut().unwrap().push(Item::Token(token));
+                }
+                Kind::Comma => {
+                    spacing = Spacing::new(in_type);
+                    elements.push(Vec::new());
+                }
+                // a comment after a comma on the same line belongs to the element before it
+                Kind::Comment
+                    if token.newlines == 0
+                        && elements.len() > 1
+                        && elements.last().unwrap().is_empty() =>
+                {
+                    let before = elements.len() - 2;
+                    elements[before].push(Item::Token(token));
+                }
+                Kind::Open => {
+                    let group = self.parse_group(token, spacing.in_type)?;
+                    spacing.after_group(&group.close);
+                    elements.last_mut().unwrap().push(Item::Group(group));
+                }
+                _ => {
+                    if token.kind != Kind::Comment {
+                        spacing.advance(&token);
+                    }
+                    elements.last_mut().unwrap().push(Item::Token(token));
+                }
+            }
+        }
+    }
+
+    ///
+    ///Reads the items of one statement, up to its semicolon or the { of its block
+    ///
+    fn statement_items(&mut self) -> Result<(Vec<Item>, End), String> {
+        let mut items: Vec<Item> = Vec::new();
+        let mut spacing = Spacing::new(false);
+        while let Some(token) = self.peek().cloned() {
+            let last_code = items.iter().rev().find(|item| !item.is_comment());
+            match token.kind {
+                Kind::Close if token.text == "}" => break,
+                Kind::Close => {
+                    return Err(format!("Unmatched '{}' at line {}", token.text, token.line))
+                }
+                Kind::Semicolon => {
+                    self.position += 1;
+                    items.push(Item::Token(token));
+                    return Ok((items, End::Semicolon));
+                }
+                Kind::Open if token.text == "{" && opens_block(last_code) => {
+                    self.position += 1;
+                    return Ok((items, End::Block(token)));
+                }
+                Kind::Open => {
+                    self.position += 1;
+                    let group = self.parse_group(token, spacing.in_type)?;
+                    spacing.after_group(&group.close);
+                    items.push(Item::Group(group));
+                }
+                // a statement without a semicolon ends where the next one starts on a new line
+                Kind::Word | Kind::Literal
+                    if token.newlines > 0 && ends_operand(last_code) && !items.is_empty() =>
+                {
+                    break
+                }
+                _ => {
+                    self.position += 1;
+                    if token.kind != Kind::Comment {
+                        spacing.advance(&token);
+                    }
+                    items.push(Item::Token(token));
+                }
+            }
+        }
+        Ok((items, End::Open))
+    }
+
+    ///
+    ///The width of the items after a group up to the next place a line can break, which is the
+    ///next group or the end of the line
+    ///
+    fn tail_width(&self, items: &[Item], spacing: &Spacing, suffix: usize) -> usize {
+        let mut spacing = spacing.clone();
+        let mut width = 0;
+        for item in items {
+            match item {
+                Item::Token(token) if token.kind == Kind::Comment => {}
+                Item::Token(token) => {
+                    width += spacing.space_before(token) as usize + token.text.chars().count();
+                    spacing.advance(token);
+                }
+                Item::Group(group) => {
+                    return width + spacing.space_before(&group.open) as usize + 1
+                }
+            }
+        }
+        width + suffix
+    }
+
+    fn flat_items(&self, items: &[&Item], spacing: &mut Spacing) -> Option<String> {
+        let mut text = String::new();
+        for item in items {
+            match item {
+                Item::Token(token) if token.kind == Kind::Comment => return None,
+                Item::Token(token) => {
+                    if spacing.space_before(token) {
+                        text.push(' ');
+                    }
+                    text.push_str(&token.text);
+                    spacing.advance(token);
+                }
+                Item::Group(group) => {
+                    if spacing.space_before(&group.open) {
+                        text.push(' ');
+                    }
+                    text.push_str(&self.flat_group(group, spacing.in_type)?);
+                    spacing.after_group(&group.close);
+                }
+            }
+        }
+        Some(text)
+    }
+
+    ///
+    ///The group on one line, None when it holds a comment and has to be broken
+    ///
+    fn flat_group(&self, group: &Group, in_type: bool) -> Option<String> {
+        let mut elements = Vec::new();
+        for element in &group.elements {
+            let items: Vec<&Item> = element.iter().collect();
+            elements.push(self.flat_items(&items, &mut Spacing::new(in_type))?);
+        }
+        let comma = if group.keeps_trailing_comma() {
+            ","
+        } else {
+            ""
+        };
+        Some(format!(
+            "{}{}{}{}",
+            group.open.text,
+            elements.join(", "),
+            comma,
+            group.close.text
+        ))
+    }
+
+    ///
+    ///Writes items that share a line, a group that does not fit on the line is broken into one
+    ///element per line. suffix is the width of what is written after the items on the same line
+    ///
+    fn write_items(&mut self, items: &[&Item], depth: usize, spacing: &mut Spacing, suffix: usize) {
+        for (i, item) in items.iter().enumerate() {
+            match item {
+                Item::Token(token) => {
+                    if spacing.space_before(token) {
+                        self.push(" ");
+                    }
+                    self.push(&token.text);
+                    spacing.advance(token);
+                }
+                Item::Group(group) => {
+                    if spacing.space_before(&group.open) {
+                        self.push(" ");
+                    }
+                    let in_type = spacing.in_type;
+                    let mut after = spacing.clone();
+                    after.after_group(&group.close);
+                    let rest: Vec<Item> =
+                        items[i + 1..].iter().map(|item| (*item).clone()).collect();
+                    let tail = self.tail_width(&rest, &after, suffix);
+
+                    match self.flat_group(group, in_type) {
+                        Some(flat)
+                            if group.elements.is_empty()
+                                || flat.contains('\n')
+                                || self.column() + flat.chars().count() + tail
+                                    <= self.config.max_line_length =>
+                        {
+                            self.push(&flat)
+                        }
+                        _ => self.write_broken(group, depth, in_type),
+                    }
+                    *spacing = after;
+                }
+            }
+        }
+    }
+
+    fn write_broken(&mut self, group: &Group, depth: usize, in_type: bool) {
+        self.push(&group.open.text);
+        let code_elements = group.code_elements();
+        let mut written = 0;
+        for element in &group.elements {
+            let (leading, code, trailing) = split_comments(element);
+            for comment in leading {
+                self.new_line(depth + 1, false);
+                self.push(&comment.text);
+            }
+            if !code.is_empty() {
+                written += 1;
+                let comma = written < code_elements || group.keeps_trailing_comma();
+                self.new_line(depth + 1, false);
+                self.write_items(&code, depth + 1, &mut Spacing::new(in_type), comma as usize);
+                if comma {
+                    self.push(",");
+                }
+            }
+            for comment in trailing {
+                self.push(" ");
+                self.push(&comment.text);
+            }
+        }
+        self.new_line(depth, false);
+        self.push(&group.close.text);
+    }
+
+    fn statement(&mut self, depth: usize, blank: bool) -> Result<(), String> {
+        let mut continuation = false;
+        // a comment trails the } before the continuation, which then starts a line of its own
+        let mut after_comment = false;
+        let mut pending: Vec<Token> = Vec::new();
+        loop {
+            let (items, end) = self.statement_items()?;
+            let (leading, code, trailing) = split_comments(&items);
+            if continuation {
+                // the other comments around `} else` move into the block after it
+                pending.extend(leading.into_iter().cloned());
+                if after_comment {
+                    self.new_line(depth, false);
+                } else {
+                    self.push(" ");
+                }
+            } else {
+                let mut blank = blank;
+                for comment in leading {
+                    self.new_line(depth, blank);
+                    self.push(&comment.text);
+                    blank = false;
+                }
+                self.new_line(depth, blank);
+            }
+
+            let header = matches!(end, End::Block(_)) as usize * 2;
+            self.write_items(&code, depth, &mut Spacing::new(false), header);
+
+            let open = match end {
+                End::Block(open) => open,
+                End::Semicolon | End::Open => {
+                    for comment in trailing {
+                        self.push(" ");
+                        self.push(&comment.text);
+                    }
+                    return Ok(());
+                }
+            };
+            pending.extend(trailing.into_iter().cloned());
+            self.push(if code.is_empty() { "{" } else { " {" });
+            self.block_start = true;
+            for comment in pending.drain(..) {
+                self.new_line(depth + 1, false);
+                self.push(&comment.text);
+            }
+            self.block(depth + 1)?;
+            match self.next() {
+                Some(token) if token.text == "}" => {}
+                _ => return Err(format!("Unclosed '{{' at line {}", open.line)),
+            }
+            if self.block_start {
+                // an empty block stays {}
+                self.block_start = false;
+            } else {
+                self.new_line(depth, false);
+            }
+            self.push("}");
+
+            // `} elif`, `} else` and `} catch` stay on the line of the closing brace
+            let next_code = self.tokens[self.position..]
+                .iter()
+                .position(|token| token.kind != Kind::Comment);
+            match next_code.map(|i| &self.tokens[self.position + i]) {
+                Some(token)
+                    if token.kind == Kind::Word
+                        && matches!(token.text.as_str(), "elif" | "else" | "catch") =>
+                {
+                    after_comment = false;
+                    while self.peek().map(|token| token.kind) == Some(Kind::Comment) {
+                        let comment = self.next().unwrap();
+                        if comment.newlines == 0 && pending.is_empty() {
+                            self.push(" ");
+                            self.push(&comment.text);
+                            after_comment = true;
+                        } else {
+                            pending.push(comment);
+                        }
+                    }
+                    continuation = true;
+                }
+                _ => return Ok(()),
+            }
+        }
+    }
+
+    ///
+    ///Writes statements and comments until the } closing the block or the end of the file
+    ///
+    fn block(&mut self, depth: usize) -> Result<(), String> {
+        while let Some(token) = self.peek().cloned() {
+            if token.kind == Kind::Close && token.text == "}" {
+                if depth == 0 {
+                    return Err(format!("Unmatched '}}' at line {}", token.line));
+                }
+                break;
+            }
+            if token.kind == Kind::Comment {
+                self.position += 1;
+                if token.newlines == 0 && !self.out.is_empty() {
+                    self.push(" ");
+                } else {
+                    self.new_line(depth, token.newlines > 1);
+                }
+                self.push(&token.text);
+                continue;
+            }
+            self.statement(depth, token.newlines > 1)?;
+        }
+        Ok(())
+    }
+}
+
+///
+///Whether a { after this item opens a block, as after `if (a)` or `else`, instead of starting a
+///dictionary literal, as after `=` or `return`
+///
+fn opens_block(last: Option<&Item>) -> bool {
+    match last {
+        None | Some(Item::Group(_)) => true,
+        Some(Item::Token(token)) => match token.kind {
+            Kind::Word => token.text != "return" && token.text != "in",
+            Kind::Literal | Kind::Close => true,
+            // the > of a return type such as array<int>
+            Kind::Operator => token.text == ">",
+            _ => false,
+        },
+    }
+}
+
+fn ends_operand(last: Option<&Item>) -> bool {
+    match last {
+        Some(Item::Group(_)) => true,
+        Some(Item::Token(token)) => match token.kind {
+            Kind::Word => !is_keyword(&token.text),
+            Kind::Literal | Kind::Close => true,
+            _ => false,
+        },
+        None => false,
+    }
+}
+
+///
+///Formats a whole script, an error when its brackets do not match or a string is not closed
+///
+pub fn format_source(source: &str, config: &FormatConfig) -> Result<String, String> {
+    let mut formatter = Formatter {
+        tokens: lex(source)?,
+        position: 0,
+        config: *config,
+        out: String::new(),
+        block_start: false,
+    };
+    formatter.block(0)?;
+    let mut formatted = formatter.out.trim_end().to_string();
+    if !formatted.is_empty() {
+        formatted.push('\n');
+    }
+    Ok(formatted)
+}
+
+#[cfg(test)]
+mod formatter_tests {
+    use super::{format_source, lex, FormatConfig, Kind};
+
+    fn format(source: &str) -> String {
+        format_source(source, &FormatConfig::default()).unwrap()
+    }
+
+    #[test]
+    fn test_format_blocks_and_spacing() {
+        let source = "let a: int = 2;\nlet b:int=1;\n\n\n\nif (a>1) {\necho(\"a is gt than b\");\n} \nelif (a < b) {\n  echo( -a );\n}\nelse {echo(a);}\n";
+        assert_eq!(
+            format(source),
+            "let a: int = 2;\nlet b: int = 1;\n\nif (a > 1) {\n    echo(\"a is gt than b\");\n} elif (a < b) {\n    echo(-a);\n} else {\n    echo(a);\n}\n"
+        );
+
+        let source = "func f(values:array<dict<string,int>>,at:int=0)->(int, string){return (values[at][\"x\"],\"a\"+\"b\");}";
+        assert_eq!(
+            format(source),
+            "func f(values: array<dict<string, int>>, at: int = 0) -> (int, string) {\n    return (values[at][\"x\"], \"a\" + \"b\");\n}\n"
+        );
+
+        let source = "let d: dict<string, int> = {\"a\"=>1};\nfor (i in 0..10) { echoln(d.get_or(\"b\", -1) * i); }\nlet t: (int) = (1,);";
+        assert_eq!(
+            format(source),
+            "let d: dict<string, int> = {\"a\" => 1};\nfor (i in 0..10) {\n    echoln(d.get_or(\"b\", -1) * i);\n}\nlet t: (int) = (1,);\n"
+        );
+    }
+
+    #[test]
+    fn test_format_keeps_comments() {
+        let source = "// header\n\n/* block\n   comment */\nlet a: array<int> = [ // numbers\n1, // one\n2\n];\nif (a.len() > 0) { // not empty\necho(a); // print\n}   // done\n";
+        assert_eq!(
+            format(source),
+            "// header\n\n/* block\n   comment */\nlet a: array<int> = [\n    // numbers\n    1, // one\n    2\n];\nif (a.len() > 0) { // not empty\n    echo(a); // print\n} // done\n"
+        );
+    }
+
+    #[test]
+    fn test_format_keeps_comment_after_brace_before_else() {
+        let source = "if (a) { b(); } // only a\n// otherwise\nelse { c(); }\ntry { d(); }\ncatch e { f(); }\n";
+        assert_eq!(
+            format(source),
+            "if (a) {\n    b();\n} // only a\nelse {\n    // otherwise\n    c();\n}\ntry {\n    d();\n} catch e {\n    f();\n}\n"
+        );
+        assert_eq!(format(&format(source)), format(source));
+    }
+
+    #[test]
+    fn test_format_line_length_and_indent() {
+        let config = FormatConfig {
+            indent_width: 2,
+            max_line_length: 30,
+        };
+        let source = "func f() -> int {\nlet names: array<string> = [\"alpha\", \"beta\", \"gamma\"];\nreturn 1;\n}";
+        assert_eq!(
+            format_source(source, &config).unwrap(),
+            "func f() -> int {\n  let names: array<string> = [\n    \"alpha\",\n    \"beta\",\n    \"gamma\"\n  ];\n  return 1;\n}\n"
+        );
+
+        // multi-line strings are copied as they are
+        let source = "func f() -> string {\nlet s: string = \"\"\"\n  kept\n\"\"\";\nreturn s;\n}";
+        assert_eq!(
+            format_source(source, &config).unwrap(),
+            "func f() -> string {\n  let s: string = \"\"\"\n  kept\n\"\"\";\n  return s;\n}\n"
+        );
+
+        assert_eq!(
+            format_source("let a: int = (1;", &config),
+            Err("Unclosed '(' at line 1".to_string())
+        );
+        assert!(format_source("echo(\"a);", &config).is_err());
+    }
+
+    #[test]
+    fn test_format_is_idempotent_and_keeps_tokens() {
+        let mut checked = 0;
+        for entry in std::fs::read_dir("test_files").unwrap() {
+            let path = entry.unwrap().path();
+            if path.extension().and_then(|e| e.to_str()) != Some("jist") {
+                continue;
+            }
+            let source = std::fs::read_to_string(&path).unwrap();
+            let formatted = match format_source(&source, &FormatConfig::default()) {
+                Ok(formatted) => formatted,
+                // scripts written to fail on a syntax error
+                Err(_) => continue,
+            };
+            assert_eq!(format(&formatted), formatted, "{}", path.display());
+
+            // the code is the same apart from commas that end a list, and every comment is kept
+            // though one may have moved
+            let texts = |source: &str| -> (Vec<String>, Vec<String>) {
+                let tokens = lex(source).unwrap();
+                let (comments, code): (Vec<_>, Vec<_>) = tokens
+                    .iter()
+                    .enumerate()
+                    .filter(|(i, token)| {
+                        token.kind != Kind::Comma
+                            || !matches!(tokens.get(i + 1), Some(next) if next.text == "]" || next.text == "}")
+                    })
+                    .map(|(_, token)| token)
+                    .partition(|token| token.kind == Kind::Comment);
+                let mut comments: Vec<String> = comments.iter().map(|t| t.text.clone()).collect();
+                comments.sort();
+                (code.iter().map(|t| t.text.clone()).collect(), comments)
+            };
+            assert_eq!(texts(&source), texts(&formatted), "{}", path.display());
+            checked += 1;
+        }
+        assert!(checked > 10);
+    }
+}
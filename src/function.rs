@@ -1,15 +1,15 @@
 use crate::function::functions::Function;
-use lazy_static::lazy_static;
-use std::sync::Mutex;
+use std::cell::RefCell;
 
-lazy_static! {
-    pub static ref FUNCTION_STACK: Mutex<Vec<Function>> = Mutex::new(Vec::new());
+thread_local! {
+    // the declared functions, their default values can be collections which are not shared
+    // between threads
+    pub static FUNCTION_STACK: RefCell<Vec<Function>> = const { RefCell::new(Vec::new()) };
 }
 
 pub mod functions {
 
     use crate::base_variable::base_types::BaseTypes;
-    use crate::node::nodes::ASTNode;
     use crate::{base_variable::variable::Variable, token_type::token_types::TokenTypes};
     use std::fmt;
 
@@ -22,6 +22,17 @@ pub mod functions {
         DoubleStringFn(fn(String, String) -> String),
         SingleStringFn(fn(String) -> String),
         EchoFn(fn(String)),
+        StringToIntFn(fn(String) -> i32),
+        StringToCharsFn(fn(String) -> Vec<char>),
+        DoubleStringToBoolFn(fn(String, String) -> bool),
+        DoubleStringToIntFn(fn(String, String) -> i32),
+        DoubleStringToListFn(fn(String, String) -> Vec<String>),
+        TripleStringFn(fn(String, String, String) -> String),
+        StringIntFn(fn(String, i32) -> Result<String, String>),
+        StringIntIntFn(fn(String, i32, i32) -> Result<String, String>),
+        StringIntStringFn(fn(String, i32, String) -> Result<String, String>),
+        ParseIntFn(fn(String) -> Result<i32, String>),
+        ParseFloatFn(fn(String) -> Result<f64, String>),
     }
 
     use std::any::Any;
@@ -29,128 +40,232 @@ pub mod functions {
     impl PartialEq for FunctionTypes {
         fn eq(&self, other: &Self) -> bool {
             match (self, other) {
-                (FunctionTypes::FloatFn(f1), FunctionTypes::FloatFn(f2)) => {
-                    f1 as *const _ == f2 as *const _
-                }
+                (FunctionTypes::FloatFn(f1), FunctionTypes::FloatFn(f2)) => std::ptr::eq(f1, f2),
                 (FunctionTypes::DoubleFloatFn(f1), FunctionTypes::DoubleFloatFn(f2)) => {
-                    f1 as *const _ == f2 as *const _
+                    std::ptr::eq(f1, f2)
                 }
                 (FunctionTypes::SingleFloatFn(f1), FunctionTypes::SingleFloatFn(f2)) => {
-                    f1 as *const _ == f2 as *const _
+                    std::ptr::eq(f1, f2)
                 }
                 (FunctionTypes::NoArgFloatFn(f1), FunctionTypes::NoArgFloatFn(f2)) => {
-                    f1 as *const _ == f2 as *const _
-                }
-                (FunctionTypes::StringFn(f1), FunctionTypes::StringFn(f2)) => {
-                    f1 as *const _ == f2 as *const _
+                    std::ptr::eq(f1, f2)
                 }
+                (FunctionTypes::StringFn(f1), FunctionTypes::StringFn(f2)) => std::ptr::eq(f1, f2),
                 (FunctionTypes::DoubleStringFn(f1), FunctionTypes::DoubleStringFn(f2)) => {
-                    f1 as *const _ == f2 as *const _
+                    std::ptr::eq(f1, f2)
                 }
                 (FunctionTypes::SingleStringFn(f1), FunctionTypes::SingleStringFn(f2)) => {
-                    f1 as *const _ == f2 as *const _
+                    std::ptr::eq(f1, f2)
+                }
+                (FunctionTypes::EchoFn(f1), FunctionTypes::EchoFn(f2)) => std::ptr::eq(f1, f2),
+                (FunctionTypes::StringToIntFn(f1), FunctionTypes::StringToIntFn(f2)) => {
+                    std::ptr::eq(f1, f2)
+                }
+                (FunctionTypes::StringToCharsFn(f1), FunctionTypes::StringToCharsFn(f2)) => {
+                    std::ptr::eq(f1, f2)
+                }
+                (
+                    FunctionTypes::DoubleStringToBoolFn(f1),
+                    FunctionTypes::DoubleStringToBoolFn(f2),
+                ) => std::ptr::eq(f1, f2),
+                (
+                    FunctionTypes::DoubleStringToIntFn(f1),
+                    FunctionTypes::DoubleStringToIntFn(f2),
+                ) => std::ptr::eq(f1, f2),
+                (
+                    FunctionTypes::DoubleStringToListFn(f1),
+                    FunctionTypes::DoubleStringToListFn(f2),
+                ) => std::ptr::eq(f1, f2),
+                (FunctionTypes::TripleStringFn(f1), FunctionTypes::TripleStringFn(f2)) => {
+                    std::ptr::eq(f1, f2)
+                }
+                (FunctionTypes::StringIntFn(f1), FunctionTypes::StringIntFn(f2)) => {
+                    std::ptr::eq(f1, f2)
                 }
-                (FunctionTypes::EchoFn(f1), FunctionTypes::EchoFn(f2)) => {
-                    f1 as *const _ == f2 as *const _
+                (FunctionTypes::StringIntIntFn(f1), FunctionTypes::StringIntIntFn(f2)) => {
+                    std::ptr::eq(f1, f2)
+                }
+                (FunctionTypes::StringIntStringFn(f1), FunctionTypes::StringIntStringFn(f2)) => {
+                    std::ptr::eq(f1, f2)
+                }
+                (FunctionTypes::ParseIntFn(f1), FunctionTypes::ParseIntFn(f2)) => {
+                    std::ptr::eq(f1, f2)
+                }
+                (FunctionTypes::ParseFloatFn(f1), FunctionTypes::ParseFloatFn(f2)) => {
+                    std::ptr::eq(f1, f2)
                 }
                 _ => false, // Different types cannot be equal
             }
         }
     }
 
-    pub fn call_function(func: &FunctionTypes, arguments: Vec<Box<dyn Any>>) -> Box<dyn Any> {
+    ///
+    ///Checks that a builtin got the number of arguments it takes
+    ///
+    fn expect_arguments(arguments: &[Box<dyn Any>], count: usize) -> Result<(), String> {
+        if arguments.len() == count {
+            Ok(())
+        } else {
+            Err(format!(
+                "Expected {} argument(s) but found {}",
+                count,
+                arguments.len()
+            ))
+        }
+    }
+
+    ///
+    ///Reads a float argument, ints are widened so max(1, 2.5) works
+    ///
+    fn float_argument(arguments: &[Box<dyn Any>], index: usize) -> Result<f64, String> {
+        let argument = &arguments[index];
+        if let Some(value) = argument.downcast_ref::<f64>() {
+            Ok(*value)
+        } else if let Some(value) = argument.downcast_ref::<i32>() {
+            Ok(*value as f64)
+        } else {
+            Err(format!("Expected a float for argument {}", index + 1))
+        }
+    }
+
+    fn int_argument(arguments: &[Box<dyn Any>], index: usize) -> Result<i32, String> {
+        arguments[index]
+            .downcast_ref::<i32>()
+            .copied()
+            .ok_or_else(|| format!("Expected an int for argument {}", index + 1))
+    }
+
+    ///
+    ///Reads a string argument, a char is accepted as a one character string
+    ///
+    fn string_argument(arguments: &[Box<dyn Any>], index: usize) -> Result<String, String> {
+        let argument = &arguments[index];
+        if let Some(value) = argument.downcast_ref::<String>() {
+            Ok(value.clone())
+        } else if let Some(value) = argument.downcast_ref::<char>() {
+            Ok(value.to_string())
+        } else {
+            Err(format!("Expected a string for argument {}", index + 1))
+        }
+    }
+
+    pub fn call_function(
+        func: &FunctionTypes,
+        arguments: Vec<Box<dyn Any>>,
+    ) -> Result<Box<dyn Any>, String> {
         match func {
+            FunctionTypes::FloatFn(f) => {
+                expect_arguments(&arguments, 1)?;
+                f(float_argument(&arguments, 0)?);
+                Ok(Box::new(()))
+            }
             FunctionTypes::DoubleFloatFn(f) => {
-                if arguments.len() == 2 {
-                    let arg1 = arguments[0].downcast_ref::<f64>().expect("Expected f64");
-                    let arg2 = arguments[1].downcast_ref::<f64>().expect("Expected f64");
-                    let result = f(*arg1, *arg2);
-                    //println!("DoubleFn result: {}", result);
-                    return Box::new(result);
-                } else {
-                    panic!("Expected exactly two arguments for DoubleFn");
-                }
+                expect_arguments(&arguments, 2)?;
+                let result = f(
+                    float_argument(&arguments, 0)?,
+                    float_argument(&arguments, 1)?,
+                );
+                Ok(Box::new(result))
             }
             FunctionTypes::SingleFloatFn(f) => {
-                if arguments.len() == 1 {
-                    let arg = arguments[0].downcast_ref::<f64>().expect("Expected f64");
-                    let result = f(*arg);
-                    //println!("SingleFn result: {}", result);
-                    return Box::new(result);
-                } else {
-                    panic!("Expected exactly one argument for SingleFn");
-                }
+                expect_arguments(&arguments, 1)?;
+                Ok(Box::new(f(float_argument(&arguments, 0)?)))
             }
             FunctionTypes::NoArgFloatFn(f) => {
-                if arguments.is_empty() {
-                    let result = f();
-                    //println!("NoArgFn result: {}", result);
-                    return Box::new(result);
-                } else {
-                    panic!("Expected no arguments for NoArgFn");
-                }
+                expect_arguments(&arguments, 0)?;
+                Ok(Box::new(f()))
             }
-            FunctionTypes::StringFn(f) => {
-                if arguments.len() == 1 {
-                    let arg = arguments[0]
-                        .downcast_ref::<String>()
-                        .expect("Expected String");
-                    f(arg.clone());
-                    //println!("StringFn called with: {}", arg);
-                    return Box::new(());
-                } else {
-                    panic!("Expected exactly one argument for StringFn");
-                }
+            FunctionTypes::StringFn(f) | FunctionTypes::EchoFn(f) => {
+                expect_arguments(&arguments, 1)?;
+                f(string_argument(&arguments, 0)?);
+                Ok(Box::new(()))
             }
             FunctionTypes::DoubleStringFn(f) => {
-                if arguments.len() == 2 {
-                    let arg1 = arguments[0]
-                        .downcast_ref::<String>()
-                        .expect("Expected String");
-                    let arg2 = arguments[1]
-                        .downcast_ref::<String>()
-                        .expect("Expected String");
-                    let result = f(arg1.clone(), arg2.clone());
-                    //println!("DoubleStringFn result: {}", result);
-                    return Box::new(result);
-                } else {
-                    panic!("Expected exactly two arguments for DoubleStringFn");
-                }
+                expect_arguments(&arguments, 2)?;
+                let result = f(
+                    string_argument(&arguments, 0)?,
+                    string_argument(&arguments, 1)?,
+                );
+                Ok(Box::new(result))
             }
             FunctionTypes::SingleStringFn(f) => {
-                if arguments.len() == 1 {
-                    let arg = arguments[0]
-                        .downcast_ref::<String>()
-                        .expect("Expected String");
-                    let result = f(arg.clone());
-                    //println!("SingleStringFn result: {}", result);
-                    return Box::new(result);
-                } else {
-                    panic!("Expected exactly one argument for SingleStringFn");
-                }
+                expect_arguments(&arguments, 1)?;
+                Ok(Box::new(f(string_argument(&arguments, 0)?)))
             }
-
-            FunctionTypes::EchoFn(f) => {
-                if arguments.len() == 1 {
-                    let arg = arguments[0].downcast_ref::<String>().expect(&format!(
-                        "Expected String, found {:?}",
-                        arguments[0].type_id()
-                    ));
-
-                    f(arg.clone());
-                    //println!("EchoFn called with: {}", arg);
-                    return Box::new(());
-                } else {
-                    println!("Arguments: {:?}", arguments);
-                    panic!(
-                        "Expected exactly one argument for EchoFn, but got {}",
-                        arguments.len()
-                    );
-                }
+            FunctionTypes::StringToIntFn(f) => {
+                expect_arguments(&arguments, 1)?;
+                Ok(Box::new(f(string_argument(&arguments, 0)?)))
             }
-
-            _ => {
-                panic!("Function not implemented");
+            FunctionTypes::StringToCharsFn(f) => {
+                expect_arguments(&arguments, 1)?;
+                Ok(Box::new(f(string_argument(&arguments, 0)?)))
+            }
+            FunctionTypes::DoubleStringToBoolFn(f) => {
+                expect_arguments(&arguments, 2)?;
+                let result = f(
+                    string_argument(&arguments, 0)?,
+                    string_argument(&arguments, 1)?,
+                );
+                Ok(Box::new(result))
+            }
+            FunctionTypes::DoubleStringToIntFn(f) => {
+                expect_arguments(&arguments, 2)?;
+                let result = f(
+                    string_argument(&arguments, 0)?,
+                    string_argument(&arguments, 1)?,
+                );
+                Ok(Box::new(result))
+            }
+            FunctionTypes::DoubleStringToListFn(f) => {
+                expect_arguments(&arguments, 2)?;
+                let result = f(
+                    string_argument(&arguments, 0)?,
+                    string_argument(&arguments, 1)?,
+                );
+                Ok(Box::new(result))
+            }
+            FunctionTypes::TripleStringFn(f) => {
+                expect_arguments(&arguments, 3)?;
+                let result = f(
+                    string_argument(&arguments, 0)?,
+                    string_argument(&arguments, 1)?,
+                    string_argument(&arguments, 2)?,
+                );
+                Ok(Box::new(result))
+            }
+            FunctionTypes::StringIntFn(f) => {
+                expect_arguments(&arguments, 2)?;
+                let result = f(
+                    string_argument(&arguments, 0)?,
+                    int_argument(&arguments, 1)?,
+                )?;
+                Ok(Box::new(result))
+            }
+            FunctionTypes::StringIntIntFn(f) => {
+                expect_arguments(&arguments, 3)?;
+                let result = f(
+                    string_argument(&arguments, 0)?,
+                    int_argument(&arguments, 1)?,
+                    int_argument(&arguments, 2)?,
+                )?;
+                Ok(Box::new(result))
+            }
+            FunctionTypes::StringIntStringFn(f) => {
+                expect_arguments(&arguments, 3)?;
+                let result = f(
+                    string_argument(&arguments, 0)?,
+                    int_argument(&arguments, 1)?,
+                    string_argument(&arguments, 2)?,
+                )?;
+                Ok(Box::new(result))
+            }
+            FunctionTypes::ParseIntFn(f) => {
+                expect_arguments(&arguments, 1)?;
+                Ok(Box::new(f(string_argument(&arguments, 0)?)?))
+            }
+            FunctionTypes::ParseFloatFn(f) => {
+                expect_arguments(&arguments, 1)?;
+                Ok(Box::new(f(string_argument(&arguments, 0)?)?))
             }
         }
     }
@@ -160,7 +275,10 @@ pub mod functions {
         pub name: String,
         pub return_type: BaseTypes,
         pub arguments: Vec<Variable>,
-        pub body: Vec<ASTNode>,
+        // the source of the body and the line and column it starts at
+        pub body: String,
+        pub body_line: usize,
+        pub body_column: usize,
     }
 
     // Constructor for creating a new function
@@ -169,13 +287,17 @@ pub mod functions {
             name: String,
             return_type: BaseTypes,
             arguments: Vec<Variable>,
-            body: Vec<ASTNode>,
+            body: String,
+            body_line: usize,
+            body_column: usize,
         ) -> Function {
             Function {
                 name,
                 return_type,
                 arguments,
                 body,
+                body_line,
+                body_column,
             }
         }
     }
@@ -193,9 +315,10 @@ pub mod functions {
 
             let body = self
                 .body
-                .iter()
-                .map(|token| format!("{:?}", token)) // Assuming TokenTypes implements Display
-                .collect::<Vec<String>>()
+                .lines()
+                .map(str::trim)
+                .filter(|line| !line.is_empty())
+                .collect::<Vec<&str>>()
                 .join("\n");
 
             write!(
@@ -6,7 +6,6 @@ pub mod functions {
 
 use crate::base_variable::base_types::BaseTypes;
 use crate::base_variable::variable::Variable;
-use crate::function::functions::Function;
 
 use crate::function::functions::FunctionTypes;
 use crate::node::nodes::ASTNode;
@@ -15,11 +14,6 @@ use std::collections::HashMap;
 use std::sync::Mutex;
 
 lazy_static::lazy_static! {
-    pub static ref USER_FUNCTIONS: Mutex<HashMap<String, Function>> = {
-        let map = HashMap::new();
-        map.into()
-    };
-
     pub static ref FUNCTIONS: Mutex<HashMap<&'static str, FunctionTypes>> = {
         let mut map = HashMap::new();
         map.insert("max", FunctionTypes::DoubleFloatFn(FunctionMap::max as fn(f64, f64) -> f64));
@@ -42,10 +36,25 @@ lazy_static::lazy_static! {
         map.insert("cos", FunctionTypes::SingleFloatFn(FunctionMap::cos as fn(f64) -> f64));
         map.insert("tan", FunctionTypes::SingleFloatFn(FunctionMap::tan as fn(f64) -> f64));
         map.insert("concat", FunctionTypes::DoubleStringFn(FunctionMap::concat as fn(String, String) -> String));
-        //map.insert("len", FunctionTypes::SingleFn(FunctionMap::len as fn(String) -> usize));
+        map.insert("len", FunctionTypes::StringToIntFn(FunctionMap::len as fn(String) -> i32));
         map.insert("to_uppercase", FunctionTypes::SingleStringFn(FunctionMap::to_uppercase as fn(String) -> String));
         map.insert("to_lowercase", FunctionTypes::SingleStringFn(FunctionMap::to_lowercase as fn(String) -> String));
         map.insert("trim", FunctionTypes::SingleStringFn(FunctionMap::trim as fn(String) -> String));
+        map.insert("trim_start", FunctionTypes::SingleStringFn(FunctionMap::trim_start as fn(String) -> String));
+        map.insert("trim_end", FunctionTypes::SingleStringFn(FunctionMap::trim_end as fn(String) -> String));
+        map.insert("split", FunctionTypes::DoubleStringToListFn(FunctionMap::split as fn(String, String) -> Vec<String>));
+        map.insert("replace", FunctionTypes::TripleStringFn(FunctionMap::replace as fn(String, String, String) -> String));
+        map.insert("contains", FunctionTypes::DoubleStringToBoolFn(FunctionMap::contains as fn(String, String) -> bool));
+        map.insert("starts_with", FunctionTypes::DoubleStringToBoolFn(FunctionMap::starts_with as fn(String, String) -> bool));
+        map.insert("ends_with", FunctionTypes::DoubleStringToBoolFn(FunctionMap::ends_with as fn(String, String) -> bool));
+        map.insert("find", FunctionTypes::DoubleStringToIntFn(FunctionMap::find as fn(String, String) -> i32));
+        map.insert("substring", FunctionTypes::StringIntIntFn(FunctionMap::substring as fn(String, i32, i32) -> Result<String, String>));
+        map.insert("chars", FunctionTypes::StringToCharsFn(FunctionMap::chars as fn(String) -> Vec<char>));
+        map.insert("repeat", FunctionTypes::StringIntFn(FunctionMap::repeat as fn(String, i32) -> Result<String, String>));
+        map.insert("pad_left", FunctionTypes::StringIntStringFn(FunctionMap::pad_left as fn(String, i32, String) -> Result<String, String>));
+        map.insert("pad_right", FunctionTypes::StringIntStringFn(FunctionMap::pad_right as fn(String, i32, String) -> Result<String, String>));
+        map.insert("parse_int", FunctionTypes::ParseIntFn(FunctionMap::parse_int as fn(String) -> Result<i32, String>));
+        map.insert("parse_float", FunctionTypes::ParseFloatFn(FunctionMap::parse_float as fn(String) -> Result<f64, String>));
         map.insert("input", FunctionTypes::SingleStringFn(FunctionMap::input as fn(String) -> String));
         map.into()
     };
@@ -106,6 +115,21 @@ enum FunctionMap {
     ToUppercase,
     ToLowercase,
     Trim,
+    TrimStart,
+    TrimEnd,
+    Split,
+    Replace,
+    Contains,
+    StartsWith,
+    EndsWith,
+    Find,
+    Substring,
+    Chars,
+    Repeat,
+    PadLeft,
+    PadRight,
+    ParseInt,
+    ParseFloat,
     Input,
 }
 
@@ -159,12 +183,26 @@ impl FunctionMap {
     }
 
     fn echo(a: String) {
-        print!("{}", a);
+        FunctionMap::write_out(&a);
         //println!("After echo");
     }
 
     fn echoln(a: String) {
-        println!("{}", a);
+        FunctionMap::write_out(&format!("{}\n", a));
+    }
+
+    ///
+    ///Writes what the script echoes. When the reader went away, like head does once it has its
+    ///lines, the script ends quietly instead of panicking on the broken pipe
+    ///
+    fn write_out(text: &str) {
+        use std::io::{ErrorKind, Write};
+        if let Err(e) = std::io::stdout().write_all(text.as_bytes()) {
+            if e.kind() == ErrorKind::BrokenPipe {
+                std::process::exit(0);
+            }
+            panic!("failed printing to stdout: {}", e);
+        }
     }
 
     fn abs(a: f64) -> f64 {
@@ -199,8 +237,9 @@ impl FunctionMap {
         [a, b].concat()
     }
 
-    fn len(s: String) -> usize {
-        s.len()
+    // String functions count chars rather than bytes so indexes line up with s[i]
+    fn len(s: String) -> i32 {
+        s.chars().count() as i32
     }
 
     fn to_uppercase(s: String) -> String {
@@ -215,6 +254,107 @@ impl FunctionMap {
         s.trim().to_string()
     }
 
+    fn trim_start(s: String) -> String {
+        s.trim_start().to_string()
+    }
+
+    fn trim_end(s: String) -> String {
+        s.trim_end().to_string()
+    }
+
+    fn split(s: String, separator: String) -> Vec<String> {
+        if separator.is_empty() {
+            return s.chars().map(String::from).collect();
+        }
+        s.split(separator.as_str()).map(String::from).collect()
+    }
+
+    fn replace(s: String, from: String, to: String) -> String {
+        s.replace(from.as_str(), to.as_str())
+    }
+
+    fn contains(s: String, pattern: String) -> bool {
+        s.contains(pattern.as_str())
+    }
+
+    fn starts_with(s: String, prefix: String) -> bool {
+        s.starts_with(prefix.as_str())
+    }
+
+    fn ends_with(s: String, suffix: String) -> bool {
+        s.ends_with(suffix.as_str())
+    }
+
+    /// Returns the char index of the first match, or -1 when there is none
+    fn find(s: String, pattern: String) -> i32 {
+        match s.find(pattern.as_str()) {
+            Some(byte_index) => s[..byte_index].chars().count() as i32,
+            None => -1,
+        }
+    }
+
+    fn substring(s: String, start: i32, end: i32) -> Result<String, String> {
+        let length = s.chars().count() as i32;
+        if start < 0 || end < start || end > length {
+            return Err(format!(
+                "substring({}, {}) is out of range for a string of length {}",
+                start, end, length
+            ));
+        }
+        Ok(s.chars()
+            .skip(start as usize)
+            .take((end - start) as usize)
+            .collect())
+    }
+
+    fn chars(s: String) -> Vec<char> {
+        s.chars().collect()
+    }
+
+    fn repeat(s: String, count: i32) -> Result<String, String> {
+        if count < 0 {
+            return Err(format!("Cannot repeat a string {} times", count));
+        }
+        Ok(s.repeat(count as usize))
+    }
+
+    fn padding(s: &str, width: i32, fill: &str) -> Result<String, String> {
+        let mut fill_chars = fill.chars();
+        let fill_char = match (fill_chars.next(), fill_chars.next()) {
+            (Some(c), None) => c,
+            _ => {
+                return Err(format!(
+                    "Padding must be a single character, found \"{}\"",
+                    fill
+                ))
+            }
+        };
+        let missing = (width.max(0) as usize).saturating_sub(s.chars().count());
+        Ok(std::iter::repeat(fill_char).take(missing).collect())
+    }
+
+    fn pad_left(s: String, width: i32, fill: String) -> Result<String, String> {
+        let padding = FunctionMap::padding(&s, width, &fill)?;
+        Ok(padding + &s)
+    }
+
+    fn pad_right(s: String, width: i32, fill: String) -> Result<String, String> {
+        let padding = FunctionMap::padding(&s, width, &fill)?;
+        Ok(s + &padding)
+    }
+
+    fn parse_int(s: String) -> Result<i32, String> {
+        s.trim()
+            .parse::<i32>()
+            .map_err(|_| format!("Cannot parse \"{}\" as an int", s))
+    }
+
+    fn parse_float(s: String) -> Result<f64, String> {
+        s.trim()
+            .parse::<f64>()
+            .map_err(|_| format!("Cannot parse \"{}\" as a float", s))
+    }
+
     fn input(s: String) -> String {
         print!("{}", s);
         let mut input = String::new();
@@ -1,2 +1,106 @@
-pub static mut IF_ELSE_SKIP: bool = false;
-pub static mut MAKE_LOOP: bool = false;
+use crate::base_variable::variables::SCRIPT_BLOCKS;
+use crate::syntax::Span;
+
+// How many user functions are running, return is only allowed inside one
+pub static mut FUNCTION_DEPTH: usize = 0;
+// Set by a return statement for the running function to pick up
+pub static mut RETURN_VALUE: Option<crate::base_variable::base_types::BaseTypes> = None;
+// The command-line arguments after the script path, read by args()
+pub static mut SCRIPT_ARGUMENTS: Vec<String> = Vec::new();
+// The user functions that are running, innermost last, read by jist debug
+pub static mut CALL_STACK: Vec<Frame> = Vec::new();
+// Called with the line of each statement before it runs, set by jist debug
+pub static mut STATEMENT_HOOK: Option<fn(usize)> = None;
+// The code of the statement that is running on the line it starts on, line 0 before the first
+pub static mut CURRENT_STATEMENT: Span = Span {
+    line: 0,
+    column: 0,
+    length: 0,
+};
+// Prints an error the script stops on against the statement it happened in, set by jist run
+pub static mut ERROR_HOOK: Option<fn(&str, Span)> = None;
+// How many try blocks are running, an error inside one goes to its catch
+pub static mut TRY_DEPTH: usize = 0;
+// Whether a value is only converted to another type with as, set by --strict or #![strict]
+pub static mut STRICT: bool = false;
+// How many user functions can run inside each other, a call past it is a runtime error
+pub const MAX_CALL_DEPTH: usize = 1000;
+
+///
+///A running user function, the line of the statement it is on and where its variables start
+///on the variable stack
+///
+pub struct Frame {
+    pub function: String,
+    pub line: usize,
+    pub variables: usize,
+}
+
+///
+///Tells the debugger, when one is attached, that the statement at span is about to run
+///
+pub fn at_statement(span: Span) {
+    unsafe { CURRENT_STATEMENT = span };
+    if let Some(hook) = unsafe { STATEMENT_HOOK } {
+        hook(span.line);
+    }
+}
+
+///
+///An error raised inside a try block, with the statement it happened in
+///
+pub struct RaisedError {
+    pub message: String,
+    pub statement: Span,
+}
+
+///
+///Prints the error the script stopped on and exits. Inside a try block the error is handed to
+///its catch instead
+///
+pub fn report_error(message: impl std::fmt::Display) -> ! {
+    let message = message.to_string();
+    if unsafe { TRY_DEPTH } > 0 {
+        let statement = unsafe { CURRENT_STATEMENT };
+        std::panic::resume_unwind(Box::new(RaisedError { message, statement }));
+    }
+    match unsafe { ERROR_HOOK } {
+        Some(hook) => hook(&message, unsafe { CURRENT_STATEMENT }),
+        None => eprintln!("{}", message),
+    }
+    std::process::exit(1)
+}
+
+///
+///Runs f as the body of a try block, an error reported while it runs is returned instead of
+///ending the script. The functions and blocks it was inside of are left
+///
+pub fn catching<R>(f: impl FnOnce() -> R) -> Result<R, RaisedError> {
+    let (depth, frames) = unsafe { (FUNCTION_DEPTH, (*std::ptr::addr_of!(CALL_STACK)).len()) };
+    let blocks = unsafe { SCRIPT_BLOCKS };
+    unsafe { TRY_DEPTH += 1 };
+    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(f));
+    unsafe { TRY_DEPTH -= 1 };
+    match result {
+        Ok(value) => Ok(value),
+        Err(payload) => match payload.downcast::<RaisedError>() {
+            Ok(error) => {
+                unsafe {
+                    FUNCTION_DEPTH = depth;
+                    (*std::ptr::addr_of_mut!(CALL_STACK)).truncate(frames);
+                    SCRIPT_BLOCKS = blocks;
+                }
+                Err(*error)
+            }
+            Err(payload) => std::panic::resume_unwind(payload),
+        },
+    }
+}
+
+///
+///Reports an error that was caught again, against the statement it happened in
+///
+pub fn raise(error: RaisedError) -> ! {
+    unsafe { CURRENT_STATEMENT = error.statement };
+    report_error(error.message)
+}
@@ -0,0 +1,418 @@
+/*
+* This file keeps track of the collections a script creates. Arrays, dictionaries and sets are
+* shared through Rc<RefCell<..>> handles, so after let b = a both names refer to the same array
+* and a change through one shows through the other. A collection is freed as soon as its last
+* handle goes away, except when collections hold each other in a cycle. collect() finds those by
+* subtracting the handles collections hold to each other from their counts: what is left over
+* is held from outside, by a variable or by the interpreter itself, and everything those reach
+* is kept. The rest only keeps itself alive and is emptied, which frees it
+*/
+use crate::base_variable::base_types::BaseTypes;
+use crate::collection::collections::{Array, Dictionary, Set};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::{Rc, Weak};
+
+///
+///A handle to a collection, cloning it gives another name for the same collection
+///
+pub type Shared<T> = Rc<RefCell<T>>;
+
+// Below this many tracked collections collect() is not run on its own
+const MIN_COLLECTION: usize = 1024;
+
+enum Object {
+    Array(Weak<RefCell<Array>>),
+    Dictionary(Weak<RefCell<Dictionary>>),
+    Set(Weak<RefCell<Set>>),
+}
+
+impl Object {
+    fn is_live(&self) -> bool {
+        match self {
+            Object::Array(array) => array.strong_count() > 0,
+            Object::Dictionary(dictionary) => dictionary.strong_count() > 0,
+            Object::Set(set) => set.strong_count() > 0,
+        }
+    }
+
+    fn upgrade(&self) -> Option<Handle> {
+        match self {
+            Object::Array(array) => array.upgrade().map(Handle::Array),
+            Object::Dictionary(dictionary) => dictionary.upgrade().map(Handle::Dictionary),
+            Object::Set(set) => set.upgrade().map(Handle::Set),
+        }
+    }
+}
+
+///
+///A collection held while collect() runs
+///
+enum Handle {
+    Array(Shared<Array>),
+    Dictionary(Shared<Dictionary>),
+    Set(Shared<Set>),
+}
+
+impl Handle {
+    fn address(&self) -> usize {
+        match self {
+            Handle::Array(array) => Rc::as_ptr(array) as usize,
+            Handle::Dictionary(dictionary) => Rc::as_ptr(dictionary) as usize,
+            Handle::Set(set) => Rc::as_ptr(set) as usize,
+        }
+    }
+
+    fn strong_count(&self) -> usize {
+        match self {
+            Handle::Array(array) => Rc::strong_count(array),
+            Handle::Dictionary(dictionary) => Rc::strong_count(dictionary),
+            Handle::Set(set) => Rc::strong_count(set),
+        }
+    }
+
+    ///
+    ///The addresses of the collections this one holds, None when it is being changed right now
+    ///and cannot be read
+    ///
+    fn children(&self) -> Option<Vec<usize>> {
+        let mut children = Vec::new();
+        match self {
+            Handle::Array(array) => {
+                for value in &array.try_borrow().ok()?.data {
+                    addresses(value, &mut children);
+                }
+            }
+            Handle::Dictionary(dictionary) => {
+                for (key, value) in dictionary.try_borrow().ok()?.entries() {
+                    addresses(key, &mut children);
+                    addresses(value, &mut children);
+                }
+            }
+            Handle::Set(set) => {
+                for value in set.try_borrow().ok()?.values() {
+                    addresses(value, &mut children);
+                }
+            }
+        }
+        Some(children)
+    }
+
+    ///
+    ///Drops everything the collection holds, which breaks the cycle it is part of
+    ///
+    fn clear(&self) {
+        match self {
+            Handle::Array(array) => {
+                if let Ok(mut array) = array.try_borrow_mut() {
+                    array.data.clear();
+                }
+            }
+            Handle::Dictionary(dictionary) => {
+                if let Ok(mut dictionary) = dictionary.try_borrow_mut() {
+                    dictionary.clear();
+                }
+            }
+            Handle::Set(set) => {
+                if let Ok(mut set) = set.try_borrow_mut() {
+                    set.clear();
+                }
+            }
+        }
+    }
+}
+
+///
+///The addresses of the collections in value, a tuple is looked through since it is not shared
+///
+fn addresses(value: &BaseTypes, found: &mut Vec<usize>) {
+    match value {
+        BaseTypes::Array(array) => found.push(Rc::as_ptr(array) as usize),
+        BaseTypes::Dictionary(dictionary) => found.push(Rc::as_ptr(dictionary) as usize),
+        BaseTypes::Set(set) => found.push(Rc::as_ptr(set) as usize),
+        BaseTypes::Tuple(values) => {
+            for value in values {
+                addresses(value, found);
+            }
+        }
+        _ => {}
+    }
+}
+
+///
+///What gc_stats() reports: how many collections were created, how many are still alive, how
+///many collect() freed from cycles and how often it ran
+///
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Stats {
+    pub allocated: usize,
+    pub live: usize,
+    pub collected: usize,
+    pub collections: usize,
+}
+
+struct Heap {
+    objects: Vec<Object>,
+    stats: Stats,
+    // collect() runs on its own once this many collections are tracked
+    threshold: usize,
+    // the collections being printed or compared, so a cycle is not followed forever
+    visiting: Vec<(usize, usize)>,
+}
+
+thread_local! {
+    static HEAP: RefCell<Heap> = const {
+        RefCell::new(Heap {
+            objects: Vec::new(),
+            stats: Stats {
+                allocated: 0,
+                live: 0,
+                collected: 0,
+                collections: 0,
+            },
+            threshold: MIN_COLLECTION,
+            visiting: Vec::new(),
+        })
+    };
+}
+
+fn track(object: Object) {
+    let full = HEAP.with(|heap| {
+        let mut heap = heap.borrow_mut();
+        heap.objects.push(object);
+        heap.stats.allocated += 1;
+        heap.objects.len() >= heap.threshold
+    });
+    if full {
+        collect();
+    }
+}
+
+impl From<Array> for BaseTypes {
+    fn from(array: Array) -> Self {
+        let array = Rc::new(RefCell::new(array));
+        track(Object::Array(Rc::downgrade(&array)));
+        BaseTypes::Array(array)
+    }
+}
+
+impl From<Dictionary> for BaseTypes {
+    fn from(dictionary: Dictionary) -> Self {
+        let dictionary = Rc::new(RefCell::new(dictionary));
+        track(Object::Dictionary(Rc::downgrade(&dictionary)));
+        BaseTypes::Dictionary(dictionary)
+    }
+}
+
+impl From<Set> for BaseTypes {
+    fn from(set: Set) -> Self {
+        let set = Rc::new(RefCell::new(set));
+        track(Object::Set(Rc::downgrade(&set)));
+        BaseTypes::Set(set)
+    }
+}
+
+///
+///Frees the collections that are only held by each other, returning how many there were
+///
+pub fn collect() -> usize {
+    let handles: Vec<Handle> = HEAP.with(|heap| {
+        let mut heap = heap.borrow_mut();
+        heap.objects.retain(Object::is_live);
+        heap.objects.iter().filter_map(Object::upgrade).collect()
+    });
+    let positions: HashMap<usize, usize> = handles
+        .iter()
+        .enumerate()
+        .map(|(i, handle)| (handle.address(), i))
+        .collect();
+    let children: Vec<Option<Vec<usize>>> = handles
+        .iter()
+        .map(|handle| {
+            let children = handle.children()?;
+            Some(
+                children
+                    .iter()
+                    .filter_map(|address| positions.get(address).copied())
+                    .collect(),
+            )
+        })
+        .collect();
+
+    let mut internal = vec![0; handles.len()];
+    for &child in children.iter().flatten().flatten() {
+        internal[child] += 1;
+    }
+    // a collection held more often than other collections hold it is held from outside, the
+    // handle in handles is not counted
+    let mut pending: Vec<usize> = (0..handles.len())
+        .filter(|&i| children[i].is_none() || handles[i].strong_count() - 1 > internal[i])
+        .collect();
+    let mut reachable = vec![false; handles.len()];
+    while let Some(i) = pending.pop() {
+        if reachable[i] {
+            continue;
+        }
+        reachable[i] = true;
+        pending.extend(
+            children[i]
+                .iter()
+                .flatten()
+                .filter(|&&child| !reachable[child]),
+        );
+    }
+
+    let mut freed = 0;
+    for (handle, reachable) in handles.iter().zip(&reachable) {
+        if !reachable {
+            handle.clear();
+            freed += 1;
+        }
+    }
+    let tracked = handles.len();
+    drop(handles);
+
+    HEAP.with(|heap| {
+        let mut heap = heap.borrow_mut();
+        heap.objects.retain(Object::is_live);
+        heap.stats.collected += freed;
+        heap.stats.collections += 1;
+        heap.threshold = MIN_COLLECTION.max(heap.objects.len() * 2);
+    });
+    trace!(
+        Gc,
+        Info,
+        "Collected {} of {} collections held only by cycles",
+        freed,
+        tracked
+    );
+    freed
+}
+
+///
+///A new collection with the elements of value, the collections inside it are still shared.
+///Any other value is simply cloned
+///
+pub fn copy(value: &BaseTypes) -> BaseTypes {
+    match value {
+        BaseTypes::Array(array) => {
+            let array = array.borrow();
+            Array::new(String::new(), array.value_type.clone(), array.data.clone()).into()
+        }
+        BaseTypes::Dictionary(dictionary) => {
+            let mut copy = dictionary.borrow().clone();
+            copy.name = String::new();
+            copy.into()
+        }
+        BaseTypes::Set(set) => {
+            let mut copy = set.borrow().clone();
+            copy.name = String::new();
+            copy.into()
+        }
+        other => other.clone(),
+    }
+}
+
+///
+///Whether both values are the same collection, not just collections with equal contents
+///
+pub fn same(left: &BaseTypes, right: &BaseTypes) -> bool {
+    match (left, right) {
+        (BaseTypes::Array(left), BaseTypes::Array(right)) => Rc::ptr_eq(left, right),
+        (BaseTypes::Dictionary(left), BaseTypes::Dictionary(right)) => Rc::ptr_eq(left, right),
+        (BaseTypes::Set(left), BaseTypes::Set(right)) => Rc::ptr_eq(left, right),
+        _ => false,
+    }
+}
+
+pub fn stats() -> Stats {
+    HEAP.with(|heap| {
+        let heap = heap.borrow();
+        Stats {
+            live: heap
+                .objects
+                .iter()
+                .filter(|object| object.is_live())
+                .count(),
+            ..heap.stats
+        }
+    })
+}
+
+///
+///Runs f unless key is already being visited further up, which means a collection holds
+///itself. Printing uses (collection, 0) as the key and comparing (left, right)
+///
+pub fn visit<R>(key: (usize, usize), f: impl FnOnce() -> R) -> Option<R> {
+    let entered = HEAP.with(|heap| {
+        let mut heap = heap.borrow_mut();
+        if heap.visiting.contains(&key) {
+            return false;
+        }
+        heap.visiting.push(key);
+        true
+    });
+    if !entered {
+        return None;
+    }
+    let result = f();
+    HEAP.with(|heap| {
+        heap.borrow_mut()
+            .visiting
+            .retain(|visiting| *visiting != key)
+    });
+    Some(result)
+}
+
+#[cfg(test)]
+mod heap_tests {
+    use super::{collect, stats};
+    use crate::base_variable::base_types::BaseTypes;
+    use crate::collection::collections::{Array, Dictionary};
+
+    fn array(data: Vec<BaseTypes>) -> BaseTypes {
+        Array::new(String::new(), "any".into(), data).into()
+    }
+
+    fn push(target: &BaseTypes, value: BaseTypes) {
+        if let BaseTypes::Array(array) = target {
+            array.borrow_mut().push(value);
+        }
+    }
+
+    #[test]
+    fn test_shared_collections() {
+        let a = array(vec![BaseTypes::Int(1)]);
+        let b = a.clone();
+        push(&b, BaseTypes::Int(2));
+        assert_eq!(a.to_string(), "[1, 2]");
+
+        // a collection that holds itself is printed once
+        push(&a, b);
+        assert_eq!(a.to_string(), "[1, 2, [...]]");
+        assert_eq!(a, a.clone());
+    }
+
+    #[test]
+    fn test_collect_cycles() {
+        let before = stats();
+        for _ in 0..100 {
+            let a = array(Vec::new());
+            let b = array(vec![a.clone()]);
+            let dictionary: BaseTypes =
+                Dictionary::new(String::new(), "int".into(), "any".into(), vec![]).into();
+            if let BaseTypes::Dictionary(d) = &dictionary {
+                d.borrow_mut().add(BaseTypes::Int(0), b.clone());
+            }
+            push(&a, dictionary);
+        }
+        let kept = array(Vec::new());
+        push(&kept, kept.clone());
+
+        assert!(collect() >= 300);
+        let after = stats();
+        assert_eq!(after.live, before.live + 1);
+        assert_eq!(after.allocated, before.allocated + 301);
+        assert_eq!(kept.to_string(), "[[...]]");
+    }
+}
@@ -1,5 +1,5 @@
 // src/highlighter.rs
-use crate::statement_tokenizer::tokenizer::tokenizers::tokenize;
+use crate::statement_tokenizer::lexer::lexers::Lexer;
 use crate::token_type::token_types::TokenTypes;
 
 #[derive(Debug, Clone)]
@@ -54,16 +54,33 @@ pub fn get_highlighting_style(token: &TokenTypes) -> HighlightStyle {
     }
 }
 
+///
+///The source cut into the text of each token with its style. The text between the tokens, like
+///spaces, line breaks and characters that are no token, is kept with no style, so joining the
+///parts gives the source back
+///
 pub fn highlight_code(source: &str) -> Vec<(String, HighlightStyle)> {
-    let tokens = tokenize(source.to_string()); // Replace this with your actual tokenizer call
+    let chars: Vec<char> = source.chars().collect();
+    let text = |from: usize, to: usize| chars[from..to].iter().collect::<String>();
+    let plain = || HighlightStyle {
+        foreground: String::new(),
+        background: String::new(),
+        font_style: None,
+    };
+    let mut lexer = Lexer::standalone(source);
     let mut highlighted_code = Vec::new();
-
-    for info in tokens {
-        let token = info.token;
-        let style = get_highlighting_style(&token);
-        highlighted_code.push((token.to_string(), style)); // Store the token with its highlighting style
+    let mut end = 0;
+    while let Some(info) = lexer.next() {
+        let start = lexer.position() - info.chars_read as usize;
+        if start > end {
+            highlighted_code.push((text(end, start), plain()));
+        }
+        end = lexer.position();
+        highlighted_code.push((text(start, end), get_highlighting_style(&info.token)));
+    }
+    if end < chars.len() {
+        highlighted_code.push((text(end, chars.len()), plain()));
     }
-
     highlighted_code
 }
 
@@ -71,6 +88,10 @@ use ansi_term::Colour;
 
 pub fn display_highlighted_code(highlighted_code: Vec<(String, HighlightStyle)>) {
     for (token, style) in highlighted_code {
+        if style.foreground.is_empty() {
+            print!("{}", token);
+            continue;
+        }
         let mut formatted_token = token.clone();
 
         // Apply foreground color
@@ -99,7 +120,4 @@ pub fn display_highlighted_code(highlighted_code: Vec<(String, HighlightStyle)>)
         // Print the formatted token
         print!("{}", formatted_token);
     }
-
-    // New line after printing all tokens
-    println!();
 }
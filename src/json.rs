@@ -0,0 +1,335 @@
+/*
+* This file reads and writes JSON for jist lsp and jist debug --dap, which talk to editors in JSON
+* messages. Objects keep their keys in the order they were read or built in, and a missing key
+* reads as null
+*/
+use crate::state_dump::json_string;
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Json {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+const NULL: Json = Json::Null;
+
+///
+///An object from its keys and values
+///
+pub fn object(entries: Vec<(&str, Json)>) -> Json {
+    Json::Object(
+        entries
+            .into_iter()
+            .map(|(key, value)| (key.to_string(), value))
+            .collect(),
+    )
+}
+
+impl Json {
+    ///
+    ///The value of a key, null when this is not an object or the key is missing
+    ///
+    pub fn get(&self, key: &str) -> &Json {
+        match self {
+            Json::Object(entries) => entries
+                .iter()
+                .find(|(k, _)| k == key)
+                .map_or(&NULL, |(_, value)| value),
+            _ => &NULL,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Json::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_usize(&self) -> Option<usize> {
+        match self {
+            Json::Number(n) if *n >= 0.0 && n.fract() == 0.0 => Some(*n as usize),
+            _ => None,
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Json::Bool(b) => Some(*b),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> &[Json] {
+        match self {
+            Json::Array(elements) => elements,
+            _ => &[],
+        }
+    }
+
+    pub fn parse(text: &str) -> Result<Json, String> {
+        let chars: Vec<char> = text.chars().collect();
+        let mut parser = Parser { chars, at: 0 };
+        let value = parser.value()?;
+        parser.skip_whitespace();
+        if parser.at < parser.chars.len() {
+            return Err(format!(
+                "Unexpected '{}' after the JSON value",
+                parser.chars[parser.at]
+            ));
+        }
+        Ok(value)
+    }
+}
+
+impl From<&str> for Json {
+    fn from(s: &str) -> Json {
+        Json::String(s.to_string())
+    }
+}
+
+impl From<String> for Json {
+    fn from(s: String) -> Json {
+        Json::String(s)
+    }
+}
+
+impl From<usize> for Json {
+    fn from(n: usize) -> Json {
+        Json::Number(n as f64)
+    }
+}
+
+impl From<bool> for Json {
+    fn from(b: bool) -> Json {
+        Json::Bool(b)
+    }
+}
+
+impl From<Vec<Json>> for Json {
+    fn from(elements: Vec<Json>) -> Json {
+        Json::Array(elements)
+    }
+}
+
+impl fmt::Display for Json {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Json::Null => write!(f, "null"),
+            Json::Bool(b) => write!(f, "{}", b),
+            // whole numbers are written without a fraction, ids and positions are integers
+            Json::Number(n) if n.fract() == 0.0 && n.abs() < 1e15 => write!(f, "{}", *n as i64),
+            Json::Number(n) => write!(f, "{}", n),
+            Json::String(s) => write!(f, "{}", json_string(s)),
+            Json::Array(elements) => {
+                write!(f, "[")?;
+                for (i, element) in elements.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+                    write!(f, "{}", element)?;
+                }
+                write!(f, "]")
+            }
+            Json::Object(entries) => {
+                write!(f, "{{")?;
+                for (i, (key, value)) in entries.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+                    write!(f, "{}:{}", json_string(key), value)?;
+                }
+                write!(f, "}}")
+            }
+        }
+    }
+}
+
+struct Parser {
+    chars: Vec<char>,
+    at: usize,
+}
+
+impl Parser {
+    fn skip_whitespace(&mut self) {
+        while self.at < self.chars.len() && self.chars[self.at].is_whitespace() {
+            self.at += 1;
+        }
+    }
+
+    fn expect(&mut self, word: &str, value: Json) -> Result<Json, String> {
+        for expected in word.chars() {
+            if self.chars.get(self.at) != Some(&expected) {
+                return Err(format!("Expected '{}'", word));
+            }
+            self.at += 1;
+        }
+        Ok(value)
+    }
+
+    fn value(&mut self) -> Result<Json, String> {
+        self.skip_whitespace();
+        match self.chars.get(self.at) {
+            None => Err("Unexpected end of JSON".to_string()),
+            Some('n') => self.expect("null", Json::Null),
+            Some('t') => self.expect("true", Json::Bool(true)),
+            Some('f') => self.expect("false", Json::Bool(false)),
+            Some('"') => self.string().map(Json::String),
+            Some('[') => {
+                self.at += 1;
+                let mut elements = Vec::new();
+                self.skip_whitespace();
+                if self.chars.get(self.at) == Some(&']') {
+                    self.at += 1;
+                    return Ok(Json::Array(elements));
+                }
+                loop {
+                    elements.push(self.value()?);
+                    self.skip_whitespace();
+                    match self.chars.get(self.at) {
+                        Some(',') => self.at += 1,
+                        Some(']') => {
+                            self.at += 1;
+                            return Ok(Json::Array(elements));
+                        }
+                        _ => return Err("Expected ',' or ']' in an array".to_string()),
+                    }
+                }
+            }
+            Some('{') => {
+                self.at += 1;
+                let mut entries = Vec::new();
+                self.skip_whitespace();
+                if self.chars.get(self.at) == Some(&'}') {
+                    self.at += 1;
+                    return Ok(Json::Object(entries));
+                }
+                loop {
+                    self.skip_whitespace();
+                    if self.chars.get(self.at) != Some(&'"') {
+                        return Err("Expected a key in an object".to_string());
+                    }
+                    let key = self.string()?;
+                    self.skip_whitespace();
+                    if self.chars.get(self.at) != Some(&':') {
+                        return Err(format!("Expected ':' after the key \"{}\"", key));
+                    }
+                    self.at += 1;
+                    entries.push((key, self.value()?));
+                    self.skip_whitespace();
+                    match self.chars.get(self.at) {
+                        Some(',') => self.at += 1,
+                        Some('}') => {
+                            self.at += 1;
+                            return Ok(Json::Object(entries));
+                        }
+                        _ => return Err("Expected ',' or '}' in an object".to_string()),
+                    }
+                }
+            }
+            Some(c) if *c == '-' || c.is_ascii_digit() => {
+                let start = self.at;
+                while self.at < self.chars.len()
+                    && (self.chars[self.at].is_ascii_digit()
+                        || "+-.eE".contains(self.chars[self.at]))
+                {
+                    self.at += 1;
+                }
+                let text: String = self.chars[start..self.at].iter().collect();
+                text.parse::<f64>()
+                    .map(Json::Number)
+                    .map_err(|_| format!("Invalid number '{}'", text))
+            }
+            Some(c) => Err(format!("Unexpected '{}' in JSON", c)),
+        }
+    }
+
+    fn hex4(&mut self) -> Result<u32, String> {
+        let digits: String = self.chars.iter().skip(self.at).take(4).collect();
+        self.at += 4;
+        u32::from_str_radix(&digits, 16).map_err(|_| format!("Invalid escape \\u{}", digits))
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        // the opening quote
+        self.at += 1;
+        let mut s = String::new();
+        loop {
+            let c = match self.chars.get(self.at) {
+                Some(c) => *c,
+                None => return Err("Unterminated string in JSON".to_string()),
+            };
+            self.at += 1;
+            match c {
+                '"' => return Ok(s),
+                '\\' => {
+                    let escape = self.chars.get(self.at).copied().unwrap_or('\0');
+                    self.at += 1;
+                    match escape {
+                        '"' | '\\' | '/' => s.push(escape),
+                        'n' => s.push('\n'),
+                        'r' => s.push('\r'),
+                        't' => s.push('\t'),
+                        'b' => s.push('\u{8}'),
+                        'f' => s.push('\u{c}'),
+                        'u' => {
+                            let mut code = self.hex4()?;
+                            // a character outside the basic plane is written as two escapes
+                            if (0xD800..0xDC00).contains(&code)
+                                && self.chars.get(self.at) == Some(&'\\')
+                                && self.chars.get(self.at + 1) == Some(&'u')
+                            {
+                                self.at += 2;
+                                let low = self.hex4()?;
+                                code = 0x10000
+                                    + ((code - 0xD800) << 10)
+                                    + (low.wrapping_sub(0xDC00) & 0x3FF);
+                            }
+                            s.push(char::from_u32(code).unwrap_or('\u{fffd}'));
+                        }
+                        _ => return Err(format!("Invalid escape \\{} in JSON", escape)),
+                    }
+                }
+                c => s.push(c),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod json_tests {
+    use super::{object, Json};
+
+    #[test]
+    fn test_parse_and_write() {
+        let value = Json::parse(
+            r#"{"id": 1, "params": {"text": "a\n\"b\" é😀", "list": [true, null, -2.5]}}"#,
+        )
+        .unwrap();
+        assert_eq!(value.get("id").as_usize(), Some(1));
+        assert_eq!(
+            value.get("params").get("text").as_str(),
+            Some("a\n\"b\" é😀")
+        );
+        assert_eq!(value.get("params").get("list").as_array().len(), 3);
+        assert_eq!(value.get("missing"), &Json::Null);
+        assert_eq!(
+            value.to_string(),
+            r#"{"id":1,"params":{"text":"a\n\"b\" é😀","list":[true,null,-2.5]}}"#
+        );
+
+        let built = object(vec![
+            ("a", "x".into()),
+            ("b", vec![Json::from(2usize)].into()),
+        ]);
+        assert_eq!(built.to_string(), r#"{"a":"x","b":[2]}"#);
+        assert!(Json::parse("{\"a\": }").is_err());
+        assert!(Json::parse("[1, 2").is_err());
+        assert!(Json::parse("1 2").is_err());
+    }
+}
@@ -1,18 +1,29 @@
+// first, so the trace! macro can be used in the modules below
+#[macro_use]
+pub mod trace;
 pub mod ast;
 pub mod base_variable;
+pub mod bindings;
 pub mod collection;
 pub mod compiler;
+pub mod config;
+pub mod formatter;
 pub mod function;
 pub mod function_map;
 pub mod globals;
+pub mod heap;
 pub mod highlighter;
 pub mod node;
+pub mod state_dump;
+pub mod statements;
+pub mod syntax;
 pub mod token_type;
 pub mod statement_tokenizer {
     pub mod basic_tokenizer;
     pub mod collection_tokenizer;
     pub mod conditional_tokenizer;
     pub mod function_tokenizer;
+    pub mod lexer;
     pub mod loop_tokenizer;
     pub mod tests;
     pub mod tokenizer;
@@ -20,10 +31,20 @@ pub mod statement_tokenizer {
 }
 
 pub mod compilers {
+    pub mod array;
+    pub mod block;
     pub mod collection;
     pub mod conditional;
+    pub mod convert;
+    pub mod dictionary;
+    pub mod expression;
+    pub mod fs;
     pub mod function;
+    pub mod gc;
     pub mod loops;
+    pub mod math;
     pub mod operation;
+    pub mod process;
+    pub mod set;
     pub mod variable;
 }
@@ -0,0 +1,940 @@
+/*
+* This file holds jist lint, which looks for code that runs but is probably wrong: variables and
+* functions that are never used, names that hide other names, code after a return, if
+* conditions that never change, comparisons between values of different types and floats
+* stored in ints. Every lint has an id and a level, allow, warn or deny. The level comes from
+* the [lint] section of jist.toml and can be changed for one statement with a comment such as
+* // #[allow(unused_variable)] in front of it, or for the whole script with // #![deny(...)]
+*/
+use crate::base_variable::base_types::BaseTypes;
+use crate::commands::{condition_start, expression_span, node_spans, postfix, statement_nodes};
+use crate::compilers::collection::declared_type;
+use crate::compilers::expression::{constant_value, parse_expression, static_type, Expression};
+use crate::config::{load_section, CONFIG_FILE};
+use crate::diagnostic::Diagnostic;
+use crate::node::nodes::ASTNode;
+use crate::statement_tokenizer::lexer::lexers::Lexer;
+use crate::statements::{
+    advance, block, branches, code_span, positioned_statements, span_in, split_leading_comments,
+    Positioned,
+};
+use crate::syntax::Span;
+use crate::token_type::token_types::TokenTypes;
+use std::collections::HashMap;
+use std::path::Path;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Level {
+    Allow,
+    Warn,
+    Deny,
+}
+
+impl Level {
+    fn parse(name: &str) -> Option<Level> {
+        match name {
+            "allow" => Some(Level::Allow),
+            "warn" => Some(Level::Warn),
+            "deny" => Some(Level::Deny),
+            _ => None,
+        }
+    }
+
+    ///
+    ///The kind a problem at this level is shown as
+    ///
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Level::Allow | Level::Warn => "Warning",
+            Level::Deny => "Error",
+        }
+    }
+}
+
+pub struct Lint {
+    pub id: &'static str,
+    pub level: Level,
+}
+
+pub const LINTS: [Lint; 8] = [
+    // a variable or parameter that is never read
+    Lint {
+        id: "unused_variable",
+        level: Level::Warn,
+    },
+    // a function that is never called
+    Lint {
+        id: "unused_function",
+        level: Level::Warn,
+    },
+    // a declaration that hides a variable or function with the same name
+    Lint {
+        id: "shadowed_name",
+        level: Level::Warn,
+    },
+    // statements after a return or exit()
+    Lint {
+        id: "unreachable_code",
+        level: Level::Warn,
+    },
+    // an if condition that is always true or always false
+    Lint {
+        id: "constant_condition",
+        level: Level::Warn,
+    },
+    // a comparison between values of different types
+    Lint {
+        id: "mismatched_comparison",
+        level: Level::Deny,
+    },
+    // a float stored in an int, which drops the fraction
+    Lint {
+        id: "float_truncation",
+        level: Level::Warn,
+    },
+    // an allow, warn or deny attribute naming a lint that does not exist
+    Lint {
+        id: "unknown_lint",
+        level: Level::Warn,
+    },
+];
+
+fn find_lint(id: &str) -> Option<&'static Lint> {
+    LINTS.iter().find(|lint| lint.id == id)
+}
+
+///
+///The levels set in jist.toml and by attributes, a later setting wins over an earlier one
+///
+#[derive(Debug, Clone, Default)]
+pub struct Levels(Vec<(String, Level)>);
+
+impl Levels {
+    ///
+    ///The levels in the [lint] section of the nearest jist.toml
+    ///
+    pub fn load(dir: &Path) -> Result<Levels, String> {
+        let mut levels = Levels::default();
+        for setting in load_section(dir, "lint")? {
+            if find_lint(&setting.key).is_none() {
+                return Err(format!(
+                    "{}:{}: Unknown lint '{}'",
+                    CONFIG_FILE, setting.line, setting.key
+                ));
+            }
+            match Level::parse(&setting.value) {
+                Some(level) => levels.0.push((setting.key, level)),
+                None => {
+                    return Err(format!(
+                        "{}:{}: The level of {} must be allow, warn or deny, found '{}'",
+                        CONFIG_FILE, setting.line, setting.key, setting.value
+                    ))
+                }
+            }
+        }
+        Ok(levels)
+    }
+
+    fn level(&self, id: &str) -> Level {
+        self.0
+            .iter()
+            .rev()
+            .find(|(setting, _)| setting == id)
+            .map(|(_, level)| *level)
+            .or_else(|| find_lint(id).map(|lint| lint.level))
+            .unwrap_or(Level::Warn)
+    }
+}
+
+///
+///Reads a comment such as // #[allow(unused_variable, shadowed_name)]. Returns whether it is
+///the #![...] form that covers the whole script, the level and the lint ids
+///
+fn attribute(comment: &str) -> Option<(bool, Level, Vec<String>)> {
+    let text = comment
+        .strip_prefix("//")
+        .or_else(|| comment.strip_prefix("/*")?.strip_suffix("*/"))?
+        .trim();
+    let (whole_script, text) = match text.strip_prefix("#![") {
+        Some(text) => (true, text),
+        None => (false, text.strip_prefix("#[")?),
+    };
+    let (level, ids) = text.strip_suffix(")]")?.split_once('(')?;
+    let ids = ids
+        .split(',')
+        .map(|id| id.trim().to_string())
+        .filter(|id| !id.is_empty())
+        .collect();
+    Some((whole_script, Level::parse(level.trim())?, ids))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum DeclarationKind {
+    Variable,
+    Parameter,
+    Function,
+}
+
+#[derive(Debug, Clone)]
+struct Declaration {
+    name: String,
+    // where the name is written
+    span: Span,
+    kind: DeclarationKind,
+    // the declared type, or the return type of a function
+    type_name: Option<String>,
+    used: bool,
+    // the levels where it was declared, an unused warning is only known once its scope ends
+    levels: Levels,
+}
+
+///
+///The names declared in one scope in the order they were written, keyed by name so a lookup does
+///not read every earlier declaration
+///
+#[derive(Debug, Default)]
+struct Scope {
+    declarations: Vec<Declaration>,
+    // the index of the last declaration of each name
+    names: HashMap<String, usize>,
+}
+
+impl Scope {
+    fn push(&mut self, declaration: Declaration) {
+        self.names
+            .insert(declaration.name.clone(), self.declarations.len());
+        self.declarations.push(declaration);
+    }
+
+    fn get(&self, name: &str) -> Option<&Declaration> {
+        self.names.get(name).map(|&index| &self.declarations[index])
+    }
+
+    fn get_mut(&mut self, name: &str) -> Option<&mut Declaration> {
+        self.names
+            .get(name)
+            .map(|&index| &mut self.declarations[index])
+    }
+}
+
+///
+///How the statements of a block can end early
+///
+fn ending(nodes: &[ASTNode]) -> Option<&'static str> {
+    match nodes {
+        [ASTNode::Return, ..] => Some("return"),
+        [ASTNode::FunctionCall(call), ..] if call.name == "exit" => Some("exit()"),
+        _ => None,
+    }
+}
+
+///
+///The type names the lint compares, boolean is another name for bool
+///
+fn normalize(type_name: &str) -> String {
+    match type_name.trim() {
+        "boolean" => "bool".to_string(),
+        other => other.to_string(),
+    }
+}
+
+fn is_number(type_name: &str) -> bool {
+    type_name == "int" || type_name == "float"
+}
+
+///
+///A problem found at span, it is given the kind of its level once it is reported
+///
+fn found(span: Span, message: &str) -> Diagnostic {
+    Diagnostic::new(Level::Warn.kind(), message).label(span, "")
+}
+
+///
+///The span of the first place name is written as a word in a statement that starts at the
+///line and column of start, from the char at offset on. Strings and comments are passed over
+///
+fn name_span(statement: &str, start: (usize, usize), name: &str, offset: usize) -> Option<Span> {
+    let chars: Vec<char> = statement.chars().collect();
+    let word: Vec<char> = name.chars().collect();
+    let is_word = |at: usize| {
+        chars
+            .get(at)
+            .is_some_and(|c| c.is_alphanumeric() || *c == '_')
+    };
+    let mut lexer = Lexer::standalone(statement);
+    while let Some(info) = lexer.next() {
+        let end = lexer.position();
+        let token_start = end - info.chars_read as usize;
+        if matches!(
+            info.token,
+            TokenTypes::String | TokenTypes::Char | TokenTypes::Comment
+        ) {
+            continue;
+        }
+        let found = (token_start.max(offset)..end).find(|&at| {
+            chars[at..end].starts_with(&word)
+                && (at == 0 || !is_word(at - 1))
+                && !is_word(at + word.len())
+        });
+        if let Some(at) = found {
+            return Some(Span {
+                length: word.len(),
+                ..span_in(statement, start, at)
+            });
+        }
+    }
+    None
+}
+
+struct Linter {
+    // the variables of each open scope, the innermost last
+    scopes: Vec<Scope>,
+    functions: Scope,
+    // the function whose body is being read, calling itself does not count as a use
+    current_function: Option<String>,
+    levels: Levels,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl Linter {
+    fn report(&mut self, levels: &Levels, id: &'static str, diagnostic: Diagnostic) {
+        let level = levels.level(id);
+        if level != Level::Allow {
+            self.diagnostics.push(Diagnostic {
+                kind: level.kind().to_string(),
+                ..diagnostic.code(id)
+            });
+        }
+    }
+
+    fn report_here(&mut self, id: &'static str, diagnostic: Diagnostic) {
+        let levels = self.levels.clone();
+        self.report(&levels, id, diagnostic);
+    }
+
+    fn find(&self, name: &str) -> Option<&Declaration> {
+        self.scopes
+            .iter()
+            .rev()
+            .chain(std::iter::once(&self.functions))
+            .find_map(|scope| scope.get(name))
+    }
+
+    fn declare(
+        &mut self,
+        name: &str,
+        span: Span,
+        kind: DeclarationKind,
+        type_name: Option<String>,
+    ) {
+        if name == "_" {
+            return;
+        }
+        if let Some(earlier) = self.find(name) {
+            let what = match earlier.kind {
+                DeclarationKind::Function => "function",
+                DeclarationKind::Parameter => "parameter",
+                DeclarationKind::Variable => "variable",
+            };
+            let message = format!(
+                "'{}' shadows the {} declared at line {}",
+                name, what, earlier.span.line
+            );
+            let diagnostic =
+                found(span, &message).secondary(earlier.span, &format!("the {} it hides", what));
+            self.report_here("shadowed_name", diagnostic);
+        }
+        let declaration = Declaration {
+            name: name.to_string(),
+            span,
+            kind,
+            type_name: type_name.map(|t| normalize(&t)),
+            used: false,
+            levels: self.levels.clone(),
+        };
+        self.scopes.last_mut().unwrap().push(declaration);
+    }
+
+    ///
+    ///Marks the variable, or else the function, the name refers to as used
+    ///
+    fn use_name(&mut self, name: &str) {
+        let current = self.current_function.clone();
+        let variable = self
+            .scopes
+            .iter_mut()
+            .rev()
+            .find_map(|scope| scope.get_mut(name));
+        if let Some(variable) = variable {
+            variable.used = true;
+        } else if current.as_deref() != Some(name) {
+            if let Some(function) = self.functions.get_mut(name) {
+                function.used = true;
+            }
+        }
+    }
+
+    fn close_scope(&mut self) {
+        for declaration in self.scopes.pop().unwrap_or_default().declarations {
+            if declaration.used || declaration.name.starts_with('_') {
+                continue;
+            }
+            let what = match declaration.kind {
+                DeclarationKind::Parameter => "parameter",
+                _ => "variable",
+            };
+            let message = format!("unused {} '{}'", what, declaration.name);
+            let diagnostic = found(declaration.span, &message).help(&format!(
+                "name it `_{}` if it is meant to be unused",
+                declaration.name
+            ));
+            self.report(&declaration.levels, "unused_variable", diagnostic);
+        }
+    }
+
+    ///
+    ///The type an expression has when it can be told without running it
+    ///
+    fn static_type(&self, expression: &Expression) -> Option<String> {
+        static_type(expression, &|name| {
+            self.find(name)
+                .and_then(|declaration| declaration.type_name.clone())
+                .filter(|type_name| !type_name.is_empty() && type_name != "any")
+        })
+    }
+
+    fn check_comparison(
+        &mut self,
+        span: Span,
+        left: &Expression,
+        operator: &str,
+        right: &Expression,
+    ) {
+        let (left, right) = match (self.static_type(left), self.static_type(right)) {
+            (Some(left), Some(right)) => (left, right),
+            _ => return,
+        };
+        if left == right || (is_number(&left) && is_number(&right)) {
+            return;
+        }
+        let outcome = match operator {
+            "==" => "is always false",
+            "!=" => "is always true",
+            _ => "fails at runtime",
+        };
+        let message = format!(
+            "comparing {} with {} using '{}' {}",
+            left, right, operator, outcome
+        );
+        self.report_here("mismatched_comparison", found(span, &message));
+    }
+
+    ///
+    ///Marks what an expression reads as used and checks its comparisons. Lambda parameters are
+    ///names of their own, so they are left out
+    ///
+    fn use_expression(&mut self, span: Span, expression: &Expression, lambda: &[String]) {
+        match expression {
+            Expression::Value(_) => {}
+            Expression::Variable(name) => {
+                if !lambda.contains(name) {
+                    self.use_name(name);
+                }
+            }
+            Expression::Call(name, arguments) => {
+                self.use_name(name);
+                for argument in arguments {
+                    self.use_expression(span, argument, lambda);
+                }
+            }
+            Expression::Method(receiver, name, arguments) => {
+                self.use_expression(span, receiver, lambda);
+                // a function declared in the script can be called as a method too
+                if self.functions.get(name).is_some() {
+                    self.use_name(name);
+                }
+                for argument in arguments {
+                    self.use_expression(span, argument, lambda);
+                }
+            }
+            Expression::Index(target, index) => {
+                self.use_expression(span, target, lambda);
+                self.use_expression(span, index, lambda);
+            }
+            Expression::Slice(target, start, end) => {
+                self.use_expression(span, target, lambda);
+                for bound in [start, end].into_iter().flatten() {
+                    self.use_expression(span, bound, lambda);
+                }
+            }
+            Expression::Unary(_, operand) | Expression::Cast(operand, _) => {
+                self.use_expression(span, operand, lambda)
+            }
+            Expression::Binary(left, operator, right) => {
+                if matches!(operator.as_str(), "==" | "!=" | "<" | ">" | "<=" | ">=") {
+                    self.check_comparison(span, left, operator, right);
+                }
+                self.use_expression(span, left, lambda);
+                self.use_expression(span, right, lambda);
+            }
+            Expression::Lambda(parameters, body) => {
+                let mut names = lambda.to_vec();
+                names.extend(parameters.iter().cloned());
+                self.use_expression(span, body, &names);
+            }
+            Expression::List(elements) | Expression::Tuple(elements) => {
+                for element in elements {
+                    self.use_expression(span, element, lambda);
+                }
+            }
+            Expression::Map(entries) => {
+                for (key, value) in entries {
+                    self.use_expression(span, key, lambda);
+                    self.use_expression(span, value, lambda);
+                }
+            }
+        }
+    }
+
+    ///
+    ///Reads the nodes as an expression at span, one that does not parse is left to jist check
+    ///
+    fn expression(&mut self, span: Span, nodes: &[ASTNode]) -> Option<Expression> {
+        let expression = parse_expression(nodes).ok()?;
+        self.use_expression(span, &expression, &[]);
+        Some(expression)
+    }
+
+    fn check_truncation(
+        &mut self,
+        span: Span,
+        name: &str,
+        type_name: Option<&str>,
+        value: &Expression,
+    ) {
+        if type_name == Some("int") && self.static_type(value).as_deref() == Some("float") {
+            let message = format!("float stored in int '{}' drops the fraction", name);
+            self.report_here("float_truncation", found(span, &message));
+        }
+    }
+
+    ///
+    ///Reads the condition of an if or loop, which starts at the line and column of at. statement
+    ///is the span it is reported at when its own cannot be told
+    ///
+    fn condition(&mut self, at: (usize, usize), condition: &str, statement: Span, is_if: bool) {
+        let Ok(nodes) = statement_nodes(condition) else {
+            return;
+        };
+        let spans = node_spans(condition, at, &nodes, statement);
+        let span = expression_span(&nodes, &spans).unwrap_or(statement);
+        let expression = match self.expression(span, &nodes) {
+            Some(expression) => expression,
+            None => return,
+        };
+        // any other constant is not a bool and is left to the type check
+        if let (true, Ok(Some(BaseTypes::Bool(always)))) = (is_if, constant_value(&expression)) {
+            let message = format!("if condition is always {}", always);
+            self.report_here("constant_condition", found(span, &message));
+        }
+    }
+
+    fn statements(&mut self, statements: Vec<Positioned>) {
+        let mut ended: Option<&'static str> = None;
+        for (start, statement) in statements {
+            let (comments, code) = split_leading_comments(&statement);
+            let saved = self.levels.clone();
+            for (span, comment) in comment_spans(&statement, start, &comments) {
+                // the whole script attributes were read before
+                if let Some((false, level, ids)) = attribute(comment) {
+                    self.apply_attribute(span, level, ids);
+                }
+            }
+
+            let span = code_span(&statement, start);
+            if let Ok(nodes) = statement_nodes(code) {
+                if let Some(what) = ended.take() {
+                    let message = format!("unreachable code after {}", what);
+                    self.report_here("unreachable_code", found(span, &message));
+                    // reported once for the rest of the block
+                    ended = Some("");
+                }
+                self.statement(span, code, &nodes);
+                if ended.is_none() {
+                    ended = ending(&nodes);
+                }
+            }
+            self.levels = saved;
+        }
+    }
+
+    fn apply_attribute(&mut self, span: Span, level: Level, ids: Vec<String>) {
+        for id in ids {
+            if find_lint(&id).is_some() {
+                self.levels.0.push((id, level));
+            } else {
+                let message = format!("unknown lint '{}'", id);
+                self.report_here("unknown_lint", found(span, &message));
+            }
+        }
+    }
+
+    ///
+    ///Reads the block of a loop or function statement that starts at span
+    ///
+    fn block(&mut self, start: Span, statement: &str, declarations: Scope) {
+        match block(statement, start.line) {
+            Some(block) => {
+                let body = span_in(statement, (start.line, start.column), block.body_start);
+                self.body(&block.body, body, declarations)
+            }
+            None => {
+                self.scopes.push(declarations);
+                self.close_scope();
+            }
+        }
+    }
+
+    ///
+    ///Reads the statements of a block that starts at the line and column of span
+    ///
+    fn body(&mut self, body: &str, span: Span, declarations: Scope) {
+        self.scopes.push(declarations);
+        if let Ok(statements) = positioned_statements(body, span.line, span.column) {
+            self.statements(statements);
+        }
+        self.close_scope();
+    }
+
+    ///
+    ///Reads one statement, start is the span of its code on the line it starts on
+    ///
+    fn statement(&mut self, start: Span, statement: &str, nodes: &[ASTNode]) {
+        let position = (start.line, start.column);
+        let spans = node_spans(statement, position, nodes, start);
+        let assignment = nodes
+            .iter()
+            .position(|node| matches!(node, ASTNode::AssignmentOperator(_)));
+        let value = assignment.map(|position| &nodes[position + 1..]);
+        let value_span = assignment
+            .and_then(|position| expression_span(&nodes[position + 1..], &spans[position + 1..]))
+            .unwrap_or(start);
+        let name_at = |name: &str, offset: usize| {
+            name_span(statement, position, name, offset).unwrap_or(start)
+        };
+
+        match nodes.first() {
+            Some(ASTNode::Variable(variable)) => {
+                let type_name = nodes.iter().find_map(|node| match node {
+                    ASTNode::VariableType(t) => Some(normalize(&t.value)),
+                    _ => None,
+                });
+                if let Some(value) = value.and_then(|value| self.expression(value_span, value)) {
+                    let name = &variable.value;
+                    self.check_truncation(value_span, name, type_name.as_deref(), &value);
+                }
+                let span = name_at(&variable.value, 0);
+                self.declare(&variable.value, span, DeclarationKind::Variable, type_name);
+            }
+            Some(ASTNode::Collection(collection)) => {
+                if let Some(value) = value {
+                    self.expression(value_span, value);
+                }
+                let type_name = declared_type(collection);
+                let span = name_at(&collection.name, 0);
+                self.declare(
+                    &collection.name,
+                    span,
+                    DeclarationKind::Variable,
+                    Some(type_name),
+                );
+            }
+            Some(ASTNode::Destructure(destructure)) => {
+                if let Some(value) = value {
+                    self.expression(value_span, value);
+                }
+                for name in &destructure.names {
+                    self.declare(name, name_at(name, 0), DeclarationKind::Variable, None);
+                }
+            }
+            Some(ASTNode::VariableCall(call)) if assignment.is_some() => {
+                let target = &nodes[..assignment.unwrap()];
+                let target_span = expression_span(target, &spans).unwrap_or(start);
+                let compound = !matches!(
+                    &nodes[assignment.unwrap()],
+                    ASTNode::AssignmentOperator(a) if a.operator == "="
+                );
+                if let Some(value) = value.and_then(|value| self.expression(value_span, value)) {
+                    let type_name = self.find(&call.name).and_then(|d| d.type_name.clone());
+                    if target.len() == 1 && !compound {
+                        let name = &call.name;
+                        self.check_truncation(value_span, name, type_name.as_deref(), &value);
+                    }
+                }
+                // writing to a variable is not reading it, but x += 1 and an index into it are
+                if target.len() > 1 || compound {
+                    self.expression(target_span, target);
+                }
+            }
+            Some(ASTNode::VariableCall(_)) if postfix(nodes).is_some() => {
+                self.expression(start, postfix(nodes).unwrap());
+            }
+            Some(ASTNode::VariableCall(_) | ASTNode::FunctionCall(_)) => {
+                let span = expression_span(nodes, &spans).unwrap_or(start);
+                self.expression(span, nodes);
+            }
+            Some(ASTNode::Return) if !matches!(&nodes[1..], [] | [ASTNode::SemiColon]) => {
+                let span = expression_span(&nodes[1..], &spans[1..]).unwrap_or(start);
+                self.expression(span, &nodes[1..]);
+            }
+            Some(ASTNode::If(_) | ASTNode::Try) => {
+                // every branch of the chain has a block with its own scope
+                for branch in branches(statement, start.line).unwrap_or_default() {
+                    let mut declarations = Scope::default();
+                    match &branch.keyword {
+                        TokenTypes::If {
+                            statement: condition,
+                        }
+                        | TokenTypes::Elif {
+                            statement: condition,
+                        } => {
+                            let at = condition_start(statement, position, branch.start);
+                            self.condition(at, condition, start, true);
+                        }
+                        TokenTypes::Catch => {
+                            if let Some(name) = &branch.name {
+                                self.scopes.push(Scope::default());
+                                let span = name_at(name, branch.start);
+                                self.declare(name, span, DeclarationKind::Variable, None);
+                                declarations = self.scopes.pop().unwrap();
+                            }
+                        }
+                        _ => {}
+                    }
+                    let body = span_in(statement, position, branch.body_start);
+                    self.body(&branch.body, body, declarations);
+                }
+            }
+            Some(ASTNode::While(node)) => {
+                let at = condition_start(statement, position, 0);
+                self.condition(at, &node.condition, start, false);
+                self.block(start, statement, Scope::default());
+            }
+            Some(ASTNode::For(node)) => {
+                // for (name in iterable)
+                let (name, iterable) = node
+                    .condition
+                    .split_once(" in ")
+                    .unwrap_or(("", &node.condition));
+                let at = advance(
+                    condition_start(statement, position, 0),
+                    &format!("{} in ", name),
+                );
+                self.condition(at, iterable, start, false);
+                self.scopes.push(Scope::default());
+                let span = name_at(name.trim(), 0);
+                self.declare(name.trim(), span, DeclarationKind::Variable, None);
+                let declarations = self.scopes.pop().unwrap();
+                self.block(start, statement, declarations);
+            }
+            Some(ASTNode::Function(function)) => {
+                if let Some(declaration) = self.functions.get_mut(&function.name) {
+                    declaration.levels = self.levels.clone();
+                }
+                let outer = self.current_function.replace(function.name.clone());
+                self.scopes.push(Scope::default());
+                // the parameters are written after the name
+                let parameters = statement.find('(').unwrap_or(0);
+                let parameters = statement[..parameters].chars().count();
+                for (name, type_name, _) in &function.arguments {
+                    self.declare(
+                        name,
+                        name_at(name, parameters),
+                        DeclarationKind::Parameter,
+                        Some(type_name.clone()),
+                    );
+                }
+                let declarations = self.scopes.pop().unwrap();
+                self.block(start, statement, declarations);
+                self.current_function = outer;
+            }
+            _ => {}
+        }
+    }
+}
+
+///
+///The comments in front of a statement that starts at the line and column of start, each with
+///its span
+///
+fn comment_spans<'a>(
+    statement: &str,
+    start: (usize, usize),
+    comments: &[&'a str],
+) -> Vec<(Span, &'a str)> {
+    let mut read = 0;
+    comments
+        .iter()
+        .map(|comment| {
+            // only whitespace is between the comments, the next one is the first match
+            let at = read + statement[read..].find(comment).unwrap_or(0);
+            read = at + comment.len();
+            let offset = statement[..at].chars().count();
+            (span_in(statement, start, offset), *comment)
+        })
+        .collect()
+}
+
+///
+///Lints the script with the levels from jist.toml and returns the problems found in the order
+///of where they are, each a Warning or an Error with the id of its lint as the code
+///
+pub fn lint_source(contents: &str, levels: Levels) -> Vec<Diagnostic> {
+    let mut linter = Linter {
+        scopes: vec![Scope::default()],
+        functions: Scope::default(),
+        current_function: None,
+        levels,
+        diagnostics: Vec::new(),
+    };
+
+    let statements = match positioned_statements(contents, 1, 1) {
+        Ok(statements) => statements,
+        // a script that does not split into statements is left to jist check
+        Err(_) => return Vec::new(),
+    };
+
+    // attributes for the whole script and the functions are known before the first statement,
+    // so a function can be called above the line that declares it
+    for (start, statement) in &statements {
+        let (comments, code) = split_leading_comments(statement);
+        for (span, comment) in comment_spans(statement, *start, &comments) {
+            if let Some((true, level, ids)) = attribute(comment) {
+                linter.apply_attribute(span, level, ids);
+            }
+        }
+        if let Ok(nodes) = statement_nodes(code) {
+            if let Some(ASTNode::Function(function)) = nodes.first() {
+                let span = code_span(statement, *start);
+                let position = (span.line, span.column);
+                linter.functions.push(Declaration {
+                    name: function.name.clone(),
+                    span: name_span(code, position, &function.name, 0).unwrap_or(span),
+                    kind: DeclarationKind::Function,
+                    type_name: Some(normalize(&function.return_type)),
+                    used: false,
+                    levels: linter.levels.clone(),
+                });
+            }
+        }
+    }
+
+    linter.statements(statements);
+    linter.close_scope();
+    for function in std::mem::take(&mut linter.functions).declarations {
+        if !function.used && !function.name.starts_with('_') {
+            let message = format!("unused function '{}'", function.name);
+            let diagnostic = found(function.span, &message);
+            linter.report(&function.levels, "unused_function", diagnostic);
+        }
+    }
+
+    let mut diagnostics = linter.diagnostics;
+    diagnostics
+        .sort_by_key(|diagnostic| diagnostic.location().map(|span| (span.line, span.column)));
+    diagnostics
+}
+
+#[cfg(test)]
+mod lint_tests {
+    use super::{attribute, lint_source, Level, Levels};
+
+    ///
+    ///The problems found as line:column and lint id
+    ///
+    fn lint(source: &str) -> Vec<String> {
+        lint_source(source, Levels::default())
+            .into_iter()
+            .map(|diagnostic| {
+                let span = diagnostic.location().unwrap();
+                let id = diagnostic.code.unwrap();
+                format!("{}:{} {}", span.line, span.column, id)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_unused_and_shadowed() {
+        let source = "let a: int = 1;\nlet b: int = a;\nfunc f(x: int, _y: int) -> int {\n    let b: int = 2;\n    return b;\n}\nfunc g() -> int {\n    return g();\n}\necholn(b);\n";
+        assert_eq!(
+            lint(source),
+            vec![
+                "3:6 unused_function",
+                "3:8 unused_variable",
+                "4:9 shadowed_name",
+                "7:6 unused_function",
+            ]
+        );
+        let shadowed = &lint_source(source, Levels::default())[2];
+        assert_eq!(shadowed.labels[1].span.line, 2);
+        assert_eq!(shadowed.labels[1].message, "the variable it hides");
+    }
+
+    #[test]
+    fn test_unreachable_constant_and_types() {
+        let source = "let s: string = \"a\";\nlet n: float = 2.5;\nlet i: int = n;\nif (1 > 2) {\n    echoln(s);\n}\nfunc f() -> int {\n    return 1;\n    echoln(\"never\");\n}\nif (s == i) {\n    echoln(f());\n}\n";
+        assert_eq!(
+            lint(source),
+            vec![
+                "3:14 float_truncation",
+                "4:5 constant_condition",
+                "9:5 unreachable_code",
+                "11:5 mismatched_comparison",
+            ]
+        );
+        let diagnostics = lint_source(source, Levels::default());
+        assert_eq!(diagnostics[3].kind, "Error");
+        assert_eq!(diagnostics[3].location().unwrap().length, 6);
+    }
+
+    #[test]
+    fn test_only_bool_constants_and_real_endings() {
+        // an int condition is a type error and break is not a statement of the language
+        let source = "if (0) {\n    echoln(1);\n}\nif (!false) {\n    break;\n    echoln(2);\n}\n";
+        let diagnostics = lint_source(source, Levels::default());
+        let messages: Vec<_> = diagnostics.iter().map(|d| d.message.as_str()).collect();
+        assert_eq!(messages, vec!["if condition is always true"]);
+        assert_eq!(diagnostics[0].location().unwrap().line, 4);
+    }
+
+    #[test]
+    fn test_attributes() {
+        assert_eq!(
+            attribute("// #[allow(unused_variable, shadowed_name)]"),
+            Some((
+                false,
+                Level::Allow,
+                vec!["unused_variable".to_string(), "shadowed_name".to_string()]
+            ))
+        );
+        assert_eq!(
+            attribute("/* #![deny(float_truncation)] */"),
+            Some((true, Level::Deny, vec!["float_truncation".to_string()]))
+        );
+        assert_eq!(attribute("// allow(unused_variable)"), None);
+
+        let source = "// #![deny(unused_variable)]\n// #[allow(unused_variable)]\nlet a: int = 1;\nlet b: int = 2;\n// #[allow(no_such_lint)]\nlet c: int = b;\necholn(c);\n";
+        let diagnostics = lint_source(source, Levels::default());
+        let found: Vec<_> = diagnostics
+            .iter()
+            .map(|d| (d.location().unwrap(), d.kind.as_str()))
+            .map(|(span, kind)| (span.line, span.column, kind))
+            .collect();
+        assert_eq!(found, vec![(5, 1, "Warning")]);
+        assert_eq!(diagnostics[0].code.as_deref(), Some("unknown_lint"));
+
+        let source = "let a: int = 1;\n";
+        let levels = Levels(vec![("unused_variable".to_string(), Level::Deny)]);
+        assert_eq!(lint_source(source, levels)[0].kind, "Error");
+    }
+}
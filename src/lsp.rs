@@ -0,0 +1,1115 @@
+/*
+* This file holds jist lsp, a language server that editors start and talk to in JSON-RPC over
+* stdin and stdout. It keeps the open scripts in memory and answers from them: the problems jist
+* check and jist lint find, highlighting from the highlighter's styles, where a variable or
+* function is declared and used, its type on hover, completion of built-in and declared names
+* and formatting with jist fmt
+*/
+use crate::commands::check_source;
+use crate::config::config_dir;
+use crate::formatter::{format_source, is_keyword, lex, FormatConfig, Kind, Token, KEYWORDS};
+use crate::function::functions::FunctionTypes;
+use crate::function_map::FUNCTIONS;
+use crate::highlighter::{get_highlighting_style, HighlightStyle};
+use crate::json::{object, Json};
+use crate::lint::{lint_source, Level, Levels};
+use crate::statement_tokenizer::tokenizer::tokenizers::tokenize;
+use crate::statements::split_statements;
+use crate::syntax::check_syntax;
+use std::collections::HashMap;
+use std::io::{self, BufRead, Write};
+
+// the semantic token types, a highlighter style is sent as the index of its type
+const TOKEN_TYPES: [&str; 6] = [
+    "number", "string", "operator", "keyword", "function", "comment",
+];
+
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const REQUEST_FAILED: i64 = -32803;
+
+///
+///The semantic token type of a highlighter style, None for text that is not highlighted
+///
+fn semantic_type(style: &HighlightStyle) -> Option<usize> {
+    let name = match style.foreground.as_str() {
+        "blue" => "number",
+        "green" | "purple" => "string",
+        "red" => "operator",
+        "cyan" => "keyword",
+        "orange" => "function",
+        "gray" => "comment",
+        _ => return None,
+    };
+    TOKEN_TYPES.iter().position(|t| *t == name)
+}
+
+///
+///The signature of a built-in function, as hover and completion show it
+///
+fn builtin_signature(name: &str, function: &FunctionTypes) -> String {
+    let (parameters, result) = match function {
+        FunctionTypes::FloatFn(_) => ("float", ""),
+        FunctionTypes::DoubleFloatFn(_) => ("float, float", "float"),
+        FunctionTypes::SingleFloatFn(_) => ("float", "float"),
+        FunctionTypes::NoArgFloatFn(_) => ("", "float"),
+        FunctionTypes::StringFn(_) | FunctionTypes::EchoFn(_) => ("any", ""),
+        FunctionTypes::DoubleStringFn(_) => ("string, string", "string"),
+        FunctionTypes::SingleStringFn(_) => ("string", "string"),
+        FunctionTypes::StringToIntFn(_) => ("string", "int"),
+        FunctionTypes::StringToCharsFn(_) => ("string", "array<char>"),
+        FunctionTypes::DoubleStringToBoolFn(_) => ("string, string", "bool"),
+        FunctionTypes::DoubleStringToIntFn(_) => ("string, string", "int"),
+        FunctionTypes::DoubleStringToListFn(_) => ("string, string", "array<string>"),
+        FunctionTypes::TripleStringFn(_) => ("string, string, string", "string"),
+        FunctionTypes::StringIntFn(_) => ("string, int", "string"),
+        FunctionTypes::StringIntIntFn(_) => ("string, int, int", "string"),
+        FunctionTypes::StringIntStringFn(_) => ("string, int, string", "string"),
+        FunctionTypes::ParseIntFn(_) => ("string", "int"),
+        FunctionTypes::ParseFloatFn(_) => ("string", "float"),
+    };
+    match result {
+        "" => format!("func {}({})", name, parameters),
+        _ => format!("func {}({}) -> {}", name, parameters, result),
+    }
+}
+
+fn builtins() -> Vec<(String, String)> {
+    let functions = FUNCTIONS.lock().unwrap();
+    let mut builtins: Vec<(String, String)> = functions
+        .iter()
+        .map(|(name, function)| (name.to_string(), builtin_signature(name, function)))
+        .collect();
+    builtins.sort();
+    builtins
+}
+
+///
+///Where each line starts, counted in chars
+///
+fn line_starts(source: &str) -> Vec<usize> {
+    let mut starts = vec![0];
+    for (i, c) in source.chars().enumerate() {
+        if c == '\n' {
+            starts.push(i + 1);
+        }
+    }
+    starts
+}
+
+///
+///The highlighted spans of the script as (line, column, length, type), lines from 0 and columns
+///and lengths in UTF-16 units. Each statement is tokenized like jist highlight does and every
+///token is found in the text after the one before it. A span over several lines is split into
+///one span per line, as editors expect
+///
+pub fn semantic_spans(source: &str) -> Vec<(usize, usize, usize, usize)> {
+    let chars: Vec<char> = source.chars().collect();
+    let mut found = Vec::new();
+    statement_spans(source, 0, &mut found);
+
+    let starts = line_starts(source);
+    let mut spans = Vec::new();
+    for (start, end, token_type) in found {
+        let mut line = starts.partition_point(|s| *s <= start) - 1;
+        let mut from = start;
+        while from < end {
+            let line_end = starts.get(line + 1).map_or(chars.len(), |s| s - 1).min(end);
+            let column: usize = chars[starts[line]..from]
+                .iter()
+                .map(|c| c.len_utf16())
+                .sum();
+            let length: usize = chars[from..line_end].iter().map(|c| c.len_utf16()).sum();
+            if length > 0 {
+                spans.push((line, column, length, token_type));
+            }
+            line += 1;
+            from = starts.get(line).copied().unwrap_or(end);
+        }
+    }
+    spans.sort();
+    spans
+}
+
+///
+///Adds the highlighted spans of the statements in source, which starts at char offset base of
+///the script, as (start, end, type) char offsets. A function body is split into statements like
+///the interpreter does before tokenizing it
+///
+fn statement_spans(source: &str, base: usize, spans: &mut Vec<(usize, usize, usize)>) {
+    let statements = split_statements(source).unwrap_or_default();
+    let mut offset = base;
+    for statement in statements {
+        let chars: Vec<char> = statement.chars().collect();
+        let code = statement.trim_start();
+        let is_function = lex(code).ok().is_some_and(|tokens| {
+            let first = tokens.iter().find(|token| token.kind != Kind::Comment);
+            first.is_some_and(|token| token.text == "func")
+        });
+        match (is_function, chars.iter().position(|c| *c == '{')) {
+            (true, Some(open)) => {
+                let close = chars.iter().rposition(|c| *c == '}').unwrap_or(chars.len());
+                let header: String = chars[..open].iter().collect();
+                token_spans(&header, offset, spans);
+                let body: String = chars[open + 1..close].iter().collect();
+                statement_spans(&body, offset + open + 1, spans);
+            }
+            _ => token_spans(&statement, offset, spans),
+        }
+        offset += chars.len();
+    }
+}
+
+fn token_spans(text: &str, base: usize, spans: &mut Vec<(usize, usize, usize)>) {
+    let chars: Vec<char> = text.chars().collect();
+    let mut cursor = 0;
+    for info in tokenize(text.to_string()) {
+        let value: Vec<char> = info.value.chars().collect();
+        if value.is_empty() || value.len() > chars.len() {
+            continue;
+        }
+        let word = |c: Option<&char>| c.is_some_and(|c| c.is_alphanumeric() || *c == '_');
+        // a name is only found as a whole word, so f is not found in func
+        let whole = |i: usize| {
+            let joined_before = word(value.first()) && i > 0 && word(chars.get(i - 1));
+            let joined_after = word(value.last()) && word(chars.get(i + value.len()));
+            !joined_before && !joined_after
+        };
+        let start = match (cursor..=chars.len() - value.len())
+            .find(|&i| chars[i..i + value.len()] == value[..] && whole(i))
+        {
+            Some(start) => start,
+            // a token whose value is not its text, such as the condition of an if
+            None => continue,
+        };
+        cursor = start + value.len();
+        if let Some(token_type) = semantic_type(&get_highlighting_style(&info.token)) {
+            spans.push((base + start, base + cursor, token_type));
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SymbolKind {
+    Variable,
+    Parameter,
+    Function,
+}
+
+///
+///A declared name. It can be used by the tokens from visible_from to visible_to, except a
+///function which can be called anywhere in the script
+///
+#[derive(Debug, Clone, PartialEq)]
+pub struct Symbol {
+    pub name: String,
+    pub kind: SymbolKind,
+    // the declaration as hover shows it, such as `let total: int`
+    pub detail: String,
+    // the token of the name where it is declared
+    pub token: usize,
+    visible_from: usize,
+    visible_to: usize,
+}
+
+///
+///The declarations of a script and the symbol each name token refers to
+///
+pub struct Index {
+    pub tokens: Vec<Token>,
+    pub symbols: Vec<Symbol>,
+    // for each token, the symbol its name refers to
+    pub resolved: Vec<Option<usize>>,
+}
+
+///
+///The text of the type tokens from `from` up to a token at depth 0 that ends the type
+///
+fn type_text(tokens: &[Token], from: usize, ends: &[&str]) -> (String, usize) {
+    let mut text = String::new();
+    let mut depth = 0;
+    let mut i = from;
+    while let Some(token) = tokens.get(i) {
+        if depth == 0 && (ends.contains(&token.text.as_str()) || token.kind == Kind::Semicolon) {
+            break;
+        }
+        match token.text.as_str() {
+            "<" | "(" | "[" => depth += 1,
+            ">" | ")" | "]" => depth -= 1,
+            _ => {}
+        }
+        if depth < 0 {
+            break;
+        }
+        text.push_str(&token.text);
+        if token.kind == Kind::Comma {
+            text.push(' ');
+        }
+        i += 1;
+    }
+    (text, i)
+}
+
+impl Index {
+    pub fn new(source: &str) -> Index {
+        let tokens: Vec<Token> = lex(source)
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|token| token.kind != Kind::Comment)
+            .collect();
+
+        // the closing brace of every opening brace, and the innermost block around each token
+        let mut closes = vec![tokens.len(); tokens.len()];
+        let mut enclosing = vec![tokens.len(); tokens.len()];
+        let mut open = Vec::new();
+        for (i, token) in tokens.iter().enumerate() {
+            match token.text.as_str() {
+                "{" => open.push(i),
+                "}" => {
+                    if let Some(start) = open.pop() {
+                        closes[start] = i;
+                        for block in enclosing.iter_mut().take(i).skip(start + 1) {
+                            if *block == tokens.len() {
+                                *block = i;
+                            }
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        let next_brace = |from: usize| (from..tokens.len()).find(|&i| tokens[i].text == "{");
+        let statement_end = |from: usize| {
+            (from..tokens.len())
+                .find(|&i| tokens[i].kind == Kind::Semicolon)
+                .unwrap_or(tokens.len())
+        };
+        let is_name = |i: usize| {
+            tokens
+                .get(i)
+                .is_some_and(|t| t.kind == Kind::Word && !is_keyword(&t.text))
+        };
+
+        let mut symbols = Vec::new();
+        let mut add = |name: usize, kind: SymbolKind, detail: String, from: usize, to: usize| {
+            symbols.push(Symbol {
+                name: tokens[name].text.clone(),
+                kind,
+                detail,
+                token: name,
+                visible_from: from,
+                visible_to: to,
+            });
+        };
+
+        for i in 0..tokens.len() {
+            match tokens[i].text.as_str() {
+                keyword @ ("let" | "const") => {
+                    // let mut x: int = ..., the name comes after the mut
+                    let mutable =
+                        keyword == "let" && tokens.get(i + 1).is_some_and(|t| t.text == "mut");
+                    let (keyword, name) = if mutable {
+                        ("let mut", i + 2)
+                    } else {
+                        (keyword, i + 1)
+                    };
+                    if is_name(name) {
+                        let detail = match tokens.get(name + 1) {
+                            Some(colon) if colon.kind == Kind::Colon => {
+                                let (type_name, _) = type_text(&tokens, name + 2, &["="]);
+                                format!("{} {}: {}", keyword, tokens[name].text, type_name)
+                            }
+                            _ => format!("{} {}", keyword, tokens[name].text),
+                        };
+                        let kind = SymbolKind::Variable;
+                        add(name, kind, detail, statement_end(i), enclosing[i]);
+                    } else if tokens.get(name).is_some_and(|t| t.text == "(") {
+                        // let (a, b): (int, string) = ...
+                        let mut j = name + 1;
+                        while let Some(token) = tokens.get(j).filter(|t| t.text != ")") {
+                            if is_name(j) {
+                                let detail = format!("{} {}", keyword, token.text);
+                                add(
+                                    j,
+                                    SymbolKind::Variable,
+                                    detail,
+                                    statement_end(i),
+                                    enclosing[i],
+                                );
+                            }
+                            j += 1;
+                        }
+                    }
+                }
+                "func" if is_name(i + 1) => {
+                    let mut parameters = Vec::new();
+                    let mut j = i + 3;
+                    while j < tokens.len() && tokens[j].text != ")" && tokens[j].text != "{" {
+                        if is_name(j) && tokens.get(j + 1).is_some_and(|t| t.kind == Kind::Colon) {
+                            let (type_name, end) = type_text(&tokens, j + 2, &[",", "=", ")"]);
+                            parameters.push((j, format!("{}: {}", tokens[j].text, type_name)));
+                            j = end;
+                        } else {
+                            j += 1;
+                        }
+                    }
+                    let return_type = match tokens.get(j + 1) {
+                        Some(arrow) if arrow.text == "->" => {
+                            format!(" -> {}", type_text(&tokens, j + 2, &["{"]).0)
+                        }
+                        _ => String::new(),
+                    };
+                    let list: Vec<&str> = parameters.iter().map(|(_, p)| p.as_str()).collect();
+                    let detail = format!(
+                        "func {}({}){}",
+                        tokens[i + 1].text,
+                        list.join(", "),
+                        return_type
+                    );
+                    add(i + 1, SymbolKind::Function, detail, 0, tokens.len());
+                    if let Some(body) = next_brace(j) {
+                        for (name, detail) in parameters {
+                            add(name, SymbolKind::Parameter, detail, body, closes[body]);
+                        }
+                    }
+                }
+                // for (item in items) and catch error or catch (error)
+                keyword @ ("for" | "catch") => {
+                    let name = if tokens.get(i + 1).is_some_and(|t| t.text == "(") {
+                        i + 2
+                    } else {
+                        i + 1
+                    };
+                    if let (true, Some(body)) = (is_name(name), next_brace(name)) {
+                        let detail = format!("{} {}", keyword, tokens[name].text);
+                        add(name, SymbolKind::Variable, detail, body, closes[body]);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        // a name refers to the closest declaration before it that it can see, or else to a
+        // function declared further down
+        let mut resolved = vec![None; tokens.len()];
+        for (i, token) in tokens.iter().enumerate() {
+            // a field or method after a . is not a declared name, a range after .. is
+            if !is_name(i) || (i > 0 && tokens[i - 1].text == ".") {
+                continue;
+            }
+            if let Some(own) = symbols.iter().position(|s| s.token == i) {
+                resolved[i] = Some(own);
+                continue;
+            }
+            resolved[i] = symbols
+                .iter()
+                .enumerate()
+                .filter(|(_, s)| s.name == token.text && s.visible_from <= i && i <= s.visible_to)
+                .max_by_key(|(_, s)| (s.token < i, s.token))
+                .map(|(position, _)| position);
+        }
+
+        Index {
+            tokens,
+            symbols,
+            resolved,
+        }
+    }
+
+    ///
+    ///The token at a position, lines from 0 like editors count
+    ///
+    pub fn token_at(&self, line: usize, character: usize) -> Option<usize> {
+        let span = |token: &Token| {
+            let length: usize = token.text.chars().map(|c| c.len_utf16()).sum();
+            (token.column, token.column + length)
+        };
+        let on_line = |i: &usize| self.tokens[*i].line == line + 1;
+        let inside = (0..self.tokens.len()).filter(on_line).find(|&i| {
+            let (start, end) = span(&self.tokens[i]);
+            start <= character && character < end
+        });
+        // the cursor just after a name, as when it is being typed
+        inside.or_else(|| {
+            (0..self.tokens.len()).filter(on_line).find(|&i| {
+                self.tokens[i].kind == Kind::Word && span(&self.tokens[i]).1 == character
+            })
+        })
+    }
+
+    ///
+    ///The symbol of the name at a position
+    ///
+    pub fn symbol_at(&self, line: usize, character: usize) -> Option<usize> {
+        self.resolved[self.token_at(line, character)?]
+    }
+
+    ///
+    ///The tokens that name the symbol, its declaration first
+    ///
+    pub fn references(&self, symbol: usize) -> Vec<usize> {
+        let mut tokens: Vec<usize> = (0..self.tokens.len())
+            .filter(|&i| self.resolved[i] == Some(symbol))
+            .collect();
+        tokens.sort_by_key(|&i| i != self.symbols[symbol].token);
+        tokens
+    }
+
+    ///
+    ///The symbols that can be used at a position, the closest one for each name
+    ///
+    pub fn visible_at(&self, line: usize, character: usize) -> Vec<&Symbol> {
+        let at = self
+            .tokens
+            .iter()
+            .position(|t| (t.line, t.column) >= (line + 1, character))
+            .unwrap_or(self.tokens.len());
+        let mut visible: Vec<&Symbol> = Vec::new();
+        for symbol in &self.symbols {
+            let in_scope = symbol.kind == SymbolKind::Function
+                || (symbol.token < at && symbol.visible_from <= at && at <= symbol.visible_to);
+            if !in_scope {
+                continue;
+            }
+            match visible.iter_mut().find(|s| s.name == symbol.name) {
+                Some(earlier) if earlier.token < symbol.token => *earlier = symbol,
+                Some(_) => {}
+                None => visible.push(symbol),
+            }
+        }
+        visible
+    }
+
+    fn range(&self, token: usize) -> Json {
+        let token = &self.tokens[token];
+        let length: usize = token.text.chars().map(|c| c.len_utf16()).sum();
+        range(token.line - 1, token.column, token.column + length)
+    }
+}
+
+fn position(line: usize, character: usize) -> Json {
+    object(vec![("line", line.into()), ("character", character.into())])
+}
+
+fn range(line: usize, start: usize, end: usize) -> Json {
+    object(vec![
+        ("start", position(line, start)),
+        ("end", position(line, end)),
+    ])
+}
+
+///
+///The path of a file:// uri, with its %-escapes decoded
+///
+fn uri_path(uri: &str) -> Option<String> {
+    let path = uri.strip_prefix("file://")?;
+    let bytes = path.as_bytes();
+    let mut decoded = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        let escape = std::str::from_utf8(bytes.get(i + 1..i + 3).unwrap_or_default()).ok();
+        match escape.and_then(|hex| u8::from_str_radix(hex, 16).ok()) {
+            Some(byte) if bytes[i] == b'%' => {
+                decoded.push(byte);
+                i += 3;
+            }
+            _ => {
+                decoded.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8(decoded).ok()
+}
+
+///
+///The problems in a script: syntax and type errors from jist check and what jist lint finds,
+///each covering the code it is about
+///
+pub fn diagnostics(uri: &str, source: &str) -> Vec<Json> {
+    let lines: Vec<&str> = source.lines().collect();
+    // spans count characters, the client counts UTF-16 code units
+    let span_range = |line: usize, column: usize, length: usize| {
+        let text = lines.get(line - 1).copied().unwrap_or("");
+        let width = |count: usize| -> usize { text.chars().take(count).map(char::len_utf16).sum() };
+        range(line - 1, width(column - 1), width(column - 1 + length))
+    };
+
+    let mut diagnostics = Vec::new();
+    let syntax_errors = check_syntax(source);
+    for error in &syntax_errors {
+        let span = error.span;
+        diagnostics.push(object(vec![
+            ("range", span_range(span.line, span.column, span.length)),
+            ("severity", 1usize.into()),
+            ("code", error.code.into()),
+            ("source", "jist".into()),
+            ("message", error.message.clone().into()),
+        ]));
+    }
+    // the statements cannot be checked further until the syntax is right
+    let checked = if syntax_errors.is_empty() {
+        check_source(source)
+    } else {
+        Vec::new()
+    };
+    for problem in checked {
+        let Some(span) = problem.location() else {
+            continue;
+        };
+        diagnostics.push(object(vec![
+            ("range", span_range(span.line, span.column, span.length)),
+            ("severity", 1usize.into()),
+            ("source", "jist".into()),
+            (
+                "message",
+                format!("{}: {}", problem.kind, problem.message).into(),
+            ),
+        ]));
+    }
+    let path = uri_path(uri);
+    let levels = Levels::load(&config_dir(path.as_deref())).unwrap_or_default();
+    for diagnostic in lint_source(source, levels) {
+        let severity: usize = if diagnostic.kind == Level::Deny.kind() {
+            1
+        } else {
+            2
+        };
+        let Some(span) = diagnostic.location() else {
+            continue;
+        };
+        diagnostics.push(object(vec![
+            ("range", span_range(span.line, span.column, span.length)),
+            ("severity", severity.into()),
+            ("code", diagnostic.code.unwrap_or_default().into()),
+            ("source", "jist lint".into()),
+            ("message", diagnostic.message.into()),
+        ]));
+    }
+    diagnostics
+}
+
+fn capabilities() -> Json {
+    let token_types: Vec<Json> = TOKEN_TYPES.iter().map(|t| Json::from(*t)).collect();
+    object(vec![
+        (
+            "capabilities",
+            object(vec![
+                // the whole script is sent on every change
+                ("textDocumentSync", 1usize.into()),
+                ("hoverProvider", true.into()),
+                ("definitionProvider", true.into()),
+                ("referencesProvider", true.into()),
+                ("completionProvider", object(vec![])),
+                ("documentFormattingProvider", true.into()),
+                (
+                    "semanticTokensProvider",
+                    object(vec![
+                        (
+                            "legend",
+                            object(vec![
+                                ("tokenTypes", token_types.into()),
+                                ("tokenModifiers", Json::Array(Vec::new())),
+                            ]),
+                        ),
+                        ("full", true.into()),
+                    ]),
+                ),
+            ]),
+        ),
+        (
+            "serverInfo",
+            object(vec![
+                ("name", "jist".into()),
+                ("version", env!("CARGO_PKG_VERSION").into()),
+            ]),
+        ),
+    ])
+}
+
+///
+///The state of the server, the scripts the editor has open by uri
+///
+#[derive(Default)]
+pub struct Server {
+    documents: HashMap<String, String>,
+    shut_down: bool,
+}
+
+fn response(id: &Json, result: Json) -> Json {
+    object(vec![
+        ("jsonrpc", "2.0".into()),
+        ("id", id.clone()),
+        ("result", result),
+    ])
+}
+
+fn error_response(id: &Json, code: i64, message: String) -> Json {
+    object(vec![
+        ("jsonrpc", "2.0".into()),
+        ("id", id.clone()),
+        (
+            "error",
+            object(vec![
+                ("code", Json::Number(code as f64)),
+                ("message", message.into()),
+            ]),
+        ),
+    ])
+}
+
+fn notification(method: &str, params: Json) -> Json {
+    object(vec![
+        ("jsonrpc", "2.0".into()),
+        ("method", method.into()),
+        ("params", params),
+    ])
+}
+
+fn publish(uri: &str, diagnostics: Vec<Json>) -> Json {
+    notification(
+        "textDocument/publishDiagnostics",
+        object(vec![
+            ("uri", uri.into()),
+            ("diagnostics", diagnostics.into()),
+        ]),
+    )
+}
+
+impl Server {
+    pub fn shut_down(&self) -> bool {
+        self.shut_down
+    }
+
+    ///
+    ///Answers one message from the editor. Returns the messages to send back, the response to a
+    ///request and the notifications it causes
+    ///
+    pub fn handle(&mut self, message: &Json) -> Vec<Json> {
+        let method = message.get("method").as_str().unwrap_or("");
+        let id = message.get("id");
+        let params = message.get("params");
+        let uri = params.get("textDocument").get("uri").as_str().unwrap_or("");
+        let is_request = *id != Json::Null;
+
+        if self.shut_down && is_request {
+            let message = "The server is shut down".to_string();
+            return vec![error_response(id, INVALID_REQUEST, message)];
+        }
+
+        let result = match method {
+            "initialize" => capabilities(),
+            "shutdown" => {
+                self.shut_down = true;
+                Json::Null
+            }
+            "textDocument/didOpen" | "textDocument/didChange" => {
+                let text = match method {
+                    "textDocument/didOpen" => params.get("textDocument").get("text"),
+                    _ => params
+                        .get("contentChanges")
+                        .as_array()
+                        .last()
+                        .map_or(&Json::Null, |change| change.get("text")),
+                };
+                let text = text.as_str().unwrap_or("").to_string();
+                let diagnostics = diagnostics(uri, &text);
+                self.documents.insert(uri.to_string(), text);
+                return vec![publish(uri, diagnostics)];
+            }
+            "textDocument/didClose" => {
+                self.documents.remove(uri);
+                return vec![publish(uri, Vec::new())];
+            }
+            _ if !is_request => return Vec::new(),
+            _ => match self.documents.get(uri) {
+                Some(source) => match document_request(method, uri, source, params) {
+                    Ok(Some(result)) => result,
+                    Ok(None) => {
+                        let message = format!("Unknown method '{}'", method);
+                        return vec![error_response(id, METHOD_NOT_FOUND, message)];
+                    }
+                    Err(e) => return vec![error_response(id, REQUEST_FAILED, e)],
+                },
+                None if method.starts_with("textDocument/") => Json::Null,
+                None => {
+                    let message = format!("Unknown method '{}'", method);
+                    return vec![error_response(id, METHOD_NOT_FOUND, message)];
+                }
+            },
+        };
+        match is_request {
+            true => vec![response(id, result)],
+            false => Vec::new(),
+        }
+    }
+}
+
+///
+///Answers a request about an open script, None when the method is not one the server has
+///
+fn document_request(
+    method: &str,
+    uri: &str,
+    source: &str,
+    params: &Json,
+) -> Result<Option<Json>, String> {
+    let line = params.get("position").get("line").as_usize().unwrap_or(0);
+    let character = params
+        .get("position")
+        .get("character")
+        .as_usize()
+        .unwrap_or(0);
+    let location = |index: &Index, token: usize| {
+        object(vec![("uri", uri.into()), ("range", index.range(token))])
+    };
+
+    let result = match method {
+        "textDocument/semanticTokens/full" => {
+            // each token is sent relative to the one before it
+            let mut data = Vec::new();
+            let (mut last_line, mut last_column) = (0, 0);
+            for (line, column, length, token_type) in semantic_spans(source) {
+                let delta_column = if line == last_line {
+                    column - last_column
+                } else {
+                    column
+                };
+                for value in [line - last_line, delta_column, length, token_type, 0] {
+                    data.push(Json::from(value));
+                }
+                (last_line, last_column) = (line, column);
+            }
+            object(vec![("data", data.into())])
+        }
+        "textDocument/definition" => {
+            let index = Index::new(source);
+            match index.symbol_at(line, character) {
+                Some(symbol) => location(&index, index.symbols[symbol].token),
+                None => Json::Null,
+            }
+        }
+        "textDocument/references" => {
+            let index = Index::new(source);
+            let with_declaration = params
+                .get("context")
+                .get("includeDeclaration")
+                .as_bool()
+                .unwrap_or(true);
+            match index.symbol_at(line, character) {
+                Some(symbol) => index
+                    .references(symbol)
+                    .into_iter()
+                    .filter(|&token| with_declaration || token != index.symbols[symbol].token)
+                    .map(|token| location(&index, token))
+                    .collect::<Vec<Json>>()
+                    .into(),
+                None => Json::Null,
+            }
+        }
+        "textDocument/hover" => {
+            let index = Index::new(source);
+            let token = index.token_at(line, character);
+            let value = token.and_then(|token| match index.resolved[token] {
+                Some(symbol) => Some(format!("```jist\n{}\n```", index.symbols[symbol].detail)),
+                None => builtins()
+                    .into_iter()
+                    .find(|(name, _)| *name == index.tokens[token].text)
+                    .map(|(_, signature)| {
+                        format!("```jist\n{}\n```\nBuilt-in function", signature)
+                    }),
+            });
+            match (value, token) {
+                (Some(value), Some(token)) => object(vec![
+                    (
+                        "contents",
+                        object(vec![("kind", "markdown".into()), ("value", value.into())]),
+                    ),
+                    ("range", index.range(token)),
+                ]),
+                _ => Json::Null,
+            }
+        }
+        "textDocument/completion" => {
+            let index = Index::new(source);
+            let visible = index.visible_at(line, character);
+            let mut items = Vec::new();
+            for symbol in &visible {
+                let kind: usize = match symbol.kind {
+                    SymbolKind::Function => 3,
+                    SymbolKind::Variable | SymbolKind::Parameter => 6,
+                };
+                items.push(object(vec![
+                    ("label", symbol.name.as_str().into()),
+                    ("kind", kind.into()),
+                    ("detail", symbol.detail.as_str().into()),
+                ]));
+            }
+            // a function declared in the script hides the built-in one
+            for (name, signature) in builtins() {
+                if visible.iter().any(|symbol| symbol.name == name) {
+                    continue;
+                }
+                items.push(object(vec![
+                    ("label", name.into()),
+                    ("kind", 3usize.into()),
+                    ("detail", signature.into()),
+                ]));
+            }
+            for keyword in KEYWORDS {
+                items.push(object(vec![
+                    ("label", keyword.into()),
+                    ("kind", 14usize.into()),
+                ]));
+            }
+            items.into()
+        }
+        "textDocument/formatting" => {
+            let path = uri_path(uri);
+            let config = FormatConfig::load(&config_dir(path.as_deref()))?;
+            let formatted =
+                format_source(source, &config).map_err(|e| format!("Syntax Error: {}", e))?;
+            if formatted == source {
+                Json::Array(Vec::new())
+            } else {
+                // one edit that replaces the whole script
+                let last = source.split('\n').next_back().unwrap_or("");
+                let end = position(line_starts(source).len() - 1, last.encode_utf16().count());
+                let edit = object(vec![
+                    (
+                        "range",
+                        object(vec![("start", position(0, 0)), ("end", end)]),
+                    ),
+                    ("newText", formatted.into()),
+                ]);
+                vec![edit].into()
+            }
+        }
+        _ => return Ok(None),
+    };
+    Ok(Some(result))
+}
+
+///
+///Reads one message, None when the editor closed stdin
+///
+pub fn read_message(input: &mut impl BufRead) -> Result<Option<String>, String> {
+    let mut length = None;
+    loop {
+        let mut header = String::new();
+        match input.read_line(&mut header) {
+            Ok(0) => return Ok(None),
+            Ok(_) => {}
+            Err(e) => return Err(format!("Failed to read a message: {}", e)),
+        }
+        let header = header.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.eq_ignore_ascii_case("Content-Length") {
+                length = value.trim().parse::<usize>().ok();
+            }
+        }
+    }
+    let length = length.ok_or("A message has no Content-Length header")?;
+    let mut body = vec![0; length];
+    input
+        .read_exact(&mut body)
+        .map_err(|e| format!("Failed to read a message: {}", e))?;
+    String::from_utf8(body)
+        .map(Some)
+        .map_err(|_| "A message is not UTF-8".to_string())
+}
+
+pub fn write_message(output: &mut impl Write, message: &Json) -> io::Result<()> {
+    let body = message.to_string();
+    write!(output, "Content-Length: {}\r\n\r\n{}", body.len(), body)?;
+    output.flush()
+}
+
+///
+///Where the messages to the editor are written. Anything else printed while checking a script
+///goes to stderr, so it cannot break the stream of messages on stdout
+///
+#[cfg(unix)]
+fn protocol_output() -> Box<dyn Write> {
+    use std::os::unix::io::FromRawFd;
+    extern "C" {
+        fn dup(fd: i32) -> i32;
+        fn dup2(from: i32, to: i32) -> i32;
+    }
+    unsafe {
+        let stdout = dup(1);
+        if stdout >= 0 && dup2(2, 1) >= 0 {
+            return Box::new(std::fs::File::from_raw_fd(stdout));
+        }
+    }
+    Box::new(io::stdout())
+}
+
+#[cfg(not(unix))]
+fn protocol_output() -> Box<dyn Write> {
+    Box::new(io::stdout())
+}
+
+///
+///Serves the editor until it sends exit and returns the exit code, 0 when the editor shut the
+///server down first
+///
+pub fn run() -> i32 {
+    let mut output = protocol_output();
+    let stdin = io::stdin();
+    let mut input = stdin.lock();
+    let mut server = Server::default();
+    loop {
+        let message = match read_message(&mut input) {
+            Ok(Some(message)) => message,
+            Ok(None) => return if server.shut_down() { 0 } else { 1 },
+            Err(e) => {
+                eprintln!("jist lsp: {}", e);
+                return 1;
+            }
+        };
+        let replies = match Json::parse(&message) {
+            Ok(message) if message.get("method").as_str() == Some("exit") => {
+                return if server.shut_down() { 0 } else { 1 };
+            }
+            Ok(message) => server.handle(&message),
+            Err(e) => vec![error_response(&Json::Null, PARSE_ERROR, e)],
+        };
+        for reply in replies {
+            if let Err(e) = write_message(&mut output, &reply) {
+                eprintln!("jist lsp: Failed to write a message: {}", e);
+                return 1;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod lsp_tests {
+    use super::{semantic_spans, uri_path, Index, Server, SymbolKind};
+    use crate::json::Json;
+
+    const SOURCE: &str = "let x: int = 1;\nfunc f(x: int, items: dict<string, int>) -> int {\n    for (i in 0..x) {\n        echoln(i);\n    }\n    return x;\n}\necholn(f(x, {}));\n";
+
+    #[test]
+    fn test_index_scopes() {
+        let index = Index::new(SOURCE);
+        let details: Vec<&str> = index.symbols.iter().map(|s| s.detail.as_str()).collect();
+        assert_eq!(
+            details,
+            vec![
+                "let x: int",
+                "func f(x: int, items: dict<string, int>) -> int",
+                "x: int",
+                "items: dict<string, int>",
+                "for i",
+            ]
+        );
+        assert_eq!(index.symbols[2].kind, SymbolKind::Parameter);
+
+        // the x in 0..x and in return x is the parameter, the one in f(x, {}) the global
+        let parameter = index.symbol_at(5, 11).unwrap();
+        assert_eq!(index.symbols[parameter].detail, "x: int");
+        let lines: Vec<usize> = index
+            .references(parameter)
+            .iter()
+            .map(|&t| index.tokens[t].line)
+            .collect();
+        assert_eq!(lines, vec![2, 3, 6]);
+        let global = index.symbol_at(7, 9).unwrap();
+        assert_eq!(global, 0);
+        assert_eq!(index.references(global).len(), 2);
+        assert_eq!(index.symbol_at(3, 15), index.symbol_at(2, 9));
+        // echoln is not declared in the script
+        assert_eq!(index.symbol_at(7, 2), None);
+
+        let outside: Vec<&str> = index
+            .visible_at(7, 0)
+            .iter()
+            .map(|s| s.name.as_str())
+            .collect();
+        assert_eq!(outside, vec!["x", "f"]);
+        let inside: Vec<&str> = index
+            .visible_at(3, 8)
+            .iter()
+            .map(|s| s.detail.as_str())
+            .collect();
+        assert_eq!(
+            inside,
+            vec![
+                "x: int",
+                "func f(x: int, items: dict<string, int>) -> int",
+                "items: dict<string, int>",
+                "for i"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_semantic_spans() {
+        let spans = semantic_spans(
+            "// note\nlet s: string = \"é\";\nfunc f() -> int {\n    return 2;\n}\n",
+        );
+        assert_eq!(
+            spans,
+            vec![
+                (0, 0, 7, 5),
+                (1, 14, 1, 2),
+                (1, 16, 3, 1),
+                (2, 5, 1, 4),
+                (3, 11, 1, 0),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_server_messages() {
+        let mut server = Server::default();
+        let message = |text: &str| Json::parse(text).unwrap();
+
+        let replies = server.handle(&message(
+            r#"{"jsonrpc": "2.0", "method": "textDocument/didOpen", "params": {"textDocument": {"uri": "file:///a.jist", "text": "let a: string = 1;\necholn(a);\n"}}}"#,
+        ));
+        let diagnostics = replies[0].get("params").get("diagnostics").as_array();
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(
+            diagnostics[0].get("message").as_str(),
+            Some("Type Error: Cannot assign int to 'a' of type string")
+        );
+
+        let replies = server.handle(&message(
+            r#"{"jsonrpc": "2.0", "id": 1, "method": "textDocument/hover", "params": {"textDocument": {"uri": "file:///a.jist"}, "position": {"line": 1, "character": 7}}}"#,
+        ));
+        assert_eq!(
+            replies[0]
+                .get("result")
+                .get("contents")
+                .get("value")
+                .as_str(),
+            Some("```jist\nlet a: string\n```")
+        );
+
+        let replies = server.handle(&message(
+            r#"{"jsonrpc": "2.0", "id": 2, "method": "shutdown"}"#,
+        ));
+        assert_eq!(replies[0].get("result"), &Json::Null);
+        assert!(server.shut_down());
+        let replies = server.handle(&message(
+            r#"{"jsonrpc": "2.0", "id": 3, "method": "textDocument/hover", "params": {}}"#,
+        ));
+        assert_eq!(replies[0].get("error").get("code"), &Json::Number(-32600.0));
+    }
+
+    #[test]
+    fn test_uri_path() {
+        assert_eq!(
+            uri_path("file:///home/me/my%20scripts/a.jist"),
+            Some("/home/me/my scripts/a.jist".to_string())
+        );
+        assert_eq!(uri_path("untitled:1"), None);
+    }
+}
@@ -1,20 +1,47 @@
+// first, so the trace! macro can be used in the modules below
+#[macro_use]
+mod trace;
 mod ast;
 pub mod base_variable;
+mod bindings;
+mod cli;
 mod collection;
+mod commands;
 pub mod compiler;
+mod config;
+mod debugger;
+mod diagnostic;
+mod formatter;
 pub mod function;
 mod function_map;
 pub mod globals;
+mod heap;
 pub mod highlighter;
+mod json;
+mod lint;
+mod lsp;
 mod node;
+mod state_dump;
+mod statements;
+mod syntax;
 pub mod token_type;
 
 mod compilers {
+    pub mod array;
+    pub mod block;
     pub mod collection;
     pub mod conditional;
+    pub mod convert;
+    pub mod dictionary;
+    pub mod expression;
+    pub mod fs;
     pub mod function;
+    pub mod gc;
     pub mod loops;
+    pub mod math;
     pub mod operation;
+    pub mod process;
+    pub mod set;
     pub mod variable;
 }
 
@@ -23,6 +50,7 @@ mod statement_tokenizer {
     pub mod collection_tokenizer;
     pub mod conditional_tokenizer;
     pub mod function_tokenizer;
+    pub mod lexer;
     pub mod loop_tokenizer;
     pub mod tests;
     pub mod tokenizer;
@@ -32,21 +60,23 @@ mod statement_tokenizer {
 use std::env;
 use std::error::Error;
 use std::ffi::OsStr;
-use std::fs;
 use std::path::Path;
 use std::process::exit;
 
 //use crate::collection::collections::{Array, Dictionary};
-use base_variable::variables::VARIABLE_STACK;
-use compiler::compilers::route_to_parser;
-use globals::MAKE_LOOP;
+use bindings::strict_pragma;
+use cli::{parse_arguments, take_global_options, Command, USAGE};
+use compilers::block::run_block;
+use globals::{SCRIPT_ARGUMENTS, STRICT};
 use jist::statement_tokenizer::tokenizer::tokenizers::ParseInfo;
-use node::nodes::match_token_to_node;
+use node::nodes::token_to_node;
 use node::nodes::ASTNode;
+use state_dump::dump_state;
 use statement_tokenizer::tokenizer::tokenizers::tokenize;
+use statements::split_statements;
+use syntax::check_syntax;
+use token_type::token_types::TokenTypes;
 
-use crate::collection::{ARRAY_STACK, DICTIONARY_STACK};
-use crate::function::FUNCTION_STACK;
 //use lazy_static::lazy_static;
 //use std::sync::Mutex;
 
@@ -64,247 +94,196 @@ fn check_file_extension(file_path: String) -> Result<bool, Box<dyn Error>> {
 }
 
 ///
-///This function prints the array stack for dev purposes
+///Tokenizes a func declaration into its header node and the nodes of its body, for the
+///tools that show or check the nodes. The body statements are tokenized one at a time like
+///the rest of the file, so a tokenizer that scans ahead never reads into the next statement.
+///None when the line is not a function
 ///
-fn print_array_stack() {
-    let array_stack = ARRAY_STACK.lock().unwrap(); // Lock the mutex
-    for array in array_stack.iter() {
-        println!("{}", array); // Now we can iterate over the Vec
+fn function_declaration_nodes(line: &str) -> Result<Option<Vec<ASTNode>>, Box<dyn Error>> {
+    // skip the comments in front of the statement
+    let mut code = line.trim_start();
+    while code.starts_with("//") || code.starts_with("/*") {
+        let rest = if code.starts_with("//") {
+            code.find('\n').map(|i| &code[i..])
+        } else {
+            code.find("*/").map(|i| &code[i + 2..])
+        };
+        code = rest.unwrap_or("").trim_start();
+    }
+    if !code.starts_with("func") || !code[4..].starts_with(char::is_whitespace) {
+        return Ok(None);
     }
-}
 
-///
-///This function prints the dictionary stack for dev purposes
-///
-fn print_dictionary_stack() {
-    let dict_stack = DICTIONARY_STACK.lock().unwrap(); // Lock the mutex
-    for dict in dict_stack.iter() {
-        println!("{}", dict); // Now we can iterate over the Vec
+    let (start, end) = match (code.find('{'), code.rfind('}')) {
+        (Some(start), Some(end)) if start < end => (start, end),
+        _ => return Ok(None),
+    };
+    let mut nodes = tokenize(code[..start].to_string())
+        .into_iter()
+        .map(token_to_node)
+        .collect::<Result<Vec<ASTNode>, String>>()?;
+    if !matches!(nodes.first(), Some(ASTNode::Function(_))) {
+        return Ok(None);
     }
-}
 
-///
-///This function prints the function stack for dev purposes
-///
-fn print_function_stack() {
-    let function_stack = FUNCTION_STACK.lock().unwrap(); // Lock the mutex
-    for function in function_stack.iter() {
-        println!("{}", function); // Now we can iterate over the Vec
+    nodes.push(ASTNode::LeftCurly);
+    for statement in split_statements(&code[start + 1..end])? {
+        for info in tokenize(statement) {
+            if info.token != TokenTypes::Comment {
+                nodes.push(token_to_node(info)?);
+            }
+        }
     }
+    nodes.push(ASTNode::RightCurly);
+    Ok(Some(nodes))
 }
 
 ///
 ///This function reads the file and parses it, it was added to support multiple lines of code,
 ///multiline coding statements and later multiple files
 ///
-// Global var if_else_skip
-use crate::globals::IF_ELSE_SKIP;
-
-fn parse_file(file_path: &str) -> Result<(), Box<dyn Error>> {
-    let contents = fs::read_to_string(file_path)?;
-    let lines: Vec<String> = contents.lines().map(|s| s.to_string()).collect();
-    let mut brace_count = 0;
-    let mut bracket_count = 0;
-    let mut current_line = String::new();
-    let mut finished_lines: Vec<String> = Vec::new();
-
-    let mut tokenized_expression = Vec::new();
-
-    for (line_number, line) in lines.iter().enumerate() {
-        for ch in line.chars() {
-            match ch {
-                '{' => {
-                    brace_count += 1;
-                    current_line.push(ch);
-                }
-                '}' => {
-                    brace_count -= 1;
-                    current_line.push(ch);
-                    if brace_count < 0 {
-                        return Err(format!(
-                            "Unmatched closing curly brace at line {}",
-                            line_number + 1
-                        )
-                        .into());
-                    }
-                    if brace_count == 0 && bracket_count == 0 {
-                        finished_lines.push(current_line.clone());
-                        current_line.clear();
-                    }
-                }
-                '[' => {
-                    bracket_count += 1;
-                    current_line.push(ch);
-                }
-                ']' => {
-                    bracket_count -= 1;
-                    current_line.push(ch);
-                    if bracket_count < 0 {
-                        return Err(format!(
-                            "Unmatched closing square bracket at line {}",
-                            line_number + 1
-                        )
-                        .into());
-                    }
-                }
-                ';' => {
-                    current_line.push(ch);
-                    if brace_count == 0 && bracket_count == 0 {
-                        finished_lines.push(current_line.clone());
-                        current_line.clear();
-                    }
-                }
-                _ => current_line.push(ch),
-            }
-        }
-    }
-
-    let _ast_nodes: Vec<ASTNode> = Vec::new();
-
-    for line in finished_lines {
-        let tokens = tokenize(line);
-        let mut hasroot = true;
-        let mut first_node: ASTNode = ASTNode::None;
-        let mut result: bool;
+fn parse_source(contents: &str) -> Result<(), Box<dyn Error>> {
+    run_block(contents, 1, 1)
+}
 
-        for (i, parsed_info) in tokens.iter().enumerate() {
-            let node = match_token_to_node(parsed_info.clone());
-            if i == 0 {
-                first_node = node.clone();
-            }
+// the stack the script runs on, enough for MAX_CALL_DEPTH calls of a debug build, which the
+// main thread does not have
+const STACK_SIZE: usize = 256 * 1024 * 1024;
 
-            match node {
-                ASTNode::SemiColon => {
-                    // Check if the expression is valid before processing
-                    if tokenized_expression.is_empty() {
-                        println!("Syntax error: expression must be more than a semicolon");
-                        std::process::exit(1);
-                    }
-
-                    // Route to parser only if there are valid tokens
-                    match first_node.clone() {
-                        ASTNode::While(_) => {
-                            result = route_to_parser(&mut tokenized_expression, None)
-                        }
-                        ASTNode::If(_) => result = route_to_parser(&mut tokenized_expression, None),
-                        ASTNode::Elif(_) => {
-                            if unsafe { IF_ELSE_SKIP } {
-                                break; // Skip processing if IF_ELSE_SKIP is true
-                            } else {
-                                result = route_to_parser(&mut tokenized_expression, None);
-
-                                while unsafe { MAKE_LOOP } {
-                                    result = route_to_parser(&mut tokenized_expression, None);
-                                }
-                            }
-                        }
-                        ASTNode::Else => {
-                            if unsafe { IF_ELSE_SKIP } {
-                                unsafe { IF_ELSE_SKIP = false }; // Reset IF_ELSE_SKIP
-                                break; // Skip further parsing
-                            } else {
-                                result = route_to_parser(&mut tokenized_expression, None);
-                                while unsafe { MAKE_LOOP } {
-                                    result = route_to_parser(&mut tokenized_expression, None);
-                                }
-                            }
-                        }
-                        _ => {
-                            result = route_to_parser(&mut tokenized_expression, None);
-                            while unsafe { MAKE_LOOP } {
-                                result = route_to_parser(&mut tokenized_expression, None);
-                            }
-                        }
-                    }
-
-                    // Clear tokenized_expression after processing
-                    tokenized_expression.clear();
-                }
-                _ => {
-                    hasroot = true; // Mark that we have a valid root
-                    tokenized_expression.push(node); // Accumulate tokens
-                }
+fn main() {
+    match std::thread::Builder::new()
+        .stack_size(STACK_SIZE)
+        .spawn(run)
+    {
+        Ok(runner) => {
+            if runner.join().is_err() {
+                exit(101);
             }
         }
+        Err(_) => run(),
     }
-
-    Ok(())
 }
 
-fn main() -> Result<(), Box<dyn Error>> {
-    // Collect command-line arguments
-    let args: Vec<String> = env::args().collect();
-    if args.len() < 2 {
-        return Err("No file path provided".into());
-    }
-    let file_path = &args[1];
-    match check_file_extension(file_path.to_owned()) {
-        Ok(true) => {
-            //println!("File path is valid");
-        }
-        Err(_) => {
-            return Err("File path not valid: Does not have extension .jist".into());
+fn run() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let (options, args) = match take_global_options(&args) {
+        Ok(taken) => taken,
+        Err(e) => {
+            eprintln!("{}\n\n{}", e, USAGE);
+            exit(2);
         }
-        _ => {
-            return Err("Some error occurred".into());
+    };
+    let command = match parse_arguments(&args) {
+        Ok(command) => command,
+        Err(e) => {
+            eprintln!("{}\n\n{}", e, USAGE);
+            exit(2);
         }
+    };
+    if let Err(e) = trace::init(options.levels.as_deref(), options.file.as_deref()) {
+        eprintln!("{}", e);
+        exit(2);
     }
+    // jist lint --format=json is the same as --error-format=json
+    let error_format = match command {
+        Command::Lint { json: true, .. } => diagnostic::ErrorFormat::Json,
+        _ => options.error_format,
+    };
+    diagnostic::init(options.color, error_format);
 
-    // Read the file contents
-    //let contents = fs::read_to_string(file_path)?;
-    //println!("{}", contents);
-    //
-    //curly braces take priority, then square braces then ;
-    //keep track of braces and make sure all are closed before finishing line and check for ; as
-    //next,
-    //if none go until ;
-    if let Err(e) = parse_file(file_path) {
-        eprintln!("Failed to parse file: {}", e);
-    }
+    let source = match &command {
+        Command::Help => {
+            println!("{}", USAGE);
+            return;
+        }
+        Command::Version => {
+            println!("jist {}", env!("CARGO_PKG_VERSION"));
+            return;
+        }
+        Command::Repl => {
+            commands::repl(parse_source);
+            return;
+        }
+        Command::Lsp => exit(lsp::run()),
+        Command::DebugAdapter => exit(debugger::run_adapter()),
+        Command::Run { source, .. }
+        | Command::Check(source)
+        | Command::Tokens(source)
+        | Command::Ast(source)
+        | Command::Highlight(source)
+        | Command::Lint { source, .. }
+        | Command::Debug { source, .. } => source,
+        Command::Fmt { sources, mode } => exit(commands::run_fmt(sources, *mode)),
+    };
+    let contents = match commands::read_source(source) {
+        Ok(contents) => contents,
+        Err(e) => {
+            eprintln!("{}", e);
+            exit(1);
+        }
+    };
+    unsafe { STRICT = options.strict || strict_pragma(&contents) };
 
-    // Tokenize each line and collect AST nodes
-
-    // Display the collected AST nodes
-    /*
-    for node in &ast_nodes {
-        let indent = " ".repeat(4);
-        match node {
-            ASTNode::Bool(b) => println!("{}BoolNode: Value: {}", indent, b.value),
-            ASTNode::Variable(v) => println!("{}VariableNode: Type: {}, Value: {}", indent, v.var_type, v.value),
-            ASTNode::Int(n) => println!("{}IntNode: Value: {}", indent, n.value),
-            ASTNode::Operator(o) => println!("{}OperatorNode: Operator: {}", indent, o.operator),
-            ASTNode::Function(f) => println!("{}FunctionNode: Name: {}", indent, f.name),
-            ASTNode::String(s) => println!("{}StringNode: Value: {}", indent, s.value),
-            ASTNode::Char(c) => println!("{}CharNode: Value: {}", indent, c.value),
-            ASTNode::Assignment(a) => println!("{}AssignmentNode: Value: {}", indent, a.value),
-            ASTNode::VarTypeAssignment(v) => println!("{}VarTypeAssignmentNode: Value: {}", indent, v.value),
-            ASTNode::FunctionCall(f) => println!("{}FunctionCallNode: Value: {}", indent, f.name),
-            ASTNode::VariableCall(v) => println!("{}VariableCallNode: Value: {}", indent, v.name),
-            ASTNode::VariableType(v) => println!("{}VariableTypeNode: Value: {}", indent, v.value),
-            ASTNode::VariableValue(v) => println!("{}VariableValueNode: Value: {}", indent, v.value),
-            ASTNode::FunctionArguments(f) => println!("{}FunctionArgumentsNode: Value: {}", indent, f.value),
-            ASTNode::AssignmentOperator(a) => println!("{}AssignmentOperatorNode: Value: {}", indent, a.operator),
-            ASTNode::ReturnTypeAssignment(r) => println!("{}ReturnTypeAssignmentNode: Value: {}", indent, r.value),
-            ASTNode::Comment(c) => println!("{}CommentNode: Value: {}", indent, c.value),
-            ASTNode::SemiColon => println!("{}SemicolonNode", indent),
-            ASTNode::LeftParenthesis => println!("{}LeftParenthesisNode", indent),
-            ASTNode::RightParenthesis => println!("{}RightParenthesisNode", indent),
-            ASTNode::ArgumentSeparator => println!("{}ArgumentSeparatorNode", indent),
-            ASTNode::LeftCurly => println!("{}LeftCurlyNode", indent),
-            ASTNode::RightCurly => println!("{}RightCurlyNode", indent),
-            ASTNode::None => println!("{}NoneNode", indent),
+    let result = match command {
+        Command::Run {
+            source,
+            dump,
+            arguments,
+        } => {
+            let name = commands::source_name(&source);
+            let errors = check_syntax(&contents);
+            if !errors.is_empty() {
+                commands::report_syntax_errors(&name, &contents, &errors);
+                exit(1);
+            }
+            diagnostic::report_against(name, contents.clone());
+            unsafe { SCRIPT_ARGUMENTS = arguments };
+            if let Err(e) = parse_source(&contents) {
+                eprintln!("Failed to parse file: {}", e);
+                exit(1);
+            }
+            if let Some(format) = dump {
+                dump_state(format);
+            }
+            Ok(())
+        }
+        Command::Check(source) => {
+            let name = commands::source_name(&source);
+            let errors = check_syntax(&contents);
+            if !errors.is_empty() {
+                commands::report_syntax_errors(&name, &contents, &errors);
+                exit(1);
+            }
+            let problems = commands::check_source(&contents);
+            if !problems.is_empty() {
+                commands::report_problems(&name, &contents, &problems);
+                exit(1);
+            }
+            if !diagnostic::is_json() {
+                println!("{}: no problems found", name);
+            }
+            Ok(())
         }
-    }*/
-    //print variable stack
-    println!("\n\nStack:");
-    for variable in unsafe { VARIABLE_STACK.iter() } {
-        variable.print();
+        Command::Lint { source, .. } => exit(commands::run_lint(&source, &contents)),
+        Command::Debug { source, arguments } => exit(debugger::run_console(
+            commands::source_name(&source),
+            &contents,
+            arguments,
+        )),
+        Command::Tokens(_) => commands::print_tokens(&contents),
+        Command::Ast(_) => commands::print_ast(&contents),
+        Command::Highlight(_) => commands::print_highlighted(&contents),
+        Command::Fmt { .. }
+        | Command::Help
+        | Command::Version
+        | Command::Repl
+        | Command::Lsp
+        | Command::DebugAdapter => Ok(()),
+    };
+    if let Err(e) = result {
+        eprintln!("Failed to parse file: {}", e);
+        exit(1);
     }
-
-    print_array_stack();
-    print_dictionary_stack();
-    print_function_stack();
-    Ok(())
 }
 
 #[cfg(test)]
@@ -331,6 +310,383 @@ mod test_input_output {
     use predicates::prelude::*;
 
     // Pass in a file path and check if the output is correct
+    #[test]
+    fn test_no_state_dump_by_default() {
+        let file_path = "test_files/int_variable_declaration.jist";
+        let mut cmd = Command::cargo_bin("jist").unwrap();
+        cmd.arg(file_path)
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("Stack:").not())
+            .stdout(predicate::str::contains("Variable Name: a").not());
+    }
+
+    #[test]
+    fn test_dump_state_json() {
+        let file_path = "test_files/collection_functions.jist";
+        let mut cmd = Command::cargo_bin("jist").unwrap();
+        cmd.arg("--dump-state=json")
+            .arg(file_path)
+            .assert()
+            .success()
+            .stdout(predicate::str::contains(
+                r#"{"name": "twice", "type": "array<int>", "value": [2, 4, 6]}"#,
+            ))
+            .stdout(predicate::str::contains(
+                r#"{"name": "fib", "arguments": [{"name": "n", "type": "int"}], "return_type": "int"}"#,
+            ));
+    }
+
+    #[test]
+    fn test_if_chains() {
+        let file_path = "test_files/if_chains.jist";
+        let mut cmd = Command::cargo_bin("jist").unwrap();
+        cmd.arg(file_path)
+            .assert()
+            .success()
+            .stdout("medium; }\ntwo\ntried\nfinally\ndone\n");
+    }
+
+    #[test]
+    fn test_nested_control_flow() {
+        let file_path = "test_files/nested_control_flow.jist";
+        let mut cmd = Command::cargo_bin("jist").unwrap();
+        cmd.arg(file_path)
+            .assert()
+            .success()
+            .stdout("fizz\none\nbang\nfizz\none\nbang\n2\n20\n-1\na\nb\n");
+
+        // a function sees its own variables and the globals, not those of the code calling it
+        for (source, name) in [
+            (
+                "func g() -> int { return secret; }\nfunc f() -> int { let secret: int = 5; return g(); }\necholn(f());",
+                "secret",
+            ),
+            (
+                "func g() -> int { return i; }\nfor (i in [1, 2]) { echoln(g()); }",
+                "i",
+            ),
+        ] {
+            let mut cmd = Command::cargo_bin("jist").unwrap();
+            cmd.args(["--color=never", "-e", source])
+                .assert()
+                .code(1)
+                .stdout("")
+                .stderr(predicate::str::starts_with(format!(
+                    "Runtime Error: Variable '{}' is not defined",
+                    name
+                )));
+        }
+        let mut cmd = Command::cargo_bin("jist").unwrap();
+        cmd.args([
+            "-e",
+            "let mut count: int = 0;\nfunc bump() -> int { count += 1; return count; }\n\
+             for (i in [1, 2]) { if (true) { echoln(bump()); } }\necholn(count);",
+        ])
+        .assert()
+        .success()
+        .stdout("1\n2\n2\n");
+
+        // a recursion that does not end is an error a try block can catch
+        let mut cmd = Command::cargo_bin("jist").unwrap();
+        cmd.args([
+            "-e",
+            "func f(n: int) -> int {\n    if (n == 0) {\n        return 0;\n    }\n    return f(n - 1) + 1;\n}\n\
+             echoln(f(999));\ntry {\n    echoln(f(5000));\n} catch e {\n    echoln(e);\n}",
+        ])
+        .assert()
+        .success()
+        .stdout("999\nRuntime Error: f(): Calls nested more than 1000 deep\n");
+    }
+
+    #[test]
+    fn test_reassignment() {
+        let file_path = "test_files/reassignment.jist";
+        let mut cmd = Command::cargo_bin("jist").unwrap();
+        cmd.arg(file_path)
+            .assert()
+            .success()
+            .stdout("1\n3.5\nabc\n[5, 12, 4]\n{x => 7, y => 1}\n[9]\n");
+
+        // the new value has to fit the type the variable was declared with
+        for (source, error) in [
+            (
+                "let mut i: int = 1;\ni = \"x\";",
+                "Type Error: Cannot assign string to 'i' of type int",
+            ),
+            (
+                "let mut s: string = \"a\";\ns++;",
+                "Type Error: Cannot apply '++' to string",
+            ),
+            (
+                "let mut a: array<int> = [1];\na = [\"x\"];",
+                "Type Error: Cannot assign array<string> to 'a' of type array<int>",
+            ),
+            ("x = 1;", "Runtime Error: Variable 'x' is not defined"),
+        ] {
+            let mut cmd = Command::cargo_bin("jist").unwrap();
+            cmd.args(["--color=never", "-e", source])
+                .assert()
+                .code(1)
+                .stderr(predicate::str::starts_with(error));
+        }
+
+        // a float is cut to an int the same as in a let, and only as converts it in strict mode
+        let source = "let mut i: int = 1;\ni = 2.9;\necholn(i);\ni += 1.5;\necholn(i);";
+        let mut cmd = Command::cargo_bin("jist").unwrap();
+        cmd.args(["-e", source]).assert().success().stdout("2\n3\n");
+        let mut cmd = Command::cargo_bin("jist").unwrap();
+        cmd.args(["--color=never", "--strict", "-e", source])
+            .assert()
+            .code(1)
+            .stderr(predicate::str::starts_with(
+                "Syntax Error[E0012]: implicit conversion from `float` to `int` in strict mode\n \
+                 --> <-e>:2:5\n",
+            ));
+        let mut cmd = Command::cargo_bin("jist").unwrap();
+        cmd.args(["--color=never", "--strict", "-e", "let mut i: int = 1;\ni += 1.5;"])
+            .assert()
+            .code(1)
+            .stderr(predicate::str::starts_with(
+                "Type Error: Cannot store float in 'i' of type int in strict mode, convert it with `as int`",
+            ));
+    }
+
+    #[test]
+    fn test_bool_conditions() {
+        let file_path = "test_files/bool_conditions.jist";
+        let mut cmd = Command::cargo_bin("jist").unwrap();
+        cmd.arg(file_path)
+            .assert()
+            .success()
+            .stdout("3\ntrue\ntrue\n[true, false]\neven\n");
+
+        // an int is never taken for a bool
+        for (source, error) in [
+            (
+                "let n: int = 1;\nif (n) {\n    echoln(n);\n}",
+                "Type Error: Expected a bool condition, found int",
+            ),
+            (
+                "let n: int = 0;\nwhile (n) {\n}",
+                "Type Error: Expected a bool condition, found int",
+            ),
+            (
+                "echoln(!1);",
+                "Type Error: Cannot apply '!' to int, it needs a bool",
+            ),
+            (
+                "echoln(false || 1);",
+                "Type Error: Cannot apply '||' to int, it needs a bool",
+            ),
+            (
+                "let b: bool = 1;\necholn(b);",
+                "Type Error: Cannot assign int to 'b' of type bool",
+            ),
+        ] {
+            let mut cmd = Command::cargo_bin("jist").unwrap();
+            cmd.args(["--color=never", "-e", source])
+                .assert()
+                .code(1)
+                .stderr(predicate::str::starts_with(error));
+        }
+
+        // a condition made of literals is found by jist check
+        let mut cmd = Command::cargo_bin("jist").unwrap();
+        cmd.args([
+            "--color=never",
+            "check",
+            "-e",
+            "if (1) {\n    echoln(1);\n}",
+        ])
+        .assert()
+        .code(1)
+        .stderr(predicate::str::starts_with(
+            "Type Error: Expected a bool condition, found int\n --> <-e>:1:5\n  |\n\
+             1 | if (1) {\n  \
+             |     ^\n",
+        ));
+    }
+
+    #[test]
+    fn test_constants() {
+        let file_path = "test_files/constants.jist";
+        let mut cmd = Command::cargo_bin("jist").unwrap();
+        cmd.arg(file_path)
+            .assert()
+            .success()
+            .stdout("north\neast\nsouth\nwest\ntrue\n(40, -4)\neast\ntrue\n");
+
+        // changing a let or a const is found before anything runs
+        let mut cmd = Command::cargo_bin("jist").unwrap();
+        cmd.args([
+            "--color=never",
+            "-e",
+            "echoln(1);\nlet total: int = 0;\nfor (i in 0..3) {\n    total += i;\n}",
+        ])
+        .assert()
+        .code(1)
+        .stdout("")
+        .stderr(
+            "Syntax Error[E0009]: cannot assign to immutable variable `total`\n \
+             --> <-e>:4:5\n  |\n\
+             2 | let total: int = 0;\n  \
+             |     ----- declared here, use `let mut total` to allow changing it\n\
+             3 | for (i in 0..3) {\n\
+             4 |     total += i;\n  \
+             |     ^^^^^^^^\n\n\
+             <-e>: 1 syntax error found\n",
+        );
+
+        for (source, error) in [
+            (
+                "const N: int = 1;\nN++;",
+                "Syntax Error[E0010]: cannot assign to constant `N`",
+            ),
+            (
+                "let n: int = 1;\nconst N: int = n;",
+                "Syntax Error[E0011]: the value of constant `N` has to be known",
+            ),
+            (
+                "const N: int = math.PI;",
+                "Syntax Error[E0011]: expected `int` for constant `N`, found `float`",
+            ),
+            (
+                "let first: array<int> = [1, 2];\nlet mut second: array<int> = first;\nsecond.push(3);",
+                "Syntax Error[E0009]: cannot share immutable variable `first` with mutable `second`",
+            ),
+            (
+                "func grow(values: array<int>) {\n    let mut same: array<int> = values;\n    same.push(3);\n}",
+                "Syntax Error[E0009]: cannot share immutable variable `values` with mutable `same`",
+            ),
+        ] {
+            let mut cmd = Command::cargo_bin("jist").unwrap();
+            cmd.args(["--color=never", "-e", source])
+                .assert()
+                .code(1)
+                .stderr(predicate::str::starts_with(error));
+        }
+    }
+
+    #[test]
+    fn test_conversions() {
+        let file_path = "test_files/conversions.jist";
+        let mut cmd = Command::cargo_bin("jist").unwrap();
+        cmd.arg(file_path).assert().success().stdout(
+            "1975\n-19\n1.5\n3 items\nJ\n74\n43\n5\ntrue\n7\n\
+             Runtime Error: Cannot parse \"seven\" as an int\n\
+             Runtime Error: Cannot parse \"1.5\" as an int\n",
+        );
+
+        for (source, error) in [
+            (
+                "echoln(\"x\".parse::<float>());",
+                "Runtime Error: Cannot parse \"x\" as a float",
+            ),
+            (
+                "echoln(1 as bool);",
+                "Type Error: Cannot convert int to bool",
+            ),
+            (
+                "echoln(math.INF as int);",
+                "Runtime Error: inf does not fit in an int",
+            ),
+            // a float stored in an int without as is cut the same way
+            (
+                "let x: int = math.NAN;",
+                "Runtime Error: NaN does not fit in an int",
+            ),
+            (
+                "let mut x: int = 0;\nx = 3000000000.0;",
+                "Runtime Error: 3000000000 does not fit in an int",
+            ),
+            (
+                "echoln(\"1\".parse());",
+                "Syntax Error: parse needs the type to parse to",
+            ),
+        ] {
+            let mut cmd = Command::cargo_bin("jist").unwrap();
+            cmd.args(["--color=never", "-e", source])
+                .assert()
+                .code(1)
+                .stderr(predicate::str::starts_with(error));
+        }
+
+        // an error nobody catches still ends the script once finally has run
+        let mut cmd = Command::cargo_bin("jist").unwrap();
+        cmd.args([
+            "--color=never",
+            "-e",
+            "try {\n    echoln(1 / 0);\n} finally {\n    echoln(\"done\");\n}\necholn(2);",
+        ])
+        .assert()
+        .code(1)
+        .stdout("done\n")
+        .stderr(predicate::str::starts_with(
+            "Runtime Error: Division by zero\n --> <-e>:2:5\n",
+        ));
+
+        // finally also runs when the try or the catch returns, the return waits for it
+        let mut cmd = Command::cargo_bin("jist").unwrap();
+        cmd.args([
+            "-e",
+            "func risky(n: int) -> int {\n    try {\n        return n;\n    } catch e {\n        return -1;\n    } finally {\n        echoln(\"fin\");\n    }\n}\n\
+             func failing() -> int {\n    try {\n        return 1 / 0;\n    } catch e {\n        return -1;\n    } finally {\n        echoln(\"fin\");\n    }\n}\n\
+             echoln(risky(5));\necholn(failing());",
+        ])
+        .assert()
+        .success()
+        .stdout("fin\n5\nfin\n-1\n");
+
+        // a float is only cut to an int by as in strict mode
+        let source = "let a: float = 2.5;\nlet b: int = a;\necholn(b);";
+        let mut cmd = Command::cargo_bin("jist").unwrap();
+        cmd.args(["-e", source]).assert().success().stdout("2\n");
+        for arguments in [
+            vec!["--color=never", "--strict", "-e", source],
+            vec!["--color=never", "check", "--strict", "-e", source],
+        ] {
+            let mut cmd = Command::cargo_bin("jist").unwrap();
+            cmd.args(arguments)
+                .assert()
+                .code(1)
+                .stderr(predicate::str::starts_with(
+                "Syntax Error[E0012]: implicit conversion from `float` to `int` in strict mode\n \
+                 --> <-e>:2:14\n",
+            ));
+        }
+        let mut cmd = Command::cargo_bin("jist").unwrap();
+        cmd.args([
+            "--color=never",
+            "-e",
+            "// #![strict]\nfunc half(x: float) -> float {\n    return x / 2;\n}\nlet b: int = half(3);",
+        ])
+        .assert()
+        .code(1)
+        .stderr(predicate::str::starts_with(
+            "Type Error: Cannot store float in 'b' of type int in strict mode, convert it with `as int`",
+        ));
+
+        // a value of an unrelated type is never made into a default
+        for arguments in [
+            vec!["--color=never", "-e", "let x: int = \"abc\";\necholn(x);"],
+            vec![
+                "--color=never",
+                "--strict",
+                "-e",
+                "let x: int = \"abc\";\necholn(x);",
+            ],
+        ] {
+            let mut cmd = Command::cargo_bin("jist").unwrap();
+            cmd.args(arguments)
+                .assert()
+                .code(1)
+                .stderr(predicate::str::starts_with(
+                    "Type Error: Cannot assign string to 'x' of type int\n",
+                ));
+        }
+    }
+
     #[test]
     fn test_int_variable_declarations() {
         let file_path = "test_files/int_variable_declaration.jist";
@@ -338,7 +694,8 @@ mod test_input_output {
         // Run the program and check the output
         let mut cmd = Command::cargo_bin("jist").unwrap();
 
-        cmd.arg(file_path)
+        cmd.arg("--dump-state")
+            .arg(file_path)
             .assert()
             .success() // Asserting that the command was successful
             .stdout(predicate::str::contains(
@@ -352,7 +709,8 @@ mod test_input_output {
 
         let mut cmd = Command::cargo_bin("jist").unwrap();
 
-        cmd.arg(file_path)
+        cmd.arg("--dump-state")
+            .arg(file_path)
             .assert()
             .success()
             .stdout(predicate::str::contains(
@@ -364,7 +722,8 @@ mod test_input_output {
     fn test_bool_variable_declaration() {
         let file_path = "test_files/boolean_variable_declaration.jist";
         let mut cmd = Command::cargo_bin("jist").unwrap();
-        cmd.arg(file_path)
+        cmd.arg("--dump-state")
+            .arg(file_path)
             .assert()
             .success()
             .stdout(predicate::str::contains(
@@ -376,7 +735,8 @@ mod test_input_output {
     fn test_char_variable_declaration() {
         let file_path = "test_files/char_variable_declaration.jist";
         let mut cmd = Command::cargo_bin("jist").unwrap();
-        cmd.arg(file_path)
+        cmd.arg("--dump-state")
+            .arg(file_path)
             .assert()
             .success()
             .stdout(predicate::str::contains(
@@ -390,7 +750,8 @@ mod test_input_output {
 
         let mut cmd = Command::cargo_bin("jist").unwrap();
 
-        cmd.arg(file_path)
+        cmd.arg("--dump-state")
+            .arg(file_path)
             .assert()
             .success()
             .stdout(predicate::str::contains(
@@ -399,112 +760,1425 @@ mod test_input_output {
     }
 
     #[test]
-    fn test_dict_boolean_string_collection_declaration() {
-        let file_path = "test_files/dict_boolean_string_collection_declaration.jist";
+    fn test_string_escape_sequences() {
+        let file_path = "test_files/string_escape_sequences.jist";
         let mut cmd = Command::cargo_bin("jist").unwrap();
         cmd.arg(file_path)
-        .assert()
-        .success()
-        .stdout(predicate::str::contains(
-            r#"a: Dict<bool, string> = {"true" => true, "false" => false, "true" => not false}"#,
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("He said \"hi\"; {ok}\n"))
+            .stdout(predicate::str::contains("C:\\path\\to"))
+            .stdout(predicate::str::contains("line one;\nline two {"))
+            .stdout(predicate::str::contains("tab:\tend \u{1F600} h\u{e9}llo"))
+            .stdout(predicate::str::contains("after"));
+
+        // a literal that is not valid is found before anything runs
+        let mut cmd = Command::cargo_bin("jist").unwrap();
+        cmd.args(["--color=never", "-e", "echoln(\"first\");\necholn(\"\\q\");"])
+            .assert()
+            .code(1)
+            .stdout("")
+            .stderr(
+                "Syntax Error[E0013]: expected an escape sequence such as `\\n` or `\\u{41}`, found `\\q`\n \
+                 --> <-e>:2:9\n  |\n\
+                 2 | echoln(\"\\q\");\n  |         ^^\n\n\
+                 <-e>: 1 syntax error found\n",
+            );
+    }
+
+    #[test]
+    fn test_expressions_are_checked_before_running() {
+        let mut cmd = Command::cargo_bin("jist").unwrap();
+        let output = cmd
+            .args([
+                "-e",
+                "echoln(\"start\");\nlet a: int = 1 +;\nlet b: int = * 2;\n",
+            ])
+            .output()
+            .unwrap();
+        assert_eq!(output.status.code(), Some(1));
+        assert!(output.stdout.is_empty());
+        let stderr = String::from_utf8(output.stderr).unwrap();
+        assert!(stderr.starts_with(
+            "Syntax Error[E0014]: expected a value, found `;`\n \
+             --> <-e>:2:17\n"
         ));
+        assert!(stderr.contains("Syntax Error[E0014]: expected a value, found `*`\n"));
+        assert!(stderr.ends_with("<-e>: 2 syntax errors found\n"));
+
+        let mut cmd = Command::cargo_bin("jist").unwrap();
+        cmd.args(["-e", "echoln(-2147483648);\necholn(1 -2);"])
+            .assert()
+            .success()
+            .stdout("-2147483648\n-1\n");
     }
 
     #[test]
-    fn test_array_boolean_collection_declaration() {
-        let file_path = "test_files/array_boolean_collection_declaration.jist";
+    fn test_string_methods() {
+        let file_path = "test_files/string_methods.jist";
         let mut cmd = Command::cargo_bin("jist").unwrap();
-        cmd.arg(file_path)
+        cmd.arg("--dump-state")
+            .arg(file_path)
             .assert()
             .success()
+            .stdout(predicate::str::contains("NAME=ZOË;AGE=42\n"))
+            .stdout(predicate::str::contains("\nZoë\n"))
+            .stdout(predicate::str::contains("name=Zoë, age=42\n"))
+            .stdout(predicate::str::contains("\n43\n"))
+            .stdout(predicate::str::contains("007|ab..|\n"))
+            .stdout(predicate::str::contains("matched"))
             .stdout(predicate::str::contains(
-                r#"a: Array<bool> = [true, false, true]"#,
+                "fields: Array<string> = [name=Zoë, age=42]",
             ));
     }
 
     #[test]
-    fn test_array_char_collection_declaration() {
-        let file_path = "test_files/array_char_collection_declaration.jist";
+    fn test_array_methods() {
+        let file_path = "test_files/array_methods.jist";
         let mut cmd = Command::cargo_bin("jist").unwrap();
-        cmd.arg(file_path)
+        cmd.arg("--dump-state")
+            .arg(file_path)
             .assert()
             .success()
-            .stdout(predicate::str::contains(r#"a: Array<char> = [a, b, c]"#));
+            .stdout(predicate::str::starts_with("[70, 95]\n"))
+            .stdout(predicate::str::contains("\n3\n"))
+            .stdout(predicate::str::contains("\nfalse\n"))
+            .stdout(predicate::str::contains("70 < 82 < 88 < 95 < 99\n"))
+            .stdout(predicate::str::contains("\n[9, 9]\n"))
+            .stdout(predicate::str::contains("\n434\n"))
+            .stdout(predicate::str::contains("\ntrue\n"))
+            .stdout(predicate::str::contains("[al, kim, robin]\n"))
+            .stdout(predicate::str::contains(
+                "[[Al, 70], [Kim, 82], [Robin, 88]]\n",
+            ))
+            .stdout(predicate::str::contains(
+                "scores: Array<int> = [70, 82, 88, 95, 99]",
+            ))
+            .stdout(predicate::str::contains(
+                "names: Array<string> = [Robin, Kim, Al]",
+            ));
     }
 
     #[test]
-    fn test_array_float_collection_declaration() {
-        let file_path = "test_files/array_float_collection_declaration.jist";
+    fn test_array_element_type() {
+        let file_path = "test_files/array_element_type.jist";
         let mut cmd = Command::cargo_bin("jist").unwrap();
         cmd.arg(file_path)
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains(
+                "Runtime Error: Cannot store String in Array<bool>",
+            ));
+    }
+
+    #[test]
+    fn test_nested_collections() {
+        let file_path = "test_files/nested_collections.jist";
+        let mut cmd = Command::cargo_bin("jist").unwrap();
+        cmd.arg("--dump-state")
+            .arg(file_path)
             .assert()
             .success()
+            .stdout(predicate::str::starts_with("9091\n"))
+            .stdout(predicate::str::contains("\neu\n"))
+            .stdout(predicate::str::contains("\n[alpha, beta]\n"))
+            .stdout(predicate::str::contains("\n[30, 4]\n"))
+            .stdout(predicate::str::contains("\n[2, 2]\n"))
             .stdout(predicate::str::contains(
-                r#"a: Array<float> = [1.2300000190734863, 2.2300000190734863, 3.2300000190734863]"#,
+                "grid: Array<array<int>> = [[1, 2], [30, 4]]",
+            ))
+            .stdout(predicate::str::contains(
+                "by_tag: Dict<string, array<string>> = {\"web\" => [alpha], \"db\" => [beta, gamma]}",
             ));
     }
 
     #[test]
-    fn test_array_int_collection_declaration() {
-        let file_path = "test_files/array_int_collection_declaration.jist";
+    fn test_nested_collection_type() {
+        let file_path = "test_files/nested_collection_type.jist";
         let mut cmd = Command::cargo_bin("jist").unwrap();
         cmd.arg(file_path)
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains(
+                "Runtime Error: Cannot assign array<any> to 'grid' of type array<array<int>>",
+            ));
+    }
+
+    #[test]
+    fn test_collection_functions() {
+        let file_path = "test_files/collection_functions.jist";
+        let mut cmd = Command::cargo_bin("jist").unwrap();
+        cmd.arg("--dump-state")
+            .arg(file_path)
             .assert()
             .success()
-            .stdout(predicate::str::contains(r#"a: Array<int> = [1, 2, 3]"#));
+            .stdout(predicate::str::starts_with("[2, 4, 6]\n"))
+            .stdout(predicate::str::contains("\n[55]\n"))
+            .stdout(predicate::str::contains("\n[alpha, beta]\n"))
+            .stdout(predicate::str::contains("\n[1, 2, 3]\n"))
+            .stdout(predicate::str::contains("twice: Array<int> = [2, 4, 6]"));
     }
 
     #[test]
-    fn test_boolean_variable_declaration() {
-        let file_path = "test_files/boolean_variable_declaration.jist";
+    fn test_set_methods() {
+        let file_path = "test_files/set_methods.jist";
         let mut cmd = Command::cargo_bin("jist").unwrap();
-        cmd.arg(file_path)
+        cmd.arg("--dump-state")
+            .arg(file_path)
             .assert()
             .success()
-            .stdout(predicate::str::contains(r#"true"#));
+            .stdout(predicate::str::starts_with("{1, 2, 3, 4}\n"))
+            .stdout(predicate::str::contains("\n{3}\n"))
+            .stdout(predicate::str::contains("\n{1, 2}\n"))
+            .stdout(predicate::str::contains("\ntrue\n"))
+            .stdout(predicate::str::contains("\n3\n"))
+            .stdout(predicate::str::contains("Variable Value: {2, 3, 9}"));
     }
 
     #[test]
-    fn test_dict_float_int_collection_declaration() {
-        let file_path = "test_files/dict_float_int_collection_declaration.jist";
+    fn test_tuple_destructuring() {
+        let file_path = "test_files/tuple_destructuring.jist";
         let mut cmd = Command::cargo_bin("jist").unwrap();
         cmd.arg(file_path)
-        .assert()
-        .success()
-        .stdout(predicate::str::contains(
-            r#"a: Dict<float, int> = {"1.100000023841858" => 1, "2.0999999046325684" => 2, "3.9000000953674316" => 4}"#,
-        ));
+            .assert()
+            .success()
+            .stdout(predicate::str::starts_with("3\n"))
+            .stdout(predicate::str::contains("\n2\n"))
+            .stdout(predicate::str::contains("\n1\n"))
+            .stdout(predicate::str::contains("\n3.5\n"))
+            .stdout(predicate::str::contains("\n(1, 2.5)\n"));
     }
 
     #[test]
-    fn test_dict_int_char_collection_declaration() {
-        let file_path = "test_files/dict_int_char_collection_declaration.jist";
+    fn test_shared_collections() {
+        let file_path = "test_files/shared_collections.jist";
+        let mut cmd = Command::cargo_bin("jist").unwrap();
+        cmd.arg(file_path).assert().success().stdout(
+            "[1, 2, 3]\n[10, 2, 3]\n3\n4\n[10, 2, 3]\n[[1, 7], [2]]\n\
+             {name => jist, self => {...}}\ntrue\n",
+        );
+    }
+
+    #[test]
+    fn test_gc_cycles() {
+        let file_path = "test_files/gc_cycles.jist";
         let mut cmd = Command::cargo_bin("jist").unwrap();
         cmd.arg(file_path)
             .assert()
             .success()
-            .stdout(predicate::str::contains(
-                r#"a: Dict<int, char> = {"1" => a, "2" => b, "3" => c}"#,
-            ));
+            .stdout("0\ntrue\ntrue\n");
     }
 
     #[test]
-    fn test_dict_int_string_collection_declaration() {
-        let file_path = "test_files/dict_int_string_collection_declaration.jist";
+    fn test_fs_report() {
+        let dir = std::env::temp_dir().join(format!("jist_fs_report_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("scores.csv"), "name,score\nann,3\nbob,5\n").unwrap();
+        let file_path = concat!(env!("CARGO_MANIFEST_DIR"), "/test_files/fs_report.jist");
         let mut cmd = Command::cargo_bin("jist").unwrap();
-        cmd.arg(file_path)
+        cmd.current_dir(&dir)
+            .arg(file_path)
             .assert()
             .success()
-            .stdout(predicate::str::contains(
-                r#"a: Dict<int, string> = {"1" => one, "2" => two, "3" => three}"#,
+            .stdout(predicate::str::starts_with("2\nnames: [ann, bob]\n"))
+            .stdout(predicate::str::contains("\n[renamed.txt, report.txt]\n"))
+            .stdout(predicate::str::contains("\n17\n"))
+            .stdout(predicate::str::contains("\nfalse\n"));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_fs_missing_file() {
+        let file_path = "test_files/fs_missing_file.jist";
+        let mut cmd = Command::cargo_bin("jist").unwrap();
+        cmd.arg(file_path)
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains(
+                "Runtime Error: fs.read_to_string(): 'test_files/no_such_file.txt': No such file",
             ));
     }
 
     #[test]
-    fn test_dict_string_float_collection_declaration() {
-        let file_path = "test_files/dict_string_float_collection_declaration.jist";
+    fn test_script_arguments() {
+        let file_path = "test_files/script_arguments.jist";
         let mut cmd = Command::cargo_bin("jist").unwrap();
         cmd.arg(file_path)
+            .args(["a", "b c", "--flag"])
+            .env("JIST_GREETING", "hi")
+            .assert()
+            .code(3)
+            .stdout(predicate::str::starts_with("[a, b c, --flag]\n"))
+            .stdout(predicate::str::contains("\nhi\n"))
+            .stdout(predicate::str::contains("\nfallback\n"))
+            .stdout(predicate::str::contains("not reached").not());
+
+        // only the low byte of a code reaches the shell, so a larger code is an error
+        for code in ["256", "-1"] {
+            let mut cmd = Command::cargo_bin("jist").unwrap();
+            cmd.args(["--color=never", "-e", &format!("exit({});", code)])
+                .assert()
+                .code(1)
+                .stderr(predicate::str::starts_with(format!(
+                    "Runtime Error: exit(): {} is not an exit code, it has to be from 0 to 255",
+                    code
+                )));
+        }
+    }
+
+    #[test]
+    fn test_missing_script_fails() {
+        let mut cmd = Command::cargo_bin("jist").unwrap();
+        cmd.arg("test_files/no_such_script.jist")
+            .assert()
+            .code(1)
+            .stderr(predicate::str::contains(
+                "Failed to read script 'test_files/no_such_script.jist'",
+            ));
+    }
+
+    #[test]
+    fn test_run_subcommand_and_one_liner() {
+        let mut cmd = Command::cargo_bin("jist").unwrap();
+        cmd.args(["run", "test_files/script_arguments.jist", "x"])
+            .assert()
+            .code(3)
+            .stdout(predicate::str::starts_with("[x]\n"));
+
+        let mut cmd = Command::cargo_bin("jist").unwrap();
+        cmd.args(["-e", "echoln(args().len() + 40);", "a", "b"])
+            .assert()
+            .success()
+            .stdout("42\n");
+    }
+
+    #[test]
+    fn test_trace() {
+        // the trace goes to stderr and leaves the output of the script alone
+        let mut cmd = Command::cargo_bin("jist").unwrap();
+        cmd.args(["--trace=runtime:debug", "test_files/debug_session.jist"])
+            .assert()
+            .success()
+            .stdout("7\n11\n")
+            .stderr(predicate::str::contains(
+                "[debug runtime] New variable: Variable { name: \"result\", value: Int(7)",
+            ))
+            .stderr(predicate::str::contains("[trace parser]").not());
+
+        let trace_file =
+            std::env::temp_dir().join(format!("jist_trace_{}.log", std::process::id()));
+        let mut cmd = Command::cargo_bin("jist").unwrap();
+        cmd.env("JIST_TRACE", "parser")
+            .arg(format!("--trace-file={}", trace_file.display()))
+            .args(["run", "test_files/debug_session.jist"])
+            .assert()
+            .success()
+            .stderr("");
+        let trace = std::fs::read_to_string(&trace_file).unwrap();
+        std::fs::remove_file(&trace_file).unwrap();
+        assert!(trace.contains("[trace parser] Node: Return\n"));
+        assert!(!trace.contains("runtime"));
+
+        let mut cmd = Command::cargo_bin("jist").unwrap();
+        cmd.args(["--trace=loops", "test_files/debug_session.jist"])
+            .assert()
+            .code(2)
+            .stderr(predicate::str::contains("Unknown trace category 'loops'"));
+    }
+
+    #[test]
+    fn test_errors_leave_stdout_to_the_script() {
+        let mut cmd = Command::cargo_bin("jist").unwrap();
+        cmd.args(["--color=never", "-e", "echoln(1);\necholn(1 / 0);"])
+            .assert()
+            .code(1)
+            .stdout("1\n")
+            .stderr(predicate::str::starts_with(
+                "Runtime Error: Division by zero\n",
+            ));
+
+        // a reader that stops early, like head, ends the script without a panic
+        let mut child = std::process::Command::new(assert_cmd::cargo::cargo_bin("jist"))
+            .args(["-e", "for (i in 0..100000) {\n    echoln(i);\n}"])
+            .stdout(std::process::Stdio::piped())
+            .stderr(std::process::Stdio::piped())
+            .spawn()
+            .unwrap();
+        let mut first = String::new();
+        std::io::BufRead::read_line(
+            &mut std::io::BufReader::new(child.stdout.take().unwrap()),
+            &mut first,
+        )
+        .unwrap();
+        assert_eq!(first, "0\n");
+        let output = child.wait_with_output().unwrap();
+        assert_eq!(output.status.code(), Some(0));
+        assert_eq!(String::from_utf8(output.stderr).unwrap(), "");
+    }
+
+    #[test]
+    fn test_run_from_stdin() {
+        let mut cmd = Command::cargo_bin("jist").unwrap();
+        cmd.arg("-")
+            .write_stdin("let a: int = 20;\necholn(a * 2);\n")
+            .assert()
+            .success()
+            .stdout(predicate::str::starts_with("40\n"));
+    }
+
+    #[test]
+    fn test_check_subcommand() {
+        let mut cmd = Command::cargo_bin("jist").unwrap();
+        cmd.args(["check", "test_files/check_errors.jist"])
+            .assert()
+            .code(1)
+            .stderr(predicate::str::contains(
+                "Type Error: Cannot assign int to 'name' of type string\n \
+                 --> test_files/check_errors.jist:2:20\n  |\n\
+                 2 | let name: string = 42;\n  \
+                 |                    ^^ expected string, found int\n  \
+                 |     ------------ declared here as string\n",
+            ))
+            .stderr(predicate::str::contains(
+                "Type Error: Cannot assign array<any> to 'factors' of type array<int>\n \
+                 --> test_files/check_errors.jist:4:31\n",
+            ));
+
+        let mut cmd = Command::cargo_bin("jist").unwrap();
+        cmd.args(["check", "test_files/collection_functions.jist"])
+            .assert()
+            .success()
+            .stdout("test_files/collection_functions.jist: no problems found\n");
+    }
+
+    #[test]
+    fn test_syntax_errors_are_all_reported() {
+        let mut cmd = Command::cargo_bin("jist").unwrap();
+        let output = cmd
+            .args(["check", "test_files/syntax_errors.jist"])
+            .output()
+            .unwrap();
+        assert_eq!(output.status.code(), Some(1));
+        assert!(output.stdout.is_empty());
+        let stderr = String::from_utf8(output.stderr).unwrap();
+        let headers: Vec<&str> = stderr
+            .lines()
+            .filter(|line| line.starts_with("Syntax Error"))
+            .collect();
+        assert_eq!(
+            headers,
+            vec![
+                "Syntax Error[E0004]: expected `)`, found `;`",
+                "Syntax Error[E0007]: expected `;`, found `2`",
+                "Syntax Error[E0007]: expected `;`, found `}`",
+                "Syntax Error[E0005]: expected `]`, found `)`",
+            ]
+        );
+        assert!(stderr.contains(
+            " --> test_files/syntax_errors.jist:7:29\n  |\n\
+             7 | let list: array<int> = [1, 2);\n  \
+             |                             ^\n  \
+             |                        - opened here\n"
+        ));
+        assert!(stderr.ends_with("test_files/syntax_errors.jist: 4 syntax errors found\n"));
+
+        // nothing of the script is run, not even the statements in front of the first error
+        let mut cmd = Command::cargo_bin("jist").unwrap();
+        cmd.args(["-e", "echoln(1);\necholn(2 2);"])
+            .assert()
+            .code(1)
+            .stdout("")
+            .stderr(
+                "Syntax Error[E0007]: expected `;`, found `2`\n \
+                 --> <-e>:2:10\n  |\n\
+                 2 | echoln(2 2);\n  \
+                 |          ^\n\n\
+                 <-e>: 1 syntax error found\n",
+            );
+    }
+
+    #[test]
+    fn test_error_rendering_options() {
+        // a runtime error points at the statement it stopped on
+        let mut cmd = Command::cargo_bin("jist").unwrap();
+        cmd.args(["--color=never", "-e", "let a: int = 1;\necholn(a / 0);"])
+            .assert()
+            .code(1)
+            .stderr(predicate::str::contains(
+                "Runtime Error: Division by zero\n \
+                 --> <-e>:2:1\n  |\n\
+                 2 | echoln(a / 0);\n  \
+                 | ^^^^^^^^^^^^^^\n",
+            ));
+
+        // only the failing statement is underlined when it shares its line
+        let mut cmd = Command::cargo_bin("jist").unwrap();
+        cmd.args(["-e", "let a: int = 1; let b: int = a / 0;"])
+            .assert()
+            .code(1)
+            .stderr(predicate::str::contains(
+                " --> <-e>:1:17\n  |\n\
+                 1 | let a: int = 1; let b: int = a / 0;\n  \
+                 |                 ^^^^^^^^^^^^^^^^^^^\n",
+            ));
+
+        let mut cmd = Command::cargo_bin("jist").unwrap();
+        cmd.args([
+            "-e",
+            "func half(x: int) -> int {\n    return x / 0;\n}\necholn(half(4));",
+        ])
+        .assert()
+        .code(1)
+        .stderr(predicate::str::contains(
+            " --> <-e>:2:5\n  |\n\
+             2 |     return x / 0;\n  \
+             |     ^^^^^^^^^^^^^\n  |\n  \
+             = note: in half()\n",
+        ));
+
+        let mut cmd = Command::cargo_bin("jist").unwrap();
+        cmd.args(["--color=always", "check", "-e", "echoln(1"])
+            .assert()
+            .code(1)
+            .stderr(predicate::str::contains(
+                "\x1b[1;31mSyntax Error[E0004]\x1b[0m",
+            ));
+
+        let mut cmd = Command::cargo_bin("jist").unwrap();
+        let output = cmd
+            .args([
+                "check",
+                "--error-format=json",
+                "test_files/check_errors.jist",
+            ])
+            .output()
+            .unwrap();
+        assert_eq!(output.status.code(), Some(1));
+        assert!(output.stdout.is_empty());
+        let stderr = String::from_utf8(output.stderr).unwrap();
+        let errors: Vec<crate::json::Json> = stderr
+            .lines()
+            .map(|line| crate::json::Json::parse(line).unwrap())
+            .collect();
+        assert_eq!(errors.len(), 2);
+        assert_eq!(errors[0].get("kind").as_str(), Some("Type Error"));
+        let spans = errors[0].get("spans").as_array();
+        assert_eq!(spans[0].get("column").as_usize(), Some(20));
+        assert_eq!(
+            spans[1].get("label").as_str(),
+            Some("declared here as string")
+        );
+
+        let mut cmd = Command::cargo_bin("jist").unwrap();
+        cmd.args(["--color=sometimes", "-e", "echoln(1);"])
+            .assert()
+            .code(2)
+            .stderr(predicate::str::contains("Unknown color choice 'sometimes'"));
+    }
+
+    #[test]
+    fn test_tokens_and_ast_subcommands() {
+        let mut cmd = Command::cargo_bin("jist").unwrap();
+        cmd.args(["tokens", "-e", "let a: int = 1;"])
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("   1  AssignmentOperator       ="))
+            .stdout(predicate::str::contains("   1  Int                      1"));
+
+        let mut cmd = Command::cargo_bin("jist").unwrap();
+        cmd.args(["ast", "-e", "func f() -> int {\n    return 1;\n}"])
+            .assert()
+            .success()
+            .stdout(predicate::str::contains(
+                "Statement (line 1)\n    Function: f\n    LeftCurly\n        Return\n        Int: 1\n",
+            ));
+    }
+
+    #[test]
+    fn test_highlight_subcommand() {
+        let source = "let x: int = 1; // one\necholn(\"x\",  x);\n";
+        let mut cmd = Command::cargo_bin("jist").unwrap();
+        let output = cmd.args(["highlight", "-e", source]).output().unwrap();
+        assert!(output.status.success());
+        let stdout = String::from_utf8(output.stdout).unwrap();
+        assert!(stdout.contains("\x1b[34m1\x1b[0m"));
+        assert!(stdout.contains("\x1b[32m\"x\"\x1b[0m"));
+        // without the colours it is the script as written
+        let mut plain = String::new();
+        let mut chars = stdout.chars();
+        while let Some(c) = chars.next() {
+            if c == '\x1b' {
+                chars.by_ref().find(|c| *c == 'm');
+            } else {
+                plain.push(c);
+            }
+        }
+        assert_eq!(plain, source);
+    }
+
+    #[test]
+    fn test_fmt_subcommand() {
+        let mut cmd = Command::cargo_bin("jist").unwrap();
+        cmd.args(["fmt", "test_files/unformatted.jist"])
+            .assert()
+            .success()
+            .stdout(
+                "// Doubles every value\nfunc double(a: int) -> int {\n    return a * 2;\n}\nlet values: array<int> = [1, 2];\nif (values.len() > 1) {\n    echoln(values.map(double));\n} // more than one\nelse {\n    /* nothing to double */\n    echoln(-1);\n}\n",
+            );
+
+        let mut cmd = Command::cargo_bin("jist").unwrap();
+        cmd.args([
+            "fmt",
+            "--check",
+            "test_files/set_methods.jist",
+            "test_files/unformatted.jist",
+        ])
+        .assert()
+        .code(1)
+        .stdout("test_files/unformatted.jist:2: not formatted\n");
+    }
+
+    #[test]
+    fn test_fmt_write_with_config() {
+        let dir = std::env::temp_dir().join(format!("jist_fmt_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("jist.toml"), "[fmt]\nindent_width = 2\n").unwrap();
+        std::fs::write(dir.join("a.jist"), "if (true) {\necholn(1);\n}\n").unwrap();
+
+        let mut cmd = Command::cargo_bin("jist").unwrap();
+        cmd.current_dir(&dir)
+            .args(["fmt", "--write", "a.jist"])
+            .assert()
+            .success()
+            .stdout("Formatted a.jist\n");
+        assert_eq!(
+            std::fs::read_to_string(dir.join("a.jist")).unwrap(),
+            "if (true) {\n  echoln(1);\n}\n"
+        );
+        let mut cmd = Command::cargo_bin("jist").unwrap();
+        cmd.current_dir(&dir)
+            .args(["fmt", "--check", "a.jist"])
+            .assert()
+            .success()
+            .stdout("");
+
+        std::fs::write(dir.join("jist.toml"), "[fmt]\nindent = 2\n").unwrap();
+        let mut cmd = Command::cargo_bin("jist").unwrap();
+        cmd.current_dir(&dir)
+            .args(["fmt", "a.jist"])
+            .assert()
+            .code(1)
+            .stderr("a.jist: jist.toml:2: Unknown fmt setting 'indent'\n");
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_lint_subcommand() {
+        let mut cmd = Command::cargo_bin("jist").unwrap();
+        cmd.args(["--color=never", "lint", "test_files/lint_problems.jist"])
+            .assert()
+            .code(1)
+            .stdout("")
+            .stderr(predicate::str::contains(
+                "Warning[float_truncation]: float stored in int 'count' drops the fraction\n \
+                 --> test_files/lint_problems.jist:3:18\n  |\n\
+                 3 | let count: int = limit;\n  \
+                 |                  ^^^^^\n",
+            ))
+            .stderr(predicate::str::contains(
+                "Warning[unused_variable]: unused parameter 'unused'\n \
+                 --> test_files/lint_problems.jist:6:27\n",
+            ))
+            .stderr(predicate::str::contains(
+                "Warning[shadowed_name]: 'label' shadows the variable declared at line 4\n \
+                 --> test_files/lint_problems.jist:7:9\n  |\n\
+                 4 | let label: string = \"count\";\n  \
+                 |     ----- the variable it hides\n",
+            ))
+            .stderr(predicate::str::contains(
+                "Error[unreachable_code]: unreachable code after return\n \
+                 --> test_files/lint_problems.jist:9:5\n",
+            ))
+            .stderr(predicate::str::contains(
+                "Warning[unused_function]: unused function 'helper'\n  \
+                 --> test_files/lint_problems.jist:12:6\n",
+            ))
+            .stderr(predicate::str::contains(
+                "Warning[constant_condition]: if condition is always false\n  \
+                 --> test_files/lint_problems.jist:20:5\n",
+            ))
+            .stderr(predicate::str::contains(
+                "Error[mismatched_comparison]: comparing string with int using '==' is always false\n  \
+                 --> test_files/lint_problems.jist:23:5\n",
+            ))
+            .stderr(predicate::str::contains(":17:").not());
+
+        // lint errors are written like the others, --format=json is --error-format=json
+        for arguments in [
+            ["lint", "--format=json", "-e"],
+            ["--error-format=json", "lint", "-e"],
+        ] {
+            let mut cmd = Command::cargo_bin("jist").unwrap();
+            let output = cmd
+                .args(arguments)
+                .arg("if (false) {\n    echoln(1);\n}")
+                .output()
+                .unwrap();
+            assert_eq!(output.status.code(), Some(0));
+            assert!(output.stdout.is_empty());
+            let error =
+                crate::json::Json::parse(String::from_utf8(output.stderr).unwrap().trim()).unwrap();
+            assert_eq!(error.get("kind").as_str(), Some("Warning"));
+            assert_eq!(error.get("code").as_str(), Some("constant_condition"));
+            assert_eq!(
+                error.get("spans").as_array()[0].get("column").as_usize(),
+                Some(5)
+            );
+        }
+
+        let mut cmd = Command::cargo_bin("jist").unwrap();
+        cmd.args(["--color=always", "lint", "-e", "let a: int = 1;"])
+            .assert()
+            .success()
+            .stderr(predicate::str::contains(
+                "\x1b[1;33mWarning[unused_variable]\x1b[0m",
+            ));
+
+        let mut cmd = Command::cargo_bin("jist").unwrap();
+        cmd.args(["lint", "test_files/collection_functions.jist"])
+            .assert()
+            .success()
+            .stdout("test_files/collection_functions.jist: no problems found\n");
+    }
+
+    #[test]
+    fn test_lint_levels_from_config() {
+        let dir = std::env::temp_dir().join(format!("jist_lint_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(
+            dir.join("jist.toml"),
+            "[lint]\nunused_function = \"deny\"\n",
+        )
+        .unwrap();
+        std::fs::write(dir.join("a.jist"), "func f() -> int {\n    return 1;\n}\n").unwrap();
+
+        let mut cmd = Command::cargo_bin("jist").unwrap();
+        cmd.current_dir(&dir)
+            .args(["lint", "a.jist"])
+            .assert()
+            .code(1)
+            .stderr(predicate::str::starts_with(
+                "Error[unused_function]: unused function 'f'\n --> a.jist:1:6\n",
+            ));
+
+        std::fs::write(dir.join("jist.toml"), "[lint]\nunused_function = \"off\"\n").unwrap();
+        let mut cmd = Command::cargo_bin("jist").unwrap();
+        cmd.current_dir(&dir)
+            .args(["lint", "a.jist"])
+            .assert()
+            .code(1)
+            .stderr(
+                "a.jist: jist.toml:2: The level of unused_function must be allow, warn or deny, found 'off'\n",
+            );
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    ///
+    ///Runs jist lsp with the messages as an editor would send them and returns the messages it
+    ///sent back, with its exit code
+    ///
+    fn lsp_session(messages: &[String]) -> (Vec<crate::json::Json>, i32) {
+        let input: String = messages
+            .iter()
+            .map(|m| format!("Content-Length: {}\r\n\r\n{}", m.len(), m))
+            .collect();
+        let mut cmd = Command::cargo_bin("jist").unwrap();
+        let output = cmd.arg("lsp").write_stdin(input).output().unwrap();
+
+        let mut stdout = String::from_utf8(output.stdout).unwrap();
+        let mut replies = Vec::new();
+        while let Some((header, rest)) = stdout.split_once("\r\n\r\n") {
+            let length: usize = header["Content-Length: ".len()..].parse().unwrap();
+            replies.push(crate::json::Json::parse(&rest[..length]).unwrap());
+            stdout = rest[length..].to_string();
+        }
+        (replies, output.status.code().unwrap())
+    }
+
+    #[test]
+    fn test_lsp_session() {
+        let uri = "file:///tmp/session.jist";
+        let text = "let total: int = 1;\nfunc add(a: int, b: int) -> int {\nreturn a + b;\n}\necholn(add(total, \"2\" == 2));\n";
+        let request = |id: usize, method: &str, line: usize, character: usize| {
+            format!(
+                r#"{{"jsonrpc": "2.0", "id": {}, "method": "{}", "params": {{"textDocument": {{"uri": "{}"}}, "position": {{"line": {}, "character": {}}}, "context": {{"includeDeclaration": true}}}}}}"#,
+                id, method, uri, line, character
+            )
+        };
+        let messages = vec![
+            r#"{"jsonrpc": "2.0", "id": 0, "method": "initialize", "params": {"capabilities": {}}}"#.to_string(),
+            r#"{"jsonrpc": "2.0", "method": "initialized", "params": {}}"#.to_string(),
+            format!(
+                r#"{{"jsonrpc": "2.0", "method": "textDocument/didOpen", "params": {{"textDocument": {{"uri": "{}", "languageId": "jist", "version": 1, "text": {}}}}}}}"#,
+                uri,
+                crate::state_dump::json_string(text)
+            ),
+            request(1, "textDocument/definition", 4, 12),
+            request(2, "textDocument/references", 2, 7),
+            request(3, "textDocument/hover", 4, 8),
+            request(4, "textDocument/completion", 2, 0),
+            request(5, "textDocument/semanticTokens/full", 0, 0),
+            request(6, "textDocument/formatting", 0, 0),
+            request(7, "textDocument/unknown", 0, 0),
+            r#"{"jsonrpc": "2.0", "id": 8, "method": "shutdown"}"#.to_string(),
+            r#"{"jsonrpc": "2.0", "method": "exit"}"#.to_string(),
+        ];
+        let (replies, code) = lsp_session(&messages);
+        assert_eq!(code, 0);
+        let reply = |id: usize| {
+            replies
+                .iter()
+                .find(|r| r.get("id").as_usize() == Some(id))
+                .unwrap()
+                .get("result")
+        };
+
+        let capabilities = reply(0).get("capabilities");
+        assert_eq!(capabilities.get("hoverProvider").as_bool(), Some(true));
+        assert_eq!(
+            capabilities
+                .get("semanticTokensProvider")
+                .get("legend")
+                .get("tokenTypes")
+                .as_array()
+                .len(),
+            6
+        );
+
+        let published = replies
+            .iter()
+            .find(|r| r.get("method").as_str() == Some("textDocument/publishDiagnostics"))
+            .unwrap();
+        let diagnostics = published.get("params").get("diagnostics").as_array();
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(
+            diagnostics[0].get("code").as_str(),
+            Some("mismatched_comparison")
+        );
+        assert_eq!(
+            diagnostics[0]
+                .get("range")
+                .get("start")
+                .get("line")
+                .as_usize(),
+            Some(4)
+        );
+
+        // total in add(total, ...) is declared on the first line
+        let definition = reply(1).get("range").get("start");
+        assert_eq!(definition.get("line").as_usize(), Some(0));
+        assert_eq!(definition.get("character").as_usize(), Some(4));
+
+        // the parameter a, where it is declared and in return a + b
+        let references: Vec<(usize, usize)> = reply(2)
+            .as_array()
+            .iter()
+            .map(|r| {
+                let start = r.get("range").get("start");
+                (
+                    start.get("line").as_usize().unwrap(),
+                    start.get("character").as_usize().unwrap(),
+                )
+            })
+            .collect();
+        assert_eq!(references, vec![(1, 9), (2, 7)]);
+
+        assert_eq!(
+            reply(3).get("contents").get("value").as_str(),
+            Some("```jist\nfunc add(a: int, b: int) -> int\n```")
+        );
+
+        let labels: Vec<&str> = reply(4)
+            .as_array()
+            .iter()
+            .filter_map(|item| item.get("label").as_str())
+            .collect();
+        for label in ["total", "add", "a", "b", "echoln", "sqrt", "while"] {
+            assert!(labels.contains(&label), "no completion for {}", label);
+        }
+
+        // the = and 1 on the first line come first
+        let data: Vec<usize> = reply(5)
+            .get("data")
+            .as_array()
+            .iter()
+            .map(|n| n.as_usize().unwrap())
+            .collect();
+        assert_eq!(&data[..10], &[0, 15, 1, 2, 0, 0, 2, 1, 0, 0]);
+
+        let edits = reply(6).as_array();
+        assert_eq!(edits.len(), 1);
+        assert!(edits[0]
+            .get("newText")
+            .as_str()
+            .unwrap()
+            .contains("\n    return a + b;\n"));
+
+        let unknown = replies
+            .iter()
+            .find(|r| r.get("id").as_usize() == Some(7))
+            .unwrap();
+        assert_eq!(
+            unknown.get("error").get("code"),
+            &crate::json::Json::Number(-32601.0)
+        );
+        assert_eq!(reply(8), &crate::json::Json::Null);
+    }
+
+    #[test]
+    fn test_lsp_invalid_literals() {
+        let uri = "file:///tmp/literals.jist";
+        let text = "let a: string = \"\\q\";\nlet b: char = 'ab';\nlet c: int = 99999999999;\necholn(a, b, c);\n";
+        let request = |id: usize, method: &str| {
+            format!(
+                r#"{{"jsonrpc": "2.0", "id": {}, "method": "{}", "params": {{"textDocument": {{"uri": "{}"}}, "position": {{"line": 3, "character": 8}}}}}}"#,
+                id, method, uri
+            )
+        };
+        let messages = vec![
+            r#"{"jsonrpc": "2.0", "id": 0, "method": "initialize", "params": {"capabilities": {}}}"#.to_string(),
+            format!(
+                r#"{{"jsonrpc": "2.0", "method": "textDocument/didOpen", "params": {{"textDocument": {{"uri": "{}", "languageId": "jist", "version": 1, "text": {}}}}}}}"#,
+                uri,
+                crate::state_dump::json_string(text)
+            ),
+            request(1, "textDocument/hover"),
+            request(2, "textDocument/semanticTokens/full"),
+            request(3, "textDocument/formatting"),
+            r#"{"jsonrpc": "2.0", "id": 4, "method": "shutdown"}"#.to_string(),
+            r#"{"jsonrpc": "2.0", "method": "exit"}"#.to_string(),
+        ];
+        // the server keeps running and reports each literal where it is
+        let (replies, code) = lsp_session(&messages);
+        assert_eq!(code, 0);
+        let published = replies
+            .iter()
+            .find(|r| r.get("method").as_str() == Some("textDocument/publishDiagnostics"))
+            .unwrap();
+        let diagnostics: Vec<(usize, &str)> = published
+            .get("params")
+            .get("diagnostics")
+            .as_array()
+            .iter()
+            .map(|d| {
+                let line = d.get("range").get("start").get("line").as_usize();
+                (line.unwrap(), d.get("code").as_str().unwrap())
+            })
+            .collect();
+        assert_eq!(diagnostics, vec![(0, "E0013"), (1, "E0013"), (2, "E0013")]);
+        for id in 1..=4 {
+            assert!(replies.iter().any(|r| r.get("id").as_usize() == Some(id)));
+        }
+
+        // the other tools report them too instead of stopping
+        for command in ["check", "lint"] {
+            let mut cmd = Command::cargo_bin("jist").unwrap();
+            cmd.args(["--color=never", command, "-e", text])
+                .assert()
+                .code(1)
+                .stderr(predicate::str::ends_with("<-e>: 3 syntax errors found\n"));
+        }
+    }
+
+    #[test]
+    fn test_lsp_exit_without_shutdown() {
+        let (replies, code) = lsp_session(&[r#"{"jsonrpc": "2.0", "method": "exit"}"#.to_string()]);
+        assert!(replies.is_empty());
+        assert_eq!(code, 1);
+    }
+
+    #[test]
+    fn test_debug_console() {
+        let mut cmd = Command::cargo_bin("jist").unwrap();
+        let output = cmd
+            .args(["debug", "test_files/debug_session.jist"])
+            .write_stdin("break 4 if n > 4\ncontinue\nbt\nlocals\nprint sum + 1\nfinish\nnext\n")
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        let stdout = String::from_utf8(output.stdout).unwrap();
+        for expected in [
+            "Stopped at line 2 in script (entry)",
+            "(jist) Breakpoint at line 4",
+            "Stopped at line 4 in total (breakpoint)\n   4 |     return sum + 1;",
+            "(jist) #0 total at line 4\n#1 script at line 9\n",
+            "(jist) n: int = 5\nsum: int = 10\n",
+            "(jist) 11\n",
+            "The script finished",
+        ] {
+            assert!(
+                stdout.contains(expected),
+                "{:?} is missing in\n{}",
+                expected,
+                stdout
+            );
+        }
+        // the breakpoint condition was false on the first call
+        assert!(stdout.find("(jist) 7\n").unwrap() < stdout.find("(breakpoint)").unwrap());
+    }
+
+    #[test]
+    fn test_debug_checks_bindings() {
+        let mut cmd = Command::cargo_bin("jist").unwrap();
+        cmd.args(["debug", "-e", "let x: int = 1;\nx = 2;\n"])
+            .write_stdin("continue\n")
+            .assert()
+            .failure()
+            .stdout("")
+            .stderr(predicate::str::contains(
+                "Syntax Error[E0009]: cannot assign to immutable variable `x`\n --> <-e>:2:1\n",
+            ));
+    }
+
+    ///
+    ///Reads the next message jist debug --dap sends that is not output of the script
+    ///
+    fn dap_message(stdout: &mut impl std::io::BufRead) -> crate::json::Json {
+        loop {
+            let mut length = 0;
+            loop {
+                let mut header = String::new();
+                stdout.read_line(&mut header).unwrap();
+                match header.trim().strip_prefix("Content-Length: ") {
+                    Some(value) => length = value.parse().unwrap(),
+                    None if header.trim().is_empty() => break,
+                    None => {}
+                }
+            }
+            let mut body = vec![0; length];
+            stdout.read_exact(&mut body).unwrap();
+            let message = crate::json::Json::parse(&String::from_utf8(body).unwrap()).unwrap();
+            if message.get("event").as_str() != Some("output") {
+                return message;
+            }
+        }
+    }
+
+    #[test]
+    fn test_debug_adapter() {
+        use std::io::{BufReader, Write};
+        let mut adapter = std::process::Command::new(assert_cmd::cargo::cargo_bin("jist"))
+            .args(["debug", "--dap"])
+            .stdin(std::process::Stdio::piped())
+            .stdout(std::process::Stdio::piped())
+            .spawn()
+            .unwrap();
+        let mut stdin = adapter.stdin.take().unwrap();
+        let mut stdout = BufReader::new(adapter.stdout.take().unwrap());
+        let mut send = |seq: usize, command: &str, arguments: &str| {
+            let message = format!(
+                r#"{{"seq": {}, "type": "request", "command": "{}", "arguments": {}}}"#,
+                seq, command, arguments
+            );
+            write!(
+                stdin,
+                "Content-Length: {}\r\n\r\n{}",
+                message.len(),
+                message
+            )
+            .unwrap();
+            stdin.flush().unwrap();
+        };
+
+        send(1, "initialize", r#"{"adapterID": "jist"}"#);
+        assert_eq!(
+            dap_message(&mut stdout)
+                .get("body")
+                .get("supportsConditionalBreakpoints")
+                .as_bool(),
+            Some(true)
+        );
+        send(
+            2,
+            "launch",
+            r#"{"program": "test_files/debug_session.jist"}"#,
+        );
+        assert_eq!(
+            dap_message(&mut stdout).get("success").as_bool(),
+            Some(true)
+        );
+        assert_eq!(
+            dap_message(&mut stdout).get("event").as_str(),
+            Some("initialized")
+        );
+        send(
+            3,
+            "setBreakpoints",
+            r#"{"source": {"path": "test_files/debug_session.jist"}, "breakpoints": [{"line": 4, "condition": "n == 5"}, {"line": 40}]}"#,
+        );
+        let breakpoints = dap_message(&mut stdout);
+        let breakpoints = breakpoints.get("body").get("breakpoints").as_array();
+        assert_eq!(breakpoints[0].get("verified").as_bool(), Some(true));
+        assert_eq!(breakpoints[1].get("verified").as_bool(), Some(false));
+        send(4, "configurationDone", "{}");
+        assert_eq!(
+            dap_message(&mut stdout).get("command").as_str(),
+            Some("configurationDone")
+        );
+        let stopped = dap_message(&mut stdout);
+        assert_eq!(stopped.get("event").as_str(), Some("stopped"));
+        assert_eq!(
+            stopped.get("body").get("reason").as_str(),
+            Some("breakpoint")
+        );
+
+        send(5, "stackTrace", r#"{"threadId": 1}"#);
+        let trace = dap_message(&mut stdout);
+        let frames = trace.get("body").get("stackFrames").as_array();
+        let frames: Vec<_> = frames
+            .iter()
+            .map(|f| {
+                (
+                    f.get("name").as_str().unwrap(),
+                    f.get("line").as_usize().unwrap(),
+                )
+            })
+            .collect();
+        assert_eq!(frames, vec![("total", 4), ("script", 9)]);
+
+        send(6, "scopes", r#"{"frameId": 1}"#);
+        let scopes = dap_message(&mut stdout);
+        let names: Vec<_> = scopes
+            .get("body")
+            .get("scopes")
+            .as_array()
+            .iter()
+            .map(|s| s.get("name").as_str().unwrap())
+            .collect();
+        assert_eq!(names, vec!["Locals", "Globals"]);
+        let variables = |message: &crate::json::Json| -> Vec<String> {
+            message
+                .get("body")
+                .get("variables")
+                .as_array()
+                .iter()
+                .map(|v| {
+                    format!(
+                        "{}: {} = {}",
+                        v.get("name").as_str().unwrap(),
+                        v.get("type").as_str().unwrap(),
+                        v.get("value").as_str().unwrap()
+                    )
+                })
+                .collect()
+        };
+        send(7, "variables", r#"{"variablesReference": 1}"#);
+        assert_eq!(
+            variables(&dap_message(&mut stdout)),
+            vec!["n: int = 5", "sum: int = 10"]
+        );
+        send(8, "variables", r#"{"variablesReference": 2}"#);
+        assert_eq!(
+            variables(&dap_message(&mut stdout)),
+            vec!["values: array<int> = [1, 2, 3]", "result: int = 7"]
+        );
+        // the elements of the array
+        send(9, "variables", r#"{"variablesReference": 3}"#);
+        assert_eq!(
+            variables(&dap_message(&mut stdout)),
+            vec!["[0]: int = 1", "[1]: int = 2", "[2]: int = 3"]
+        );
+        send(10, "evaluate", r#"{"expression": "sum * 10"}"#);
+        assert_eq!(
+            dap_message(&mut stdout).get("body").get("result").as_str(),
+            Some("100")
+        );
+
+        send(11, "continue", r#"{"threadId": 1}"#);
+        assert_eq!(
+            dap_message(&mut stdout).get("success").as_bool(),
+            Some(true)
+        );
+        let exited = dap_message(&mut stdout);
+        assert_eq!(exited.get("event").as_str(), Some("exited"));
+        assert_eq!(exited.get("body").get("exitCode").as_usize(), Some(0));
+        assert_eq!(
+            dap_message(&mut stdout).get("event").as_str(),
+            Some("terminated")
+        );
+        send(12, "disconnect", "{}");
+        assert_eq!(
+            dap_message(&mut stdout).get("command").as_str(),
+            Some("disconnect")
+        );
+        assert!(adapter.wait().unwrap().success());
+    }
+
+    #[test]
+    fn test_help_version_and_usage_errors() {
+        let mut cmd = Command::cargo_bin("jist").unwrap();
+        cmd.arg("--version")
+            .assert()
+            .success()
+            .stdout(format!("jist {}\n", env!("CARGO_PKG_VERSION")));
+
+        let mut cmd = Command::cargo_bin("jist").unwrap();
+        cmd.arg("--help")
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("Usage: jist"));
+
+        let mut cmd = Command::cargo_bin("jist").unwrap();
+        cmd.args(["check"])
+            .assert()
+            .code(2)
+            .stderr(predicate::str::contains("No file path provided"));
+    }
+
+    #[test]
+    fn test_repl() {
+        let mut cmd = Command::cargo_bin("jist").unwrap();
+        cmd.arg("repl")
+            .write_stdin("let a: int = 2;\nif (a > 1) {\n    echoln(a * 21);\n}\n:quit\n")
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("jist> "))
+            .stdout(predicate::str::contains("...> "))
+            .stdout(predicate::str::contains("42\n"));
+    }
+
+    #[test]
+    fn test_repl_keeps_running_after_an_error() {
+        let mut cmd = Command::cargo_bin("jist").unwrap();
+        cmd.arg("repl")
+            .write_stdin("let a: int = 2;\necholn(y);\necholn(a + 1);\n:quit\n")
+            .assert()
+            .success()
+            .stderr(predicate::str::contains(
+                "Runtime Error: Variable 'y' is not defined\n",
+            ))
+            .stdout(predicate::str::contains("3\n"));
+    }
+
+    #[test]
+    fn test_repl_checks_bindings() {
+        let mut cmd = Command::cargo_bin("jist").unwrap();
+        let output = cmd
+            .arg("repl")
+            .write_stdin(
+                "let x: int = 1;\nx = 3;\nconst C: int = 1;\nC = 5;\necholn(x + C);\n:quit\n",
+            )
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        let stdout = String::from_utf8(output.stdout).unwrap();
+        let stderr = String::from_utf8(output.stderr).unwrap();
+        assert!(stderr.contains(
+            "Syntax Error[E0009]: cannot assign to immutable variable `x`\n --> <repl>:1:1\n"
+        ));
+        assert!(stderr.contains("<repl>:1:1"), "{}", stderr);
+        assert_eq!(stderr.matches("Syntax Error").count(), 2, "{}", stderr);
+        // neither assignment ran
+        assert!(stdout.contains("2\n"), "{}", stdout);
+        assert!(
+            !stdout.contains("3\n") && !stdout.contains("5\n"),
+            "{}",
+            stdout
+        );
+    }
+
+    #[test]
+    fn test_function_argument_type() {
+        let file_path = "test_files/function_argument_type.jist";
+        let mut cmd = Command::cargo_bin("jist").unwrap();
+        cmd.arg(file_path)
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains(
+                "Runtime Error: doubled(): Argument 'values' should be array<int> but found array<string>",
+            ));
+    }
+
+    #[test]
+    fn test_dict_boolean_string_collection_declaration() {
+        let file_path = "test_files/dict_boolean_string_collection_declaration.jist";
+        let mut cmd = Command::cargo_bin("jist").unwrap();
+        cmd.arg(file_path)
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains(
+                "Runtime Error: Duplicate key true in dictionary literal",
+            ));
+    }
+
+    #[test]
+    fn test_dictionary_methods() {
+        let file_path = "test_files/dictionary_methods.jist";
+        let mut cmd = Command::cargo_bin("jist").unwrap();
+        cmd.arg("--dump-state")
+            .arg(file_path)
+            .assert()
+            .success()
+            .stdout(predicate::str::starts_with("31\n"))
+            .stdout(predicate::str::contains("\ntrue\n"))
+            .stdout(predicate::str::contains("\n27\n"))
+            .stdout(predicate::str::contains("\n[ann, cy]\n"))
+            .stdout(predicate::str::contains("\n[[ann, 32], [cy, 40]]\n"))
+            .stdout(predicate::str::contains("\n0\n"))
+            .stdout(predicate::str::contains(
+                "\n{ann => 32, cy => 41, dee => 19}\n",
+            ))
+            .stdout(predicate::str::contains(
+                r#"ages: Dict<string, int> = {"ann" => 32, "cy" => 41, "dee" => 19}"#,
+            ));
+    }
+
+    #[test]
+    fn test_dictionary_missing_key() {
+        let file_path = "test_files/dictionary_missing_key.jist";
+        let mut cmd = Command::cargo_bin("jist").unwrap();
+        cmd.arg(file_path)
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains(
+                "Runtime Error: Key \"nobody\" not found",
+            ));
+
+        // a key type that cannot be hashed is refused where it is declared, even when empty
+        for (source, error) in [
+            (
+                "let d: dict<array<int>, int> = {};",
+                "Type Error: array<int> cannot be used as a dictionary key",
+            ),
+            (
+                "let a: array<set<dict<int, int>>> = [];",
+                "Type Error: dict<int, int> cannot be used as a set element",
+            ),
+        ] {
+            let mut cmd = Command::cargo_bin("jist").unwrap();
+            cmd.args(["--color=never", "-e", source])
+                .assert()
+                .code(1)
+                .stderr(predicate::str::starts_with(error));
+        }
+
+        // an index into a dictionary in a collection reports the key the same way
+        let mut cmd = Command::cargo_bin("jist").unwrap();
+        cmd.args([
+            "-e",
+            "let a: array<dict<string, int>> = [{\"a\" => 1}];\necholn(a[0][\"zz\"]);",
+        ])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "Runtime Error: Key \"zz\" not found",
+        ));
+    }
+
+    #[test]
+    fn test_array_boolean_collection_declaration() {
+        let file_path = "test_files/array_boolean_collection_declaration.jist";
+        let mut cmd = Command::cargo_bin("jist").unwrap();
+        cmd.arg("--dump-state")
+            .arg(file_path)
+            .assert()
+            .success()
+            .stdout(predicate::str::contains(
+                r#"a: Array<bool> = [true, false, true]"#,
+            ));
+    }
+
+    #[test]
+    fn test_array_char_collection_declaration() {
+        let file_path = "test_files/array_char_collection_declaration.jist";
+        let mut cmd = Command::cargo_bin("jist").unwrap();
+        cmd.arg("--dump-state")
+            .arg(file_path)
+            .assert()
+            .success()
+            .stdout(predicate::str::contains(r#"a: Array<char> = [a, b, c]"#));
+    }
+
+    #[test]
+    fn test_array_float_collection_declaration() {
+        let file_path = "test_files/array_float_collection_declaration.jist";
+        let mut cmd = Command::cargo_bin("jist").unwrap();
+        cmd.arg("--dump-state")
+            .arg(file_path)
+            .assert()
+            .success()
+            .stdout(predicate::str::contains(
+                r#"a: Array<float> = [1.2300000190734863, 2.2300000190734863, 3.2300000190734863]"#,
+            ));
+    }
+
+    #[test]
+    fn test_array_int_collection_declaration() {
+        let file_path = "test_files/array_int_collection_declaration.jist";
+        let mut cmd = Command::cargo_bin("jist").unwrap();
+        cmd.arg("--dump-state")
+            .arg(file_path)
+            .assert()
+            .success()
+            .stdout(predicate::str::contains(r#"a: Array<int> = [1, 2, 3]"#));
+    }
+
+    #[test]
+    fn test_boolean_variable_declaration() {
+        let file_path = "test_files/boolean_variable_declaration.jist";
+        let mut cmd = Command::cargo_bin("jist").unwrap();
+        cmd.arg("--dump-state")
+            .arg(file_path)
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("Variable Value: true"));
+    }
+
+    #[test]
+    fn test_dict_float_int_collection_declaration() {
+        let file_path = "test_files/dict_float_int_collection_declaration.jist";
+        let mut cmd = Command::cargo_bin("jist").unwrap();
+        cmd.arg("--dump-state")
+            .arg(file_path)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            r#"a: Dict<float, int> = {"1.100000023841858" => 1, "2.0999999046325684" => 2, "3.9000000953674316" => 4}"#,
+        ));
+    }
+
+    #[test]
+    fn test_dict_int_char_collection_declaration() {
+        let file_path = "test_files/dict_int_char_collection_declaration.jist";
+        let mut cmd = Command::cargo_bin("jist").unwrap();
+        cmd.arg("--dump-state")
+            .arg(file_path)
+            .assert()
+            .success()
+            .stdout(predicate::str::contains(
+                r#"a: Dict<int, char> = {"1" => a, "2" => b, "3" => c}"#,
+            ));
+    }
+
+    #[test]
+    fn test_dict_int_string_collection_declaration() {
+        let file_path = "test_files/dict_int_string_collection_declaration.jist";
+        let mut cmd = Command::cargo_bin("jist").unwrap();
+        cmd.arg("--dump-state")
+            .arg(file_path)
+            .assert()
+            .success()
+            .stdout(predicate::str::contains(
+                r#"a: Dict<int, string> = {"1" => one, "2" => two, "3" => three}"#,
+            ));
+    }
+
+    #[test]
+    fn test_dict_string_float_collection_declaration() {
+        let file_path = "test_files/dict_string_float_collection_declaration.jist";
+        let mut cmd = Command::cargo_bin("jist").unwrap();
+        cmd.arg("--dump-state")
+            .arg(file_path)
         .assert()
         .success()
         .stdout(predicate::str::contains(
@@ -5,6 +5,8 @@
 
 pub mod nodes {
     use crate::base_variable::base_types::BaseTypes;
+    use crate::globals::report_error;
+    use crate::statement_tokenizer::basic_tokenizer::basic_tokenizers::unescape_literal;
     use crate::statement_tokenizer::tokenizer::tokenizers::ParseInfo;
     use crate::token_type::token_types::*;
     use std::fmt;
@@ -42,6 +44,8 @@ pub mod nodes {
             ASTNode::RightBracket => Some(BaseTypes::Null),
             ASTNode::LeftBracket => Some(BaseTypes::Null),
             ASTNode::FatArrow => Some(BaseTypes::Null),
+            ASTNode::Return => Some(BaseTypes::Null),
+            ASTNode::Destructure(_) => Some(BaseTypes::Null),
             ASTNode::While(_) => Some(BaseTypes::Null),
             ASTNode::For(_) => Some(BaseTypes::Null),
             ASTNode::If(_) => Some(BaseTypes::Null),
@@ -106,6 +110,8 @@ pub mod nodes {
         LeftCurly,
         RightCurly,
         FatArrow,
+        Return,
+        Destructure(DestructureNode),
         None,
     }
 
@@ -152,6 +158,8 @@ pub mod nodes {
                 ASTNode::FunctionCallArguments(call_args) => write!(f, "{}", call_args), // Call Display
                 ASTNode::FunctionArguments(args) => write!(f, "{}", args), // Call Display
                 ASTNode::FatArrow => write!(f, "FatArrow"),
+                ASTNode::Return => write!(f, "Return"),
+                ASTNode::Destructure(d) => write!(f, "{}", d),
                 ASTNode::None => write!(f, "None"),
             }
         }
@@ -484,6 +492,23 @@ pub mod nodes {
         }
     }
 
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct DestructureNode {
+        pub names: Vec<String>,
+    }
+
+    impl DestructureNode {
+        pub fn new(names: Vec<String>) -> Self {
+            DestructureNode { names }
+        }
+    }
+
+    impl fmt::Display for DestructureNode {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            write!(f, "Destructure: ({})", self.names.join(", "))
+        }
+    }
+
     #[derive(Debug, Clone, PartialEq)]
     pub struct ReturnTypeAssignmentNode {
         pub value: String,
@@ -777,40 +802,57 @@ pub mod nodes {
     }
 
     ///
-    ///Checks to see if the parse info token is a valid ASTNode
+    ///Checks to see if the parse info token is a valid ASTNode, a syntax error ends the script
     ///
     ///args: parse_info: ParseInfo, the tokenized info to be checked
     ///
     ///returns: ASTNode, the ASTNode that corresponds to the token
     ///
     pub fn match_token_to_node(parse_info: ParseInfo) -> ASTNode {
-        match parse_info.token {
-            TokenTypes::Int => {
-                if let Ok(value) = parse_info.value.parse::<i32>() {
-                    ASTNode::Int(IntNode::new(value))
-                } else if let Ok(value) = parse_info.value.parse::<i32>() {
-                    ASTNode::Int(IntNode::new(value))
-                } else {
-                    panic!("Failed to parse Int: {}", parse_info.value);
-                }
-            }
+        token_to_node(parse_info).unwrap_or_else(|e| report_error(format!("Syntax Error: {}", e)))
+    }
+
+    ///
+    ///The ASTNode that corresponds to the token, or why its literal is not valid: an int that
+    ///does not fit in an int, an unknown escape sequence or a char literal that does not hold
+    ///one character. The tools that read a script without running it use this
+    ///
+    pub fn token_to_node(parse_info: ParseInfo) -> Result<ASTNode, String> {
+        let node = match parse_info.token {
+            TokenTypes::Int => match parse_info.value.parse::<i32>() {
+                Ok(value) => ASTNode::Int(IntNode::new(value)),
+                Err(_) => return Err(format!("Int literal {} is out of range", parse_info.value)),
+            },
             TokenTypes::String => {
-                let value = &parse_info.value[1..parse_info.value.len() - 1]; // Removes the first and last characters (quotes)
-                ASTNode::String(StringNode::new(value.to_string()))
+                // Removes the quotes and resolves any escape sequences
+                ASTNode::String(StringNode::new(unescape_literal(&parse_info.value)?))
             }
-            TokenTypes::Bool => ASTNode::Bool(BoolNode::new(
-                parse_info.value.parse::<bool>().expect("Invalid bool"),
-            )),
-            TokenTypes::Float => ASTNode::Float(FloatNode::new(
-                parse_info.value.parse::<f32>().expect("Invalid float"),
-            )),
+            TokenTypes::Bool => match parse_info.value.parse::<bool>() {
+                Ok(value) => ASTNode::Bool(BoolNode::new(value)),
+                Err(_) => return Err(format!("Invalid bool {}", parse_info.value)),
+            },
+            TokenTypes::Float => match parse_info.value.parse::<f32>() {
+                Ok(value) => ASTNode::Float(FloatNode::new(value)),
+                Err(_) => return Err(format!("Invalid float {}", parse_info.value)),
+            },
             TokenTypes::Char => {
                 // cut out ' and ' from the string to get the value
-                let char_value = parse_info.value.chars().nth(1).unwrap();
-
-                ASTNode::Char(CharNode::new(char_value))
+                let value = unescape_literal(&parse_info.value)?;
+                let mut chars = value.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(c), None) => ASTNode::Char(CharNode::new(c)),
+                    _ => {
+                        return Err(format!(
+                            "Char literal {} must hold exactly one character",
+                            parse_info.value
+                        ))
+                    }
+                }
+            }
+            // ! is read by the expression parser like the other prefix operators
+            TokenTypes::Operator | TokenTypes::Not => {
+                ASTNode::Operator(OperatorNode::new(parse_info.value))
             }
-            TokenTypes::Operator => ASTNode::Operator(OperatorNode::new(parse_info.value)),
             TokenTypes::AssignmentOperator => {
                 ASTNode::AssignmentOperator(AssignmentOperatorNode::new(parse_info.value))
             }
@@ -861,6 +903,8 @@ pub mod nodes {
             TokenTypes::LeftBracket => ASTNode::LeftBracket,
             TokenTypes::RightBracket => ASTNode::RightBracket,
             TokenTypes::FatArrow => ASTNode::FatArrow,
+            TokenTypes::Return => ASTNode::Return,
+            TokenTypes::Destructure { names } => ASTNode::Destructure(DestructureNode::new(names)),
             TokenTypes::While { statement } => ASTNode::While(WhileNode::new(statement)),
             TokenTypes::For { statement } => ASTNode::For(ForNode::new(statement)),
             TokenTypes::If { statement } => ASTNode::If(IfNode::new(statement)),
@@ -870,9 +914,8 @@ pub mod nodes {
             TokenTypes::Catch => ASTNode::Catch,
             TokenTypes::Finally => ASTNode::Finally,
 
-            _ => {
-                panic!("Unrecognized token: {:?}", parse_info.token);
-            }
-        }
+            _ => return Err(format!("Unrecognized token: {:?}", parse_info.token)),
+        };
+        Ok(node)
     }
 }
@@ -0,0 +1,210 @@
+/*
+* This file prints the state a script leaves behind, its variables, collections and functions.
+* It is only printed when jist is run with --dump-state, as text for reading or as JSON for
+* tools and tests
+*/
+use crate::base_variable::base_types::BaseTypes;
+use crate::base_variable::variables::VARIABLE_STACK;
+use crate::collection::collections::type_name_of;
+use crate::function::FUNCTION_STACK;
+use crate::heap::visit;
+use std::rc::Rc;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DumpFormat {
+    Text,
+    Json,
+}
+
+impl DumpFormat {
+    ///
+    ///Reads the value of --dump-state=format, --dump-state alone is text
+    ///
+    pub fn parse(flag: &str) -> Option<DumpFormat> {
+        match flag {
+            "--dump-state" | "--dump-state=text" => Some(DumpFormat::Text),
+            "--dump-state=json" => Some(DumpFormat::Json),
+            _ => None,
+        }
+    }
+}
+
+pub fn dump_state(format: DumpFormat) {
+    match format {
+        DumpFormat::Text => print_text(),
+        DumpFormat::Json => println!("{}", state_json()),
+    }
+}
+
+fn print_text() {
+    println!("\n\nStack:");
+    for variable in unsafe { (*std::ptr::addr_of!(VARIABLE_STACK)).iter() } {
+        // collections are printed with their elements below
+        if !matches!(
+            variable.value,
+            BaseTypes::Array(_) | BaseTypes::Dictionary(_)
+        ) {
+            variable.print();
+        }
+    }
+
+    for variable in unsafe { (*std::ptr::addr_of!(VARIABLE_STACK)).iter() } {
+        if let BaseTypes::Array(array) = &variable.value {
+            println!("{}", array.borrow());
+        }
+    }
+    for variable in unsafe { (*std::ptr::addr_of!(VARIABLE_STACK)).iter() } {
+        if let BaseTypes::Dictionary(dict) = &variable.value {
+            println!("{}", dict.borrow());
+        }
+    }
+    FUNCTION_STACK.with(|stack| {
+        for function in stack.borrow().iter() {
+            println!("{}", function);
+        }
+    });
+}
+
+///
+///The whole state as one JSON object with a variables and a functions list
+///
+pub fn state_json() -> String {
+    let variables: Vec<String> = unsafe { (*std::ptr::addr_of!(VARIABLE_STACK)).iter() }
+        .map(|variable| {
+            format!(
+                "{{\"name\": {}, \"type\": {}, \"value\": {}}}",
+                json_string(&variable.name),
+                json_string(&type_name_of(&variable.value)),
+                to_json(&variable.value)
+            )
+        })
+        .collect();
+
+    let function_stack = FUNCTION_STACK.with(|stack| stack.borrow().clone());
+    let functions: Vec<String> = function_stack
+        .iter()
+        .map(|function| {
+            let arguments: Vec<String> = function
+                .arguments
+                .iter()
+                .map(|argument| {
+                    format!(
+                        "{{\"name\": {}, \"type\": {}}}",
+                        json_string(&argument.name),
+                        json_string(&argument.var_type.to_string())
+                    )
+                })
+                .collect();
+            format!(
+                "{{\"name\": {}, \"arguments\": [{}], \"return_type\": {}}}",
+                json_string(&function.name),
+                arguments.join(", "),
+                json_string(&function.return_type.to_string())
+            )
+        })
+        .collect();
+
+    format!(
+        "{{\"variables\": [{}], \"functions\": [{}]}}",
+        variables.join(", "),
+        functions.join(", ")
+    )
+}
+
+///
+///Converts a value to JSON. Dictionaries become objects with their keys written as strings,
+///sets and tuples become arrays, and floats JSON cannot hold (NaN, inf) become null. So does a
+///collection inside itself, JSON has no way to refer back to it
+///
+pub fn to_json(value: &BaseTypes) -> String {
+    let address = match value {
+        BaseTypes::Array(array) => Rc::as_ptr(array) as usize,
+        BaseTypes::Dictionary(dictionary) => Rc::as_ptr(dictionary) as usize,
+        BaseTypes::Set(set) => Rc::as_ptr(set) as usize,
+        _ => return value_json(value),
+    };
+    visit((address, 0), || value_json(value)).unwrap_or_else(|| "null".to_string())
+}
+
+fn value_json(value: &BaseTypes) -> String {
+    let list = |values: &[BaseTypes]| {
+        let values: Vec<String> = values.iter().map(to_json).collect();
+        format!("[{}]", values.join(", "))
+    };
+    match value {
+        BaseTypes::Int(i) => i.to_string(),
+        BaseTypes::Float(f) if f.is_finite() => format!("{:?}", f),
+        BaseTypes::Float(_) | BaseTypes::Null => "null".to_string(),
+        BaseTypes::Bool(b) => b.to_string(),
+        BaseTypes::StringWrapper(s) => json_string(s),
+        BaseTypes::Char(c) => json_string(&c.to_string()),
+        BaseTypes::Array(array) => list(&array.borrow().data),
+        BaseTypes::Set(set) => list(set.borrow().values()),
+        BaseTypes::Tuple(values) => list(values),
+        BaseTypes::Dictionary(dictionary) => {
+            let entries: Vec<String> = dictionary
+                .borrow()
+                .entries()
+                .map(|(key, value)| {
+                    let key = match key {
+                        BaseTypes::StringWrapper(s) => s.clone(),
+                        other => other.to_string(),
+                    };
+                    format!("{}: {}", json_string(&key), to_json(value))
+                })
+                .collect();
+            format!("{{{}}}", entries.join(", "))
+        }
+        other => json_string(&other.to_string()),
+    }
+}
+
+pub fn json_string(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len() + 2);
+    escaped.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c if (c as u32) < 0x20 => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped.push('"');
+    escaped
+}
+
+#[cfg(test)]
+mod state_dump_tests {
+    use super::{json_string, to_json};
+    use crate::base_variable::base_types::BaseTypes;
+    use crate::collection::collections::{Array, Dictionary};
+
+    #[test]
+    fn test_to_json() {
+        assert_eq!(json_string("a \"b\"\n"), "\"a \\\"b\\\"\\n\"");
+        assert_eq!(to_json(&BaseTypes::Float(2.0)), "2.0");
+        assert_eq!(to_json(&BaseTypes::Float(f64::NAN)), "null");
+
+        let array = BaseTypes::from(Array::new(
+            String::new(),
+            "any".into(),
+            vec![
+                BaseTypes::Int(1),
+                BaseTypes::Char('x'),
+                BaseTypes::Bool(true),
+            ],
+        ));
+        assert_eq!(to_json(&array), "[1, \"x\", true]");
+
+        let mut dictionary = Dictionary::new(String::new(), "int".into(), "any".into(), Vec::new());
+        dictionary.add(BaseTypes::Int(1), array);
+        assert_eq!(
+            to_json(&BaseTypes::from(dictionary)),
+            "{\"1\": [1, \"x\", true]}"
+        );
+    }
+}
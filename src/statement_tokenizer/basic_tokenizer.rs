@@ -2,164 +2,219 @@ pub mod basic_tokenizers {
     use crate::statement_tokenizer::tokenizer::tokenizers::ParseInfo;
     use crate::token_type::token_types::TokenTypes;
 
-    pub fn read_boolean(expression: String, index: usize) -> ParseInfo {
-        let mut j = index;
-        let mut boolean: String = String::new();
-        let bool_compare1 = "True";
-        let bool_compare2 = "False";
-        let bool_compare3 = "true";
-        let bol_compare4 = "false";
-
-        while j < expression.len() {
-            if let Some(char) = expression.chars().nth(j) {
-                if char.is_alphabetic() {
-                    boolean.push(char);
-                    j += 1; // Increment the index to progress through the string
-                } else {
-                    break;
-                }
-            } else {
-                break;
-            }
-        }
-
-        if boolean == bool_compare1
-            || boolean == bool_compare2
-            || boolean == bool_compare3
-            || boolean == bol_compare4
-        {
-            return ParseInfo::new(TokenTypes::Bool, (j - index).try_into().unwrap(), boolean);
-        }
-
-        ParseInfo::new(TokenTypes::None, 0, "none".to_string())
-    }
-
-    pub fn read_operators(
-        expression: String,
-        char: char,
-        next_char: char,
-        index: usize,
-    ) -> ParseInfo {
+    ///
+    ///Reads the operator or punctuation at the start of the two characters, the longer operator
+    ///wins so == is not read as two =
+    ///
+    pub fn read_operators(char: char, next_char: char) -> ParseInfo {
         let two_chars = format!("{}{}", char, next_char);
-        match two_chars.to_string().as_str() {
-            "==" | "!=" | ">=" | "<=" | "&&" | "||" | "++" | "--" => {
-                let chars_read = 2;
-                return ParseInfo::new(
-                    TokenTypes::Operator,
-                    chars_read.try_into().unwrap(),
-                    two_chars,
-                );
+        match two_chars.as_str() {
+            "=>" => return ParseInfo::new(TokenTypes::FatArrow, 2, two_chars),
+            "+=" | "-=" | "*=" | "/=" | "%=" => {
+                return ParseInfo::new(TokenTypes::AssignmentOperator, 2, two_chars);
+            }
+            "==" | "!=" | ">=" | "<=" | "&&" | "||" | "++" | "--" | ".." => {
+                return ParseInfo::new(TokenTypes::Operator, 2, two_chars);
             }
             _ => {}
         }
         match char {
-            '+' | '-' | '*' | '/' | '>' | '<' | '!' => {
-                let chars_read = 1;
-                return ParseInfo::new(
-                    TokenTypes::Operator,
-                    chars_read.try_into().unwrap(),
-                    char.to_string(),
-                );
+            '+' | '-' | '*' | '/' | '%' | '>' | '<' => {
+                ParseInfo::new(TokenTypes::Operator, 1, char.to_string())
             }
-            '(' => return ParseInfo::new(TokenTypes::LeftParenthesis, 1, char.to_string()),
-            ')' => return ParseInfo::new(TokenTypes::RightParenthesis, 1, char.to_string()),
-            '{' => return ParseInfo::new(TokenTypes::LeftCurly, 1, char.to_string()),
-            '}' => return ParseInfo::new(TokenTypes::RightCurly, 1, char.to_string()),
-            ',' => return ParseInfo::new(TokenTypes::ArgumentSeparator, 1, char.to_string()),
-            '=' => return ParseInfo::new(TokenTypes::AssignmentOperator, 1, char.to_string()),
-            _ => {
-                return ParseInfo::new(TokenTypes::None, 0, "none".to_string());
+            '!' => ParseInfo::new(TokenTypes::Not, 1, char.to_string()),
+            '(' => ParseInfo::new(TokenTypes::LeftParenthesis, 1, char.to_string()),
+            ')' => ParseInfo::new(TokenTypes::RightParenthesis, 1, char.to_string()),
+            '{' => ParseInfo::new(TokenTypes::LeftCurly, 1, char.to_string()),
+            '}' => ParseInfo::new(TokenTypes::RightCurly, 1, char.to_string()),
+            '[' => ParseInfo::new(TokenTypes::LeftBracket, 1, char.to_string()),
+            ']' => ParseInfo::new(TokenTypes::RightBracket, 1, char.to_string()),
+            ',' => ParseInfo::new(TokenTypes::ArgumentSeparator, 1, char.to_string()),
+            '=' => ParseInfo::new(TokenTypes::AssignmentOperator, 1, char.to_string()),
+            _ => ParseInfo::new(TokenTypes::None, 0, "none".to_string()),
+        }
+    }
+
+    ///
+    ///Reads a string or char literal starting at index. Handles escaped quotes inside normal
+    ///literals, raw strings (r"..." and r#"..."#) and triple quoted multi-line strings.
+    ///The returned value is the literal exactly as written, quotes included, it is decoded
+    ///later by unescape_literal when the ASTNode is made
+    ///
+    pub fn read_strings_chars(chars: &[char], index: usize) -> ParseInfo {
+        match scan_literal(chars, index) {
+            Some((end, _)) => {
+                let token = if chars[index] == '\'' {
+                    TokenTypes::Char
+                } else {
+                    TokenTypes::String
+                };
+                ParseInfo::new(
+                    token,
+                    (end - index).try_into().unwrap(),
+                    chars[index..end].iter().collect(),
+                )
             }
+            None => ParseInfo::new(TokenTypes::None, 0, "none".to_string()),
         }
     }
 
-    pub fn read_strings_chars(expression: String, char: char, index: usize) -> ParseInfo {
-        match char {
-            '"' | '\'' => {
+    ///
+    ///Finds the end of the string or char literal that starts at index. Returns the index just
+    ///past the closing quote and whether the literal was closed at all, or None if no literal
+    ///starts at index
+    ///
+    pub fn scan_literal(chars: &[char], index: usize) -> Option<(usize, bool)> {
+        let at = |i: usize| chars.get(i).copied().unwrap_or('\0');
+
+        match at(index) {
+            'r' if at(index + 1) == '"' || at(index + 1) == '#' => {
+                // count the hashes so r#"..."# can hold a bare "
                 let mut j = index + 1;
-                while j < expression.len() {
-                    match char {
-                        '"' => {
-                            if expression.chars().nth(j).unwrap() == char {
-                                return ParseInfo::new(
-                                    TokenTypes::String,
-                                    (j - index + 1).try_into().unwrap(),
-                                    expression[index..j + 1].to_string(),
-                                );
-                            }
-                        }
-                        '\'' => {
-                            if expression.chars().nth(j).unwrap() == char {
-                                return ParseInfo::new(
-                                    TokenTypes::Char,
-                                    (j - index + 1).try_into().unwrap(),
-                                    expression[index..j + 1].to_string(),
-                                );
-                            }
-                        }
-                        _ => {}
+                while at(j) == '#' {
+                    j += 1;
+                }
+                if at(j) != '"' {
+                    return None;
+                }
+                let hashes = j - index - 1;
+                j += 1;
+                while j < chars.len() {
+                    if chars[j] == '"' && (1..=hashes).all(|h| at(j + h) == '#') {
+                        return Some((j + hashes + 1, true));
                     }
-                    if expression.chars().nth(j).unwrap() == char {
-                        return ParseInfo::new(
-                            TokenTypes::Char,
-                            1,
-                            expression[index..j + 1].to_string(),
-                        );
+                    j += 1;
+                }
+                Some((chars.len(), false))
+            }
+            '"' if at(index + 1) == '"' && at(index + 2) == '"' => {
+                let mut j = index + 3;
+                while j < chars.len() {
+                    if chars[j] == '\\' {
+                        j += 2;
+                        continue;
+                    }
+                    if chars[j] == '"' && at(j + 1) == '"' && at(j + 2) == '"' {
+                        return Some((j + 3, true));
                     }
                     j += 1;
                 }
-                {
-                    let chars_read = j - index + 1;
-                    return ParseInfo::new(
-                        TokenTypes::String,
-                        chars_read.try_into().unwrap(),
-                        expression[index..j + 1].to_string(),
-                    );
+                Some((chars.len(), false))
+            }
+            quote @ ('"' | '\'') => {
+                let mut j = index + 1;
+                while j < chars.len() {
+                    if chars[j] == '\\' {
+                        j += 2;
+                        continue;
+                    }
+                    if chars[j] == quote {
+                        return Some((j + 1, true));
+                    }
+                    j += 1;
                 }
+                Some((chars.len(), false))
+            }
+            _ => None,
+        }
+    }
+
+    ///
+    ///Turns the written form of a string or char literal into its value: strips the quotes
+    ///and resolves escape sequences (\n \t \r \0 \\ \" \' \u{...}). Raw strings are returned
+    ///as written and triple quoted strings drop the newline directly after the opening quotes
+    ///
+    pub fn unescape_literal(literal: &str) -> Result<String, String> {
+        if let Some(raw) = literal.strip_prefix('r') {
+            let hashes = raw.len() - raw.trim_start_matches('#').len();
+            let body = &raw[hashes..];
+            if body.len() < 2 + hashes || !body.starts_with('"') {
+                return Err(format!("Unterminated raw string literal: {}", literal));
+            }
+            return Ok(body[1..body.len() - 1 - hashes].to_string());
+        }
+
+        let body =
+            if literal.len() >= 6 && literal.starts_with("\"\"\"") && literal.ends_with("\"\"\"") {
+                let inner = &literal[3..literal.len() - 3];
+                inner
+                    .strip_prefix("\r\n")
+                    .or_else(|| inner.strip_prefix('\n'))
+                    .unwrap_or(inner)
+            } else if literal.len() >= 2 {
+                &literal[1..literal.len() - 1]
+            } else {
+                return Err(format!("Unterminated literal: {}", literal));
+            };
+
+        let mut value = String::new();
+        let mut chars = body.chars();
+        while let Some(c) = chars.next() {
+            if c != '\\' {
+                value.push(c);
+                continue;
             }
-            _ => {
-                return ParseInfo::new(TokenTypes::None, 0, "none".to_string());
+            match chars.next() {
+                Some('n') => value.push('\n'),
+                Some('t') => value.push('\t'),
+                Some('r') => value.push('\r'),
+                Some('0') => value.push('\0'),
+                Some('\\') => value.push('\\'),
+                Some('"') => value.push('"'),
+                Some('\'') => value.push('\''),
+                Some('u') => {
+                    if chars.next() != Some('{') {
+                        return Err("Expected '{' after \\u in escape sequence".to_string());
+                    }
+                    let mut hex = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('}') => break,
+                            Some(h) => hex.push(h),
+                            None => return Err("Unterminated \\u{...} escape sequence".to_string()),
+                        }
+                    }
+                    let code = u32::from_str_radix(&hex, 16)
+                        .ok()
+                        .and_then(char::from_u32)
+                        .ok_or(format!("Invalid unicode escape \\u{{{}}}", hex))?;
+                    value.push(code);
+                }
+                Some(other) => return Err(format!("Unknown escape sequence \\{}", other)),
+                None => return Err("Escape sequence at end of literal".to_string()),
             }
         }
+        Ok(value)
     }
 
-    pub fn read_numbers(expression: String, char: char, index: usize) -> ParseInfo {
-        // Extract the number substring
+    ///
+    ///Reads an int or a float like 1.5 or .5. The number stops in front of a .. range operator
+    ///and at a second decimal point
+    ///
+    pub fn read_numbers(chars: &[char], index: usize) -> ParseInfo {
         let mut j = index;
         let mut decimals = 0;
 
-        // Traverse through the expression to identify the full number (including decimals)
-        while j < expression.len()
-            && (expression[j..j + 1].chars().all(|c| c.is_digit(10))
-                || expression[j..j + 1] == *".")
-        {
-            if expression[j..j + 1] == *"." {
+        while j < chars.len() && (chars[j].is_ascii_digit() || chars[j] == '.') {
+            if chars[j] == '.' {
+                // 1..3 is a range, the number stops before the ..
+                if decimals == 1 || chars.get(j + 1) == Some(&'.') {
+                    break;
+                }
                 decimals += 1;
             }
             j += 1;
         }
 
-        let number_str = &expression[index..j];
-
-        // Check if it's a valid number and if there's only one decimal point
-        if decimals <= 1 && number_str.parse::<f64>().is_ok() {
-            let chars_read = j - index;
-            if decimals == 0 {
-                return ParseInfo::new(
-                    TokenTypes::Int,
-                    chars_read.try_into().unwrap(),
-                    number_str.to_string(),
-                );
-            } else {
-                return ParseInfo::new(
-                    TokenTypes::Float,
-                    chars_read.try_into().unwrap(),
-                    number_str.to_string(),
-                );
-            }
+        let number_str: String = chars[index..j].iter().collect();
+        if number_str.parse::<f64>().is_err() {
+            return ParseInfo::new(TokenTypes::None, 0, "none".to_string());
         }
-
-        // Return None token type if parsing fails
-        ParseInfo::new(TokenTypes::None, 0, "none".to_string())
+        let token = if decimals == 0 {
+            TokenTypes::Int
+        } else {
+            TokenTypes::Float
+        };
+        ParseInfo::new(token, (j - index).try_into().unwrap(), number_str)
     }
 }
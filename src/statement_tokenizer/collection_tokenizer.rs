@@ -1,39 +1,33 @@
 pub mod collection_tokenizers {
+    use crate::collection::collections::{normalize_type, split_type};
+    use crate::statement_tokenizer::lexer::lexers::{
+        is_name_start, read_name, skip_binding_keyword,
+    };
     use crate::statement_tokenizer::tokenizer::tokenizers::ParseInfo;
     use crate::token_type::token_types::TokenTypes;
 
-    pub fn read_collection_assignment(expression: &str, index: usize) -> ParseInfo {
-        let chars: Vec<char> = expression.chars().collect();
+    ///
+    ///Reads name: type< ... > = or name: (types) =, with or without a let, let mut or const in
+    ///front. The token stops in front of the =
+    ///
+    pub fn read_collection_assignment(chars: &[char], index: usize) -> ParseInfo {
         let mut j = index;
 
-        let mut collection_name = String::new();
         let mut collection_type = String::new();
         let mut stored_value_type_tuple = (String::new(), String::new());
         let mut inside_angle_brackets = false;
         let mut found_comma = false;
 
-        // Check for "let" keyword
-        if j < expression.len() && expression[j..].starts_with("let") {
-            j += 3; // Move past "let"
-        }
-
-        // Skip any spaces after "let"
-        while j < expression.len() && chars[j].is_whitespace() {
-            j += 1;
-        }
+        // Skip the let, let mut or const, letter: is a name that only starts like it
+        j = skip_binding_keyword(chars, j);
 
         // Collect the collection name
-        while j < expression.len() {
-            let char = chars[j];
-            if char.is_alphabetic() || char == '_' {
-                collection_name.push(char);
-            } else if char == ':' {
-                break; // Break on type declaration
-            } else if !char.is_whitespace() {
-                return ParseInfo::new(TokenTypes::None, 0, "none".to_string());
-            }
-            j += 1;
+        if !chars.get(j).is_some_and(|c| is_name_start(*c)) {
+            return ParseInfo::new(TokenTypes::None, 0, "none".to_string());
         }
+        let name_start = j;
+        j = read_name(chars, j);
+        let collection_name: String = chars[name_start..j].iter().collect();
 
         // Skip spaces after the collection name
         while j < chars.len() && chars[j].is_whitespace() {
@@ -47,6 +41,41 @@ pub mod collection_tokenizers {
                 j += 1;
             }
 
+            // A tuple type like (int, string) is kept whole as the single stored type
+            if chars.get(j) == Some(&'(') {
+                let start = j;
+                let mut depth = 0;
+                while j < chars.len() {
+                    match chars[j] {
+                        '(' => depth += 1,
+                        ')' => depth -= 1,
+                        _ => {}
+                    }
+                    j += 1;
+                    if depth == 0 {
+                        break;
+                    }
+                }
+                let tuple_type: String = chars[start..j].iter().collect();
+                while j < chars.len() && chars[j].is_whitespace() {
+                    j += 1;
+                }
+                if depth != 0 || chars.get(j) != Some(&'=') {
+                    return ParseInfo::new(TokenTypes::None, 0, "none".to_string());
+                }
+                let tuple_type = normalize_type(&tuple_type);
+                return ParseInfo::new(
+                    TokenTypes::Collection {
+                        name: collection_name.clone(),
+                        collection_type: "tuple".to_string(),
+                        stored_value_type_single: tuple_type.clone(),
+                        stored_value_type_tuple: ("".to_string(), "".to_string()),
+                    },
+                    (j - index).try_into().unwrap(),
+                    format!("{} collection_type: {}", collection_name, tuple_type),
+                );
+            }
+
             // Collect the collection type (e.g., "dict")
             while j < chars.len() {
                 let char = chars[j];
@@ -62,26 +91,34 @@ pub mod collection_tokenizers {
                 }
             }
 
-            // Now we are inside the '<>' brackets to collect the tuple types (e.g., char, int)
+            // Now we are inside the '<>' brackets to collect the element types. They can be
+            // collections themselves, like dict<string, array<int>>, so only a comma at the top
+            // level separates the key type from the value type
+            let mut inner = String::new();
+            let mut depth = 1;
             while j < chars.len() && inside_angle_brackets {
                 let char = chars[j];
-                if char == '>' {
-                    //inside_angle_brackets = false;
-                    j += 1; // Move past '>'
-                    break;
-                } else if char == ',' {
-                    found_comma = true;
-                    j += 1; // Move past ','
-                    continue;
+                j += 1;
+                match char {
+                    '<' => depth += 1,
+                    '>' => {
+                        depth -= 1;
+                        if depth == 0 {
+                            break;
+                        }
+                    }
+                    _ => {}
                 }
-
-                // Collect key type (before comma) and value type (after comma)
-                if found_comma {
-                    stored_value_type_tuple.1.push(char);
-                } else {
-                    stored_value_type_tuple.0.push(char);
+                inner.push(char);
+            }
+            let (_, parameters) = split_type(&format!("{}<{}>", collection_type, inner));
+            match parameters.as_slice() {
+                [single] => stored_value_type_tuple.0 = single.clone(),
+                [key, value] => {
+                    found_comma = true;
+                    stored_value_type_tuple = (key.clone(), value.clone());
                 }
-                j += 1;
+                _ => {}
             }
 
             // Skip spaces after the type declaration
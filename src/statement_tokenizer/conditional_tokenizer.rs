@@ -1,5 +1,6 @@
 pub mod conditional_tokenizers {
 
+    use crate::statement_tokenizer::lexer::lexers::{skip_whitespace, Keyword};
     use crate::statement_tokenizer::tokenizer::tokenizers::ParseInfo;
     use crate::token_type::token_types::TokenTypes;
 
@@ -24,110 +25,50 @@ pub mod conditional_tokenizers {
         (statement, index)
     }
 
-    fn parse_keyword(expression: &str, index: usize, keyword: &str) -> Option<usize> {
-        let slice = &expression[index..];
-        if slice.starts_with(keyword) {
-            Some(index + keyword.len())
-        } else {
-            None
+    ///
+    ///Reads if (condition), elif (condition) or else {, index is at the keyword. An if or elif
+    ///stops in front of the closing ) and an else takes its { along
+    ///
+    pub fn tokenize_if_elif_else_statement(
+        chars: &[char],
+        index: usize,
+        keyword: Keyword,
+    ) -> ParseInfo {
+        let none = ParseInfo::new(TokenTypes::None, 0, "none".to_string());
+        let (word, opening) = match keyword {
+            Keyword::If => ("if", '('),
+            Keyword::Elif => ("elif", '('),
+            Keyword::Else => ("else", '{'),
+            _ => return none,
+        };
+        let j = skip_whitespace(chars, index + word.len());
+        if chars.get(j) != Some(&opening) {
+            return none;
         }
-    }
-
-    pub fn tokenize_if_elif_else_statement(expression: &str, index: usize) -> ParseInfo {
-        let chars: Vec<char> = expression.chars().collect();
-        let mut j = index;
-        let mut i = 0;
-
-        let mut keyword = String::new();
-
-        while j < chars.len() {
-            // Handle 'if'
-            if let Some(new_index) = parse_keyword(expression, j, "if") {
-                j = new_index;
-                while j < chars.len() && chars[j].is_whitespace() {
-                    j += 1;
-                }
-                if chars[j] == '(' {
-                    let (statement, new_j) = extract_statement(&chars, j + 1);
-                    return ParseInfo::new(
-                        TokenTypes::If { statement },
-                        new_j.try_into().unwrap(),
-                        "if".to_string(),
-                    );
-                }
-            }
-            // Handle 'elif'
-            else if let Some(new_index) = parse_keyword(expression, j, "elif") {
-                j = new_index;
-                while j < chars.len() && chars[j].is_whitespace() {
-                    j += 1;
-                }
-                if chars[j] == '(' {
-                    let (statement, new_j) = extract_statement(&chars, j + 1);
-                    return ParseInfo::new(
-                        TokenTypes::Elif { statement },
-                        new_j.try_into().unwrap(),
-                        "elif".to_string(),
-                    );
-                }
-            }
-            // Handle 'else'
-            else if let Some(new_index) = parse_keyword(expression, j, "else") {
-                j = new_index;
-                while j < chars.len() && chars[j].is_whitespace() {
-                    j += 1;
-                }
-                if chars[j] == '{' {
-                    // Consume the '{' and create an Else token
-                    j += 1; // skip over '{'
-                    return ParseInfo::new(
-                        TokenTypes::Else, // You may want to create a new TokenTypes variant if you need additional info.
-                        j.try_into().unwrap(),
-                        "else".to_string(),
-                    );
-                }
-            }
-
-            j += 1;
+        if keyword == Keyword::Else {
+            return ParseInfo::new(
+                TokenTypes::Else,
+                (j + 1 - index).try_into().unwrap(),
+                word.to_string(),
+            );
         }
 
-        ParseInfo::new(TokenTypes::None, 0, "none".to_string())
+        let (statement, close) = extract_statement(chars, j + 1);
+        let token = if keyword == Keyword::If {
+            TokenTypes::If { statement }
+        } else {
+            TokenTypes::Elif { statement }
+        };
+        ParseInfo::new(token, (close - index).try_into().unwrap(), word.to_string())
     }
 
-    pub fn tokenize_try_catch_finally_statement(expression: &str, index: usize) -> ParseInfo {
-        let chars: Vec<char> = expression.chars().collect();
-        let mut j = index;
-
-        while j < chars.len() {
-            let c = chars[j];
-            if c.is_whitespace() {
-                j += 1;
-                continue;
-            }
-
-            if let Some(new_index) = parse_keyword(expression, j, "try") {
-                return ParseInfo::new(
-                    TokenTypes::Try,
-                    new_index.try_into().unwrap(),
-                    "try".to_string(),
-                );
-            } else if let Some(new_index) = parse_keyword(expression, j, "catch") {
-                return ParseInfo::new(
-                    TokenTypes::Catch,
-                    new_index.try_into().unwrap(),
-                    "catch".to_string(),
-                );
-            } else if let Some(new_index) = parse_keyword(expression, j, "finally") {
-                return ParseInfo::new(
-                    TokenTypes::Finally,
-                    new_index.try_into().unwrap(),
-                    "finally".to_string(),
-                );
-            }
-
-            j += 1;
-        }
-
-        ParseInfo::new(TokenTypes::None, 0, "none".to_string())
+    pub fn tokenize_try_catch_finally_statement(keyword: Keyword) -> ParseInfo {
+        let (token, word) = match keyword {
+            Keyword::Try => (TokenTypes::Try, "try"),
+            Keyword::Catch => (TokenTypes::Catch, "catch"),
+            Keyword::Finally => (TokenTypes::Finally, "finally"),
+            _ => return ParseInfo::new(TokenTypes::None, 0, "none".to_string()),
+        };
+        ParseInfo::new(token, word.len().try_into().unwrap(), word.to_string())
     }
 }
@@ -1,272 +1,187 @@
 pub mod function_tokenizers {
+    use crate::collection::collections::normalize_type;
+    use crate::statement_tokenizer::lexer::lexers::{is_name_start, read_name, skip_whitespace};
     use crate::statement_tokenizer::tokenizer::tokenizers::ParseInfo;
     use crate::token_type::token_types::TokenTypes;
 
-    pub fn read_function_assignment(expression: &String, index: usize) -> ParseInfo {
-        let chars: Vec<char> = expression.chars().collect();
-        let mut j = index;
-
-        // Look for the `->` pattern
-        while j < chars.len() {
-            let char = chars[j];
-            let nextchar = if j + 1 < chars.len() {
-                chars[j + 1]
-            } else {
-                '\0' // Null character for out-of-bounds safety
-            };
-
-            if char == '-' && nextchar == '>' {
-                j += 2; // Move past `->`
-
-                // Skip whitespace after `->`
-                while j < chars.len() && chars[j].is_whitespace() {
-                    j += 1;
-                }
-
-                // Collect the return type (lowercase type)
-                let mut return_type = String::new();
-                while j < chars.len() {
-                    let char = chars[j];
-                    if char.is_lowercase() || char == '_' {
-                        return_type.push(char);
-                        j += 1;
-                    } else {
-                        break;
-                    }
-                }
-
-                // Check if we collected a valid return type
-                if !return_type.is_empty() {
-                    return ParseInfo::new(
-                        TokenTypes::ReturnTypeAssignment,
-                        (j - index).try_into().unwrap(),
-                        return_type,
-                    );
-                }
-            } else {
-                j += 1; // Continue scanning if `->` not found
-            }
+    ///
+    ///Reads -> type outside of a function header, index is at the ->
+    ///
+    pub fn read_function_assignment(chars: &[char], index: usize) -> ParseInfo {
+        if chars.get(index..index + 2) != Some(&['-', '>'][..]) {
+            return ParseInfo::new(TokenTypes::None, 0, "none".to_string());
         }
-        ParseInfo::new(TokenTypes::None, 0, "none".to_string())
+        let mut j = skip_whitespace(chars, index + 2);
+        let type_start = j;
+        while j < chars.len() && (chars[j].is_lowercase() || chars[j] == '_') {
+            j += 1;
+        }
+        if j == type_start {
+            return ParseInfo::new(TokenTypes::None, 0, "none".to_string());
+        }
+        ParseInfo::new(
+            TokenTypes::ReturnTypeAssignment,
+            (j - index).try_into().unwrap(),
+            chars[type_start..j].iter().collect(),
+        )
     }
 
-    static mut PARSEFUNCTIONCALL: bool = false;
-
-    pub fn read_function_call(expression: &String, index: usize) -> ParseInfo {
+    ///
+    ///Reads name( or a method call like s.len(, index is at the name or the . in front of a
+    ///chained call. A type to convert to, as in s.parse::<int>(, is part of the name. The
+    ///token stops in front of the (
+    ///
+    pub fn read_function_call(chars: &[char], index: usize) -> ParseInfo {
         let mut j = index;
-        let mut function_name = String::new();
-        let chars: Vec<char> = expression.chars().collect();
-
-        // Collect the function name
         while j < chars.len() {
-            let char = chars[j];
-            //let next_char = chars.get(j + 1).cloned().unwrap_or('\0');
-
-            if char == '=' {
-                return ParseInfo::new(TokenTypes::None, 0, "none".to_string());
+            let c = chars[j];
+            // a . joins a method to its value, two of them are a range
+            let dot = c == '.' && chars.get(j + 1).is_some_and(|n| is_name_start(*n));
+            if !(c.is_alphanumeric() || c == '_' || dot) {
+                break;
             }
-            if char == '(' {
-                // Detected function call: mark it and return info
-                unsafe { PARSEFUNCTIONCALL = true };
+            if dot {
+                j = read_name(chars, j + 1);
+            } else {
+                j += 1;
+            }
+        }
+        let mut function_name: String = chars[index..j].iter().collect();
+        // the type a call converts to, like the ::<int> of s.parse::<int>()
+        if chars.get(j..j + 3) == Some(&[':', ':', '<'][..]) {
+            let start = skip_whitespace(chars, j + 3);
+            let end = read_name(chars, start);
+            let close = skip_whitespace(chars, end);
+            if end > start && chars.get(close) == Some(&'>') {
+                let type_name: String = chars[start..end].iter().collect();
+                function_name = format!("{}::<{}>", function_name, type_name);
+                j = close + 1;
+            }
+        }
+        if !function_name.is_empty() && !function_name.starts_with(|c: char| c.is_ascii_digit()) {
+            let j = skip_whitespace(chars, j);
+            if chars.get(j) == Some(&'(') {
                 return ParseInfo::new(
                     TokenTypes::FunctionCall,
                     (j - index).try_into().unwrap(),
-                    function_name.clone(),
+                    function_name,
                 );
-            } else if !char.is_whitespace() {
-                // Build up function name
-                function_name.push(char);
             }
-            j += 1;
         }
 
-        // Parse the function parameters
-        if unsafe { PARSEFUNCTIONCALL } {
-            let mut parameter = String::new();
-
-            while j < chars.len() {
-                let char = chars[j];
-                //let next_char = chars.get(j + 1).cloned().unwrap_or('\0');
-
-                if char == ')' {
-                    // End of function call parameters
-                    unsafe { PARSEFUNCTIONCALL = false };
-                    return ParseInfo::new(
-                        TokenTypes::RightParenthesis,
-                        (j - index).try_into().unwrap(),
-                        ")".to_string(),
-                    );
-                } else if char == ',' {
-                    // Handle function arguments separated by commas
-                    if !parameter.is_empty() {
-                        return ParseInfo::new(
-                            TokenTypes::FunctionArguments,
-                            (j - index).try_into().unwrap(),
-                            parameter.clone(),
-                        );
-                    }
-                    parameter.clear();
-                } else if !char.is_whitespace() {
-                    // Collect parameter characters
-                    parameter.push(char);
-                }
+        // Default return if no valid function call found
+        ParseInfo::new(TokenTypes::None, 0, "none".to_string())
+    }
 
-                j += 1;
+    ///
+    ///Reads the type at j, like int or dict<string, array<int>>, and returns it with the
+    ///position after it. Commas inside the angle brackets belong to the type
+    ///
+    fn read_type(chars: &[char], mut j: usize) -> (String, usize) {
+        let mut type_name = String::new();
+        let mut depth = 0;
+        while j < chars.len() {
+            let char = chars[j];
+            match char {
+                '<' | '(' => depth += 1,
+                '>' | ')' if depth > 0 => depth -= 1,
+                ',' if depth > 0 => {}
+                c if c.is_alphanumeric() || c == '_' => {}
+                c if c.is_whitespace() && depth > 0 => {}
+                _ => break,
             }
+            type_name.push(char);
+            j += 1;
         }
-
-        // Default return if no valid function call or parameters found
-        ParseInfo::new(TokenTypes::None, 0, "none".to_string())
+        (normalize_type(&type_name), j)
     }
 
-    pub fn read_function_declaration(expression: &String, index: usize) -> ParseInfo {
+    ///
+    ///Reads the header of func name(a: int, b: array<int>) -> int, the body after it is
+    ///tokenized as usual. Arguments are stored as (name, type, default value)
+    ///
+    pub fn read_function_declaration(chars: &[char], index: usize) -> ParseInfo {
+        let none = ParseInfo::new(TokenTypes::None, 0, "none".to_string());
         let mut j = index;
-        let mut function: String = String::new();
-        let func_compare = "func";
+
+        if chars.get(j..j + 4) != Some(&['f', 'u', 'n', 'c'][..]) {
+            return none;
+        }
+        j = skip_whitespace(chars, j + 4);
 
         let mut function_name = String::new();
-        let mut function_arguments: Vec<(String, String, String)> = Vec::new(); // Tuples for argument name and type
-        let mut return_type = String::new();
+        while j < chars.len() && (chars[j].is_alphanumeric() || chars[j] == '_') {
+            function_name.push(chars[j]);
+            j += 1;
+        }
+        j = skip_whitespace(chars, j);
+        if function_name.is_empty() || chars.get(j) != Some(&'(') {
+            return none;
+        }
+        j += 1;
 
-        // Collect alphabetic characters forming the function name
-        while j < expression.len() {
-            if let Some(char) = expression.chars().nth(j) {
-                if char.is_alphabetic() {
-                    function.push(char);
-                } else {
+        let mut function_arguments: Vec<(String, String, String)> = Vec::new();
+        loop {
+            j = skip_whitespace(chars, j);
+            match chars.get(j) {
+                Some(')') => {
+                    j += 1;
                     break;
                 }
+                Some(',') => {
+                    j += 1;
+                    continue;
+                }
+                None => return none,
+                _ => {}
             }
-            j += 1;
 
-            // If we've collected 4 characters, check if they form "func"
-            if function.len() == 4 {
-                if function == func_compare {
-                    // Skip whitespace and collect the actual function name
-                    while j < expression.len() {
-                        if let Some(char) = expression.chars().nth(j) {
-                            if char.is_alphabetic() {
-                                function_name.push(char);
-                            } else if char == '(' {
-                                break; // End of function name, start of arguments
-                            } else if char != ' ' {
-                                return ParseInfo::new(
-                                    TokenTypes::None,
-                                    (j - index).try_into().unwrap(),
-                                    "Invalid function declaration".to_string(),
-                                );
-                            }
-                        }
-                        j += 1;
-                    }
-
-                    if let Some('(') = expression.chars().nth(j) {
-                        j += 1; // Skip the '('
-                        let mut arg_name = String::new();
-                        let mut arg_type = String::new();
-                        let mut arg_value = String::from("null"); // Default value set to "null"
-                        let mut is_type = false;
-                        let mut is_value = false;
-
-                        while j < expression.len() {
-                            if let Some(char) = expression.chars().nth(j) {
-                                if char == ')' {
-                                    if !arg_name.is_empty() && !arg_type.is_empty() {
-                                        // Push the argument with its name, type, and value
-                                        function_arguments.push((
-                                            arg_name.clone(),
-                                            arg_type.clone(),
-                                            arg_value.clone(),
-                                        ));
-                                    }
-                                    break; // End of arguments
-                                } else if char == ':' {
-                                    is_type = true; // Start collecting type
-                                    is_value = false; // Ensure we stop collecting value
-                                } else if char == '=' {
-                                    is_value = true; // Start collecting default value
-                                } else if char == ',' {
-                                    // End of an argument, push collected data
-                                    if !arg_name.is_empty() && !arg_type.is_empty() {
-                                        function_arguments.push((
-                                            arg_name.clone(),
-                                            arg_type.clone(),
-                                            arg_value.clone(),
-                                        ));
-                                    }
-                                    // Clear the buffers for the next argument
-                                    arg_name.clear();
-                                    arg_type.clear();
-                                    arg_value = String::from("null"); // Reset default value to "null"
-                                    is_type = false;
-                                    is_value = false;
-                                } else if char.is_alphanumeric() || char == '_' {
-                                    // Collect the argument name, type, or value based on flags
-                                    if is_value {
-                                        arg_value.push(char);
-                                    } else if is_type {
-                                        arg_type.push(char);
-                                    } else {
-                                        arg_name.push(char);
-                                    }
-                                }
-                            }
-                            j += 1;
-                        }
-                    }
-
-                    // Look for the return type after the `->`
-                    while j < expression.len() {
-                        if let Some(char) = expression.chars().nth(j) {
-                            if char == '-' && expression.chars().nth(j + 1) == Some('>') {
-                                j += 2; // Skip `->`
-                                while j < expression.len()
-                                    && expression.chars().nth(j).unwrap().is_whitespace()
-                                {
-                                    j += 1; // Skip whitespace
-                                }
-                                while j < expression.len() {
-                                    if let Some(char) = expression.chars().nth(j) {
-                                        if char.is_alphanumeric() || char == '_' {
-                                            return_type.push(char);
-                                        } else {
-                                            break;
-                                        }
-                                    }
-                                    j += 1;
-                                }
-                                break;
-                            }
-                        }
-                        j += 1;
-                    }
+            let mut arg_name = String::new();
+            while j < chars.len() && (chars[j].is_alphanumeric() || chars[j] == '_') {
+                arg_name.push(chars[j]);
+                j += 1;
+            }
+            j = skip_whitespace(chars, j);
+            if arg_name.is_empty() || chars.get(j) != Some(&':') {
+                return none;
+            }
+            let (arg_type, next) = read_type(chars, skip_whitespace(chars, j + 1));
+            j = skip_whitespace(chars, next);
 
-                    // Return ParseInfo with the parsed function details
-                    return ParseInfo::new(
-                        TokenTypes::Function {
-                            name: function_name.clone(),
-                            arguments: function_arguments.clone(),
-                            return_type: return_type.clone(),
-                        },
-                        (j - index).try_into().unwrap(),
-                        function_name.to_string(),
-                    );
-                } else {
-                    return ParseInfo::new(
-                        TokenTypes::None,
-                        (j - index).try_into().unwrap(),
-                        "none".to_string(),
-                    );
+            // A default value runs up to the next , or )
+            let mut arg_value = String::from("null");
+            if chars.get(j) == Some(&'=') {
+                arg_value.clear();
+                j += 1;
+                while j < chars.len() && chars[j] != ',' && chars[j] != ')' {
+                    arg_value.push(chars[j]);
+                    j += 1;
                 }
+                arg_value = arg_value.trim().to_string();
             }
+            function_arguments.push((arg_name, arg_type, arg_value));
+        }
 
-            // If more than 4 characters, remove the first character (sliding window)
-            if function.len() > 4 {
-                function.remove(0);
-            }
+        // The return type is optional, a function without one returns nothing
+        let mut return_type = String::new();
+        let after_arguments = j;
+        j = skip_whitespace(chars, j);
+        if chars.get(j) == Some(&'-') && chars.get(j + 1) == Some(&'>') {
+            let (type_name, next) = read_type(chars, skip_whitespace(chars, j + 2));
+            return_type = type_name;
+            j = next;
+        } else {
+            j = after_arguments;
         }
 
-        // Return default ParseInfo if function not found
-        ParseInfo::new(TokenTypes::None, 0, "none".to_string())
+        ParseInfo::new(
+            TokenTypes::Function {
+                name: function_name.clone(),
+                arguments: function_arguments,
+                return_type,
+            },
+            (j - index).try_into().unwrap(),
+            function_name,
+        )
     }
 }
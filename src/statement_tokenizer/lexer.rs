@@ -0,0 +1,429 @@
+/*
+* This file scans the written source code once, from the first character to the last, and hands
+* out its tokens one at a time. A word is read whole before it is looked up in the keywords, so
+* names like iffy or format are never taken for the keyword they start with
+*/
+
+pub mod lexers {
+    use crate::statement_tokenizer::basic_tokenizer::basic_tokenizers::{
+        read_numbers, read_operators, read_strings_chars,
+    };
+    use crate::statement_tokenizer::collection_tokenizer::collection_tokenizers::read_collection_assignment;
+    use crate::statement_tokenizer::conditional_tokenizer::conditional_tokenizers::{
+        tokenize_if_elif_else_statement, tokenize_try_catch_finally_statement,
+    };
+    use crate::statement_tokenizer::function_tokenizer::function_tokenizers::{
+        read_function_assignment, read_function_call, read_function_declaration,
+    };
+    use crate::statement_tokenizer::loop_tokenizer::loop_tokenizers::tokenize_for_while_statement;
+    use crate::statement_tokenizer::tokenizer::tokenizers::ParseInfo;
+    use crate::statement_tokenizer::variable_tokenizer::variable_tokenizers::{
+        read_destructure, read_variable_assignment, read_variable_declaration,
+    };
+    use crate::token_type::token_types::TokenTypes;
+
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub enum Keyword {
+        Let,
+        Const,
+        Func,
+        Return,
+        If,
+        Elif,
+        Else,
+        For,
+        While,
+        Try,
+        Catch,
+        Finally,
+        Bool,
+        As,
+    }
+
+    ///
+    ///The reserved words, these are never read as names
+    ///
+    pub const KEYWORDS: [(&str, Keyword); 17] = [
+        ("let", Keyword::Let),
+        ("const", Keyword::Const),
+        ("func", Keyword::Func),
+        ("return", Keyword::Return),
+        ("if", Keyword::If),
+        ("elif", Keyword::Elif),
+        ("else", Keyword::Else),
+        ("for", Keyword::For),
+        ("while", Keyword::While),
+        ("try", Keyword::Try),
+        ("catch", Keyword::Catch),
+        ("finally", Keyword::Finally),
+        ("true", Keyword::Bool),
+        ("false", Keyword::Bool),
+        ("True", Keyword::Bool),
+        ("False", Keyword::Bool),
+        ("as", Keyword::As),
+    ];
+
+    ///
+    ///The keyword a whole word is, None when it is a name
+    ///
+    pub fn keyword(word: &str) -> Option<Keyword> {
+        KEYWORDS
+            .iter()
+            .find(|(reserved, _)| *reserved == word)
+            .map(|(_, keyword)| *keyword)
+    }
+
+    pub fn is_name_start(c: char) -> bool {
+        c.is_alphabetic() || c == '_'
+    }
+
+    ///
+    ///The index just past the name that starts at index
+    ///
+    pub fn read_name(chars: &[char], mut index: usize) -> usize {
+        while index < chars.len() && (chars[index].is_alphanumeric() || chars[index] == '_') {
+            index += 1;
+        }
+        index
+    }
+
+    ///
+    ///The index of the name after the let or const at index, past the mut of a let mut. The
+    ///index is returned as it is when no such keyword starts there
+    ///
+    pub fn skip_binding_keyword(chars: &[char], index: usize) -> usize {
+        let end = read_name(chars, index);
+        let word: String = chars[index..end].iter().collect();
+        if !matches!(keyword(&word), Some(Keyword::Let | Keyword::Const)) {
+            return index;
+        }
+        let start = skip_whitespace(chars, end);
+        let after = read_name(chars, start);
+        // mut on its own is still a name, like the one of let mut: int
+        let mutable = word == "let"
+            && chars[start..after] == ['m', 'u', 't']
+            && chars
+                .get(skip_whitespace(chars, after))
+                .is_some_and(|c| is_name_start(*c) || *c == '(');
+        if mutable {
+            return skip_whitespace(chars, after);
+        }
+        start
+    }
+
+    pub fn skip_whitespace(chars: &[char], mut index: usize) -> usize {
+        while index < chars.len() && chars[index].is_whitespace() {
+            index += 1;
+        }
+        index
+    }
+
+    // A /* comment can be split over several statements, the next one carries on inside it
+    static mut MULTLINECOMMENT: bool = false;
+
+    pub struct Lexer {
+        chars: Vec<char>,
+        position: usize,
+        // where the open multi-line comment started, 0 when it started in an earlier statement
+        comment_start: Option<usize>,
+        // whether an open comment is carried over from and into the other statements
+        carry_comment: bool,
+        // the input that is no token, with where it starts
+        skipped: Vec<(usize, String)>,
+        // the last token ended a value, so a - after it subtracts rather than negates
+        after_value: bool,
+    }
+
+    impl Lexer {
+        pub fn new(expression: &str) -> Self {
+            Lexer {
+                chars: expression.chars().collect(),
+                position: 0,
+                comment_start: unsafe { MULTLINECOMMENT }.then_some(0),
+                carry_comment: true,
+                skipped: Vec::new(),
+                after_value: false,
+            }
+        }
+
+        ///
+        ///A lexer for a whole script, a comment left open in it is not carried into the
+        ///statements tokenized after it
+        ///
+        pub fn standalone(source: &str) -> Self {
+            Lexer {
+                comment_start: None,
+                carry_comment: false,
+                ..Lexer::new(source)
+            }
+        }
+
+        ///
+        ///Where the next token starts, after the one returned last
+        ///
+        pub fn position(&self) -> usize {
+            self.position
+        }
+
+        ///
+        ///Where the /* comment the input ended inside of starts
+        ///
+        pub fn open_comment(&self) -> Option<usize> {
+            self.comment_start
+        }
+
+        ///
+        ///The words and characters that were passed over because they are no token
+        ///
+        pub fn skipped(&self) -> &[(usize, String)] {
+            &self.skipped
+        }
+
+        fn at(&self, index: usize) -> char {
+            self.chars.get(index).copied().unwrap_or('\0')
+        }
+
+        ///
+        ///Moves past the token read at the cursor, or returns None when nothing was read
+        ///
+        fn take(&mut self, info: ParseInfo) -> Option<ParseInfo> {
+            if info.token == TokenTypes::None {
+                return None;
+            }
+            self.position += info.chars_read as usize;
+            Some(info)
+        }
+
+        ///
+        ///Reads on to the end of the open multi-line comment. Without one the comment carries on
+        ///into the next statement and nothing is returned yet
+        ///
+        fn block_comment(&mut self, start: usize) -> Option<ParseInfo> {
+            let close = (self.position..self.chars.len().saturating_sub(1))
+                .find(|&i| self.chars[i] == '*' && self.chars[i + 1] == '/');
+            let Some(close) = close else {
+                self.position = self.chars.len();
+                return None;
+            };
+            self.position = close + 2;
+            self.comment_start = None;
+            if self.carry_comment {
+                unsafe { MULTLINECOMMENT = false };
+            }
+            Some(ParseInfo::new(
+                TokenTypes::Comment,
+                (self.position - start) as i32,
+                self.chars[start..self.position].iter().collect(),
+            ))
+        }
+
+        fn line_comment(&mut self) -> ParseInfo {
+            let start = self.position;
+            let end = self.chars[start..]
+                .iter()
+                .position(|c| *c == '\n')
+                .map_or(self.chars.len(), |length| start + length);
+            let text: String = self.chars[start..end].iter().collect();
+            ParseInfo::new(
+                TokenTypes::Comment,
+                (end - start) as i32,
+                text.trim_end().to_string(),
+            )
+        }
+
+        ///
+        ///Reads what starts with a word: a keyword and what belongs to it, a call, a typed
+        ///declaration or a name
+        ///
+        fn word(&mut self) -> Option<ParseInfo> {
+            let start = self.position;
+            let end = read_name(&self.chars, start);
+            let word: String = self.chars[start..end].iter().collect();
+            let chars = &self.chars;
+
+            let info = match keyword(&word) {
+                Some(Keyword::Return) => ParseInfo::new(TokenTypes::Return, 6, word.clone()),
+                // x as int converts x, it is read like the other binary operators
+                Some(Keyword::As) => ParseInfo::new(TokenTypes::Operator, 2, word.clone()),
+                Some(Keyword::Bool) => ParseInfo::new(
+                    TokenTypes::Bool,
+                    word.len().try_into().unwrap(),
+                    word.clone(),
+                ),
+                Some(Keyword::Let | Keyword::Const) => [
+                    read_destructure,
+                    read_variable_declaration,
+                    read_collection_assignment,
+                ]
+                .iter()
+                .map(|read| read(chars, start))
+                .find(|info| info.token != TokenTypes::None)
+                .unwrap_or_else(|| ParseInfo::new(TokenTypes::None, 0, "none".to_string())),
+                Some(Keyword::Func) => read_function_declaration(chars, start),
+                Some(keyword @ (Keyword::If | Keyword::Elif | Keyword::Else)) => {
+                    tokenize_if_elif_else_statement(chars, start, keyword)
+                }
+                Some(keyword @ (Keyword::For | Keyword::While)) => {
+                    tokenize_for_while_statement(chars, start, keyword)
+                }
+                Some(keyword) => tokenize_try_catch_finally_statement(keyword),
+                None => return Some(self.name(start, end)),
+            };
+            if info.token == TokenTypes::None {
+                trace!(
+                    Tokenizer,
+                    Warn,
+                    "No token found for '{}' at {}",
+                    word,
+                    start
+                );
+                self.skipped.push((start, word));
+                self.position = end;
+                return None;
+            }
+            self.take(info)
+        }
+
+        ///
+        ///Reads a word that is not a keyword. It is a call when a ( follows, a declaration when
+        ///a : type = follows and a variable otherwise, together with the names joined to it by
+        ///a . like math.PI
+        ///
+        fn name(&mut self, start: usize, end: usize) -> ParseInfo {
+            let chars = &self.chars;
+            let mut info = read_function_call(chars, start);
+            if info.token == TokenTypes::None {
+                let colon = skip_whitespace(chars, end);
+                if chars.get(colon) == Some(&':') {
+                    info = read_variable_assignment(chars, colon);
+                    if info.token != TokenTypes::None {
+                        info.chars_read += (colon - start) as i32;
+                    } else {
+                        info = read_collection_assignment(chars, start);
+                    }
+                }
+            }
+            if info.token == TokenTypes::None {
+                // a name read from a module, like math.PI
+                let mut end = end;
+                while chars.get(end) == Some(&'.')
+                    && chars.get(end + 1).is_some_and(|c| is_name_start(*c))
+                {
+                    end = read_name(chars, end + 1);
+                }
+                info = ParseInfo::new(
+                    TokenTypes::VariableCall,
+                    (end - start).try_into().unwrap(),
+                    chars[start..end].iter().collect(),
+                );
+            }
+            self.position += info.chars_read as usize;
+            info
+        }
+
+        ///
+        ///Reads the token at the cursor, None when the characters there are not a token
+        ///
+        fn token(&mut self) -> Option<ParseInfo> {
+            let c = self.at(self.position);
+            let next = self.at(self.position + 1);
+            let chars = &self.chars;
+            match c {
+                ';' => self.take(ParseInfo::new(TokenTypes::SemiColon, 1, ";".to_string())),
+                '/' if next == '/' => {
+                    let info = self.line_comment();
+                    self.take(info)
+                }
+                // the : type = after the name of a let
+                ':' => self.take(read_variable_assignment(chars, self.position)),
+                '"' | '\'' => self.take(read_strings_chars(chars, self.position)),
+                'r' if next == '"' || next == '#' => {
+                    let info = read_strings_chars(chars, self.position);
+                    self.take(info).or_else(|| self.word())
+                }
+                c if c.is_ascii_digit() || (c == '.' && next.is_ascii_digit()) => {
+                    self.take(read_numbers(chars, self.position))
+                }
+                // a negative number is one literal, so -2147483648 fits in an int
+                '-' if !self.after_value && next.is_ascii_digit() => {
+                    let mut info = read_numbers(chars, self.position + 1);
+                    if info.token == TokenTypes::None {
+                        return self.take(read_operators(c, next));
+                    }
+                    info.chars_read += 1;
+                    info.value.insert(0, '-');
+                    self.take(info)
+                }
+                // a method called on the value before it, like the .len of s.len()
+                '.' if is_name_start(next) => self.take(read_function_call(chars, self.position)),
+                '-' if next == '>' => {
+                    let info = read_function_assignment(chars, self.position);
+                    self.take(info)
+                        .or_else(|| self.take(read_operators(c, next)))
+                }
+                c if is_name_start(c) => self.word(),
+                _ => self.take(read_operators(c, next)),
+            }
+        }
+    }
+
+    impl Iterator for Lexer {
+        type Item = ParseInfo;
+
+        fn next(&mut self) -> Option<ParseInfo> {
+            loop {
+                if let Some(start) = self.comment_start {
+                    let info = self.block_comment(start);
+                    if info.is_some() || self.position >= self.chars.len() {
+                        return info;
+                    }
+                }
+
+                self.position = skip_whitespace(&self.chars, self.position);
+                if self.position >= self.chars.len() {
+                    return None;
+                }
+
+                if self.at(self.position) == '/' && self.at(self.position + 1) == '*' {
+                    self.comment_start = Some(self.position);
+                    if self.carry_comment {
+                        unsafe { MULTLINECOMMENT = true };
+                    }
+                    self.position += 2;
+                    continue;
+                }
+
+                let start = self.position;
+                if let Some(info) = self.token() {
+                    if info.token != TokenTypes::Comment {
+                        self.after_value = matches!(
+                            info.token,
+                            TokenTypes::Int
+                                | TokenTypes::Float
+                                | TokenTypes::String
+                                | TokenTypes::Char
+                                | TokenTypes::Bool
+                                | TokenTypes::VariableCall
+                                | TokenTypes::RightParenthesis
+                                | TokenTypes::RightBracket
+                                | TokenTypes::RightCurly
+                        );
+                    }
+                    return Some(info);
+                }
+                // the word readers move past a word they could not read themselves
+                if self.position == start {
+                    trace!(
+                        Tokenizer,
+                        Warn,
+                        "No token found for '{}' at {}",
+                        self.at(start),
+                        start
+                    );
+                    self.skipped.push((start, self.at(start).to_string()));
+                    self.position += 1;
+                }
+            }
+        }
+    }
+}
@@ -1,5 +1,5 @@
 pub mod loop_tokenizers {
-    use crate::statement_tokenizer::tokenizer::tokenizers::tokenize;
+    use crate::statement_tokenizer::lexer::lexers::{skip_whitespace, Keyword};
     use crate::statement_tokenizer::tokenizer::tokenizers::ParseInfo;
     use crate::token_type::token_types::TokenTypes;
 
@@ -21,74 +21,39 @@ pub mod loop_tokenizers {
             condition.push(c);
             index += 1;
         }
-        //tokenize the condition
         (condition, index)
     }
 
-    fn parse_keyword(expression: &str, index: usize, keyword: &str) -> Option<usize> {
-        let slice = &expression[index..];
-        if slice.starts_with(keyword) {
-            Some(index + keyword.len())
-        } else {
-            None
+    ///
+    ///Reads for (condition) or while (condition), index is at the keyword. The token stops in
+    ///front of the closing )
+    ///
+    pub fn tokenize_for_while_statement(
+        chars: &[char],
+        index: usize,
+        keyword: Keyword,
+    ) -> ParseInfo {
+        let none = ParseInfo::new(TokenTypes::None, 0, "none".to_string());
+        let word = match keyword {
+            Keyword::For => "for",
+            Keyword::While => "while",
+            _ => return none,
+        };
+        let j = skip_whitespace(chars, index + word.len());
+        if chars.get(j) != Some(&'(') {
+            return none;
         }
-    }
-
-    pub fn tokenize_for_while_statement(expression: &str, index: usize) -> ParseInfo {
-        let chars: Vec<char> = expression.chars().collect();
-        let mut j = index;
 
-        while j < chars.len() {
-            let c = chars[j];
-            if c.is_whitespace() {
-                j += 1;
-                continue;
+        let (condition, close) = extract_condition(chars, j + 1);
+        let token = if keyword == Keyword::For {
+            TokenTypes::For {
+                statement: condition,
             }
-
-            // Tokenize the "for" loop
-            if let Some(new_index) = parse_keyword(expression, j, "for") {
-                j = new_index;
-
-                // Look for '(' and extract the loop condition
-                while j < chars.len() && chars[j].is_whitespace() {
-                    j += 1;
-                }
-
-                if chars[j] == '(' {
-                    let (condition, new_j) = extract_condition(&chars, j + 1);
-                    return ParseInfo::new(
-                        TokenTypes::For {
-                            statement: condition,
-                        },
-                        new_j.try_into().unwrap(),
-                        "for".to_string(),
-                    );
-                }
-
-            // Tokenize the "while" loop
-            } else if let Some(new_index) = parse_keyword(expression, j, "while") {
-                j = new_index;
-
-                // Look for '(' and extract the loop condition
-                while j < chars.len() && chars[j].is_whitespace() {
-                    j += 1;
-                }
-
-                if chars[j] == '(' {
-                    let (condition, new_j) = extract_condition(&chars, j + 1);
-                    return ParseInfo::new(
-                        TokenTypes::While {
-                            statement: condition,
-                        },
-                        new_j.try_into().unwrap(),
-                        "while".to_string(),
-                    );
-                }
+        } else {
+            TokenTypes::While {
+                statement: condition,
             }
-
-            j += 1;
-        }
-
-        ParseInfo::new(TokenTypes::None, 0, "none".to_string())
+        };
+        ParseInfo::new(token, (close - index).try_into().unwrap(), word.to_string())
     }
 }
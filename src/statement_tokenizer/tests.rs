@@ -1,5 +1,7 @@
 #[cfg(test)]
 mod tokenizer_tests {
+    use crate::statement_tokenizer::basic_tokenizer::basic_tokenizers::unescape_literal;
+    use crate::statement_tokenizer::lexer::lexers::{keyword, Keyword, Lexer};
     use crate::statement_tokenizer::tokenizer::tokenizers::ParseInfo;
     use crate::statement_tokenizer::tokenizer::tokenizers::{self, tokenize};
     use crate::token_type::token_types::TokenTypes;
@@ -294,12 +296,12 @@ mod tokenizer_tests {
         let expected = vec![
             ParseInfo {
                 token: TokenTypes::Variable,
-                chars_read: 1,
+                chars_read: 5,
                 value: "a".to_string(),
             },
             ParseInfo {
                 token: TokenTypes::VarTypeAssignment,
-                chars_read: 10,
+                chars_read: 6,
                 value: "int".to_string(),
             },
             ParseInfo {
@@ -329,12 +331,12 @@ mod tokenizer_tests {
         let expected = vec![
             ParseInfo {
                 token: TokenTypes::Variable,
-                chars_read: 1,
+                chars_read: 5,
                 value: "a".to_string(),
             },
             ParseInfo {
                 token: TokenTypes::VarTypeAssignment,
-                chars_read: 12,
+                chars_read: 8,
                 value: "float".to_string(),
             },
             ParseInfo {
@@ -363,12 +365,12 @@ mod tokenizer_tests {
         let expected = vec![
             ParseInfo {
                 token: TokenTypes::Variable,
-                chars_read: 1,
+                chars_read: 5,
                 value: "a".to_string(),
             },
             ParseInfo {
                 token: TokenTypes::VarTypeAssignment,
-                chars_read: 13,
+                chars_read: 9,
                 value: "string".to_string(),
             },
             ParseInfo {
@@ -397,12 +399,12 @@ mod tokenizer_tests {
         let expected = vec![
             ParseInfo {
                 token: TokenTypes::Variable,
-                chars_read: 1,
+                chars_read: 5,
                 value: "a".to_string(),
             },
             ParseInfo {
                 token: TokenTypes::VarTypeAssignment,
-                chars_read: 11,
+                chars_read: 7,
                 value: "char".to_string(),
             },
             ParseInfo {
@@ -431,12 +433,12 @@ mod tokenizer_tests {
         let expected = vec![
             ParseInfo {
                 token: TokenTypes::Variable,
-                chars_read: 1,
+                chars_read: 5,
                 value: "a".to_string(),
             },
             ParseInfo {
                 token: TokenTypes::VarTypeAssignment,
-                chars_read: 11,
+                chars_read: 7,
                 value: "bool".to_string(),
             },
             ParseInfo {
@@ -465,12 +467,12 @@ mod tokenizer_tests {
         let expected = vec![
             ParseInfo {
                 token: TokenTypes::Variable,
-                chars_read: 1,
+                chars_read: 5,
                 value: "a".to_string(),
             },
             ParseInfo {
                 token: TokenTypes::VarTypeAssignment,
-                chars_read: 11,
+                chars_read: 7,
                 value: "bool".to_string(),
             },
             ParseInfo {
@@ -749,4 +751,370 @@ mod tokenizer_tests {
         let result = tokenizers::tokenize(input);
         assert_eq!(result, expected);
     }
+
+    #[test]
+    fn test_tokenize_string_with_escaped_quotes() {
+        let input = r#"echo("He said \"hi\"; {ok}");"#.to_string();
+        let result = tokenizers::tokenize(input);
+        assert_eq!(
+            result[2],
+            ParseInfo {
+                token: TokenTypes::String,
+                chars_read: 22,
+                value: r#""He said \"hi\"; {ok}""#.to_string(),
+            }
+        );
+        assert_eq!(result[3].token, TokenTypes::RightParenthesis);
+    }
+
+    #[test]
+    fn test_tokenize_raw_and_triple_quoted_strings() {
+        let input = "r\"C:\\path\" r#\"say \"hi\"\"# \"\"\"\nline one\nline two\"\"\";".to_string();
+        let result = tokenizers::tokenize(input);
+        let values: Vec<String> = result.iter().map(|info| info.value.clone()).collect();
+        assert_eq!(
+            values,
+            vec![
+                "r\"C:\\path\"".to_string(),
+                "r#\"say \"hi\"\"#".to_string(),
+                "\"\"\"\nline one\nline two\"\"\"".to_string(),
+                ";".to_string(),
+            ]
+        );
+        assert!(result[..3]
+            .iter()
+            .all(|info| info.token == TokenTypes::String));
+    }
+
+    #[test]
+    fn test_tokenize_escaped_char() {
+        let input = r"let a: char = '\'';".to_string();
+        let result = tokenizers::tokenize(input);
+        assert_eq!(
+            result[3],
+            ParseInfo {
+                token: TokenTypes::Char,
+                chars_read: 4,
+                value: r"'\''".to_string(),
+            }
+        );
+    }
+
+    #[test]
+    fn test_unescape_literal() {
+        assert_eq!(
+            unescape_literal(r#""a\nb\t\\""#),
+            Ok("a\nb\t\\".to_string())
+        );
+        assert_eq!(unescape_literal(r#""\"hi\"""#), Ok("\"hi\"".to_string()));
+        assert_eq!(unescape_literal(r"'\''"), Ok("'".to_string()));
+        assert_eq!(
+            unescape_literal(r#""\u{1F600}""#),
+            Ok("\u{1F600}".to_string())
+        );
+        assert_eq!(
+            unescape_literal(r#"r"C:\path""#),
+            Ok(r"C:\path".to_string())
+        );
+        assert_eq!(
+            unescape_literal(r##"r#"a "b" c"#"##),
+            Ok("a \"b\" c".to_string())
+        );
+        assert_eq!(
+            unescape_literal("\"\"\"\nfirst\n  second\"\"\""),
+            Ok("first\n  second".to_string())
+        );
+        assert!(unescape_literal(r#""\q""#).is_err());
+    }
+
+    #[test]
+    fn test_tokenize_method_calls() {
+        let input = r#"echo("a,b".split(",").len());"#.to_string();
+        let result = tokenizers::tokenize(input);
+        let tokens: Vec<(TokenTypes, String)> = result
+            .into_iter()
+            .map(|info| (info.token, info.value))
+            .collect();
+        assert_eq!(
+            tokens,
+            vec![
+                (TokenTypes::FunctionCall, "echo".to_string()),
+                (TokenTypes::LeftParenthesis, "(".to_string()),
+                (TokenTypes::String, "\"a,b\"".to_string()),
+                (TokenTypes::FunctionCall, ".split".to_string()),
+                (TokenTypes::LeftParenthesis, "(".to_string()),
+                (TokenTypes::String, "\",\"".to_string()),
+                (TokenTypes::RightParenthesis, ")".to_string()),
+                (TokenTypes::FunctionCall, ".len".to_string()),
+                (TokenTypes::LeftParenthesis, "(".to_string()),
+                (TokenTypes::RightParenthesis, ")".to_string()),
+                (TokenTypes::RightParenthesis, ")".to_string()),
+                (TokenTypes::SemiColon, ";".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_tokenize_range_operator() {
+        let input = "[1..3]".to_string();
+        let result = tokenizers::tokenize(input);
+        let tokens: Vec<(TokenTypes, String)> = result
+            .into_iter()
+            .map(|info| (info.token, info.value))
+            .collect();
+        assert_eq!(
+            tokens,
+            vec![
+                (TokenTypes::LeftBracket, "[".to_string()),
+                (TokenTypes::Int, "1".to_string()),
+                (TokenTypes::Operator, "..".to_string()),
+                (TokenTypes::Int, "3".to_string()),
+                (TokenTypes::RightBracket, "]".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_tokenize_compound_assignment() {
+        let input = "total += a[i] %= 2;".to_string();
+        let result = tokenizers::tokenize(input);
+        let tokens: Vec<(TokenTypes, String)> = result
+            .into_iter()
+            .map(|info| (info.token, info.value))
+            .collect();
+        assert_eq!(
+            tokens,
+            vec![
+                (TokenTypes::VariableCall, "total".to_string()),
+                (TokenTypes::AssignmentOperator, "+=".to_string()),
+                (TokenTypes::VariableCall, "a".to_string()),
+                (TokenTypes::LeftBracket, "[".to_string()),
+                (TokenTypes::VariableCall, "i".to_string()),
+                (TokenTypes::RightBracket, "]".to_string()),
+                (TokenTypes::AssignmentOperator, "%=".to_string()),
+                (TokenTypes::Int, "2".to_string()),
+                (TokenTypes::SemiColon, ";".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_tokenize_mut_and_const() {
+        let input = "let mut n: int = 1; const R: float = math.PI; let mut (a, b) = t;".to_string();
+        let result = tokenizers::tokenize(input);
+        let tokens: Vec<(TokenTypes, String, i32)> = result
+            .into_iter()
+            .map(|info| (info.token, info.value, info.chars_read))
+            .collect();
+        assert_eq!(tokens[0], (TokenTypes::Variable, "n".to_string(), 9));
+        assert_eq!(tokens[5], (TokenTypes::Variable, "R".to_string(), 7));
+        assert_eq!(
+            tokens[8],
+            (TokenTypes::VariableCall, "math.PI".to_string(), 7)
+        );
+        assert_eq!(
+            tokens[10].0,
+            TokenTypes::Destructure {
+                names: vec!["a".to_string(), "b".to_string()]
+            }
+        );
+
+        // mut on its own is an ordinary name
+        let result = tokenizers::tokenize("let mut: int = 1;".to_string());
+        assert_eq!(result[0].token, TokenTypes::Variable);
+        assert_eq!(result[0].value, "mut");
+    }
+
+    #[test]
+    fn test_tokenize_conversions() {
+        let input = "total as float + s.parse::<int>() + t.parse::< bool >();".to_string();
+        let result = tokenizers::tokenize(input);
+        let tokens: Vec<(TokenTypes, String)> = result
+            .into_iter()
+            .map(|info| (info.token, info.value))
+            .collect();
+        assert_eq!(tokens[1], (TokenTypes::Operator, "as".to_string()));
+        assert_eq!(tokens[2], (TokenTypes::VariableCall, "float".to_string()));
+        assert_eq!(
+            tokens[4],
+            (TokenTypes::FunctionCall, "s.parse::<int>".to_string())
+        );
+        assert_eq!(
+            tokens[8],
+            (TokenTypes::FunctionCall, "t.parse::<bool>".to_string())
+        );
+    }
+
+    #[test]
+    fn test_tokenize_lambda() {
+        let input = "nums.map((n, i) => n * i);".to_string();
+        let result = tokenizers::tokenize(input);
+        let tokens: Vec<(TokenTypes, String)> = result
+            .into_iter()
+            .map(|info| (info.token, info.value))
+            .collect();
+        assert_eq!(
+            tokens,
+            vec![
+                (TokenTypes::FunctionCall, "nums.map".to_string()),
+                (TokenTypes::LeftParenthesis, "(".to_string()),
+                (TokenTypes::LeftParenthesis, "(".to_string()),
+                (TokenTypes::VariableCall, "n".to_string()),
+                (TokenTypes::ArgumentSeparator, ",".to_string()),
+                (TokenTypes::VariableCall, "i".to_string()),
+                (TokenTypes::RightParenthesis, ")".to_string()),
+                (TokenTypes::FatArrow, "=>".to_string()),
+                (TokenTypes::VariableCall, "n".to_string()),
+                (TokenTypes::Operator, "*".to_string()),
+                (TokenTypes::VariableCall, "i".to_string()),
+                (TokenTypes::RightParenthesis, ")".to_string()),
+                (TokenTypes::SemiColon, ";".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_tokenize_nested_collection_type() {
+        let input = "let index: dict<string, array<dict<int, any>>> = {};".to_string();
+        let result = tokenizers::tokenize(input);
+        assert_eq!(
+            result[0].token,
+            TokenTypes::Collection {
+                name: "index".to_string(),
+                collection_type: "dict".to_string(),
+                stored_value_type_single: "".to_string(),
+                stored_value_type_tuple: (
+                    "string".to_string(),
+                    "array<dict<int, any>>".to_string()
+                ),
+            }
+        );
+    }
+
+    #[test]
+    fn test_tokenize_function_declaration() {
+        let input = "func pick(rows: array<dict<string, int>>, at: int = 0) -> dict<string, int> {"
+            .to_string();
+        let result = tokenizers::tokenize(input);
+        assert_eq!(
+            result[0].token,
+            TokenTypes::Function {
+                name: "pick".to_string(),
+                arguments: vec![
+                    (
+                        "rows".to_string(),
+                        "array<dict<string, int>>".to_string(),
+                        "null".to_string()
+                    ),
+                    ("at".to_string(), "int".to_string(), "0".to_string()),
+                ],
+                return_type: "dict<string, int>".to_string(),
+            }
+        );
+        assert_eq!(result[1].token, TokenTypes::LeftCurly);
+
+        let result = tokenizers::tokenize("return rows[at];".to_string());
+        assert_eq!(result[0].token, TokenTypes::Return);
+        assert_eq!(result[1].token, TokenTypes::VariableCall);
+    }
+
+    #[test]
+    fn test_tokenize_tuples() {
+        let input = "let pair: (int, string) = (1, \"a\");".to_string();
+        let result = tokenizers::tokenize(input);
+        assert_eq!(
+            result[0].token,
+            TokenTypes::Collection {
+                name: "pair".to_string(),
+                collection_type: "tuple".to_string(),
+                stored_value_type_single: "(int, string)".to_string(),
+                stored_value_type_tuple: ("".to_string(), "".to_string()),
+            }
+        );
+
+        let result = tokenizers::tokenize("let (q, _) = divmod(7, 2);".to_string());
+        assert_eq!(
+            result[0].token,
+            TokenTypes::Destructure {
+                names: vec!["q".to_string(), "_".to_string()],
+            }
+        );
+        assert_eq!(result[1].token, TokenTypes::AssignmentOperator);
+        assert_eq!(result[2].token, TokenTypes::FunctionCall);
+    }
+
+    #[test]
+    fn test_tokenize_comments_keep_text() {
+        let input = "/* counts\n   things */ let a: int = 1; // one   ".to_string();
+        let comments: Vec<String> = tokenizers::tokenize(input)
+            .into_iter()
+            .filter(|info| info.token == TokenTypes::Comment)
+            .map(|info| info.value)
+            .collect();
+        assert_eq!(comments, vec!["/* counts\n   things */", "// one"]);
+    }
+
+    #[test]
+    fn test_tokenize_names_starting_with_keywords() {
+        let input = "echo(tryhard(iffy, format, true_x, returned, letter));".to_string();
+        let tokens: Vec<(TokenTypes, String)> = tokenizers::tokenize(input)
+            .into_iter()
+            .filter(|info| info.token != TokenTypes::ArgumentSeparator)
+            .map(|info| (info.token, info.value))
+            .collect();
+        assert_eq!(
+            tokens,
+            vec![
+                (TokenTypes::FunctionCall, "echo".to_string()),
+                (TokenTypes::LeftParenthesis, "(".to_string()),
+                (TokenTypes::FunctionCall, "tryhard".to_string()),
+                (TokenTypes::LeftParenthesis, "(".to_string()),
+                (TokenTypes::VariableCall, "iffy".to_string()),
+                (TokenTypes::VariableCall, "format".to_string()),
+                (TokenTypes::VariableCall, "true_x".to_string()),
+                (TokenTypes::VariableCall, "returned".to_string()),
+                (TokenTypes::VariableCall, "letter".to_string()),
+                (TokenTypes::RightParenthesis, ")".to_string()),
+                (TokenTypes::RightParenthesis, ")".to_string()),
+                (TokenTypes::SemiColon, ";".to_string()),
+            ]
+        );
+        assert_eq!(keyword("while"), Some(Keyword::While));
+        assert_eq!(keyword("whiley"), None);
+    }
+
+    #[test]
+    fn test_lexer_reads_tokens_one_at_a_time() {
+        let mut lexer = Lexer::new("// first\nif (n < 2) { return n; } else { echo(n); }");
+        assert_eq!(
+            lexer.next().map(|info| info.token),
+            Some(TokenTypes::Comment)
+        );
+        assert_eq!(
+            lexer.next().map(|info| info.token),
+            Some(TokenTypes::If {
+                statement: "n < 2".to_string()
+            })
+        );
+        let rest: Vec<TokenTypes> = lexer.map(|info| info.token).collect();
+        assert_eq!(
+            rest[..3],
+            [
+                TokenTypes::RightParenthesis,
+                TokenTypes::LeftCurly,
+                TokenTypes::Return
+            ]
+        );
+        assert!(rest.contains(&TokenTypes::Else));
+        assert_eq!(rest.last(), Some(&TokenTypes::RightCurly));
+    }
+
+    #[test]
+    fn test_tokenize_long_statement() {
+        let numbers: Vec<String> = (0..50_000).map(|n| n.to_string()).collect();
+        let input = format!("[{}];", numbers.join(", "));
+        let result = tokenizers::tokenize(input);
+        assert_eq!(result.len(), 2 * numbers.len() + 2);
+        assert_eq!(result[result.len() - 3].value, "49999");
+    }
 }
@@ -1,225 +1,120 @@
 pub mod variable_tokenizers {
-    use crate::base_variable::variables::VARIABLE_STACK;
+    use crate::statement_tokenizer::lexer::lexers::{
+        is_name_start, read_name, skip_binding_keyword, skip_whitespace,
+    };
     use crate::statement_tokenizer::tokenizer::tokenizers::ParseInfo;
     use crate::token_type::token_types::TokenTypes;
 
-    static mut VARIABLE_DECLARATION: bool = false;
-    pub fn read_variable_declaration(expression: &String, index: usize) -> ParseInfo {
-        let mut j = index;
-        let let_compare = "let";
-        let mut variable: String = String::new();
-        let mut variable_name = String::new();
+    ///
+    ///Reads let name: type for a single value, index is at the let, let mut or const. The token
+    ///covers the keywords and the name, the : type after it is read as a VarTypeAssignment. Collections and tuples are
+    ///left to the collection tokenizer
+    ///
+    pub fn read_variable_declaration(chars: &[char], index: usize) -> ParseInfo {
+        let none = ParseInfo::new(TokenTypes::None, 0, "none".to_string());
+        let start = skip_binding_keyword(chars, index);
+        if !chars.get(start).is_some_and(|c| is_name_start(*c)) {
+            return none;
+        }
+        let end = read_name(chars, start);
+        let variable_name: String = chars[start..end].iter().collect();
 
-        // Collect characters to check for the "let" keyword
-        while j < expression.len() {
-            if let Some(char) = expression.chars().nth(j) {
-                if char.is_alphabetic() {
-                    variable.push(char);
-                } else {
-                    break;
-                }
-            }
+        let mut j = skip_whitespace(chars, end);
+        if chars.get(j) != Some(&':') {
+            return none;
+        }
+        j = skip_whitespace(chars, j + 1);
+        let type_start = j;
+        while j < chars.len() && chars[j].is_alphabetic() {
             j += 1;
-
-            // Check if the collected string equals "let"
-            if variable.len() == 3 && variable == let_compare {
-                // Reset variable to start collecting the actual variable name
-                variable.clear();
-                while j < expression.len() {
-                    if let Some(char) = expression.chars().nth(j) {
-                        if char.is_alphabetic() || char == '_' {
-                            variable_name.push(char);
-                        } else if char == ':' {
-                            // Found the type declaration, break to parse the type
-                            break;
-                        } else if !char.is_whitespace() {
-                            // If it's neither whitespace nor ':', exit
-                            return ParseInfo::new(TokenTypes::None, 0, "none".to_string());
-                        }
-                    }
-                    j += 1;
-                }
-
-                // Check for ':' and potentially a collection type
-                while j < expression.len() {
-                    if expression.chars().nth(j) == Some(':') {
-                        j += 1; // Move past ':'
-                                // Skip whitespace after ':'
-                        while j < expression.len()
-                            && expression.chars().nth(j).unwrap().is_whitespace()
-                        {
-                            j += 1;
-                        }
-
-                        // Check for '<' indicating a collection
-                        if expression.chars().nth(j) == Some('<') {
-                            println!(
-                                "Invalid variable declaration for collections: {}",
-                                variable_name
-                            );
-                            // Return None for invalid declaration
-                            return ParseInfo::new(TokenTypes::None, 0, "none".to_string());
-                        } else {
-                            // Handle normal variable type (no collection)
-                            let type_declaration_start = j;
-                            while j < expression.len()
-                                && expression.chars().nth(j).unwrap().is_alphabetic()
-                            {
-                                j += 1;
-                            }
-
-                            let next_char = expression.chars().nth(j).unwrap_or('\0');
-                            let type_declaration = &expression[type_declaration_start..j];
-
-                            if type_declaration.contains('<') || next_char == '<' {
-                                return ParseInfo::new(TokenTypes::None, 0, "none".to_string());
-                            }
-
-                            unsafe {
-                                VARIABLE_DECLARATION = true; // Update global state
-                            }
-
-                            println!(
-                                /*"Variable Name: '{}', Type Declaration: '{}'",
-                                variable_name, type_declaration */
-                            );
-                            return ParseInfo::new(
-                                TokenTypes::Variable,
-                                variable_name.len().try_into().unwrap(),
-                                variable_name,
-                            );
-                        }
-                    }
-                    j += 1; // Continue scanning
-                }
-                // Return None if we reach here without valid declarations
-                return ParseInfo::new(TokenTypes::None, 0, "none".to_string());
-            }
-            // Remove the first character if more than 3 characters are collected
-            if variable.len() > 3 {
-                variable.remove(0);
-            }
         }
 
-        // Return default ParseInfo if function not found
-        ParseInfo::new(TokenTypes::None, 0, "none".to_string())
-    }
-
-    pub fn read_variable_assignment(expression: &String, index: usize) -> ParseInfo {
-        let chars: Vec<char> = expression.chars().collect();
-        let mut j = index;
-        let original_index = index;
-
-        // Look for the `:` pattern
-        while j < chars.len() {
-            let char = chars[j];
-
-            if char == ':' {
-                j += 1; // Move past `:`
-
-                // Skip whitespace after `:`
-                while j < chars.len() && chars[j].is_whitespace() {
-                    j += 1;
-                }
-
-                // Collect the type (assuming it is lowercase or an identifier)
-                let mut var_type = String::new();
-                while j < chars.len() {
-                    let char = chars[j];
-                    if char.is_lowercase() || char.is_alphanumeric() || char == '_' {
-                        var_type.push(char);
-                        j += 1;
-                    } else {
-                        break;
-                    }
-                }
-
-                // Skip whitespace after the type and check for `=`
-                while j < chars.len() && chars[j].is_whitespace() {
-                    j += 1;
-                }
-
-                if j < chars.len() && chars[j] == '=' {
-                    // Print for debugging
-                    /*
-                        print!(
-
-                            "Variable Type: '{}', Length: {}\n",
-                            var_type,
-                            j - original_index
-                        );
-                    */
-                    unsafe { VARIABLE_DECLARATION = false };
-                    return ParseInfo::new(
-                        TokenTypes::VarTypeAssignment,
-                        (j - original_index).try_into().unwrap(),
-                        var_type,
-                    );
-                }
-            } else {
-                j += 1; // Continue scanning if `:` not found
-            }
+        // collections and tuples are read by the collection tokenizer
+        if matches!(chars.get(j), Some('<') | Some('(')) {
+            trace!(
+                Tokenizer,
+                Debug,
+                "Not a single value declaration: {}",
+                variable_name
+            );
+            return none;
         }
 
-        // Return None if no valid variable assignment found
-        unsafe { VARIABLE_DECLARATION = false };
-        ParseInfo::new(TokenTypes::None, 0, "none".to_string())
+        let type_declaration: String = chars[type_start..j].iter().collect();
+        trace!(
+            Tokenizer,
+            Trace,
+            "Variable Name: '{}', Type Declaration: '{}'",
+            variable_name,
+            type_declaration
+        );
+        ParseInfo::new(
+            TokenTypes::Variable,
+            (end - index).try_into().unwrap(),
+            variable_name,
+        )
     }
 
-    pub fn read_variable_call(expression: &String, index: usize) -> ParseInfo {
-        let mut j = index;
-        let mut variable_name = String::new();
-
-        // Iterate over characters in the string starting at 'index'
-        let chars = expression.chars().skip(index).enumerate();
-
-        for (_i, char) in chars {
-            // Check if the current or next character is '=' (assignment)
-            let next_char = expression.chars().nth(j + 1).unwrap_or('\0');
-            if char == '(' || next_char == '(' {
-                return ParseInfo::new(
-                    TokenTypes::FunctionCall,
-                    (j - index).try_into().unwrap(),
-                    variable_name,
-                );
-            } else if char == '=' || next_char == '=' {
-                return ParseInfo::new(
-                    TokenTypes::VariableCall,
-                    (j - index).try_into().unwrap(),
-                    variable_name,
-                );
-            }
-            // Collect valid variable name characters (alphanumeric and '_')
-
-            if char.is_alphanumeric() || char == '_' {
-                variable_name.push(char);
-            } else if char.is_whitespace() {
-                continue;
-            } else {
-                break;
-            }
+    ///
+    ///Reads the : type of a declaration, index is at the :. The token stops in front of the =,
+    ///without one there is no assignment to type
+    ///
+    pub fn read_variable_assignment(chars: &[char], index: usize) -> ParseInfo {
+        let none = ParseInfo::new(TokenTypes::None, 0, "none".to_string());
+        if chars.get(index) != Some(&':') {
+            return none;
+        }
 
+        let mut j = skip_whitespace(chars, index + 1);
+        let type_start = j;
+        while j < chars.len() && (chars[j].is_alphanumeric() || chars[j] == '_') {
             j += 1;
         }
+        let var_type: String = chars[type_start..j].iter().collect();
 
-        // After collecting the variable name, check if it exists in the stack
-        if !variable_name.is_empty() {
-            for variable in unsafe { &VARIABLE_STACK } {
-                if variable.name == variable_name {
-                    return ParseInfo::new(
-                        TokenTypes::VariableCall,
-                        (j - index).try_into().unwrap(),
-                        variable_name,
-                    );
-                }
-            }
+        j = skip_whitespace(chars, j);
+        if chars.get(j) != Some(&'=') || chars.get(j + 1) == Some(&'>') {
+            return none;
         }
+        ParseInfo::new(
+            TokenTypes::VarTypeAssignment,
+            (j - index).try_into().unwrap(),
+            var_type,
+        )
+    }
 
-        // Return None if no valid variable call found
+    ///
+    ///Reads let (a, b), the names a tuple is unpacked into. The = and the value after it are
+    ///left for the following tokens
+    ///
+    pub fn read_destructure(chars: &[char], index: usize) -> ParseInfo {
+        let none = ParseInfo::new(TokenTypes::None, 0, "none".to_string());
+        let j = skip_binding_keyword(chars, index);
+        if chars.get(j) != Some(&'(') {
+            return none;
+        }
+        let close = match chars[j..].iter().position(|c| *c == ')') {
+            Some(close) => j + close,
+            None => return none,
+        };
+        let inner: String = chars[j + 1..close].iter().collect();
+        let names: Vec<String> = inner
+            .split(',')
+            .map(|name| name.trim().to_string())
+            .collect();
+        let valid = |name: &String| {
+            name.chars().next().is_some_and(is_name_start)
+                && name.chars().all(|c| c.is_alphanumeric() || c == '_')
+        };
+        if !names.iter().all(valid) {
+            return none;
+        }
         ParseInfo::new(
-            TokenTypes::None,
-            0,
-            "No valid variable call found".to_string(),
+            TokenTypes::Destructure {
+                names: names.clone(),
+            },
+            (close + 1 - index) as i32,
+            format!("({})", names.join(", ")),
         )
     }
 }
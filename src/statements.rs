@@ -0,0 +1,529 @@
+/*
+* This file splits a script into its statements from the tokens the lexer reads, so a ; or a }
+* inside a string or a comment never ends one. An if with its elif and else branches is one
+* statement, as is a try with its catch and finally, and the branches are split back out of it
+* to be run one block at a time, like the block of a loop or a function
+*/
+use crate::statement_tokenizer::basic_tokenizer::basic_tokenizers::scan_literal;
+use crate::statement_tokenizer::lexer::lexers::Lexer;
+use crate::statement_tokenizer::tokenizer::tokenizers::ParseInfo;
+use crate::syntax::Span;
+use crate::token_type::token_types::TokenTypes;
+use std::error::Error;
+
+///
+///The tokens of the source with the char offsets they start and end at, without the comments
+///
+fn located_tokens(source: &str) -> Vec<(usize, usize, ParseInfo)> {
+    let mut lexer = Lexer::standalone(source);
+    let mut tokens = Vec::new();
+    while let Some(info) = lexer.next() {
+        let end = lexer.position();
+        let start = end - info.chars_read as usize;
+        if info.token != TokenTypes::Comment {
+            tokens.push((start, end, info));
+        }
+    }
+    tokens
+}
+
+fn is_unterminated(info: &ParseInfo) -> bool {
+    let chars: Vec<char> = info.value.chars().collect();
+    matches!(info.token, TokenTypes::String | TokenTypes::Char)
+        && matches!(scan_literal(&chars, 0), Some((_, false)))
+}
+
+///
+///A branch that carries on the if or try statement in front of it
+///
+fn continues_chain(token: &TokenTypes) -> bool {
+    matches!(
+        token,
+        TokenTypes::Elif { .. } | TokenTypes::Else | TokenTypes::Catch | TokenTypes::Finally
+    )
+}
+
+///
+///Splits the source into top level statements on `;` and on the closing `}` of a block. A `}`
+///followed by elif, else, catch or finally does not end the statement, the branch belongs to
+///it. What follows the last complete statement is left out
+///
+pub fn split_statements(contents: &str) -> Result<Vec<String>, Box<dyn Error>> {
+    let chars: Vec<char> = contents.chars().collect();
+    let line_of = |offset: usize| chars[..offset].iter().filter(|c| **c == '\n').count() + 1;
+    let tokens = located_tokens(contents);
+
+    let mut statements = Vec::new();
+    let mut start = 0;
+    let mut curly = 0;
+    let mut square = 0;
+    let mut parentheses = 0;
+    for (i, (token_start, end, info)) in tokens.iter().enumerate() {
+        if is_unterminated(info) {
+            return Err(format!(
+                "Unterminated string literal starting at line {}",
+                line_of(*token_start)
+            )
+            .into());
+        }
+        let ends = match &info.token {
+            // an else token takes its { along
+            TokenTypes::LeftCurly | TokenTypes::Else => {
+                curly += 1;
+                false
+            }
+            TokenTypes::RightCurly => {
+                if curly == 0 {
+                    return Err(format!(
+                        "Unmatched closing curly brace at line {}",
+                        line_of(*token_start)
+                    )
+                    .into());
+                }
+                curly -= 1;
+                // a } of a value is followed by the rest of the statement, like its ;
+                let next = tokens.get(i + 1).map(|(_, _, next)| &next.token);
+                curly == 0
+                    && square == 0
+                    && parentheses == 0
+                    && !matches!(next, Some(TokenTypes::SemiColon))
+                    && !next.is_some_and(continues_chain)
+            }
+            TokenTypes::LeftBracket => {
+                square += 1;
+                false
+            }
+            TokenTypes::RightBracket => {
+                if square == 0 {
+                    return Err(format!(
+                        "Unmatched closing square bracket at line {}",
+                        line_of(*token_start)
+                    )
+                    .into());
+                }
+                square -= 1;
+                false
+            }
+            // the condition tokens take their ( along
+            TokenTypes::LeftParenthesis
+            | TokenTypes::If { .. }
+            | TokenTypes::Elif { .. }
+            | TokenTypes::For { .. }
+            | TokenTypes::While { .. } => {
+                parentheses += 1;
+                false
+            }
+            TokenTypes::RightParenthesis => {
+                parentheses = (parentheses - 1).max(0);
+                false
+            }
+            // no ( is open across a ;, one left open by a broken statement must not hold the
+            // rest of the script in it
+            TokenTypes::SemiColon => {
+                parentheses = 0;
+                curly == 0 && square == 0
+            }
+            _ => false,
+        };
+        if ends {
+            statements.push(chars[start..*end].iter().collect());
+            start = *end;
+        }
+    }
+    Ok(statements)
+}
+
+///
+///Splits the comments in front of a statement from its code
+///
+pub fn split_leading_comments(statement: &str) -> (Vec<&str>, &str) {
+    let mut comments = Vec::new();
+    let mut code = statement.trim_start();
+    loop {
+        let end = if code.starts_with("//") {
+            code.find('\n').unwrap_or(code.len())
+        } else if code.starts_with("/*") {
+            code.find("*/").map_or(code.len(), |i| i + 2)
+        } else {
+            break;
+        };
+        comments.push(&code[..end]);
+        code = code[end..].trim_start();
+    }
+    (comments, code)
+}
+
+///
+///The statements of the source with the line each one starts on, the first line being
+///first_line. Leading whitespace and comments do not count, so the line is the one the code
+///is on
+///
+pub fn numbered_statements(
+    contents: &str,
+    first_line: usize,
+) -> Result<Vec<(usize, String)>, Box<dyn Error>> {
+    let mut line = first_line;
+    let mut statements = Vec::new();
+    for statement in split_statements(contents)? {
+        let code = split_leading_comments(&statement).1;
+        let leading = &statement[..statement.len() - code.len()];
+        statements.push((
+            line + leading.matches('\n').count(),
+            statement.trim_start().to_string(),
+        ));
+        line += statement.matches('\n').count();
+    }
+    Ok(statements)
+}
+
+// a statement with the line and column it starts at
+pub type Positioned = ((usize, usize), String);
+
+///
+///The statements of the source without the whitespace in front of them, each with the line
+///and column it starts at. The source starts at first_column of first_line
+///
+pub fn positioned_statements(
+    contents: &str,
+    first_line: usize,
+    first_column: usize,
+) -> Result<Vec<Positioned>, Box<dyn Error>> {
+    let mut start = (first_line, first_column);
+    let mut statements = Vec::new();
+    for statement in split_statements(contents)? {
+        let trimmed = statement.trim_start();
+        let at = advance(start, &statement[..statement.len() - trimmed.len()]);
+        statements.push((at, trimmed.to_string()));
+        start = advance(start, &statement);
+    }
+    Ok(statements)
+}
+
+///
+///The span of the code of a statement that starts at the line and column of start on the line
+///the code starts on, the comments in front of it left out
+///
+pub fn code_span(statement: &str, start: (usize, usize)) -> Span {
+    let code = split_leading_comments(statement).1;
+    let (line, column) = advance(start, &statement[..statement.len() - code.len()]);
+    let first_line = code.lines().next().unwrap_or("").trim_end();
+    Span {
+        line,
+        column,
+        length: first_line.chars().count(),
+    }
+}
+
+///
+///The statements of the source without the comments in front of them, each with the span of
+///its code on the line it starts on. The source starts at first_column of first_line
+///
+pub fn located_statements(
+    contents: &str,
+    first_line: usize,
+    first_column: usize,
+) -> Result<Vec<(Span, String)>, Box<dyn Error>> {
+    Ok(positioned_statements(contents, first_line, first_column)?
+        .into_iter()
+        .map(|(start, statement)| {
+            let code = split_leading_comments(&statement).1.to_string();
+            (code_span(&statement, start), code)
+        })
+        .collect())
+}
+
+///
+///The line and column right after text, when it starts at the line and column of start
+///
+pub fn advance(start: (usize, usize), text: &str) -> (usize, usize) {
+    match text.rfind('\n') {
+        Some(newline) => (
+            start.0 + text.matches('\n').count(),
+            text[newline + 1..].chars().count() + 1,
+        ),
+        None => (start.0, start.1 + text.chars().count()),
+    }
+}
+
+///
+///The span of the code from the char at offset of a statement to the end of the line it is
+///on, the statement starting at the line and column of start
+///
+pub fn span_in(statement: &str, start: (usize, usize), offset: usize) -> Span {
+    let before: String = statement.chars().take(offset).collect();
+    let (line, column) = advance(start, &before);
+    let rest = statement[before.len()..].lines().next().unwrap_or("");
+    Span {
+        line,
+        column,
+        length: rest.trim_end().chars().count(),
+    }
+}
+
+///
+///The index of the } that closes the block opened at index open
+///
+fn closing_curly(tokens: &[(usize, usize, ParseInfo)], open: usize) -> Option<usize> {
+    let mut depth = 1;
+    (open + 1..tokens.len()).find(|&j| {
+        match tokens[j].2.token {
+            TokenTypes::LeftCurly | TokenTypes::Else => depth += 1,
+            TokenTypes::RightCurly => depth -= 1,
+            _ => {}
+        }
+        depth == 0
+    })
+}
+
+///
+///A branch of an if or try statement: its keyword token, which holds the condition of an if or
+///elif, the name a catch binds the error to and the statements of its block
+///
+#[derive(Debug, Clone, PartialEq)]
+pub struct Branch {
+    pub keyword: TokenTypes,
+    pub name: Option<String>,
+    pub body: String,
+    // the line of the keyword and the line the block starts on
+    pub line: usize,
+    pub body_line: usize,
+    // the chars in the statement in front of the keyword and in front of the block
+    pub start: usize,
+    pub body_start: usize,
+}
+
+///
+///The branches of an if or try statement that starts on line, None when the statement is not
+///one or a block of it is not closed
+///
+pub fn branches(statement: &str, line: usize) -> Option<Vec<Branch>> {
+    let chars: Vec<char> = statement.chars().collect();
+    let tokens = located_tokens(statement);
+    let first = tokens.first()?;
+    if !matches!(first.2.token, TokenTypes::If { .. } | TokenTypes::Try) {
+        return None;
+    }
+    let line_at = |offset: usize| {
+        line + chars[first.0..offset]
+            .iter()
+            .filter(|c| **c == '\n')
+            .count()
+    };
+
+    let mut branches = Vec::new();
+    let mut i = 0;
+    while i < tokens.len() {
+        let (start, end, info) = &tokens[i];
+        let keyword = info.token.clone();
+        match keyword {
+            TokenTypes::SemiColon => break,
+            TokenTypes::If { .. } | TokenTypes::Try if i == 0 => {}
+            _ if continues_chain(&keyword) => {}
+            _ => return None,
+        }
+
+        let mut name = None;
+        let mut open = *end;
+        if keyword != TokenTypes::Else {
+            let curly =
+                (i + 1..tokens.len()).find(|&j| tokens[j].2.token == TokenTypes::LeftCurly)?;
+            if keyword == TokenTypes::Catch {
+                name = tokens[i + 1..curly]
+                    .iter()
+                    .find(|(_, _, info)| info.token == TokenTypes::VariableCall)
+                    .map(|(_, _, info)| info.value.clone());
+            }
+            open = tokens[curly].1;
+            i = curly;
+        }
+
+        let close = closing_curly(&tokens, i)?;
+        branches.push(Branch {
+            keyword,
+            name,
+            body: chars[open..tokens[close].0].iter().collect(),
+            line: line_at(*start),
+            body_line: line_at(open),
+            start: *start,
+            body_start: open,
+        });
+        i = close + 1;
+    }
+    Some(branches)
+}
+
+///
+///The block of a while, for or func statement that starts on line, its keyword token holds the
+///condition or the function. None when the statement is not one or its block is not closed
+///
+pub fn block(statement: &str, line: usize) -> Option<Branch> {
+    let chars: Vec<char> = statement.chars().collect();
+    let tokens = located_tokens(statement);
+    let (start, _, first) = tokens.first()?;
+    if !matches!(
+        first.token,
+        TokenTypes::While { .. } | TokenTypes::For { .. } | TokenTypes::Function { .. }
+    ) {
+        return None;
+    }
+    let curly = tokens
+        .iter()
+        .position(|(_, _, info)| info.token == TokenTypes::LeftCurly)?;
+    let close = closing_curly(&tokens, curly)?;
+    let open = tokens[curly].1;
+    let line_at =
+        |offset: usize| line + chars[*start..offset].iter().filter(|c| **c == '\n').count();
+    Some(Branch {
+        keyword: first.token.clone(),
+        name: None,
+        body: chars[open..tokens[close].0].iter().collect(),
+        line,
+        body_line: line_at(open),
+        start: *start,
+        body_start: open,
+    })
+}
+
+#[cfg(test)]
+mod statements_tests {
+    use super::{
+        block, branches, located_statements, numbered_statements, span_in, split_statements,
+    };
+    use crate::syntax::Span;
+    use crate::token_type::token_types::TokenTypes;
+
+    #[test]
+    fn test_split_statements_ignores_literals_and_comments() {
+        let source = "// don't split; here {\necho(\"a;b}\");\nlet c: char = ';';\nif (1 < 2) { echo(\"{\"); }\n// }\necho(\"}\");";
+        let result = split_statements(source).unwrap();
+        assert_eq!(
+            result,
+            vec![
+                "// don't split; here {\necho(\"a;b}\");".to_string(),
+                "\nlet c: char = ';';".to_string(),
+                "\nif (1 < 2) { echo(\"{\"); }".to_string(),
+                "\n// }\necho(\"}\");".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_split_statements_keeps_multiline_strings() {
+        let source = "let a: string = \"\"\"\none;\ntwo }\"\"\";\necho(a);";
+        let result = split_statements(source).unwrap();
+        assert_eq!(result.len(), 2);
+        assert_eq!(result[0], "let a: string = \"\"\"\none;\ntwo }\"\"\";");
+    }
+
+    #[test]
+    fn test_split_statements_unterminated_string() {
+        let result = split_statements("let a: int = 1;\necho(\"oops);");
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "Unterminated string literal starting at line 2"
+        );
+        let result = split_statements("echo(1);\n}");
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "Unmatched closing curly brace at line 2"
+        );
+    }
+
+    #[test]
+    fn test_split_statements_groups_chains() {
+        let source = "if (a) {\n    echo(1);\n}\nelif (b) { echo(2); } else {\n    echo(3);\n}\nlet d: dict<string, int> = {\"a\": 1};\necho({\"b\": 2});\ntry { f(); } catch e { g(); }\nfinally { h(); }\nwhile (x) { y; }";
+        let result = split_statements(source).unwrap();
+        assert_eq!(
+            result,
+            vec![
+                "if (a) {\n    echo(1);\n}\nelif (b) { echo(2); } else {\n    echo(3);\n}"
+                    .to_string(),
+                "\nlet d: dict<string, int> = {\"a\": 1};".to_string(),
+                "\necho({\"b\": 2});".to_string(),
+                "\ntry { f(); } catch e { g(); }\nfinally { h(); }".to_string(),
+                "\nwhile (x) { y; }".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_branches() {
+        let statement = "if (a > 1) {\n    echo(1);\n} elif (a > 0) { if (b) { c; } else { d; } }\nelse {\n    e;\n}";
+        let found = branches(statement, 3).unwrap();
+        assert_eq!(found.len(), 3);
+        assert_eq!(
+            found[0].keyword,
+            TokenTypes::If {
+                statement: "a > 1".to_string()
+            }
+        );
+        assert_eq!(found[0].body, "\n    echo(1);\n");
+        assert_eq!((found[0].line, found[0].body_line), (3, 3));
+        assert_eq!(found[1].body, " if (b) { c; } else { d; } ");
+        assert_eq!(found[1].line, 5);
+        assert_eq!(found[2].keyword, TokenTypes::Else);
+        assert_eq!((found[2].line, found[2].body_line), (6, 6));
+
+        let found = branches("try { f(); } catch error { g(error); } finally { h(); }", 1).unwrap();
+        assert_eq!(found[1].keyword, TokenTypes::Catch);
+        assert_eq!(found[1].name, Some("error".to_string()));
+        assert_eq!(found[2].body, " h(); ");
+
+        assert_eq!(branches("echo(1);", 1), None);
+        assert_eq!(branches("if (a) { b;", 1), None);
+    }
+
+    #[test]
+    fn test_block() {
+        let found = block("while (a < 3) {\n    if (b) { c; }\n}", 4).unwrap();
+        assert_eq!(
+            found.keyword,
+            TokenTypes::While {
+                statement: "a < 3".to_string()
+            }
+        );
+        assert_eq!(found.body, "\n    if (b) { c; }\n");
+        assert_eq!((found.line, found.body_line), (4, 4));
+
+        let found = block("func f(a: int) -> int\n{\n    return a;\n}", 1).unwrap();
+        assert!(matches!(found.keyword, TokenTypes::Function { .. }));
+        assert_eq!(found.body_line, 2);
+        assert_eq!(block("if (a) { b; }", 1), None);
+        assert_eq!(block("while (a) { b;", 1), None);
+    }
+
+    #[test]
+    fn test_numbered_statements() {
+        let found = numbered_statements("let a: int = 1;\n\n// b\necho(a);", 3).unwrap();
+        assert_eq!(
+            found,
+            vec![
+                (3, "let a: int = 1;".to_string()),
+                (6, "// b\necho(a);".to_string())
+            ]
+        );
+    }
+
+    #[test]
+    fn test_located_statements() {
+        let found = located_statements(" a = 1;  b++;\n/* c */ if (a) {\n    b;\n}", 2, 7).unwrap();
+        let spans: Vec<(usize, usize, usize)> = found
+            .iter()
+            .map(|(span, _)| (span.line, span.column, span.length))
+            .collect();
+        assert_eq!(spans, vec![(2, 8, 6), (2, 16, 4), (3, 9, 8)]);
+        assert_eq!(found[2].1, "if (a) {\n    b;\n}");
+
+        let statement = "if (a) { b; } elif (c) {\n    d;\n}";
+        let found = branches(statement, 4).unwrap();
+        assert_eq!(
+            span_in(statement, (4, 5), found[1].start),
+            Span {
+                line: 4,
+                column: 19,
+                length: 10
+            }
+        );
+        assert_eq!(span_in(statement, (4, 5), found[1].body_start).column, 29);
+    }
+}
@@ -0,0 +1,974 @@
+/*
+* This file reads a whole script for syntax errors before it runs. It does not stop at the first
+* one: after an error the rest of the statement is skipped, up to the next ; or the } that
+* closes the block, and reading carries on from there, so every error is reported in one go.
+* Each error has a code, the span of the source it is about and says what was expected there
+* and what was found instead. Once the tokens are read, the values of each statement without an
+* error are parsed as expressions the way they are when the script runs
+*/
+use crate::bindings::check_bindings;
+use crate::compilers::expression::expression_error;
+use crate::node::nodes::{token_to_node, ASTNode};
+use crate::statement_tokenizer::basic_tokenizer::basic_tokenizers::{
+    scan_literal, unescape_literal,
+};
+use crate::statement_tokenizer::lexer::lexers::{keyword, Keyword, Lexer};
+use crate::statement_tokenizer::tokenizer::tokenizers::ParseInfo;
+use crate::token_type::token_types::TokenTypes;
+use std::fmt;
+
+// input the lexer has no token for
+const UNKNOWN_TOKEN: &str = "E0001";
+// a string, char or comment that is never closed
+const UNTERMINATED: &str = "E0002";
+// a ), ] or } that closes nothing
+const UNMATCHED_CLOSE: &str = "E0003";
+// a (, [ or { that is never closed
+const UNCLOSED: &str = "E0004";
+// a closing delimiter that belongs to another opening one
+const MISMATCHED_CLOSE: &str = "E0005";
+// a ; with no statement in front of it
+const EMPTY_STATEMENT: &str = "E0006";
+// two statements without a ; between them
+const MISSING_SEMICOLON: &str = "E0007";
+// a token that cannot be used where it is
+const UNEXPECTED_TOKEN: &str = "E0008";
+// an int that does not fit in an int, an unknown escape sequence or a char literal that does
+// not hold one character
+const INVALID_LITERAL: &str = "E0013";
+// a value that is missing, or a token where an expression needs a value or an operator
+const INVALID_EXPRESSION: &str = "E0014";
+
+///
+///Where in the source an error is. Lines and columns count from 1, the length is in characters
+///
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Span {
+    pub line: usize,
+    pub column: usize,
+    pub length: usize,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SyntaxError {
+    pub code: &'static str,
+    pub message: String,
+    pub span: Span,
+    // other places the error is about, like the ( a ; was found inside of
+    pub labels: Vec<(Span, String)>,
+}
+
+impl fmt::Display for SyntaxError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Syntax Error[{}]: {}", self.code, self.message)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Open {
+    // the body of an if, a loop, a function or a try
+    Block,
+    // the ( of an if, elif, for or while, its ) is followed by a block
+    Condition,
+    // a dictionary, a call, a group or an array
+    Value,
+}
+
+struct Delimiter {
+    open: Open,
+    close: char,
+    start: usize,
+    length: usize,
+}
+
+struct Checker {
+    chars: Vec<char>,
+    // the offset each line starts at
+    lines: Vec<usize>,
+    errors: Vec<SyntaxError>,
+    delimiters: Vec<Delimiter>,
+    // nothing has been read of the current statement yet
+    empty: bool,
+    // the last token ended a value, so an operator or the end of the statement has to follow
+    after_value: bool,
+    // what a { has to follow next, like `if (...)`
+    expect_block: Option<String>,
+    // the name a catch can bind the error to is still allowed
+    catch_name: bool,
+    // an error was found in the statement, the rest of it is skipped
+    recovering: bool,
+    // where the last token ended
+    end: usize,
+}
+
+impl Checker {
+    fn span(&self, start: usize, length: usize) -> Span {
+        span_at(&self.lines, start, length)
+    }
+
+    fn error(&mut self, code: &'static str, message: String, start: usize, length: usize) {
+        let span = self.span(start, length);
+        self.errors.push(SyntaxError {
+            code,
+            message,
+            span,
+            labels: Vec::new(),
+        });
+    }
+
+    ///
+    ///Reports an error in the current statement and skips the rest of it. False when the
+    ///statement already had one, it is not reported then
+    ///
+    fn statement_error(
+        &mut self,
+        code: &'static str,
+        message: String,
+        start: usize,
+        length: usize,
+    ) -> bool {
+        if self.recovering {
+            return false;
+        }
+        self.error(code, message, start, length);
+        self.recovering = true;
+        true
+    }
+
+    ///
+    ///Points the error reported last at where the delimiter it is about was opened
+    ///
+    fn opened_here(&mut self, delimiter: (usize, usize), text: &str) {
+        let span = self.span(delimiter.0, delimiter.1);
+        if let Some(error) = self.errors.last_mut() {
+            error.labels.push((span, text.to_string()));
+        }
+    }
+
+    fn end_statement(&mut self) {
+        self.empty = true;
+        self.after_value = false;
+        self.expect_block = None;
+        self.catch_name = false;
+        self.recovering = false;
+    }
+
+    fn open(&mut self, open: Open, close: char, start: usize, length: usize) {
+        self.delimiters.push(Delimiter {
+            open,
+            close,
+            start,
+            length,
+        });
+        self.after_value = false;
+        if open == Open::Block {
+            self.end_statement();
+        }
+    }
+
+    fn close(&mut self, close: char, start: usize) {
+        let Some(position) = self.delimiters.iter().rposition(|d| d.close == close) else {
+            // like the ) of [1, 2), it is taken to close what is open
+            if let Some(inner) = self.delimiters.last().filter(|d| d.open != Open::Block) {
+                let message = format!("expected `{}`, found `{}`", inner.close, close);
+                let opened = (inner.start, inner.length);
+                if self.statement_error(MISMATCHED_CLOSE, message, start, 1) {
+                    self.opened_here(opened, "opened here");
+                }
+                self.delimiters.pop();
+                self.after_value = true;
+                return;
+            }
+            self.statement_error(
+                UNMATCHED_CLOSE,
+                format!(
+                    "expected a statement, found `{}` that closes nothing",
+                    close
+                ),
+                start,
+                1,
+            );
+            return;
+        };
+        if position + 1 < self.delimiters.len() {
+            let inner = self.delimiters.last().unwrap();
+            let message = format!("expected `{}`, found `{}`", inner.close, close);
+            let opened = (inner.start, inner.length);
+            if self.statement_error(MISMATCHED_CLOSE, message, start, 1) {
+                self.opened_here(opened, "opened here");
+            }
+            self.delimiters.truncate(position + 1);
+        }
+
+        let delimiter = self.delimiters.pop().unwrap();
+        if delimiter.open == Open::Block && !self.empty {
+            let message = "expected `;`, found `}`".to_string();
+            self.statement_error(MISSING_SEMICOLON, message, start, 1);
+        }
+        match delimiter.open {
+            Open::Block => self.end_statement(),
+            Open::Condition => {
+                self.after_value = false;
+                self.expect_block = Some("the condition".to_string());
+            }
+            Open::Value => self.after_value = true,
+        }
+    }
+
+    fn semicolon(&mut self, start: usize) {
+        while let Some(delimiter) = self.delimiters.last() {
+            if delimiter.open == Open::Block {
+                break;
+            }
+            let message = format!("expected `{}`, found `;`", delimiter.close);
+            let opened = (delimiter.start, delimiter.length);
+            if self.statement_error(UNCLOSED, message, start, 1) {
+                self.opened_here(opened, "never closed");
+            }
+            self.delimiters.pop();
+        }
+        if self.empty {
+            self.statement_error(
+                EMPTY_STATEMENT,
+                "expected a statement, found `;`".to_string(),
+                start,
+                1,
+            );
+        }
+        self.end_statement();
+    }
+
+    fn token(&mut self, info: &ParseInfo, start: usize) {
+        let length = info.chars_read as usize;
+        let found = describe(info);
+
+        if let Some(after) = self.expect_block.clone() {
+            if self.catch_name && info.token == TokenTypes::VariableCall {
+                self.catch_name = false;
+                return;
+            }
+            if info.token != TokenTypes::LeftCurly {
+                self.expect_block = None;
+                let message = format!("expected `{{` after {}, found {}", after, found);
+                self.statement_error(UNEXPECTED_TOKEN, message, start, length);
+            }
+        }
+
+        match &info.token {
+            TokenTypes::SemiColon => return self.semicolon(start),
+            TokenTypes::LeftCurly if self.expect_block.take().is_some() => {
+                return self.open(Open::Block, '}', start, length)
+            }
+            TokenTypes::LeftCurly => self.open(Open::Value, '}', start, length),
+            TokenTypes::LeftParenthesis => self.open(Open::Value, ')', start, length),
+            TokenTypes::LeftBracket => self.open(Open::Value, ']', start, length),
+            TokenTypes::RightCurly => return self.close('}', start),
+            TokenTypes::RightParenthesis => return self.close(')', start),
+            TokenTypes::RightBracket => return self.close(']', start),
+            _ => {}
+        }
+
+        if self.empty && is_infix(info) {
+            let message = format!("expected a statement, found {}", found);
+            self.statement_error(UNEXPECTED_TOKEN, message, start, length);
+        } else if self.after_value && starts_value(info) {
+            let message = format!("expected `;`, found {}", found);
+            self.statement_error(MISSING_SEMICOLON, message, start, length);
+        }
+        if let TokenTypes::String | TokenTypes::Char = info.token {
+            let chars: Vec<char> = info.value.chars().collect();
+            if let Some((_, false)) = scan_literal(&chars, 0) {
+                let kind = if info.token == TokenTypes::Char {
+                    "char"
+                } else {
+                    "string"
+                };
+                let message = format!(
+                    "unterminated {}: expected its closing quote, found end of file",
+                    kind
+                );
+                self.error(UNTERMINATED, message, start, 1);
+            } else {
+                self.literal(info, start);
+            }
+        }
+        if info.token == TokenTypes::Int {
+            self.literal(info, start);
+        }
+
+        self.empty = false;
+        self.after_value = ends_value(info);
+        match &info.token {
+            TokenTypes::If { .. }
+            | TokenTypes::Elif { .. }
+            | TokenTypes::For { .. }
+            | TokenTypes::While { .. } => {
+                let open = start + info.chars_read as usize;
+                self.condition_literals(start, open);
+                self.delimiters.push(Delimiter {
+                    open: Open::Condition,
+                    close: ')',
+                    start,
+                    length: open - start,
+                });
+            }
+            TokenTypes::Else => self.open(Open::Block, '}', start, length),
+            TokenTypes::Try | TokenTypes::Finally => {
+                self.expect_block = Some(format!("`{}`", info.value))
+            }
+            TokenTypes::Catch => {
+                self.expect_block = Some("`catch`".to_string());
+                self.catch_name = true;
+            }
+            TokenTypes::Function { .. } => {
+                self.expect_block = Some(format!("the header of `{}`", info.value))
+            }
+            _ => {}
+        }
+    }
+
+    ///
+    ///Checks that an int fits in an int, the escape sequences of a closed string or char
+    ///literal, and that a char literal holds one character
+    ///
+    fn literal(&mut self, info: &ParseInfo, start: usize) {
+        if info.token == TokenTypes::Int {
+            if info.value.parse::<i32>().is_err() {
+                let message = format!(
+                    "expected an int from {} to {}, found `{}`",
+                    i32::MIN,
+                    i32::MAX,
+                    info.value
+                );
+                self.error(INVALID_LITERAL, message, start, info.chars_read as usize);
+            }
+            return;
+        }
+        let chars: Vec<char> = info.value.chars().collect();
+        if let Some((at, length)) = bad_escape(&chars) {
+            let escape: String = chars[at..at + length].iter().collect();
+            let message = format!(
+                "expected an escape sequence such as `\\n` or `\\u{{41}}`, found `{}`",
+                escape
+            );
+            return self.error(INVALID_LITERAL, message, start + at, length);
+        }
+        if info.token != TokenTypes::Char {
+            return;
+        }
+        let count = unescape_literal(&info.value).map_or(0, |value| value.chars().count());
+        if count != 1 {
+            let message = format!("expected one character in a char literal, found {}", count);
+            self.error(INVALID_LITERAL, message, start, chars.len());
+        }
+    }
+
+    ///
+    ///Checks the literals in the condition of an if, elif, for or while, which is read as one
+    ///token from start to end
+    ///
+    fn condition_literals(&mut self, start: usize, end: usize) {
+        let Some(open) = self.chars[start..end].iter().position(|c| *c == '(') else {
+            return;
+        };
+        let condition: String = self.chars[start + open + 1..end].iter().collect();
+        let mut lexer = Lexer::standalone(&condition);
+        while let Some(info) = lexer.next() {
+            let at = start + open + 1 + lexer.position() - info.chars_read as usize;
+            let closed = |info: &ParseInfo| {
+                let chars: Vec<char> = info.value.chars().collect();
+                scan_literal(&chars, 0).is_some_and(|(_, closed)| closed)
+            };
+            match info.token {
+                TokenTypes::Int => self.literal(&info, at),
+                TokenTypes::String | TokenTypes::Char if closed(&info) => self.literal(&info, at),
+                _ => {}
+            }
+        }
+    }
+
+    fn skipped(&mut self, start: usize, text: &str) {
+        let message = match keyword(text) {
+            Some(Keyword::Let | Keyword::Const) => {
+                format!("expected a name and its type after `{}`", text)
+            }
+            Some(Keyword::Func) => "expected a name and `(` after `func`".to_string(),
+            Some(Keyword::Else) => "expected `{` after `else`".to_string(),
+            Some(_) => format!("expected `(` after `{}`", text),
+            None => format!("expected a token, found unknown character `{}`", text),
+        };
+        self.statement_error(UNKNOWN_TOKEN, message, start, text.chars().count());
+        self.empty = false;
+    }
+
+    fn end_of_file(&mut self, open_comment: Option<usize>) {
+        if let Some(start) = open_comment {
+            let message = "unterminated comment: expected `*/`, found end of file".to_string();
+            self.error(UNTERMINATED, message, start, 2);
+        }
+        if let Some(after) = self.expect_block.take() {
+            let message = format!("expected `{{` after {}, found end of file", after);
+            self.statement_error(UNEXPECTED_TOKEN, message, self.end, 1);
+        }
+        let unclosed = std::mem::take(&mut self.delimiters);
+        for delimiter in &unclosed {
+            let message = format!(
+                "expected `{}` to close this, found end of file",
+                delimiter.close
+            );
+            self.error(UNCLOSED, message, delimiter.start, delimiter.length);
+        }
+        if unclosed.is_empty() && !self.empty {
+            self.statement_error(
+                MISSING_SEMICOLON,
+                "expected `;`, found end of file".to_string(),
+                self.end,
+                1,
+            );
+        }
+    }
+}
+
+impl Checker {
+    ///
+    ///Reads the values of each statement as expressions: what follows the = of a declaration
+    ///or an assignment, what a return gives back, the condition of an if, elif, for or while
+    ///and any other statement as a whole. A statement that has an error already is skipped
+    ///
+    fn expressions(&mut self, tokens: &[(ParseInfo, usize)]) {
+        let mut first = 0;
+        let mut depth = 0;
+        // whether each open { is a block rather than a dictionary
+        let mut curlies = Vec::new();
+        for (index, (info, _)) in tokens.iter().enumerate() {
+            match info.token {
+                TokenTypes::LeftParenthesis | TokenTypes::LeftBracket => depth += 1,
+                TokenTypes::RightParenthesis | TokenTypes::RightBracket => depth -= 1,
+                TokenTypes::LeftCurly if opens_block(tokens, index) => {
+                    self.statement(tokens, first, index);
+                    curlies.push(true);
+                    first = index + 1;
+                }
+                TokenTypes::LeftCurly => {
+                    curlies.push(false);
+                    depth += 1;
+                }
+                TokenTypes::RightCurly => match curlies.pop() {
+                    Some(true) | None => {
+                        self.statement(tokens, first, index);
+                        first = index + 1;
+                    }
+                    Some(false) => depth -= 1,
+                },
+                TokenTypes::SemiColon if depth <= 0 => {
+                    self.statement(tokens, first, index);
+                    first = index + 1;
+                }
+                _ => {}
+            }
+        }
+        self.statement(tokens, first, tokens.len());
+    }
+
+    ///
+    ///Checks the expressions of the statement made of the tokens from up to to, the token at
+    ///to ends it
+    ///
+    fn statement(&mut self, tokens: &[(ParseInfo, usize)], from: usize, to: usize) {
+        if from >= to {
+            return;
+        }
+        let first = self.span(tokens[from].1, 1);
+        let last = self.span(tokens[to - 1].1, 1);
+        let position = |span: &Span| (span.line, span.column);
+        if self
+            .errors
+            .iter()
+            .any(|error| (position(&first)..=position(&last)).contains(&position(&error.span)))
+        {
+            return;
+        }
+        let statement = &tokens[from..to];
+        let end = tokens.get(to);
+        let assignment = statement
+            .iter()
+            .position(|(info, _)| info.token == TokenTypes::AssignmentOperator);
+        match &statement[0].0.token {
+            TokenTypes::Variable
+            | TokenTypes::Collection { .. }
+            | TokenTypes::Destructure { .. } => {
+                if let Some(equals) = assignment {
+                    self.expression(&statement[equals + 1..], end);
+                }
+            }
+            TokenTypes::Return if statement.len() > 1 => self.expression(&statement[1..], end),
+            TokenTypes::If { .. } | TokenTypes::Elif { .. } | TokenTypes::While { .. } => {
+                self.condition(&statement[0], statement.get(1), false)
+            }
+            TokenTypes::For { .. } => self.condition(&statement[0], statement.get(1), true),
+            TokenTypes::Return
+            | TokenTypes::Function { .. }
+            | TokenTypes::Else
+            | TokenTypes::Try
+            | TokenTypes::Catch
+            | TokenTypes::Finally
+            | TokenTypes::Break
+            | TokenTypes::Continue => {}
+            // x = value, xs[i] += value, x++ or a statement that is a value on its own
+            _ => match assignment {
+                Some(equals) => {
+                    self.expression(&statement[..equals], statement.get(equals));
+                    self.expression(&statement[equals + 1..], end);
+                }
+                None => match statement.split_last() {
+                    Some(((last, _), target))
+                        if matches!(last.value.as_str(), "++" | "--")
+                            && last.token == TokenTypes::Operator =>
+                    {
+                        self.expression(target, statement.last())
+                    }
+                    _ => self.expression(statement, end),
+                },
+            },
+        }
+    }
+
+    ///
+    ///Checks that the condition read with the if, elif, for or while token is an expression,
+    ///for a loop what follows its in. The ) after the token closes it
+    ///
+    fn condition(
+        &mut self,
+        (info, start): &(ParseInfo, usize),
+        close: Option<&(ParseInfo, usize)>,
+        is_for: bool,
+    ) {
+        let end = start + info.chars_read as usize;
+        let Some(open) = self.chars[*start..end].iter().position(|c| *c == '(') else {
+            return;
+        };
+        let offset = start + open + 1;
+        let condition: String = self.chars[offset..end].iter().collect();
+        let mut lexer = Lexer::standalone(&condition);
+        let mut tokens = Vec::new();
+        while let Some(info) = lexer.next() {
+            let at = offset + lexer.position() - info.chars_read as usize;
+            if info.token != TokenTypes::Comment {
+                tokens.push((info, at));
+            }
+        }
+        if !lexer.skipped().is_empty() {
+            return;
+        }
+        if !is_for {
+            return self.expression(&tokens, close);
+        }
+        let Some(values) = tokens.iter().position(|(info, _)| info.value == "in") else {
+            return;
+        };
+        let tokens = &tokens[values + 1..];
+        // the bounds of a range are read on their own
+        let mut depth = 0;
+        let range = tokens.iter().position(|(info, _)| {
+            match info.token {
+                TokenTypes::LeftBracket | TokenTypes::LeftParenthesis => depth += 1,
+                TokenTypes::RightBracket | TokenTypes::RightParenthesis => depth -= 1,
+                _ => {}
+            }
+            depth == 0 && info.token == TokenTypes::Operator && info.value == ".."
+        });
+        match range {
+            Some(range) => {
+                self.expression(&tokens[..range], tokens.get(range));
+                self.expression(&tokens[range + 1..], close);
+            }
+            None => self.expression(tokens, close),
+        }
+    }
+
+    ///
+    ///Reports where the tokens stop reading as one expression, end is the token after them
+    ///
+    fn expression(&mut self, tokens: &[(ParseInfo, usize)], end: Option<&(ParseInfo, usize)>) {
+        let Ok(nodes) = tokens
+            .iter()
+            .map(|(info, _)| token_to_node(info.clone()))
+            .collect::<Result<Vec<ASTNode>, String>>()
+        else {
+            return;
+        };
+        let Some((at, expected)) = expression_error(&nodes) else {
+            return;
+        };
+        let expected = expected.replace('\'', "`");
+        let (found, start, length) = match tokens.get(at).or(end) {
+            Some((info, start)) => (describe(info), *start, info.chars_read as usize),
+            None => ("end of file".to_string(), self.end, 1),
+        };
+        let message = format!("expected {}, found {}", expected, found);
+        self.error(INVALID_EXPRESSION, message, start, length);
+    }
+}
+
+///
+///Whether the { at index opens the block of the statement before it, rather than a dictionary
+///
+fn opens_block(tokens: &[(ParseInfo, usize)], index: usize) -> bool {
+    let before = |back: usize| index.checked_sub(back).map(|i| &tokens[i].0.token);
+    match before(1) {
+        Some(
+            TokenTypes::Function { .. }
+            | TokenTypes::Else
+            | TokenTypes::Try
+            | TokenTypes::Catch
+            | TokenTypes::Finally,
+        ) => true,
+        // the ) that ends the condition read with the if
+        Some(TokenTypes::RightParenthesis) => matches!(
+            before(2),
+            Some(
+                TokenTypes::If { .. }
+                    | TokenTypes::Elif { .. }
+                    | TokenTypes::For { .. }
+                    | TokenTypes::While { .. }
+            )
+        ),
+        Some(TokenTypes::VariableCall) => matches!(before(2), Some(TokenTypes::Catch)),
+        _ => false,
+    }
+}
+
+///
+///The offset each line of the source starts at
+///
+pub fn line_starts(source: &str) -> Vec<usize> {
+    let mut lines = vec![0];
+    for (offset, c) in source.chars().enumerate() {
+        if c == '\n' {
+            lines.push(offset + 1);
+        }
+    }
+    lines
+}
+
+///
+///The span of length characters from the offset start, lines as line_starts gives them
+///
+pub fn span_at(lines: &[usize], start: usize, length: usize) -> Span {
+    let line = lines.partition_point(|&line| line <= start);
+    Span {
+        line,
+        column: start - lines[line - 1] + 1,
+        length: length.max(1),
+    }
+}
+
+///
+///Where the first escape sequence the literal cannot have starts and how long it is, None when
+///they are all known. A raw string has no escape sequences
+///
+fn bad_escape(chars: &[char]) -> Option<(usize, usize)> {
+    if chars.first() == Some(&'r') {
+        return None;
+    }
+    let mut i = 0;
+    while i < chars.len() {
+        if chars[i] != '\\' {
+            i += 1;
+            continue;
+        }
+        match chars.get(i + 1) {
+            Some('n' | 't' | 'r' | '0' | '\\' | '"' | '\'') => i += 2,
+            // \u{...} with the hex code of a char
+            Some('u') => {
+                let close = chars[i..].iter().position(|c| *c == '}').map(|j| i + j);
+                let code = close
+                    .filter(|_| chars.get(i + 2) == Some(&'{'))
+                    .map(|close| chars[i + 3..close].iter().collect::<String>())
+                    .and_then(|hex| u32::from_str_radix(&hex, 16).ok())
+                    .and_then(char::from_u32);
+                match (code, close) {
+                    (Some(_), Some(close)) => i = close + 1,
+                    (None, Some(close)) => return Some((i, close + 1 - i)),
+                    (_, None) => return Some((i, 2)),
+                }
+            }
+            _ => return Some((i, 2)),
+        }
+    }
+    None
+}
+
+///
+///How a token is named in a message
+///
+fn describe(info: &ParseInfo) -> String {
+    let text = match &info.token {
+        TokenTypes::Variable | TokenTypes::Collection { .. } | TokenTypes::Destructure { .. } => {
+            "let".to_string()
+        }
+        TokenTypes::Function { .. } => "func".to_string(),
+        TokenTypes::VarTypeAssignment => ":".to_string(),
+        _ => info.value.clone(),
+    };
+    if text.chars().count() > 24 {
+        format!("`{}...`", text.chars().take(24).collect::<String>())
+    } else {
+        format!("`{}`", text)
+    }
+}
+
+///
+///An operator that needs a value in front of it, so it cannot start a statement
+///
+fn is_infix(info: &ParseInfo) -> bool {
+    match &info.token {
+        TokenTypes::AssignmentOperator | TokenTypes::FatArrow | TokenTypes::ArgumentSeparator => {
+            true
+        }
+        TokenTypes::Operator => !matches!(info.value.as_str(), "!" | "-" | "++" | "--"),
+        _ => false,
+    }
+}
+
+fn starts_value(info: &ParseInfo) -> bool {
+    match &info.token {
+        TokenTypes::FunctionCall => !info.value.starts_with('.'),
+        TokenTypes::Int
+        | TokenTypes::Float
+        | TokenTypes::String
+        | TokenTypes::Char
+        | TokenTypes::Bool
+        | TokenTypes::VariableCall
+        | TokenTypes::Variable
+        | TokenTypes::Collection { .. }
+        | TokenTypes::Destructure { .. }
+        | TokenTypes::Function { .. }
+        | TokenTypes::Return
+        | TokenTypes::If { .. }
+        | TokenTypes::For { .. }
+        | TokenTypes::While { .. }
+        | TokenTypes::Try => true,
+        _ => false,
+    }
+}
+
+fn ends_value(info: &ParseInfo) -> bool {
+    matches!(
+        info.token,
+        TokenTypes::Int
+            | TokenTypes::Float
+            | TokenTypes::String
+            | TokenTypes::Char
+            | TokenTypes::Bool
+            | TokenTypes::VariableCall
+    )
+}
+
+///
+///All syntax errors in the script, in the order they appear, or the errors in how it changes
+///its variables and consts when there are none. An empty list means the script can be run
+///
+pub fn check_syntax(source: &str) -> Vec<SyntaxError> {
+    let mut checker = Checker {
+        chars: source.chars().collect(),
+        lines: line_starts(source),
+        errors: Vec::new(),
+        delimiters: Vec::new(),
+        empty: true,
+        after_value: false,
+        expect_block: None,
+        catch_name: false,
+        recovering: false,
+        end: 0,
+    };
+
+    let mut lexer = Lexer::standalone(source);
+    let mut skipped = 0;
+    let mut tokens = Vec::new();
+    loop {
+        let info = lexer.next();
+        for (start, text) in &lexer.skipped()[skipped..] {
+            checker.skipped(*start, text);
+        }
+        skipped = lexer.skipped().len();
+
+        let Some(info) = info else {
+            break;
+        };
+        if info.token == TokenTypes::Comment {
+            continue;
+        }
+        let start = lexer.position() - info.chars_read as usize;
+        checker.token(&info, start);
+        checker.end = lexer.position();
+        tokens.push((info, start));
+    }
+    checker.end_of_file(lexer.open_comment());
+    checker.expressions(&tokens);
+
+    let mut errors = checker.errors;
+    errors.sort_by_key(|error| (error.span.line, error.span.column));
+    // how names are changed is only checked once the script reads as a whole
+    if errors.is_empty() {
+        errors = check_bindings(source);
+    }
+    errors
+}
+
+#[cfg(test)]
+mod syntax_tests {
+    use super::check_syntax;
+
+    fn errors(source: &str) -> Vec<(usize, usize, &'static str, String)> {
+        check_syntax(source)
+            .into_iter()
+            .map(|e| (e.span.line, e.span.column, e.code, e.message))
+            .collect()
+    }
+
+    #[test]
+    fn test_valid_scripts_have_no_errors() {
+        let source = "let a: int = 1;\n// a comment;\nfunc f(x: int) -> int {\n    if (x > 1) {\n        return x;\n    } else {\n        return 0;\n    }\n}\nlet d: dict<string, int> = {\"a\" => 1};\ntry {\n    echo(f(a));\n} catch e {\n    echo(e);\n}\nlet s: string = \"a;b}\".trim().len();\n";
+        assert_eq!(errors(source), vec![]);
+    }
+
+    #[test]
+    fn test_reports_every_error() {
+        let source =
+            "let a: int = 1 2;\n;\necho(a];\nlet b: int = 3;\necho(b @ 1);\nif (b > 1) echo(b);\n}\n";
+        assert_eq!(
+            errors(source),
+            vec![
+                (1, 16, "E0007", "expected `;`, found `2`".to_string()),
+                (2, 1, "E0006", "expected a statement, found `;`".to_string()),
+                (3, 7, "E0005", "expected `)`, found `]`".to_string()),
+                (
+                    5,
+                    8,
+                    "E0001",
+                    "expected a token, found unknown character `@`".to_string()
+                ),
+                (
+                    6,
+                    12,
+                    "E0008",
+                    "expected `{` after the condition, found `echo`".to_string()
+                ),
+                (
+                    7,
+                    1,
+                    "E0003",
+                    "expected a statement, found `}` that closes nothing".to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_invalid_literals() {
+        let source = "let a: int = 99999999999;\nlet b: string = \"ok\\t\\u{41} \\q\";\nlet c: char = 'ab';\nif (b == \"\\u{zz}\") {\n}\nlet d: string = r\"\\q\";\n";
+        assert_eq!(
+            errors(source),
+            vec![
+                (
+                    1,
+                    14,
+                    "E0013",
+                    "expected an int from -2147483648 to 2147483647, found `99999999999`"
+                        .to_string()
+                ),
+                (
+                    2,
+                    29,
+                    "E0013",
+                    "expected an escape sequence such as `\\n` or `\\u{41}`, found `\\q`"
+                        .to_string()
+                ),
+                (
+                    3,
+                    15,
+                    "E0013",
+                    "expected one character in a char literal, found 2".to_string()
+                ),
+                (
+                    4,
+                    11,
+                    "E0013",
+                    "expected an escape sequence such as `\\n` or `\\u{41}`, found `\\u{zz}`"
+                        .to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_negative_int_literals() {
+        assert_eq!(
+            errors("echoln(-2147483648);\nlet a: int = 1 -2147483648;\n"),
+            vec![(
+                2,
+                17,
+                "E0013",
+                "expected an int from -2147483648 to 2147483647, found `2147483648`".to_string()
+            )]
+        );
+    }
+
+    #[test]
+    fn test_invalid_expressions() {
+        let source = "echoln(\"start\");\nlet a: int = 1 +;\nlet b: int = * 2;\nlet c: int = ;\nlet d: dict<string, int> = {\"a\", 1};\nif (a >) {\n}\nfor (i in 0..) {\n}\nb = ;\nreturn;\n";
+        assert_eq!(
+            errors(source),
+            vec![
+                (2, 17, "E0014", "expected a value, found `;`".to_string()),
+                (3, 14, "E0014", "expected a value, found `*`".to_string()),
+                (4, 14, "E0014", "expected a value, found `;`".to_string()),
+                (5, 32, "E0014", "expected `=>`, found `,`".to_string()),
+                (6, 8, "E0014", "expected a value, found `)`".to_string()),
+                (8, 14, "E0014", "expected a value, found `)`".to_string()),
+                (10, 5, "E0014", "expected a value, found `;`".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_unclosed_at_end_of_file() {
+        assert_eq!(
+            errors("func f() {\n    echo(\"open);\n"),
+            vec![
+                (
+                    1,
+                    10,
+                    "E0004",
+                    "expected `}` to close this, found end of file".to_string()
+                ),
+                (
+                    2,
+                    9,
+                    "E0004",
+                    "expected `)` to close this, found end of file".to_string()
+                ),
+                (
+                    2,
+                    10,
+                    "E0002",
+                    "unterminated string: expected its closing quote, found end of file"
+                        .to_string()
+                ),
+            ]
+        );
+        assert_eq!(
+            errors("echo(1)\n/* open"),
+            vec![
+                (1, 8, "E0007", "expected `;`, found end of file".to_string()),
+                (
+                    2,
+                    1,
+                    "E0002",
+                    "unterminated comment: expected `*/`, found end of file".to_string()
+                ),
+            ]
+        );
+    }
+}
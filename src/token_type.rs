@@ -128,6 +128,16 @@ pub mod token_types {
          * =>
          */
         FatArrow,
+        /*
+         * return, ends a function with the value after it
+         */
+        Return,
+        /*
+         * let (a, b), the names a tuple is unpacked into
+         */
+        Destructure {
+            names: Vec<String>,
+        },
         /*
         Used as a bad return value
         */
@@ -242,6 +252,11 @@ pub mod token_types {
                 (TokenTypes::LeftBracket, TokenTypes::LeftBracket) => true,
                 (TokenTypes::RightBracket, TokenTypes::RightBracket) => true,
                 (TokenTypes::FatArrow, TokenTypes::FatArrow) => true,
+                (TokenTypes::Return, TokenTypes::Return) => true,
+                (
+                    TokenTypes::Destructure { names: ref names_a },
+                    TokenTypes::Destructure { names: ref names_b },
+                ) => names_a == names_b,
                 (TokenTypes::None, TokenTypes::None) => true,
                 (
                     TokenTypes::If {
@@ -307,6 +322,8 @@ pub mod token_types {
                 TokenTypes::Catch => "Catch".to_string(),
                 TokenTypes::Finally => "Finally".to_string(),
                 TokenTypes::FatArrow => "FatArrow".to_string(),
+                TokenTypes::Return => "Return".to_string(),
+                TokenTypes::Destructure { names } => format!("Destructure: {}", names.join(", ")),
                 TokenTypes::FunctionCallArguments => "FunctionCallArguments".to_string(),
                 TokenTypes::Float => "Float".to_string(),
                 TokenTypes::SemiColon => "SemiColon".to_string(),
@@ -0,0 +1,224 @@
+/*
+* This file traces what the interpreter does inside, for working on jist itself. Every event has
+* a category and a level and is only written when tracing is on for its category, with --trace
+* or the JIST_TRACE environment variable. Events go to stderr, or to the file --trace-file or
+* JIST_TRACE_FILE names, so they never mix with what the script prints
+*/
+use std::env;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, Write};
+use std::sync::{Mutex, OnceLock};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Level {
+    Error,
+    Warn,
+    Info,
+    Debug,
+    Trace,
+}
+
+const LEVELS: [Level; 5] = [
+    Level::Error,
+    Level::Warn,
+    Level::Info,
+    Level::Debug,
+    Level::Trace,
+];
+
+impl Level {
+    pub fn name(self) -> &'static str {
+        match self {
+            Level::Error => "error",
+            Level::Warn => "warn",
+            Level::Info => "info",
+            Level::Debug => "debug",
+            Level::Trace => "trace",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Category {
+    Tokenizer,
+    Parser,
+    Runtime,
+    Gc,
+}
+
+const CATEGORIES: [Category; 4] = [
+    Category::Tokenizer,
+    Category::Parser,
+    Category::Runtime,
+    Category::Gc,
+];
+
+impl Category {
+    pub fn name(self) -> &'static str {
+        match self {
+            Category::Tokenizer => "tokenizer",
+            Category::Parser => "parser",
+            Category::Runtime => "runtime",
+            Category::Gc => "gc",
+        }
+    }
+}
+
+///
+///The most detailed level traced for each category, None when it is not traced
+///
+pub type Levels = [Option<Level>; 4];
+
+///
+///Reads a trace setting: categories separated by commas, each with an optional :level. A
+///category without a level traces all of its events and all stands for every category, so
+///runtime,parser:info traces everything the runtime does and the parser's info and above
+///
+pub fn parse_levels(setting: &str) -> Result<Levels, String> {
+    let mut levels = [None; 4];
+    for part in setting.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+        let (name, level) = match part.split_once(':') {
+            Some((name, level)) => {
+                let level = LEVELS
+                    .into_iter()
+                    .find(|l| l.name() == level.trim())
+                    .ok_or_else(|| {
+                        format!(
+                            "Unknown trace level '{}', expected error, warn, info, debug or trace",
+                            level.trim()
+                        )
+                    })?;
+                (name.trim(), level)
+            }
+            None => (part, Level::Trace),
+        };
+        if name == "all" {
+            levels = [Some(level); 4];
+            continue;
+        }
+        let category = CATEGORIES
+            .iter()
+            .position(|c| c.name() == name)
+            .ok_or_else(|| {
+                format!(
+                    "Unknown trace category '{}', expected tokenizer, parser, runtime, gc or all",
+                    name
+                )
+            })?;
+        levels[category] = Some(level);
+    }
+    Ok(levels)
+}
+
+struct Tracer {
+    levels: Levels,
+    output: Mutex<Box<dyn Write + Send>>,
+}
+
+static TRACER: OnceLock<Tracer> = OnceLock::new();
+
+fn open(setting: Option<String>, file: Option<String>) -> Result<Tracer, String> {
+    let levels = match setting {
+        Some(setting) => parse_levels(&setting)?,
+        None => [None; 4],
+    };
+    let output: Box<dyn Write + Send> = match file {
+        Some(path) => Box::new(
+            File::create(&path)
+                .map_err(|e| format!("Failed to create trace file '{}': {}", path, e))?,
+        ),
+        None => Box::new(io::stderr()),
+    };
+    Ok(Tracer {
+        levels,
+        output: Mutex::new(output),
+    })
+}
+
+///
+///Turns tracing on from the command line, falling back to JIST_TRACE and JIST_TRACE_FILE for
+///what is not given there. Only the first call has an effect
+///
+pub fn init(setting: Option<&str>, file: Option<&str>) -> Result<(), String> {
+    let tracer = open(
+        setting
+            .map(String::from)
+            .or_else(|| env::var("JIST_TRACE").ok()),
+        file.map(String::from)
+            .or_else(|| env::var("JIST_TRACE_FILE").ok()),
+    )?;
+    let _ = TRACER.set(tracer);
+    Ok(())
+}
+
+fn tracer() -> &'static Tracer {
+    TRACER.get_or_init(|| {
+        open(
+            env::var("JIST_TRACE").ok(),
+            env::var("JIST_TRACE_FILE").ok(),
+        )
+        .unwrap_or_else(|_| Tracer {
+            levels: [None; 4],
+            output: Mutex::new(Box::new(io::stderr())),
+        })
+    })
+}
+
+///
+///Whether events of this category and level are written, checked before an event is formatted
+///
+pub fn enabled(category: Category, level: Level) -> bool {
+    tracer().levels[category as usize].is_some_and(|traced| level <= traced)
+}
+
+pub fn write(category: Category, level: Level, message: fmt::Arguments) {
+    let mut output = match tracer().output.lock() {
+        Ok(guard) => guard,
+        Err(poisoned) => poisoned.into_inner(),
+    };
+    let _ = writeln!(output, "[{} {}] {}", level.name(), category.name(), message);
+}
+
+///
+///Traces an event, trace!(Runtime, Debug, "format", arguments...)
+///
+macro_rules! trace {
+    ($category:ident, $level:ident, $($message:tt)+) => {
+        if $crate::trace::enabled(
+            $crate::trace::Category::$category,
+            $crate::trace::Level::$level,
+        ) {
+            $crate::trace::write(
+                $crate::trace::Category::$category,
+                $crate::trace::Level::$level,
+                format_args!($($message)+),
+            );
+        }
+    };
+}
+
+#[cfg(test)]
+mod trace_tests {
+    use super::{parse_levels, Level};
+
+    #[test]
+    fn test_parse_levels() {
+        assert_eq!(
+            parse_levels("runtime"),
+            Ok([None, None, Some(Level::Trace), None])
+        );
+        assert_eq!(
+            parse_levels("all:warn, parser:debug"),
+            Ok([
+                Some(Level::Warn),
+                Some(Level::Debug),
+                Some(Level::Warn),
+                Some(Level::Warn)
+            ])
+        );
+        assert_eq!(parse_levels(""), Ok([None; 4]));
+        assert!(parse_levels("runtime:loud").is_err());
+        assert!(parse_levels("lexer").is_err());
+    }
+}